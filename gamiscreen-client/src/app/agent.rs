@@ -12,7 +12,7 @@ use tokio::time::{Instant, Sleep, sleep};
 use tracing::{debug, error, info, warn};
 
 use crate::config::ClientConfig;
-use crate::{AppError, platform, sse, update};
+use crate::{AppError, platform, update, ws};
 
 const RELOCK_POLL_INTERVAL: Duration = Duration::from_secs(5);
 const RELOCK_INITIAL_DELAY_SECS: u64 = 60;
@@ -73,15 +73,23 @@ pub async fn run(config_path: Option<PathBuf>) -> Result<(), AppError> {
         .clone()
         .ok_or_else(|| AppError::Config("device token missing device_id".into()))?;
 
-    let hub = match sse::SseHub::new(&cfg.server_url, &token, &claims) {
-        Ok(h) => Some(h),
+    let cancel = tokio_util::sync::CancellationToken::new();
+    let link = match ws::AgentLink::new(
+        &cfg.server_url,
+        &claims.tenant_id,
+        &child_id,
+        &device_id,
+        &token,
+        cancel.child_token(),
+    ) {
+        Ok(l) => Some(l),
         Err(e) => {
-            warn!(error=%e, "SSE hub init failed; continuing without SSE");
+            warn!(error=%e, "agent link init failed; using HTTP heartbeats only");
             None
         }
     };
-    if let Some(h) = &hub {
-        relocker.attach_sse(h).await;
+    if let Some(l) = &link {
+        relocker.attach_link(l).await;
     }
 
     let countdown_task = CountdownTask::new(
@@ -100,7 +108,6 @@ pub async fn run(config_path: Option<PathBuf>) -> Result<(), AppError> {
         );
     }
 
-    let cancel = tokio_util::sync::CancellationToken::new();
     let cfg_cloned = cfg.clone();
     let token_cloned = token.clone();
     let relocker_cloned = relocker.clone();
@@ -118,6 +125,7 @@ pub async fn run(config_path: Option<PathBuf>) -> Result<(), AppError> {
                 claims: claims_cloned,
                 child_id: child_id_cloned,
                 device_id: device_id_cloned,
+                link,
                 relocker: relocker_cloned,
                 platform: plat_cloned,
                 countdown_task,
@@ -150,6 +158,7 @@ struct MainLoopContext {
     claims: JwtClaims,
     child_id: String,
     device_id: String,
+    link: Option<ws::AgentLink>,
     relocker: ReLocker,
     platform: Arc<dyn platform::Platform>,
     countdown_task: CountdownTask,
@@ -166,6 +175,7 @@ async fn main_loop(
         claims,
         child_id,
         device_id,
+        link,
         relocker,
         platform,
         countdown_task,
@@ -202,6 +212,7 @@ async fn main_loop(
         }
 
         match send_pending(
+            link.as_ref(),
             &cfg.server_url,
             &claims.tenant_id,
             &child_id,
@@ -264,6 +275,7 @@ async fn main_loop(
 }
 
 async fn send_pending(
+    link: Option<&ws::AgentLink>,
     server_url: &str,
     tenant_id: &str,
     child_id: &str,
@@ -274,8 +286,17 @@ async fn send_pending(
     if pending_minutes.is_empty() {
         return Ok(None);
     }
-    let base = crate::config::normalize_server_url(server_url);
     let minutes = pending_minutes.snapshot();
+    if let Some(link) = link {
+        match link.heartbeat(minutes.clone()).await {
+            Ok(resp) => {
+                pending_minutes.mark_sent(&minutes)?;
+                return Ok(Some(resp));
+            }
+            Err(e) => debug!(error=%e, "heartbeat over agent link failed; using HTTP"),
+        }
+    }
+    let base = crate::config::normalize_server_url(server_url);
     let resp = api::rest::child_device_heartbeat_with_minutes(
         &base, tenant_id, child_id, device_id, token, &minutes,
     )
@@ -285,7 +306,7 @@ async fn send_pending(
     Ok(Some(resp))
 }
 
-fn read_token_from_keyring(server_url: &str) -> Result<String, AppError> {
+pub(crate) fn read_token_from_keyring(server_url: &str) -> Result<String, AppError> {
     let entry = crate::keyring_entry(server_url)?;
     entry
         .get_password()
@@ -593,7 +614,7 @@ enum CountdownAction {
 struct ReLocker {
     platform: Arc<dyn platform::Platform>,
     handle: std::sync::Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
-    sse_task: std::sync::Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    link_task: std::sync::Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
 }
 
 impl ReLocker {
@@ -601,7 +622,7 @@ impl ReLocker {
         Self {
             platform,
             handle: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
-            sse_task: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
+            link_task: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
         }
     }
 
@@ -667,35 +688,56 @@ impl ReLocker {
         self.platform.hide_notification().await;
     }
 
-    async fn attach_sse(&self, hub: &sse::SseHub) {
-        let mut guard = self.sse_task.lock().await;
+    async fn attach_link(&self, link: &ws::AgentLink) {
+        use gamiscreen_shared::api::{AgentCommand, AgentServerMessage, ServerEvent};
+
+        let mut guard = self.link_task.lock().await;
         if guard.is_some() {
             return;
         }
-        let mut rx = hub.subscribe();
+        let mut rx = link.subscribe();
         let relocker = self.clone();
         let handle = tokio::spawn(async move {
+            // The server sends the child's state right before each command.
+            let mut remaining: Option<i32> = None;
             loop {
                 match rx.recv().await {
-                    Ok(gamiscreen_shared::api::ServerEvent::RemainingUpdated {
-                        remaining_minutes,
-                        ..
+                    Ok(AgentServerMessage::Event {
+                        event:
+                            ServerEvent::RemainingUpdated {
+                                remaining_minutes, ..
+                            },
                     }) => {
-                        if remaining_minutes > 0 {
-                            relocker.disable().await;
-                        } else {
-                            relocker.enable(Some(remaining_minutes)).await;
+                        remaining = Some(remaining_minutes);
+                    }
+                    Ok(AgentServerMessage::Event {
+                        event:
+                            ServerEvent::TimeRequestResolved {
+                                request_id,
+                                minutes,
+                                approved,
+                                ..
+                            },
+                    }) => {
+                        // Granted minutes arrive separately as RemainingUpdated
+                        tracing::info!(request_id, minutes, approved, "time request resolved");
+                    }
+                    Ok(AgentServerMessage::Command { command }) => {
+                        tracing::info!(?command, "server command received");
+                        match command {
+                            AgentCommand::Lock => relocker.enable(remaining).await,
+                            AgentCommand::Unlock => relocker.disable().await,
                         }
                     }
                     Ok(_) => {}
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                        tracing::warn!(missed=%n, "SSE relocker subscriber lagged; resyncing");
+                        tracing::warn!(missed=%n, "agent link subscriber lagged; resyncing");
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                 }
             }
             tracing::warn!(
-                "SSE relocker subscriber exiting; no longer responding to server events"
+                "agent link subscriber exiting; no longer responding to server commands"
             );
         });
         *guard = Some(handle);
@@ -703,7 +745,7 @@ impl ReLocker {
 
     async fn shutdown(&self) {
         self.disable().await;
-        let mut s = self.sse_task.lock().await;
+        let mut s = self.link_task.lock().await;
         if let Some(h) = s.take() {
             h.abort();
         }
@@ -50,8 +50,21 @@ pub enum Command {
         #[arg(long)]
         server: Option<String>,
         /// Username. Falls back to prompt.
-        #[arg(long)]
+        #[arg(long, conflicts_with = "code")]
         username: Option<String>,
+        /// Pairing code created by a parent (e.g., ABCD-EFGH). Registers this device
+        /// without a username or password.
+        #[arg(long)]
+        code: Option<String>,
+    },
+    /// Ask a parent for extra screen time
+    RequestTime {
+        /// Extra minutes to ask for
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        minutes: u32,
+        /// Why the time is needed (shown to the parent)
+        #[arg(long)]
+        reason: String,
     },
     /// Install background agent/service for this platform
     ///
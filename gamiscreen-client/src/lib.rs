@@ -3,8 +3,9 @@ pub mod cli;
 pub mod config;
 pub mod login;
 pub mod platform;
-pub mod sse;
+pub mod request_time;
 pub mod update;
+pub mod ws;
 
 pub use cli::{Cli, Command};
 pub use config::{ClientConfig, load_config, resolve_config_path};
@@ -115,7 +116,14 @@ pub async fn run(cli: Cli) -> Result<(), AppError> {
 
     match command {
         Command::Agent => app::agent::run(config.clone()).await,
-        Command::Login { server, username } => login::login(server, username, config.clone()).await,
+        Command::Login {
+            server,
+            username,
+            code,
+        } => login::login(server, username, code, config.clone()).await,
+        Command::RequestTime { minutes, reason } => {
+            request_time::request_time(minutes, reason, config.clone()).await
+        }
         Command::Install { user } => {
             let plat = platform::detect_default().await?;
             plat.install(user).await
@@ -11,6 +11,7 @@ use crate::config::{load_config, resolve_config_path};
 pub async fn login(
     server_arg: Option<String>,
     username_arg: Option<String>,
+    code_arg: Option<String>,
     cfg_path_opt: Option<PathBuf>,
 ) -> Result<(), AppError> {
     // Resolve server url: CLI arg > config if present > prompt; normalize and strip trailing slash
@@ -30,6 +31,24 @@ pub async fn login(
         }
     };
 
+    let device_id = {
+        let plat = crate::platform::detect_default().await.map_err(|e| {
+            AppError::Io(std::io::Error::other(format!(
+                "platform detect failed: {e}"
+            )))
+        })?;
+        plat.device_id()
+    };
+
+    // A pairing code from a parent registers the device without credentials
+    if let Some(code) = code_arg {
+        let req = api::PairReq { code, device_id };
+        let reg = api::rest::pair_device(&server_url, &req)
+            .await
+            .map_err(|e| AppError::Http(format!("pairing failed: {e}")))?;
+        return save_device_token(&server_url, &reg.token);
+    }
+
     let username = match username_arg {
         Some(u) => u,
         None => prompt("Username: ")?,
@@ -37,17 +56,22 @@ pub async fn login(
     let password = rpassword::prompt_password("Password: ")
         .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
 
-    let body: api::AuthResp = match api::rest::login(
-        &server_url,
-        &api::AuthReq {
-            username: username.clone(),
-            password: password.clone(),
-        },
-    )
-    .await
-    {
-        Ok(v) => v,
-        Err(e) => return Err(AppError::Http(format!("login failed: {e}"))),
+    let mut req = api::AuthReq {
+        username: username.clone(),
+        password: password.clone(),
+        totp_code: None,
+    };
+    let body: api::AuthResp = loop {
+        match api::rest::login(&server_url, &req).await {
+            Ok(v) => break v,
+            // Parents with two-factor authentication need a second attempt with a code
+            Err(api::rest::RestError::Status { status: 401, body })
+                if req.totp_code.is_none() && body.contains("totp_required") =>
+            {
+                req.totp_code = Some(prompt("Authentication code: ")?);
+            }
+            Err(e) => return Err(AppError::Http(format!("login failed: {e}"))),
+        }
     };
 
     // Inspect token to determine role/child_id. If parent, prompt for child_id to register.
@@ -63,14 +87,6 @@ pub async fn login(
     };
 
     // Register client to obtain device-scoped token, then write config
-    let device_id = {
-        let plat = crate::platform::detect_default().await.map_err(|e| {
-            AppError::Io(std::io::Error::other(format!(
-                "platform detect failed: {e}"
-            )))
-        })?;
-        plat.device_id()
-    };
     let reg = register_client(
         &server_url,
         &tenant_id,
@@ -79,16 +95,20 @@ pub async fn login(
         &device_id,
     )
     .await?;
+    save_device_token(&server_url, &reg.token)
+}
+
+fn save_device_token(server_url: &str, token: &str) -> Result<(), AppError> {
     // Save device token in keyring under the server_url only (single-user support)
-    let entry = keyring_entry_for_login(&server_url)?;
+    let entry = keyring_entry_for_login(server_url)?;
     entry
-        .set_password(&reg.token)
+        .set_password(token)
         .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
     entry
         .get_password()
         .map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
     let cfg = crate::config::ClientConfig {
-        server_url: server_url.clone(),
+        server_url: server_url.to_string(),
     };
     let path = crate::config::default_config_path()
         .ok_or_else(|| AppError::Config("could not determine config dir".into()))?;
@@ -112,6 +132,9 @@ fn prompt(msg: &str) -> Result<String, AppError> {
 
 type RegisterResp = api::ClientRegisterResp;
 
+/// How often a pending device checks whether a parent approved it.
+const APPROVAL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
 async fn register_client(
     server_url: &str,
     tenant_id: &str,
@@ -119,9 +142,42 @@ async fn register_client(
     child_id: &str,
     device_id: &str,
 ) -> Result<RegisterResp, AppError> {
-    api::rest::child_register(server_url, tenant_id, child_id, device_id, login_token)
-        .await
-        .map_err(|e| AppError::Http(format!("registration failed: {e}")))
+    let register = || async {
+        api::rest::child_register(server_url, tenant_id, child_id, device_id, login_token)
+            .await
+            .map_err(|e| AppError::Http(format!("registration failed: {e}")))
+    };
+    let outcome = register().await?;
+    if let api::rest::RegisterOutcome::Registered(reg) = outcome {
+        return Ok(reg);
+    }
+    // A child registering a new device waits for a parent to approve it
+    println!("Waiting for a parent to approve device '{device_id}'...");
+    loop {
+        tokio::time::sleep(APPROVAL_POLL_INTERVAL).await;
+        let device =
+            api::rest::child_device(server_url, tenant_id, child_id, device_id, login_token)
+                .await
+                .map_err(|e| AppError::Http(format!("device status check failed: {e}")))?;
+        match device.status.as_str() {
+            "pending" => continue,
+            "approved" => return register_outcome_token(register().await?),
+            other => {
+                return Err(AppError::Http(format!(
+                    "device registration was not approved ({other})"
+                )));
+            }
+        }
+    }
+}
+
+fn register_outcome_token(outcome: api::rest::RegisterOutcome) -> Result<RegisterResp, AppError> {
+    match outcome {
+        api::rest::RegisterOutcome::Registered(reg) => Ok(reg),
+        api::rest::RegisterOutcome::Pending(_) => Err(AppError::Http(
+            "registration failed: device is still pending approval".into(),
+        )),
+    }
 }
 
 fn keyring_entry_for_login(server_url: &str) -> Result<keyring::Entry, AppError> {
@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use gamiscreen_shared::{api, jwt};
+
+use crate::AppError;
+use crate::app::agent::read_token_from_keyring;
+use crate::config::ClientConfig;
+
+/// Ask a parent for extra minutes using the device token stored by `login`.
+pub async fn request_time(
+    minutes: u32,
+    reason: String,
+    config_path: Option<PathBuf>,
+) -> Result<(), AppError> {
+    let (_, cfg) = ClientConfig::find_and_load(config_path)?;
+    let key = crate::config::normalize_server_url(&cfg.server_url);
+    let token = read_token_from_keyring(&key)?;
+    let claims = jwt::decode_unverified(&token)
+        .map_err(|e| AppError::Http(format!("invalid token: {e}")))?;
+    let child_id = claims
+        .child_id
+        .ok_or_else(|| AppError::Config("device token missing child_id".into()))?;
+    let minutes =
+        i32::try_from(minutes).map_err(|_| AppError::Config("minutes out of range".into()))?;
+
+    let resp = api::rest::child_request_time(
+        &cfg.server_url,
+        &claims.tenant_id,
+        &child_id,
+        &token,
+        &api::TimeRequestReq { minutes, reason },
+    )
+    .await
+    .map_err(|e| AppError::Http(format!("time request failed: {e}")))?;
+    println!(
+        "Asked for {} more minutes (request #{}); waiting for a parent to decide.",
+        resp.minutes, resp.id
+    );
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use gamiscreen_shared::api::{AgentMessage, AgentServerMessage, HeartbeatResp};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinSet;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_util::sync::CancellationToken;
+
+use crate::AppError;
+
+/// How long to wait for the server to acknowledge a heartbeat.
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+/// The server pings every 20s; reconnect if it goes quiet for much longer.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+type HeartbeatRequest = (Vec<i64>, oneshot::Sender<HeartbeatResp>);
+
+/// WebSocket connection to the server for one device: sends heartbeats and
+/// broadcasts the events and commands the server pushes back. Reconnects
+/// with backoff until cancelled.
+#[derive(Clone)]
+pub struct AgentLink {
+    tx: broadcast::Sender<AgentServerMessage>,
+    heartbeat_tx: mpsc::Sender<HeartbeatRequest>,
+    connected: Arc<AtomicBool>,
+    _joinset: Arc<tokio::sync::Mutex<JoinSet<()>>>,
+}
+
+impl AgentLink {
+    pub fn new(
+        server_base: &str,
+        tenant_id: &str,
+        child_id: &str,
+        device_id: &str,
+        token: &str,
+        cancel: CancellationToken,
+    ) -> Result<Self, AppError> {
+        let base = crate::config::normalize_server_url(server_base);
+        if base.is_empty() {
+            return Err(AppError::Config("agent link: server_base empty".into()));
+        }
+        let url = to_ws_url(&gamiscreen_shared::api::endpoints::child_device_ws(
+            &base, tenant_id, child_id, device_id,
+        ))?;
+
+        let (tx, _) = broadcast::channel(64);
+        let (heartbeat_tx, heartbeat_rx) = mpsc::channel(4);
+        let connected = Arc::new(AtomicBool::new(false));
+        let mut js = JoinSet::new();
+        js.spawn(run(
+            url,
+            token.to_string(),
+            tx.clone(),
+            heartbeat_rx,
+            connected.clone(),
+            cancel,
+        ));
+
+        Ok(Self {
+            tx,
+            heartbeat_tx,
+            connected,
+            _joinset: Arc::new(tokio::sync::Mutex::new(js)),
+        })
+    }
+
+    /// Subscribe to server events and commands.
+    pub fn subscribe(&self) -> broadcast::Receiver<AgentServerMessage> {
+        self.tx.subscribe()
+    }
+
+    /// Send a heartbeat over the socket and wait for its acknowledgement.
+    /// Fails straight away while disconnected so callers can fall back to HTTP.
+    pub async fn heartbeat(&self, minutes: Vec<i64>) -> Result<HeartbeatResp, AppError> {
+        if !self.connected.load(Ordering::Relaxed) {
+            return Err(AppError::Http("agent link: not connected".into()));
+        }
+        let (ack_tx, ack_rx) = oneshot::channel();
+        self.heartbeat_tx
+            .send((minutes, ack_tx))
+            .await
+            .map_err(|_| AppError::Http("agent link: stopped".into()))?;
+        match tokio::time::timeout(ACK_TIMEOUT, ack_rx).await {
+            Ok(Ok(resp)) => Ok(resp),
+            Ok(Err(_)) => Err(AppError::Http("agent link: connection lost".into())),
+            Err(_) => Err(AppError::Http("agent link: heartbeat timed out".into())),
+        }
+    }
+}
+
+async fn run(
+    url: String,
+    token: String,
+    tx: broadcast::Sender<AgentServerMessage>,
+    mut heartbeat_rx: mpsc::Receiver<HeartbeatRequest>,
+    connected: Arc<AtomicBool>,
+    cancel: CancellationToken,
+) {
+    let mut backoff_secs = 1u64;
+    loop {
+        let mut req = match url.as_str().into_client_request() {
+            Ok(req) => req,
+            Err(e) => {
+                tracing::error!(error=%e, "agent link: invalid url");
+                return;
+            }
+        };
+        match format!("Bearer {token}").parse() {
+            Ok(value) => {
+                req.headers_mut().insert("authorization", value);
+            }
+            Err(e) => {
+                tracing::error!(error=%e, "agent link: invalid token");
+                return;
+            }
+        }
+
+        let connect = tokio::select! {
+            _ = cancel.cancelled() => return,
+            res = tokio_tungstenite::connect_async(req) => res,
+        };
+        match connect {
+            Ok((ws, _)) => {
+                tracing::info!("agent link: connected");
+                backoff_secs = 1;
+                connected.store(true, Ordering::Relaxed);
+                let reason = serve(ws, &tx, &mut heartbeat_rx, &cancel).await;
+                connected.store(false, Ordering::Relaxed);
+                tracing::warn!(reason, "agent link: disconnected");
+                if cancel.is_cancelled() {
+                    return;
+                }
+            }
+            Err(e) => tracing::warn!(error=%e, "agent link: connect failed"),
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(Duration::from_secs(backoff_secs)) => {}
+        }
+        backoff_secs = std::cmp::min(backoff_secs * 2, 30);
+    }
+}
+
+async fn serve<S>(
+    ws: tokio_tungstenite::WebSocketStream<S>,
+    tx: &broadcast::Sender<AgentServerMessage>,
+    heartbeat_rx: &mut mpsc::Receiver<HeartbeatRequest>,
+    cancel: &CancellationToken,
+) -> &'static str
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let (mut sink, mut stream) = ws.split();
+    // Heartbeats awaiting an ack, oldest first; dropped senders tell callers
+    // the connection went away.
+    let mut pending: VecDeque<HeartbeatRequest> = VecDeque::new();
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                let _ = sink.send(Message::Close(None)).await;
+                return "shutting down";
+            }
+            req = heartbeat_rx.recv() => {
+                let Some((minutes, ack)) = req else { return "heartbeat channel closed" };
+                let msg = AgentMessage::Heartbeat { minutes: minutes.clone() };
+                let text = match serde_json::to_string(&msg) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        tracing::error!(error=%e, "agent link: failed to encode heartbeat");
+                        continue;
+                    }
+                };
+                if sink.send(Message::Text(text.into())).await.is_err() {
+                    return "send failed";
+                }
+                pending.push_back((minutes, ack));
+            }
+            msg = tokio::time::timeout(IDLE_TIMEOUT, stream.next()) => {
+                let msg = match msg {
+                    Err(_) => return "server stopped responding",
+                    Ok(None) => return "closed by server",
+                    Ok(Some(Err(e))) => {
+                        tracing::warn!(error=%e, "agent link: socket error");
+                        return "socket error";
+                    }
+                    Ok(Some(Ok(msg))) => msg,
+                };
+                let text = match msg {
+                    Message::Text(text) => text,
+                    Message::Close(_) => return "closed by server",
+                    // Pings are answered by tungstenite while reading.
+                    _ => continue,
+                };
+                match serde_json::from_str::<AgentServerMessage>(text.as_str()) {
+                    Ok(AgentServerMessage::HeartbeatAck {
+                        minutes,
+                        remaining_minutes,
+                        balance,
+                        blocked_by_tasks,
+                    }) => {
+                        if let Some(pos) = pending.iter().position(|(m, _)| *m == minutes)
+                            && let Some((_, ack)) = pending.remove(pos)
+                        {
+                            let _ = ack.send(HeartbeatResp {
+                                remaining_minutes,
+                                balance,
+                                blocked_by_tasks,
+                            });
+                        }
+                    }
+                    Ok(AgentServerMessage::Error { message }) => {
+                        tracing::warn!(%message, "agent link: server reported an error");
+                        // Errors answer the oldest heartbeat; let it fall back to HTTP.
+                        pending.pop_front();
+                    }
+                    Ok(msg) => {
+                        tracing::trace!(?msg, "agent link: received message");
+                        let _ = tx.send(msg);
+                    }
+                    Err(e) => tracing::warn!(error=%e, "agent link: failed to parse message"),
+                }
+            }
+        }
+    }
+}
+
+fn to_ws_url(http_url: &str) -> Result<String, AppError> {
+    let mut u = url::Url::parse(http_url)
+        .map_err(|e| AppError::Config(format!("invalid server_url: {e}")))?;
+    let scheme = match u.scheme() {
+        "https" => "wss",
+        _ => "ws",
+    };
+    u.set_scheme(scheme)
+        .map_err(|_| AppError::Config("invalid server_url scheme".into()))?;
+    Ok(u.into())
+}
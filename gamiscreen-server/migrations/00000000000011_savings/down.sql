@@ -0,0 +1,8 @@
+-- Return any saved minutes to the spendable balance before dropping the pot.
+UPDATE balances SET minutes_remaining = minutes_remaining + savings_minutes;
+
+DROP TABLE IF EXISTS savings_transactions;
+
+ALTER TABLE balances DROP COLUMN savings_interest_at;
+ALTER TABLE balances DROP COLUMN savings_locked_until;
+ALTER TABLE balances DROP COLUMN savings_minutes;
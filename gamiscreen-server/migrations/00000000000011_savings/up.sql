@@ -0,0 +1,17 @@
+-- Savings pot: minutes moved out of minutes_remaining that cannot be spent
+-- until withdrawn. Interest accrues weekly from savings_interest_at.
+ALTER TABLE balances ADD COLUMN savings_minutes INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE balances ADD COLUMN savings_locked_until TIMESTAMP;
+ALTER TABLE balances ADD COLUMN savings_interest_at TIMESTAMP;
+
+-- Ledger of all savings movements (deposit, withdraw, interest)
+CREATE TABLE IF NOT EXISTS savings_transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    savings_after INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_savings_transactions_child_id ON savings_transactions(child_id);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS minute_transfers;
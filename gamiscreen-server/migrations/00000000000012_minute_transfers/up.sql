@@ -0,0 +1,16 @@
+-- Minutes given by one child to another. Child-initiated transfers start as
+-- 'pending' and need parent approval; parent transfers are applied directly.
+CREATE TABLE IF NOT EXISTS minute_transfers (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  from_child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  to_child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  minutes INTEGER NOT NULL,
+  note TEXT,
+  requested_by TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending',
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  resolved_by TEXT,
+  resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_minute_transfers_status ON minute_transfers(status);
@@ -0,0 +1,16 @@
+-- Extra screen time asked for by a child. Parents approve a request as a gift
+-- or as a loan (is_borrowed), or deny it.
+CREATE TABLE IF NOT EXISTS time_requests (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  minutes INTEGER NOT NULL,
+  reason TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending',
+  is_borrowed BOOLEAN NOT NULL DEFAULT 0,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  resolved_by TEXT,
+  resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_time_requests_status ON time_requests(status);
+CREATE INDEX IF NOT EXISTS idx_time_requests_child ON time_requests(child_id);
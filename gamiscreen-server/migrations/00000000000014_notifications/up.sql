@@ -0,0 +1,51 @@
+-- Persistent inbox. Parent notifications (audience 'parents') are shared by all
+-- parent users; child notifications (audience 'child') belong to child_id.
+-- Read/dismissed state is tracked per recipient in notification_receipts.
+CREATE TABLE IF NOT EXISTS notifications (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  audience TEXT NOT NULL,
+  child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  kind TEXT NOT NULL,
+  -- JSON-encoded NotificationPayload
+  payload TEXT NOT NULL,
+  -- Submission, transfer or time request the notification asks a parent to resolve
+  source_id INTEGER,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  resolved_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_audience ON notifications(audience, child_id);
+CREATE INDEX IF NOT EXISTS idx_notifications_source ON notifications(kind, source_id);
+
+CREATE TABLE IF NOT EXISTS notification_receipts (
+  notification_id INTEGER NOT NULL REFERENCES notifications(id) ON DELETE CASCADE,
+  -- Parent username, or child id for child notifications
+  recipient TEXT NOT NULL,
+  read_at TIMESTAMP,
+  dismissed_at TIMESTAMP,
+  PRIMARY KEY (notification_id, recipient)
+);
+
+-- Carry over items that are still waiting for a parent
+INSERT INTO notifications (audience, child_id, kind, payload, source_id, created_at)
+SELECT 'parents', s.child_id, 'task_submission',
+       json_object('kind', 'task_submission', 'submission_id', s.id, 'task_id', t.id,
+                   'task_name', t.name, 'minutes', t.minutes),
+       s.id, s.submitted_at
+FROM task_submissions s JOIN tasks t ON t.id = s.task_id;
+
+INSERT INTO notifications (audience, child_id, kind, payload, source_id, created_at)
+SELECT 'parents', m.from_child_id, 'transfer',
+       json_object('kind', 'transfer', 'transfer_id', m.id, 'to_child_id', m.to_child_id,
+                   'to_child_display_name', c.display_name, 'minutes', m.minutes, 'note', m.note),
+       m.id, m.created_at
+FROM minute_transfers m JOIN children c ON c.id = m.to_child_id
+WHERE m.status = 'pending';
+
+INSERT INTO notifications (audience, child_id, kind, payload, source_id, created_at)
+SELECT 'parents', r.child_id, 'time_request',
+       json_object('kind', 'time_request', 'request_id', r.id, 'minutes', r.minutes,
+                   'reason', r.reason),
+       r.id, r.created_at
+FROM time_requests r
+WHERE r.status = 'pending';
@@ -0,0 +1,16 @@
+-- Photos attached to task submissions as proof. Submissions are deleted when
+-- resolved, so attachments are keyed by submission id without a foreign key
+-- and kept until delete_after (NULL while the submission is pending).
+CREATE TABLE IF NOT EXISTS submission_attachments (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  submission_id INTEGER NOT NULL,
+  child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  content_type TEXT NOT NULL,
+  size_bytes INTEGER NOT NULL,
+  data BLOB NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  delete_after TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_submission_attachments_submission ON submission_attachments(submission_id);
+CREATE INDEX IF NOT EXISTS idx_submission_attachments_delete_after ON submission_attachments(delete_after);
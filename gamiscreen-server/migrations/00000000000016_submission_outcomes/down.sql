@@ -0,0 +1,9 @@
+DELETE FROM task_submissions WHERE status <> 'pending';
+DROP INDEX IF EXISTS idx_task_submissions_status;
+DROP INDEX IF EXISTS idx_task_submissions_child;
+ALTER TABLE task_submissions DROP COLUMN resolved_at;
+ALTER TABLE task_submissions DROP COLUMN resolved_by;
+ALTER TABLE task_submissions DROP COLUMN comment;
+ALTER TABLE task_submissions DROP COLUMN minutes_awarded;
+ALTER TABLE task_submissions DROP COLUMN status;
+ALTER TABLE task_completions DROP COLUMN minutes_awarded;
@@ -0,0 +1,13 @@
+-- Keep resolved task submissions as history instead of deleting them, with
+-- the parent's decision, comment and (possibly adjusted) credit.
+ALTER TABLE task_submissions ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+ALTER TABLE task_submissions ADD COLUMN minutes_awarded INTEGER;
+ALTER TABLE task_submissions ADD COLUMN comment TEXT;
+ALTER TABLE task_submissions ADD COLUMN resolved_by TEXT;
+ALTER TABLE task_submissions ADD COLUMN resolved_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_task_submissions_status ON task_submissions(status);
+CREATE INDEX IF NOT EXISTS idx_task_submissions_child ON task_submissions(child_id);
+
+-- Minutes actually credited for a completion, when known (NULL for older rows).
+ALTER TABLE task_completions ADD COLUMN minutes_awarded INTEGER;
@@ -0,0 +1,18 @@
+-- Scheduled chores from the config, expanded into dated instances. An instance
+-- can be completed from available_from on; once due_at passes while still
+-- open it is marked missed and its penalty (if any) is deducted.
+CREATE TABLE IF NOT EXISTS chore_instances (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  chore_id TEXT NOT NULL,
+  child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+  available_from TIMESTAMP NOT NULL,
+  due_at TIMESTAMP NOT NULL,
+  penalty_minutes INTEGER NOT NULL DEFAULT 0,
+  status TEXT NOT NULL DEFAULT 'open',
+  completed_at TIMESTAMP,
+  UNIQUE (chore_id, due_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_chore_instances_child_task ON chore_instances(child_id, task_id, status);
+CREATE INDEX IF NOT EXISTS idx_chore_instances_due ON chore_instances(status, due_at);
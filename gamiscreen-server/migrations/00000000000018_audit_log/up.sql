@@ -0,0 +1,21 @@
+-- Append-only record of state-changing actions and who performed them.
+-- child_id has no foreign key so entries outlive removed children.
+CREATE TABLE IF NOT EXISTS audit_log (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  actor TEXT NOT NULL,
+  role TEXT NOT NULL,
+  -- x-request-id of the HTTP request that performed the action
+  request_id TEXT,
+  action TEXT NOT NULL,
+  child_id TEXT,
+  -- Id of the affected row (submission, transfer, time request, device)
+  target_id TEXT,
+  -- JSON-encoded values before and after the action
+  before TEXT,
+  after TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created ON audit_log(created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor, created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_log_child ON audit_log(child_id, created_at);
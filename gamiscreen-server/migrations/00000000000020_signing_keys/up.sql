@@ -0,0 +1,10 @@
+-- Asymmetric JWT signing keys. The key without `retired_at` signs new
+-- tokens; retired keys keep verifying until the tokens they signed expire.
+CREATE TABLE IF NOT EXISTS signing_keys (
+  kid TEXT PRIMARY KEY NOT NULL,
+  alg TEXT NOT NULL,
+  -- PKCS#8 DER private key
+  private_key BLOB NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  retired_at TIMESTAMP
+);
@@ -0,0 +1,21 @@
+-- TOTP second factor for parent logins. A row without `enabled_at` is an
+-- enrollment that has not been confirmed with a code yet.
+CREATE TABLE IF NOT EXISTS totp_credentials (
+  username TEXT PRIMARY KEY NOT NULL,
+  secret BLOB NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  enabled_at TIMESTAMP,
+  -- Time step of the last accepted code, so a code cannot be replayed
+  last_step BIGINT
+);
+
+-- One-time recovery codes, stored as SHA-256 hex digests.
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  username TEXT NOT NULL REFERENCES totp_credentials(username) ON DELETE CASCADE,
+  code_hash TEXT NOT NULL,
+  used_at TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_totp_recovery_codes_username
+  ON totp_recovery_codes(username);
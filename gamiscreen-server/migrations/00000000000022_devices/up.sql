@@ -0,0 +1,22 @@
+-- Devices registered for a child. Registrations a child starts wait for a
+-- parent ('pending'); parents approve or deny them and can block a device id.
+CREATE TABLE IF NOT EXISTS devices (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  child_id TEXT NOT NULL REFERENCES children(id) ON DELETE CASCADE,
+  device_id TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending',
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  resolved_by TEXT,
+  resolved_at TIMESTAMP,
+  UNIQUE (child_id, device_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_devices_status ON devices(status);
+
+-- Devices that already reported usage were registered before approvals existed.
+INSERT OR IGNORE INTO devices (child_id, device_id, status, resolved_at)
+  SELECT DISTINCT child_id, device_id, 'approved', CURRENT_TIMESTAMP FROM usage_minutes;
+
+-- Device a session's token is bound to, so blocking a device can end its
+-- sessions. Filled in on first use for sessions created before this column.
+ALTER TABLE sessions ADD COLUMN device_id TEXT;
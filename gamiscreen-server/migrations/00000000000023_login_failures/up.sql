@@ -0,0 +1,21 @@
+-- Failed logins of configured users, kept for review.
+CREATE TABLE IF NOT EXISTS login_failures (
+  id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+  username TEXT NOT NULL,
+  ip TEXT NOT NULL,
+  -- 'password' or 'totp'
+  reason TEXT NOT NULL,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_failures_created_at ON login_failures(created_at);
+
+-- Failures in a row per account. Reaching the configured threshold locks the
+-- account; `lockouts` counts the locks since the last successful login, and
+-- each one lasts twice as long as the previous.
+CREATE TABLE IF NOT EXISTS login_lockouts (
+  username TEXT PRIMARY KEY NOT NULL,
+  failed_attempts INTEGER NOT NULL DEFAULT 0,
+  lockouts INTEGER NOT NULL DEFAULT 0,
+  locked_until TIMESTAMP
+);
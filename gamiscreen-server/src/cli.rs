@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 const HELP_EPILOG: &str = r#"Server options can also be provided via environment variables:
   CONFIG_PATH (default: ./config.yaml)
@@ -8,7 +8,24 @@ const HELP_EPILOG: &str = r#"Server options can also be provided via environment
   PORT        (default: 5151 or config.listen_port)
 
 The `install` command helps set up a systemd service and a default config.
-Run it as root (or with sudo) for system-wide install.
+Run it as root (or with sudo) for system-wide install. With --interactive it
+asks for the first parent user and whether to enable Web Push, and writes a
+config the server can start with.
+
+`gen vapid` prints a new VAPID key pair for the `push` section, and
+`gen password-hash` prints a bcrypt hash for a user's `password_hash`
+(prompted twice, or read from stdin when piped).
+
+`keys rotate` works on the database at DB_PATH; a running server picks up
+the new key within a minute.
+
+`config check [path]` reports errors and warnings in a config file without
+starting the server, and exits with status 1 when there are errors.
+`config schema` prints a JSON Schema of config.yaml for editors.
+
+`totp reset <username>` turns off two-factor authentication for a parent who
+lost their authenticator and recovery codes; they can log in with just the
+password and enroll again.
 "#;
 
 #[derive(Debug, Parser)]
@@ -53,6 +70,10 @@ pub enum Command {
         /// Overwrite files if they already exist
         #[arg(long)]
         force: bool,
+        /// Ask for the parent user and push settings instead of writing the
+        /// example config
+        #[arg(long, short)]
+        interactive: bool,
     },
     /// Uninstall systemd unit; optionally remove config
     Uninstall {
@@ -66,4 +87,80 @@ pub enum Command {
         #[arg(long, default_value = "/etc/gamiscreen/config.yaml")]
         config_path: PathBuf,
     },
+    /// Check the config file or print its schema
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Generate values to put in config.yaml
+    Gen {
+        #[command(subcommand)]
+        command: GenCommand,
+    },
+    /// Manage the keys JWTs are signed with
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+    /// Manage two-factor authentication of users
+    Totp {
+        #[command(subcommand)]
+        command: TotpCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Report errors and warnings in a config file
+    Check {
+        /// Config file to check (defaults to CONFIG_PATH or ./config.yaml)
+        path: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of config.yaml
+    Schema,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GenCommand {
+    /// Print a new VAPID key pair for Web Push
+    Vapid,
+    /// Print a bcrypt hash of a password for `password_hash`
+    PasswordHash,
+    /// Write a self-signed certificate and key for the `tls` section, for
+    /// servers only reachable on the local network
+    SelfSigned {
+        /// Host names and IP addresses the certificate is valid for
+        #[arg(long = "name", default_values_t = ["localhost".to_string(), "127.0.0.1".to_string()])]
+        names: Vec<String>,
+        /// Directory to write cert.pem and key.pem to
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TotpCommand {
+    /// Remove a user's TOTP secret and recovery codes, so they can log in
+    /// with just their password
+    Reset {
+        /// Username as configured in config.yaml
+        username: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum KeysCommand {
+    /// Create a new signing key and retire the current one; tokens it signed
+    /// stay valid until they expire
+    Rotate {
+        /// Algorithm of the new key
+        #[arg(long, value_enum, default_value_t = KeyAlgorithm::Eddsa)]
+        alg: KeyAlgorithm,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum KeyAlgorithm {
+    Eddsa,
+    Es256,
 }
@@ -0,0 +1,122 @@
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rand_core::OsRng;
+
+/// A VAPID key pair for `push.vapid_public` / `push.vapid_private`.
+#[derive(serde::Serialize)]
+pub struct VapidKeys {
+    pub public: String,
+    pub private: String,
+}
+
+/// Generate a P-256 key pair, encoded the way Web Push expects: the
+/// uncompressed public point and the raw private scalar, both base64url.
+pub fn vapid_keys() -> VapidKeys {
+    let secret = p256::SecretKey::random(&mut OsRng);
+    let public = secret.public_key().to_encoded_point(false);
+    VapidKeys {
+        public: URL_SAFE_NO_PAD.encode(public.as_bytes()),
+        private: URL_SAFE_NO_PAD.encode(secret.to_bytes()),
+    }
+}
+
+pub fn password_hash(password: &str) -> Result<String, String> {
+    bcrypt::hash(password, bcrypt::DEFAULT_COST).map_err(|e| format!("hash password: {e}"))
+}
+
+/// Write a self-signed certificate for `names` and its private key to
+/// `cert.pem` and `key.pem` in `dir`. Existing files are not overwritten.
+pub fn self_signed(names: &[String], dir: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let cert = rcgen::generate_simple_self_signed(names.to_vec())
+        .map_err(|e| format!("generate certificate: {e}"))?;
+    std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    for path in [&cert_path, &key_path] {
+        if path.exists() {
+            return Err(format!("{} already exists", path.display()));
+        }
+    }
+    std::fs::write(&cert_path, cert.cert.pem())
+        .map_err(|e| format!("write {}: {e}", cert_path.display()))?;
+    write_private(&key_path, &cert.signing_key.serialize_pem())?;
+    Ok((cert_path, key_path))
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut f| f.write_all(contents.as_bytes()))
+        .map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &str) -> Result<(), String> {
+    std::fs::write(path, contents).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+/// Read a new password: prompted twice without echo on a terminal, or a
+/// single line from stdin when piped.
+pub fn read_new_password(label: &str) -> Result<String, String> {
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| format!("read password: {e}"))?;
+        let password = line.trim_end_matches(['\r', '\n']).to_string();
+        if password.is_empty() {
+            return Err("empty password on stdin".into());
+        }
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password(format!("{label}: "))
+        .map_err(|e| format!("read password: {e}"))?;
+    if password.is_empty() {
+        return Err("password must not be empty".into());
+    }
+    let repeated = rpassword::prompt_password(format!("Repeat {}: ", label.to_lowercase()))
+        .map_err(|e| format!("read password: {e}"))?;
+    if password != repeated {
+        return Err("passwords do not match".into());
+    }
+    Ok(password)
+}
+
+/// Ask for a line of input, falling back to `default` when it is left empty.
+pub fn prompt(msg: &str, default: &str) -> Result<String, String> {
+    if default.is_empty() {
+        print!("{msg}: ");
+    } else {
+        print!("{msg} [{default}]: ");
+    }
+    let _ = std::io::stdout().flush();
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("read input: {e}"))?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() {
+        default.to_string()
+    } else {
+        answer.to_string()
+    })
+}
+
+pub fn prompt_yes_no(msg: &str) -> Result<bool, String> {
+    let answer = prompt(&format!("{msg} [y/N]"), "")?;
+    Ok(matches!(
+        answer.chars().next().map(|c| c.to_ascii_lowercase()),
+        Some('y')
+    ))
+}
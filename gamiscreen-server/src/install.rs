@@ -4,24 +4,12 @@ use std::path::Path;
 
 use tinytemplate::TinyTemplate;
 
+use crate::generate::{self, VapidKeys};
+
 const EXAMPLE_CONFIG: &str = include_str!("../config.yaml.example");
+const INSTALL_CONFIG_TEMPLATE: &str = include_str!("../config.install.yaml");
 const UNIT_TEMPLATE: &str = include_str!("../systemd/gamiscreen-server.service");
 
-fn generate_secret() -> String {
-    // Use UUIDv4 as a simple random secret seed
-    uuid::Uuid::new_v4().to_string()
-}
-
-fn render_default_config() -> String {
-    let secret = generate_secret();
-    // Replace the placeholder secret if present; otherwise append/patch minimally
-    if EXAMPLE_CONFIG.contains("change-this-to-a-long-random-secret") {
-        EXAMPLE_CONFIG.replace("change-this-to-a-long-random-secret", &secret)
-    } else {
-        EXAMPLE_CONFIG.to_string()
-    }
-}
-
 #[derive(serde::Serialize)]
 struct UnitCtx<'a> {
     binary_path: &'a str,
@@ -39,6 +27,55 @@ fn render_unit(ctx: &UnitCtx) -> Result<String, String> {
     tt.render("unit", ctx).map_err(|e| format!("render: {e}"))
 }
 
+#[derive(serde::Serialize)]
+struct ConfigCtx {
+    config_version: &'static str,
+    parent_username: String,
+    parent_password_hash: String,
+    push: Option<VapidKeys>,
+    contact_email: String,
+}
+
+/// Ask for the initial parent user and whether to turn on Web Push.
+fn prompt_config() -> Result<ConfigCtx, String> {
+    println!("Setting up the initial parent user.");
+    let parent_username = generate::prompt("Parent username", "parent")?;
+    if parent_username.is_empty() {
+        return Err("username must not be empty".into());
+    }
+    let password = generate::read_new_password("Password")?;
+    let parent_password_hash = generate::password_hash(&password)?;
+    let push =
+        generate::prompt_yes_no("Enable Web Push notifications?")?.then(generate::vapid_keys);
+    let mut contact_email = String::new();
+    if push.is_some() {
+        contact_email = generate::prompt("Contact email for push services (optional)", "")?;
+        if !contact_email.is_empty() && !contact_email.starts_with("mailto:") {
+            contact_email = format!("mailto:{contact_email}");
+        }
+    }
+    Ok(ConfigCtx {
+        config_version: env!("CARGO_PKG_VERSION"),
+        parent_username,
+        parent_password_hash,
+        push,
+        contact_email,
+    })
+}
+
+fn render_config(ctx: &ConfigCtx) -> Result<String, String> {
+    let mut tt = TinyTemplate::new();
+    tt.set_default_formatter(&tinytemplate::format_unescaped);
+    // A JSON string is a valid double-quoted YAML scalar
+    tt.add_formatter("quoted", |value, out| {
+        out.push_str(&value.to_string());
+        Ok(())
+    });
+    tt.add_template("config", INSTALL_CONFIG_TEMPLATE)
+        .map_err(|e| format!("template: {e}"))?;
+    tt.render("config", ctx).map_err(|e| format!("render: {e}"))
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn install_system(
     unit_path: &Path,
@@ -49,6 +86,7 @@ pub fn install_system(
     group: &str,
     working_dir: &Path,
     force: bool,
+    interactive: bool,
 ) -> Result<(), String> {
     // Ensure dirs
     if let Some(dir) = config_path.parent() {
@@ -68,10 +106,14 @@ pub fn install_system(
             config_path.display()
         );
     } else {
-        let cfg = render_default_config();
+        let config_txt = if interactive {
+            render_config(&prompt_config()?)?
+        } else {
+            EXAMPLE_CONFIG.to_string()
+        };
         let mut f = fs::File::create(config_path)
             .map_err(|e| format!("write {}: {}", config_path.display(), e))?;
-        f.write_all(cfg.as_bytes())
+        f.write_all(config_txt.as_bytes())
             .map_err(|e| format!("write {}: {}", config_path.display(), e))?;
         #[cfg(unix)]
         {
@@ -133,3 +175,55 @@ pub fn uninstall_system(
     println!("Run: sudo systemctl daemon-reload && sudo systemctl disable --now gamiscreen-server");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use gamiscreen_server::server::{AppConfig, IssueSeverity};
+
+    use super::*;
+
+    fn parse(ctx: &ConfigCtx) -> AppConfig {
+        let text = render_config(ctx).expect("render");
+        let config: AppConfig = serde_yaml::from_str(&text).expect("parse");
+        let errors: Vec<_> = config
+            .check()
+            .into_iter()
+            .filter(|issue| issue.severity == IssueSeverity::Error)
+            .collect();
+        assert!(errors.is_empty(), "{errors:?}");
+        config
+    }
+
+    #[test]
+    fn renders_a_valid_config_with_push() {
+        let config = parse(&ConfigCtx {
+            config_version: env!("CARGO_PKG_VERSION"),
+            parent_username: "mum \"admin\"".into(),
+            parent_password_hash: "$2b$04$abcdefghijklmnopqrstuu5CrrJc3Fv/ul4fDb3pTGvnxhZJ3KVB6"
+                .into(),
+            push: Some(generate::vapid_keys()),
+            contact_email: "mailto:admin@example.com".into(),
+        });
+        assert_eq!(config.users[0].username, "mum \"admin\"");
+        let push = config.push.expect("push section");
+        assert!(push.enabled);
+        assert_eq!(
+            push.contact_email.as_deref(),
+            Some("mailto:admin@example.com")
+        );
+    }
+
+    #[test]
+    fn renders_a_valid_config_without_push() {
+        let config = parse(&ConfigCtx {
+            config_version: env!("CARGO_PKG_VERSION"),
+            parent_username: "parent".into(),
+            parent_password_hash: "$2b$04$abcdefghijklmnopqrstuu5CrrJc3Fv/ul4fDb3pTGvnxhZJ3KVB6"
+                .into(),
+            push: None,
+            contact_email: String::new(),
+        });
+        assert!(!config.push.expect("push section").enabled);
+        assert!(config.children.is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 use gamiscreen_server::{server, storage};
 mod cli;
+mod generate;
 mod install;
 
 use std::net::SocketAddr;
 
+use gamiscreen_shared::jwt::SigningAlgorithm;
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
@@ -21,6 +23,7 @@ async fn main() {
                 group,
                 working_dir,
                 force,
+                interactive,
             } => {
                 let bin = bin_path.unwrap_or_else(|| {
                     std::env::current_exe().unwrap_or_else(|_| {
@@ -36,6 +39,7 @@ async fn main() {
                     &group,
                     &working_dir,
                     force,
+                    interactive,
                 ) {
                     eprintln!("Install error: {}", e);
                     std::process::exit(2);
@@ -53,6 +57,97 @@ async fn main() {
                 }
                 return;
             }
+            cli::Command::Config {
+                command: cli::ConfigCommand::Check { path },
+            } => {
+                let path = path.unwrap_or_else(server::AppConfig::path);
+                match check_config(&path) {
+                    Ok(true) => {}
+                    Ok(false) => std::process::exit(1),
+                    Err(server::ConfigError::Io(e)) => {
+                        eprintln!("Config check error: {}: {}", path.display(), e);
+                        std::process::exit(2);
+                    }
+                    Err(e) => {
+                        println!("error: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+            cli::Command::Config {
+                command: cli::ConfigCommand::Schema,
+            } => {
+                let schema = server::AppConfig::json_schema();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&schema).expect("schema serializes")
+                );
+                return;
+            }
+            cli::Command::Gen {
+                command: cli::GenCommand::Vapid,
+            } => {
+                let keys = generate::vapid_keys();
+                println!("push:");
+                println!("  enabled: true");
+                println!("  vapid_public: \"{}\"", keys.public);
+                println!("  vapid_private: \"{}\"", keys.private);
+                return;
+            }
+            cli::Command::Gen {
+                command: cli::GenCommand::PasswordHash,
+            } => {
+                match generate::read_new_password("Password")
+                    .and_then(|password| generate::password_hash(&password))
+                {
+                    Ok(hash) => println!("{hash}"),
+                    Err(e) => {
+                        eprintln!("Password hash error: {}", e);
+                        std::process::exit(2);
+                    }
+                }
+                return;
+            }
+            cli::Command::Gen {
+                command: cli::GenCommand::SelfSigned { names, out_dir },
+            } => {
+                match generate::self_signed(&names, &out_dir) {
+                    Ok((cert_path, key_path)) => {
+                        println!("tls:");
+                        println!("  enabled: true");
+                        println!("  cert_path: \"{}\"", cert_path.display());
+                        println!("  key_path: \"{}\"", key_path.display());
+                    }
+                    Err(e) => {
+                        eprintln!("Certificate error: {}", e);
+                        std::process::exit(2);
+                    }
+                }
+                return;
+            }
+            cli::Command::Keys {
+                command: cli::KeysCommand::Rotate { alg },
+            } => {
+                let alg = match alg {
+                    cli::KeyAlgorithm::Eddsa => SigningAlgorithm::EdDSA,
+                    cli::KeyAlgorithm::Es256 => SigningAlgorithm::ES256,
+                };
+                if let Err(e) = rotate_keys(alg).await {
+                    eprintln!("Key rotation error: {}", e);
+                    std::process::exit(2);
+                }
+                return;
+            }
+            cli::Command::Totp {
+                command: cli::TotpCommand::Reset { username },
+            } => {
+                if let Err(e) = reset_totp(&username).await {
+                    eprintln!("TOTP reset error: {}", e);
+                    std::process::exit(2);
+                }
+                return;
+            }
         }
     }
     // Console-only logging with env-driven level
@@ -63,7 +158,8 @@ async fn main() {
         .with_ansi(true)
         .init();
 
-    let config = match server::AppConfig::load() {
+    let config_path = server::AppConfig::path();
+    let config = match server::AppConfig::load_from_path(&config_path) {
         Ok(c) => c,
         Err(e) => {
             tracing::error!(error=%e, "Failed to load config");
@@ -72,13 +168,7 @@ async fn main() {
     };
 
     // Connect storage (SQLite via SeaORM)
-    let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "data/app.db".into());
-    // Ensure data dir exists when using default
-    if let Some(parent) = std::path::Path::new(&db_path).parent()
-        && !parent.as_os_str().is_empty()
-    {
-        let _ = std::fs::create_dir_all(parent);
-    }
+    let db_path = db_path();
     let store = match storage::Store::connect_sqlite(&db_path).await {
         Ok(s) => s,
         Err(e) => {
@@ -103,28 +193,59 @@ async fn main() {
         .or(config.listen_port)
         .unwrap_or(5151);
 
-    let state = server::AppState::new(config, store);
+    let tls = config.tls_config().cloned();
+    let certs = match &tls {
+        Some(tls) => match server::tls::Certificates::load(tls).await {
+            Ok(certs) => Some(certs),
+            Err(e) => {
+                tracing::error!(error=%e, "Failed to load TLS certificate");
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    let state = server::AppState::new(config, store).with_config_path(config_path);
+    server::spawn_background_jobs(&state);
+    #[cfg(unix)]
+    spawn_reload_on_sighup(state.clone(), certs.clone());
     let shutdown_token = state.shutdown_token();
     let shutdown_token_for_server = shutdown_token.clone();
+    if let Some(certs) = &certs {
+        certs.spawn_watch(shutdown_token.clone());
+    }
 
     let app = server::router(state);
 
     let addr: SocketAddr = ([0, 0, 0, 0], port).into();
-    tracing::info!(%addr, "Starting server");
-
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .expect("bind listener");
+    tracing::info!(%addr, tls = certs.is_some(), "Starting server");
 
     // Graceful shutdown on SIGINT/SIGTERM with fallback timeout to force-close long-lived connections (e.g., SSE)
-    let mut server_task = tokio::spawn(async move {
-        axum::serve(
-            listener,
-            app.into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .with_graceful_shutdown(shutdown_token_for_server.cancelled_owned())
-        .await
-    });
+    let mut server_task = match certs {
+        Some(certs) => {
+            let redirect_http_port = tls.and_then(|t| t.redirect_http_port);
+            tokio::spawn(server::tls::serve(
+                addr,
+                app,
+                certs,
+                redirect_http_port,
+                shutdown_token_for_server,
+            ))
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr)
+                .await
+                .expect("bind listener");
+            tokio::spawn(async move {
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(shutdown_token_for_server.cancelled_owned())
+                .await
+            })
+        }
+    };
 
     // Wait for OS signal; then trigger graceful, and if it hangs beyond timeout, force abort.
     shutdown_signal().await;
@@ -144,6 +265,113 @@ async fn main() {
     }
 }
 
+fn db_path() -> String {
+    let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "data/app.db".into());
+    // Ensure data dir exists when using default
+    if let Some(parent) = std::path::Path::new(&db_path).parent()
+        && !parent.as_os_str().is_empty()
+    {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    db_path
+}
+
+async fn rotate_keys(alg: SigningAlgorithm) -> Result<(), String> {
+    let store = storage::Store::connect_sqlite(&db_path())
+        .await
+        .map_err(|e| e.to_string())?;
+    let kid = server::keys::rotate(&store, alg)
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("New signing key: {kid} ({alg})");
+    for key in server::keys::list(&store)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        let state = match key.retired_at {
+            Some(at) => format!("retired {}", at.format("%Y-%m-%d %H:%M")),
+            None => "active".to_string(),
+        };
+        println!("  {}  {:<6} {}", key.kid, key.alg, state);
+    }
+    Ok(())
+}
+
+/// Print the issues of the config at `path`; false if any is an error.
+fn check_config(path: &std::path::Path) -> Result<bool, server::ConfigError> {
+    let issues = server::AppConfig::check_file(path)?;
+    let mut errors = 0;
+    for issue in &issues {
+        let label = match issue.severity {
+            server::IssueSeverity::Error => {
+                errors += 1;
+                "error"
+            }
+            server::IssueSeverity::Warning => "warning",
+        };
+        println!("{label}: {issue}");
+    }
+    let warnings = issues.len() - errors;
+    if issues.is_empty() {
+        println!("{}: OK", path.display());
+    } else {
+        println!(
+            "{}: {errors} error(s), {warnings} warning(s)",
+            path.display()
+        );
+    }
+    Ok(errors == 0)
+}
+
+async fn reset_totp(username: &str) -> Result<(), String> {
+    let store = storage::Store::connect_sqlite(&db_path())
+        .await
+        .map_err(|e| e.to_string())?;
+    if store
+        .delete_totp(username)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        println!("Two-factor authentication turned off for {username}");
+    } else {
+        println!("{username} has no two-factor authentication set up");
+    }
+    Ok(())
+}
+
+/// Reload the config file, and the TLS certificate if one is served,
+/// whenever the process receives SIGHUP.
+#[cfg(unix)]
+fn spawn_reload_on_sighup(state: server::AppState, certs: Option<server::tls::Certificates>) {
+    use tokio::signal::unix::{SignalKind, signal};
+    let mut sighup = signal(SignalKind::hangup()).expect("listen SIGHUP");
+    let shutdown = state.shutdown_token();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                received = sighup.recv() => if received.is_none() { break },
+            }
+            tracing::info!("config: received SIGHUP, reloading");
+            match state.reload_config_on_signal().await {
+                Ok(report) if !report.restart_required.is_empty() => tracing::warn!(
+                    restart_required = ?report.restart_required,
+                    "config: some changes need a restart"
+                ),
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!(error = %e, "config: reload failed, keeping the running config")
+                }
+            }
+            if let Some(certs) = &certs
+                && let Err(e) = certs.reload().await
+            {
+                tracing::error!(error = %e, "tls: reload failed, keeping the current certificate");
+            }
+        }
+    });
+}
+
 async fn shutdown_signal() {
     #[cfg(unix)]
     {
@@ -0,0 +1,156 @@
+//! Process-wide Prometheus metrics, rendered by the server's `/metrics`
+//! endpoint.
+
+use std::sync::LazyLock;
+
+use prometheus::{
+    Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+pub struct Metrics {
+    registry: Registry,
+    /// Heartbeats received, by child and device.
+    pub heartbeats: IntCounterVec,
+    /// Minute grants and deductions, by `kind` (`grant` or `penalty`).
+    pub rewards: IntCounterVec,
+    /// Open SSE streams.
+    pub sse_connections: IntGauge,
+    /// Connected device agent WebSockets.
+    pub agent_connections: IntGauge,
+    /// Web Push sends, by `result` (`success` or `failure`).
+    pub push_deliveries: IntCounterVec,
+    /// Login attempts rejected by the per-IP rate limiter.
+    pub login_rate_limited: IntCounter,
+    /// Token renewals and device registrations rejected by the per-IP rate
+    /// limiter.
+    pub token_rate_limited: IntCounter,
+    /// Duration of individual SQL statements.
+    pub db_query_seconds: Histogram,
+    /// Effective remaining minutes per child, refreshed on every scrape.
+    pub remaining_minutes: IntGaugeVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The shared metrics instance.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("gamiscreen".to_string()), None).expect("valid prefix");
+        let heartbeats = IntCounterVec::new(
+            Opts::new("heartbeats_total", "Heartbeats received from devices"),
+            &["child_id", "device_id"],
+        )
+        .expect("valid metric");
+        let rewards = IntCounterVec::new(
+            Opts::new("rewards_total", "Minute grants and penalties applied"),
+            &["kind"],
+        )
+        .expect("valid metric");
+        let sse_connections = IntGauge::new("sse_connections", "Open server-sent event streams")
+            .expect("valid metric");
+        let agent_connections =
+            IntGauge::new("agent_connections", "Connected device agent WebSockets")
+                .expect("valid metric");
+        let push_deliveries = IntCounterVec::new(
+            Opts::new("push_deliveries_total", "Web Push delivery attempts"),
+            &["result"],
+        )
+        .expect("valid metric");
+        let login_rate_limited = IntCounter::new(
+            "login_rate_limited_total",
+            "Login attempts rejected by the rate limiter",
+        )
+        .expect("valid metric");
+        let token_rate_limited = IntCounter::new(
+            "token_rate_limited_total",
+            "Token renewals and device registrations rejected by the rate limiter",
+        )
+        .expect("valid metric");
+        let db_query_seconds = Histogram::with_opts(
+            HistogramOpts::new("db_query_duration_seconds", "SQL statement duration").buckets(
+                vec![
+                    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0,
+                ],
+            ),
+        )
+        .expect("valid metric");
+        let remaining_minutes = IntGaugeVec::new(
+            Opts::new(
+                "child_remaining_minutes",
+                "Effective remaining minutes per child",
+            ),
+            &["child_id"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(heartbeats.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(rewards.clone()),
+            Box::new(sse_connections.clone()),
+            Box::new(agent_connections.clone()),
+            Box::new(push_deliveries.clone()),
+            Box::new(login_rate_limited.clone()),
+            Box::new(token_rate_limited.clone()),
+            Box::new(db_query_seconds.clone()),
+            Box::new(remaining_minutes.clone()),
+        ] {
+            registry.register(collector).expect("unique metric");
+        }
+
+        Self {
+            registry,
+            heartbeats,
+            rewards,
+            sse_connections,
+            agent_connections,
+            push_deliveries,
+            login_rate_limited,
+            token_rate_limited,
+            db_query_seconds,
+            remaining_minutes,
+        }
+    }
+
+    /// Count a change of `minutes` as a grant or, when negative, a penalty.
+    pub fn record_reward(&self, minutes: i32) {
+        let kind = if minutes < 0 { "penalty" } else { "grant" };
+        self.rewards.with_label_values(&[kind]).inc();
+    }
+
+    pub fn record_push_delivery(&self, success: bool) {
+        let result = if success { "success" } else { "failure" };
+        self.push_deliveries.with_label_values(&[result]).inc();
+    }
+
+    /// Render all metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_else(|e| {
+                tracing::error!(error = %e, "metrics: failed to encode");
+                String::new()
+            })
+    }
+}
+
+/// Keeps a gauge incremented for as long as it is alive.
+pub struct GaugeGuard(IntGauge);
+
+impl GaugeGuard {
+    pub fn new(gauge: &IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge.clone())
+    }
+}
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
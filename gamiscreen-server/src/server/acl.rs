@@ -26,19 +26,15 @@ pub async fn enforce_acl(
     let claims = &auth.claims;
 
     let segs = segmented(&path);
-    let tenant_prefix = ["api", "v1", "family", state.config.tenant_id.as_str()];
+    let config = state.config();
+    let tenant_prefix = ["api", "v1", "family", config.tenant_id.as_str()];
     if !segs.as_slice().starts_with(&tenant_prefix) {
         tracing::warn!(?segs, "ACL: path outside tenant scope");
         return Err(AppError::forbidden());
     }
     let rest = &segs[tenant_prefix.len()..];
 
-    let decision = match claims.role {
-        Role::Parent => allow_parent(&method, rest),
-        Role::Child => allow_child(&method, rest, claims),
-    };
-
-    if let Err(err) = decision {
+    if let Err(err) = authorize(claims, &method, rest) {
         tracing::warn!(
             method = %method,
             path = %path,
@@ -54,12 +50,33 @@ pub async fn enforce_acl(
     Ok(next.run(req).await)
 }
 
+/// Decide whether `claims` may call `method` on the tenant-relative path
+/// segments `rest`. Also applied to commands arriving over MQTT.
+pub(super) fn authorize(
+    claims: &JwtClaims,
+    method: &Method,
+    rest: &[&str],
+) -> Result<(), AppError> {
+    match claims.role {
+        Role::Parent => allow_parent(method, rest),
+        Role::Child => allow_child(method, rest, claims),
+    }
+}
+
 fn allow_parent(method: &Method, rest: &[&str]) -> Result<(), AppError> {
     match rest {
         ["children"] if *method == Method::GET => Ok(()),
         ["tasks"] if *method == Method::GET => Ok(()),
         ["notifications"] if *method == Method::GET => Ok(()),
         ["notifications", "count"] if *method == Method::GET => Ok(()),
+        ["notifications", "read-all"] if *method == Method::POST => Ok(()),
+        ["notifications", id, action]
+            if *method == Method::POST
+                && (action == &"read" || action == &"dismiss")
+                && id.parse::<i32>().is_ok() =>
+        {
+            Ok(())
+        }
         ["notifications", "task-submissions", id, action]
             if *method == Method::POST
                 && (action == &"approve" || action == &"discard")
@@ -67,16 +84,64 @@ fn allow_parent(method: &Method, rest: &[&str]) -> Result<(), AppError> {
         {
             Ok(())
         }
+        ["notifications", "transfers", id, action]
+            if *method == Method::POST
+                && (action == &"approve" || action == &"discard")
+                && id.parse::<i32>().is_ok() =>
+        {
+            Ok(())
+        }
+        ["notifications", "time-requests", id, action]
+            if *method == Method::POST
+                && (action == &"approve" || action == &"discard")
+                && id.parse::<i32>().is_ok() =>
+        {
+            Ok(())
+        }
+        ["notifications", "devices", id, action]
+            if *method == Method::POST
+                && (action == &"approve" || action == &"discard")
+                && id.parse::<i32>().is_ok() =>
+        {
+            Ok(())
+        }
+        ["attachments", id] if *method == Method::GET && id.parse::<i32>().is_ok() => Ok(()),
+        ["audit"] if *method == Method::GET => Ok(()),
+        ["config", "reload"] if *method == Method::POST => Ok(()),
+        ["webhooks"] if *method == Method::GET => Ok(()),
+        ["webhooks", _, "deliveries"] if *method == Method::GET => Ok(()),
+        ["webhooks", _, "test"] if *method == Method::POST => Ok(()),
         ["children", _, "remaining"] if *method == Method::GET => Ok(()),
         ["children", _, "usage"] if *method == Method::GET => Ok(()),
         ["children", _, "reward"] if *method == Method::GET || *method == Method::POST => Ok(()),
         ["children", _, "tasks"] if *method == Method::GET => Ok(()),
+        ["children", _, "submissions"] if *method == Method::GET => Ok(()),
+        ["children", _, "transfers"] if *method == Method::POST => Ok(()),
+        ["children", _, "time-requests"] if *method == Method::GET => Ok(()),
+        ["children", _, "notifications"] if *method == Method::GET => Ok(()),
+        ["children", _, "notifications", "count"] if *method == Method::GET => Ok(()),
+        ["children", _, "savings"] if *method == Method::GET => Ok(()),
+        ["children", _, "savings", "history"] if *method == Method::GET => Ok(()),
+        ["children", _, "savings", action]
+            if *method == Method::POST && (action == &"deposit" || action == &"withdraw") =>
+        {
+            Ok(())
+        }
         ["children", _, "register"] if *method == Method::POST => Ok(()),
+        ["children", _, "pairing-code"] if *method == Method::POST => Ok(()),
+        ["children", _, "devices"] if *method == Method::GET => Ok(()),
+        ["children", _, "devices", _] if *method == Method::GET => Ok(()),
+        ["children", _, "devices", _, action]
+            if *method == Method::POST && (action == &"block" || action == &"unblock") =>
+        {
+            Ok(())
+        }
         ["children", _, "push", "subscriptions"] if *method == Method::POST => Ok(()),
         ["children", _, "push", "subscriptions", "unsubscribe"] if *method == Method::POST => {
             Ok(())
         }
         ["config"] if *method == Method::GET => Ok(()),
+        ["sse", "ticket"] if *method == Method::POST => Ok(()),
         _ => Err(AppError::forbidden()),
     }
 }
@@ -87,15 +152,52 @@ fn allow_child(method: &Method, rest: &[&str], claims: &JwtClaims) -> Result<(),
         ["children", child, "remaining"] if *method == Method::GET => ensure_child(claims, child),
         ["children", child, "usage"] if *method == Method::GET => ensure_child(claims, child),
         ["children", child, "tasks"] if *method == Method::GET => ensure_child(claims, child),
+        ["children", child, "submissions"] if *method == Method::GET => ensure_child(claims, child),
         ["children", child, "reward"] if *method == Method::GET => ensure_child(claims, child),
+        ["children", child, "transfers"] if *method == Method::POST => ensure_child(claims, child),
+        ["children", child, "time-requests"]
+            if *method == Method::GET || *method == Method::POST =>
+        {
+            ensure_child(claims, child)
+        }
+        ["children", child, "notifications"] if *method == Method::GET => {
+            ensure_child(claims, child)
+        }
+        ["children", child, "notifications", "count"] if *method == Method::GET => {
+            ensure_child(claims, child)
+        }
+        ["children", child, "notifications", "read-all"] if *method == Method::POST => {
+            ensure_child(claims, child)
+        }
+        ["children", child, "notifications", id, action]
+            if *method == Method::POST
+                && (action == &"read" || action == &"dismiss")
+                && id.parse::<i32>().is_ok() =>
+        {
+            ensure_child(claims, child)
+        }
+        ["children", child, "savings"] if *method == Method::GET => ensure_child(claims, child),
+        ["children", child, "savings", "history"] if *method == Method::GET => {
+            ensure_child(claims, child)
+        }
+        ["children", child, "savings", action]
+            if *method == Method::POST && (action == &"deposit" || action == &"withdraw") =>
+        {
+            ensure_child(claims, child)
+        }
         ["children", child, "tasks", _, "submit"] if *method == Method::POST => {
             ensure_child(claims, child)
         }
         ["children", child, "register"] if *method == Method::POST => ensure_child(claims, child),
+        ["children", child, "devices", _] if *method == Method::GET => ensure_child(claims, child),
         ["children", child, "device", device, "heartbeat"] if *method == Method::POST => {
             ensure_child(claims, child)?;
             ensure_device(claims, device)
         }
+        ["children", child, "device", device, "ws"] if *method == Method::GET => {
+            ensure_child(claims, child)?;
+            ensure_device(claims, device)
+        }
         ["children", child, "push", "subscriptions"] if *method == Method::POST => {
             ensure_child(claims, child)
         }
@@ -103,6 +205,7 @@ fn allow_child(method: &Method, rest: &[&str], claims: &JwtClaims) -> Result<(),
             ensure_child(claims, child)
         }
         ["config"] if *method == Method::GET => Ok(()),
+        ["sse", "ticket"] if *method == Method::POST => validate_ws_access_from_claims(claims),
         _ => Err(AppError::forbidden()),
     }
 }
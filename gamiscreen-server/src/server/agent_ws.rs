@@ -0,0 +1,343 @@
+//! WebSocket channel for device agents: heartbeats in, the child's events and
+//! lock/unlock commands out, with server-driven ping/pong liveness.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Extension, Path, State};
+use axum::response::Response;
+use futures::stream::SplitSink;
+use futures::{SinkExt, StreamExt};
+use gamiscreen_shared::api::{
+    AgentCommand, AgentMessage, AgentServerMessage, NotificationAudience, NotificationPayload,
+    ServerEvent,
+};
+use gamiscreen_shared::jwt::JwtClaims;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{info, warn};
+
+use super::auth::AuthCtx;
+use super::{AppState, ChildDevicePath, event_visible_to, process_heartbeat};
+use crate::metrics;
+
+/// How often the server pings a connected agent.
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+/// An agent that sends nothing (not even a pong) for this long is dropped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(50);
+
+/// How long a dropped agent may take to reconnect before parents are told
+/// its device went offline.
+const OFFLINE_GRACE: Duration = Duration::from_secs(120);
+
+type Sender = SplitSink<WebSocket, Message>;
+
+/// Open agent sockets per child and device, with a generation bumped on
+/// each connect, so an offline check can tell whether the device came back.
+#[derive(Default)]
+pub(super) struct AgentConnections(Mutex<HashMap<(String, String), (usize, u64)>>);
+
+impl AgentConnections {
+    fn connect(&self, key: &(String, String)) {
+        let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let (open, generation) = map.entry(key.clone()).or_default();
+        *open += 1;
+        *generation += 1;
+    }
+
+    /// Returns the generation to pass to [`Self::still_offline`].
+    fn disconnect(&self, key: &(String, String)) -> u64 {
+        let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let (open, generation) = map.entry(key.clone()).or_default();
+        *open = open.saturating_sub(1);
+        *generation
+    }
+
+    /// True when nothing connected for `key` since the disconnect that
+    /// returned `generation`.
+    fn still_offline(&self, key: &(String, String), generation: u64) -> bool {
+        let mut map = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match map.get(key) {
+            Some((0, current)) if *current == generation => {
+                map.remove(key);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+pub(super) async fn agent_socket(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(p): Path<ChildDevicePath>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| async move {
+        let _connection = metrics::GaugeGuard::new(&metrics::metrics().agent_connections);
+        let key = (p.id.clone(), p.device_id.clone());
+        state.agents.connect(&key);
+        info!(child_id = %p.id, device_id = %p.device_id, "agent connected");
+        state.dispatch_event(ServerEvent::DeviceConnection {
+            child_id: p.id.clone(),
+            device_id: p.device_id.clone(),
+            connected: true,
+        });
+
+        let reason = serve(&state, &auth.claims, &p, socket).await;
+
+        info!(child_id = %p.id, device_id = %p.device_id, reason, "agent disconnected");
+        let generation = state.agents.disconnect(&key);
+        state.dispatch_event(ServerEvent::DeviceConnection {
+            child_id: p.id,
+            device_id: p.device_id,
+            connected: false,
+        });
+        // Parents know about shutdowns and blocked devices already
+        if !matches!(reason, "server shutting down" | "session revoked") {
+            spawn_offline_check(state, key, generation);
+        }
+    })
+}
+
+/// Notify parents if the agent has not reconnected after [`OFFLINE_GRACE`].
+fn spawn_offline_check(state: AppState, key: (String, String), generation: u64) {
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = state.shutdown.cancelled() => return,
+            _ = tokio::time::sleep(OFFLINE_GRACE) => {}
+        }
+        if !state.agents.still_offline(&key, generation) {
+            return;
+        }
+        let (child_id, device_id) = key;
+        info!(%child_id, %device_id, "agent offline");
+        state
+            .notify(
+                NotificationAudience::Parents,
+                &child_id,
+                NotificationPayload::DeviceOffline {
+                    child_id: child_id.clone(),
+                    device_id,
+                },
+                None,
+            )
+            .await;
+    });
+}
+
+/// Run one agent connection until it ends, returning why.
+async fn serve(
+    state: &AppState,
+    claims: &JwtClaims,
+    p: &ChildDevicePath,
+    socket: WebSocket,
+) -> &'static str {
+    let (mut tx, mut rx) = socket.split();
+    let mut events = state.events.subscribe();
+    let mut lock = LockState::default();
+
+    if send_status(state, &p.id, &mut tx, &mut lock).await.is_err() {
+        return "send failed";
+    }
+
+    let mut ping = tokio::time::interval_at(Instant::now() + PING_INTERVAL, PING_INTERVAL);
+    ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_seen = Instant::now();
+    // Close the socket when its session is revoked, e.g. the device is blocked.
+    let ended = state.session_ended(claims.jti.clone());
+    tokio::pin!(ended);
+
+    loop {
+        let sent = tokio::select! {
+            _ = state.shutdown.cancelled() => {
+                let _ = tx.send(Message::Close(None)).await;
+                return "server shutting down";
+            }
+            _ = &mut ended => {
+                let _ = tx.send(Message::Close(None)).await;
+                return "session revoked";
+            }
+            msg = rx.next() => {
+                let msg = match msg {
+                    None => return "connection closed",
+                    Some(Err(e)) => {
+                        warn!(error = %e, child_id = %p.id, device_id = %p.device_id, "agent socket error");
+                        return "socket error";
+                    }
+                    Some(Ok(msg)) => msg,
+                };
+                last_seen = Instant::now();
+                match msg {
+                    Message::Text(text) => {
+                        let reply = handle_text(state, p, text.as_str(), &mut lock).await;
+                        let mut result = Ok(());
+                        for msg in reply {
+                            result = send(&mut tx, &msg).await;
+                            if result.is_err() {
+                                break;
+                            }
+                        }
+                        result
+                    }
+                    Message::Close(_) => return "closed by agent",
+                    // Pings are answered by axum; pongs only refresh `last_seen`.
+                    _ => Ok(()),
+                }
+            }
+            ev = events.recv() => match ev {
+                Ok(item) if event_visible_to(claims, &item.event) => {
+                    forward_event(&mut tx, item.event, &mut lock).await
+                }
+                Ok(_) => Ok(()),
+                Err(RecvError::Lagged(missed)) => {
+                    warn!(missed, child_id = %p.id, "agent socket lagged; resending status");
+                    send_status(state, &p.id, &mut tx, &mut lock).await
+                }
+                Err(RecvError::Closed) => return "server shutting down",
+            },
+            _ = ping.tick() => {
+                if last_seen.elapsed() > IDLE_TIMEOUT {
+                    let _ = tx.send(Message::Close(None)).await;
+                    return "missed pongs";
+                }
+                tx.send(Message::Ping(Default::default())).await
+            }
+        };
+        if sent.is_err() {
+            return "send failed";
+        }
+    }
+}
+
+async fn handle_text(
+    state: &AppState,
+    p: &ChildDevicePath,
+    text: &str,
+    lock: &mut LockState,
+) -> Vec<AgentServerMessage> {
+    let msg = match serde_json::from_str::<AgentMessage>(text) {
+        Ok(msg) => msg,
+        Err(e) => {
+            return vec![AgentServerMessage::Error {
+                message: format!("invalid message: {e}"),
+            }];
+        }
+    };
+    match msg {
+        AgentMessage::Heartbeat { minutes } => {
+            match process_heartbeat(state, &p.id, &p.device_id, &minutes).await {
+                Ok(resp) => {
+                    let mut out = vec![AgentServerMessage::HeartbeatAck {
+                        minutes,
+                        remaining_minutes: resp.remaining_minutes,
+                        balance: resp.balance,
+                        blocked_by_tasks: resp.blocked_by_tasks,
+                    }];
+                    out.extend(lock.update(resp.remaining_minutes));
+                    out
+                }
+                Err(e) => {
+                    warn!(error = ?e, child_id = %p.id, device_id = %p.device_id, "agent heartbeat failed");
+                    vec![AgentServerMessage::Error {
+                        message: "heartbeat failed".into(),
+                    }]
+                }
+            }
+        }
+    }
+}
+
+/// Send the child's current state, as on connect or after missing events.
+async fn send_status(
+    state: &AppState,
+    child_id: &str,
+    tx: &mut Sender,
+    lock: &mut LockState,
+) -> Result<(), axum::Error> {
+    match state.compute_child_status(child_id).await {
+        Ok((remaining_minutes, balance, blocked_by_tasks)) => {
+            let event = ServerEvent::RemainingUpdated {
+                child_id: child_id.to_string(),
+                remaining_minutes,
+                balance,
+                blocked_by_tasks,
+            };
+            forward_event(tx, event, lock).await
+        }
+        Err(e) => {
+            warn!(error = ?e, child_id, "agent socket: failed to load child status");
+            Ok(())
+        }
+    }
+}
+
+async fn forward_event(
+    tx: &mut Sender,
+    event: ServerEvent,
+    lock: &mut LockState,
+) -> Result<(), axum::Error> {
+    let command = match &event {
+        ServerEvent::RemainingUpdated {
+            remaining_minutes, ..
+        } => lock.update(*remaining_minutes),
+        _ => None,
+    };
+    send(tx, &AgentServerMessage::Event { event }).await?;
+    if let Some(msg) = command {
+        send(tx, &msg).await?;
+    }
+    Ok(())
+}
+
+async fn send(tx: &mut Sender, msg: &AgentServerMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(msg).expect("agent message serializes");
+    tx.send(Message::Text(text.into())).await
+}
+
+/// Last lock state sent on this connection, so commands go out only on change.
+#[derive(Default)]
+struct LockState(Option<bool>);
+
+impl LockState {
+    fn update(&mut self, remaining_minutes: i32) -> Option<AgentServerMessage> {
+        let locked = remaining_minutes <= 0;
+        if self.0 == Some(locked) {
+            return None;
+        }
+        self.0 = Some(locked);
+        let command = if locked {
+            AgentCommand::Lock
+        } else {
+            AgentCommand::Unlock
+        };
+        Some(AgentServerMessage::Command { command })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_only_when_no_agent_reconnected() {
+        let agents = AgentConnections::default();
+        let key = ("alice".to_string(), "laptop".to_string());
+
+        agents.connect(&key);
+        let dropped = agents.disconnect(&key);
+        agents.connect(&key);
+        assert!(!agents.still_offline(&key, dropped), "reconnected");
+
+        let dropped = agents.disconnect(&key);
+        assert!(agents.still_offline(&key, dropped));
+
+        // A second socket of the same device keeps it online
+        agents.connect(&key);
+        agents.connect(&key);
+        let dropped = agents.disconnect(&key);
+        assert!(!agents.still_offline(&key, dropped));
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use axum::extract::{FromRequest, Multipart, Request};
+use axum::http::header;
+
+use super::{AppError, AppState};
+
+/// How often expired attachments are purged.
+const PURGE_TICK: Duration = Duration::from_secs(60 * 60);
+
+/// Multipart field carrying a photo.
+pub(super) const PHOTO_FIELD: &str = "photo";
+
+/// Detect the image type from its leading bytes.
+///
+/// The client-supplied content type is ignored so that only real images are
+/// stored and later served back to parents.
+fn sniff_image_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if data.len() >= 12
+        && &data[4..8] == b"ftyp"
+        && matches!(&data[8..12], b"heic" | b"heix" | b"mif1")
+    {
+        Some("image/heic")
+    } else {
+        None
+    }
+}
+
+/// Read the photos of a task submission.
+///
+/// Requests without a multipart body carry no photos; otherwise every part
+/// must be a `photo` field holding a JPEG, PNG, WebP or HEIC image within the
+/// configured size and count limits.
+pub async fn read_submission_photos(
+    state: &AppState,
+    request: Request,
+) -> Result<Vec<(&'static str, Vec<u8>)>, AppError> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("multipart/form-data"));
+    if !is_multipart {
+        return Ok(Vec::new());
+    }
+    let limits = state.config().attachments_config();
+    let mut multipart = Multipart::from_request(request, state)
+        .await
+        .map_err(|e| AppError::bad_request(e.body_text()))?;
+    let mut photos = Vec::new();
+    while let Some(mut field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::bad_request(e.body_text()))?
+    {
+        if field.name() != Some(PHOTO_FIELD) {
+            return Err(AppError::bad_request(format!(
+                "unexpected form field; photos must be sent as '{PHOTO_FIELD}'"
+            )));
+        }
+        if photos.len() >= limits.max_per_submission {
+            return Err(AppError::bad_request(format!(
+                "at most {} photos per submission",
+                limits.max_per_submission
+            )));
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(|e| AppError::bad_request(e.body_text()))?
+        {
+            if data.len() + chunk.len() > limits.max_bytes {
+                return Err(AppError::bad_request(format!(
+                    "photo exceeds {} bytes",
+                    limits.max_bytes
+                )));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if data.is_empty() {
+            return Err(AppError::bad_request("empty photo"));
+        }
+        let Some(content_type) = sniff_image_type(&data) else {
+            return Err(AppError::bad_request(
+                "unsupported photo type (use JPEG, PNG, WebP or HEIC)",
+            ));
+        };
+        photos.push((content_type, data));
+    }
+    Ok(photos)
+}
+
+/// Apply the retention policy to the photos of a resolved submission.
+pub async fn release_submission_photos(state: &AppState, submission_id: i32) {
+    let retention_days = state.config().attachments_config().retention_days;
+    let keep_until = (retention_days > 0).then(|| {
+        chrono::Utc::now().naive_utc() + chrono::Duration::days(i64::from(retention_days))
+    });
+    if let Err(e) = state
+        .store
+        .release_submission_attachments(submission_id, keep_until)
+        .await
+    {
+        tracing::error!(submission_id, error = %e, "attachments: failed to release photos");
+    }
+}
+
+/// Spawn the job deleting photos whose retention period has ended.
+pub fn spawn_purge_job(state: &AppState) {
+    let store = state.store.clone();
+    let shutdown = state.shutdown_token();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PURGE_TICK);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            let now = chrono::Utc::now().naive_utc();
+            match store.purge_expired_attachments(now).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!(count = n, "attachments: purged expired photos"),
+                Err(e) => tracing::error!(error = %e, "attachments: purge job failed"),
+            }
+        }
+    });
+}
@@ -0,0 +1,75 @@
+use gamiscreen_shared::auth::Role;
+use serde_json::{Value, json};
+
+use super::auth::AuthCtx;
+use super::{AppState, ReqId};
+use crate::storage::models::NewAuditEntry;
+
+/// Actor of entries recorded by [`record_system`].
+const SYSTEM_ACTOR: &str = "system";
+
+/// An action to record in the audit log.
+pub(super) struct AuditEvent<'a> {
+    /// Dotted action name, e.g. `"reward.grant"`.
+    pub action: &'static str,
+    pub child_id: Option<&'a str>,
+    pub target_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// Store an audit entry for an action performed by the authenticated caller.
+///
+/// Errors are logged rather than returned: the audited action has already
+/// been committed.
+pub(super) async fn record(
+    state: &AppState,
+    auth: &AuthCtx,
+    req_id: &ReqId,
+    event: AuditEvent<'_>,
+) {
+    let role = match auth.claims.role {
+        Role::Parent => "parent",
+        Role::Child => "child",
+    };
+    let entry = NewAuditEntry {
+        actor: auth.claims.sub.clone(),
+        role: role.to_string(),
+        request_id: Some(req_id.0.clone()),
+        action: event.action.to_string(),
+        child_id: event.child_id.map(str::to_string),
+        target_id: event.target_id,
+        before: event.before.map(|v| v.to_string()),
+        after: event.after.map(|v| v.to_string()),
+    };
+    if let Err(e) = state.store.record_audit(entry).await {
+        tracing::error!(error = %e, action = event.action, "audit: failed to record entry");
+    }
+}
+
+/// Store an audit entry for an action the server took without a request,
+/// such as a reload on SIGHUP. Only the host's administrator can trigger
+/// these, so they are recorded with parent authority as actor `system`.
+pub(super) async fn record_system(state: &AppState, event: AuditEvent<'_>) {
+    let entry = NewAuditEntry {
+        actor: SYSTEM_ACTOR.to_string(),
+        role: "parent".to_string(),
+        request_id: None,
+        action: event.action.to_string(),
+        child_id: event.child_id.map(str::to_string),
+        target_id: event.target_id,
+        before: event.before.map(|v| v.to_string()),
+        after: event.after.map(|v| v.to_string()),
+    };
+    if let Err(e) = state.store.record_audit(entry).await {
+        tracing::error!(error = %e, action = event.action, "audit: failed to record entry");
+    }
+}
+
+/// Stored remaining minutes and account balance of a child, for the
+/// before/after values of actions that move minutes.
+pub(super) async fn balance_snapshot(state: &AppState, child_id: &str) -> Option<Value> {
+    let remaining = state.store.get_remaining(child_id).await.ok()?;
+    let balance = state.store.get_balance(child_id).await.ok()?;
+    Some(json!({ "remaining_minutes": remaining, "balance": balance }))
+}
@@ -3,7 +3,7 @@ use axum::middleware::Next;
 use axum::response::Response;
 use chrono::{Duration, Utc};
 use gamiscreen_shared::auth::Role;
-use gamiscreen_shared::jwt::{self, JwtClaims};
+use gamiscreen_shared::jwt::JwtClaims;
 use tracing::{error, warn};
 
 use super::{AppError, AppState};
@@ -15,7 +15,7 @@ const USER_TOKEN_TTL_DAYS: i64 = 30;
 /// How many days of inactivity before a device session is considered expired.
 const DEVICE_SESSION_IDLE_DAYS: i64 = 30;
 /// How many days before mandatory re-login for devices.
-const DEVICE_TOKEN_TTL_DAYS: i64 = 2 * DEVICE_SESSION_IDLE_DAYS;
+pub(crate) const DEVICE_TOKEN_TTL_DAYS: i64 = 2 * DEVICE_SESSION_IDLE_DAYS;
 
 #[derive(Clone, Debug)]
 pub struct AuthCtx {
@@ -39,7 +39,7 @@ pub async fn require_bearer(
     }
     let token = &header_str[prefix.len()..];
 
-    let claims = match jwt::decode_and_verify(token, state.config.jwt_secret.as_bytes()) {
+    let claims = match state.keyring().await?.verify(token) {
         Ok(c) => c,
         Err(e) => {
             tracing::warn!(error=%e, "auth: jwt decode failed");
@@ -53,10 +53,10 @@ pub async fn require_bearer(
         AppError::unauthorized()
     })?;
 
-    if claims.tenant_id != state.config.tenant_id {
+    if claims.tenant_id != state.config().tenant_id {
         tracing::warn!(
             token_tenant=%claims.tenant_id,
-            config_tenant=%state.config.tenant_id,
+            config_tenant=%state.config().tenant_id,
             "auth: tenant mismatch"
         );
         return unauthorized();
@@ -70,7 +70,7 @@ pub async fn require_bearer(
     let cutoff = Utc::now() - Duration::days(idle_days);
     match state
         .store
-        .touch_session_with_cutoff(&jti, cutoff.naive_utc())
+        .touch_session_with_cutoff(&jti, cutoff.naive_utc(), claims.device_id.as_deref())
         .await
     {
         Ok(true) => {}
@@ -123,13 +123,13 @@ pub async fn issue_jwt_for_user(
 
     state
         .store
-        .create_session(&jti, username)
+        .create_session(&jti, username, claims.device_id.as_deref())
         .await
         .map_err(|e| {
             error!(username, error=%e, "login/register: create_session failed");
             AppError::internal(e)
         })?;
-    let token = jwt::encode(&claims, state.config.jwt_secret.as_bytes()).map_err(|e| {
+    let token = state.keyring().await?.sign(&claims).map_err(|e| {
         error!(username, error=%e, "login/register: jwt encode failed");
         AppError::internal(e)
     })?;
@@ -137,17 +137,17 @@ pub async fn issue_jwt_for_user(
 }
 
 fn validate_claims(state: &AppState, claims: &JwtClaims) -> Result<(), AppError> {
-    if claims.tenant_id != state.config.tenant_id {
+    if claims.tenant_id != state.config().tenant_id {
         warn!(
             username = %claims.sub,
             requested_tenant = %claims.tenant_id,
-            configured_tenant = %state.config.tenant_id,
+            configured_tenant = %state.config().tenant_id,
             "issue_jwt: tenant mismatch"
         );
         return Err(AppError::forbidden());
     }
-    let user = state
-        .config
+    let config = state.config();
+    let user = config
         .users
         .iter()
         .find(|u| u.username == claims.sub)
@@ -205,7 +205,7 @@ fn validate_claims(state: &AppState, claims: &JwtClaims) -> Result<(), AppError>
                 );
                 return Err(AppError::forbidden());
             }
-            if !state.config.children.iter().any(|c| c.id == child_id) {
+            if !state.config().children.iter().any(|c| c.id == child_id) {
                 warn!(child_id, "issue_jwt: child not configured");
                 return Err(AppError::not_found(format!(
                     "child not found: {}",
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDateTime};
+use gamiscreen_shared::api::{NotificationAudience, NotificationPayload};
+
+use super::{AppState, ChoreConfig, ChoreRepeat, dispatch_remaining_updated};
+use crate::storage::models::NewChoreInstance;
+
+/// How often the chore job creates upcoming instances and applies penalties.
+const CHORE_TICK: Duration = Duration::from_secs(60);
+
+/// How far ahead chore instances are created.
+const HORIZON_DAYS: i64 = 8;
+
+/// Instances of `chore` due after `now` and within the scheduling horizon.
+///
+/// A recurring instance becomes available once the previous occurrence is
+/// due, so at most one instance of a chore can be completed at a time.
+fn upcoming_instances(chore: &ChoreConfig, now: NaiveDateTime) -> Vec<NewChoreInstance> {
+    let horizon = now + chrono::Duration::days(HORIZON_DAYS);
+    let occurs_on = |date: chrono::NaiveDate| match chore.repeat {
+        ChoreRepeat::Once => Some(date) == chore.due_date,
+        ChoreRepeat::Daily => true,
+        ChoreRepeat::Weekly => chore.weekdays.contains(&date.weekday()),
+    };
+    let mut out = Vec::new();
+    let mut date = now.date();
+    while date <= horizon.date() {
+        let due_at = date.and_time(chore.due_time);
+        if occurs_on(date) && due_at > now && due_at <= horizon {
+            let available_from = match chore.repeat {
+                ChoreRepeat::Once => now,
+                _ => (1..=7)
+                    .map(|back| date - chrono::Duration::days(back))
+                    .find(|d| occurs_on(*d))
+                    .map(|d| d.and_time(chore.due_time))
+                    .unwrap_or(now),
+            };
+            out.push(NewChoreInstance {
+                chore_id: chore.id.clone(),
+                child_id: chore.child_id.clone(),
+                task_id: chore.task_id.clone(),
+                available_from,
+                due_at,
+                penalty_minutes: chore.penalty_minutes as i32,
+            });
+        }
+        date = date.succ_opt().expect("date within range");
+    }
+    out
+}
+
+/// Spawn the job that schedules chore instances and penalizes missed ones.
+///
+/// Open instances of chores removed from the config, or no longer on a
+/// changed schedule, are dropped; completed and missed instances are kept for
+/// history.
+pub fn spawn_chore_job(state: &AppState) {
+    let state = state.clone();
+    let shutdown = state.shutdown_token();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHORE_TICK);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            if let Err(e) = run_chore_pass(&state, chrono::Utc::now().naive_utc()).await {
+                tracing::error!(error = %e, "chores: scheduling pass failed");
+            }
+        }
+    });
+}
+
+/// Create instances up to the horizon and settle everything overdue at `now`.
+async fn run_chore_pass(
+    state: &AppState,
+    now: NaiveDateTime,
+) -> Result<(), crate::storage::StorageError> {
+    let chores = &state.config().chores;
+    let ids = chores.iter().map(|c| c.id.clone()).collect();
+    let upcoming = chores
+        .iter()
+        .flat_map(|c| upcoming_instances(c, now))
+        .collect();
+    let inserted = state.store.sync_chore_instances(ids, upcoming, now).await?;
+    if inserted > 0 {
+        tracing::debug!(count = inserted, "chores: scheduled instances");
+    }
+
+    for (instance, task, new_remaining) in state.store.apply_overdue_chores(now).await? {
+        tracing::info!(
+            child_id = %instance.child_id,
+            chore_id = %instance.chore_id,
+            penalty = instance.penalty_minutes,
+            "chores: instance missed"
+        );
+        if instance.penalty_minutes > 0 {
+            crate::metrics::metrics().record_reward(-instance.penalty_minutes);
+        }
+        if let Some(remaining) = new_remaining {
+            {
+                let m = state.child_mutex(&instance.child_id).await;
+                *m.lock().await = Some(remaining);
+            }
+            if let Err(e) = dispatch_remaining_updated(state, &instance.child_id).await {
+                tracing::error!(error = ?e, child_id = %instance.child_id, "chores: failed to broadcast remaining");
+            }
+        }
+        state
+            .notify(
+                NotificationAudience::Child,
+                &instance.child_id,
+                NotificationPayload::ChoreMissed {
+                    chore_id: instance.chore_id.clone(),
+                    task_id: task.id.clone(),
+                    task_name: task.name.clone(),
+                    due_at: instance.due_at.and_utc().to_rfc3339(),
+                    penalty_minutes: instance.penalty_minutes,
+                },
+                Some(instance.id),
+            )
+            .await;
+    }
+    Ok(())
+}
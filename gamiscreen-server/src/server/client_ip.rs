@@ -0,0 +1,189 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::HeaderMap;
+use axum::http::request::Parts;
+use ipnet::IpNet;
+
+use super::{AppError, AppState};
+
+/// Parse `security.trusted_proxies` entries: CIDR ranges, or single
+/// addresses.
+pub(crate) fn parse_trusted_proxies(entries: &[String]) -> Result<Vec<IpNet>, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let entry = entry.trim();
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| format!("'{entry}' is not an IP address or CIDR range"))
+        })
+        .collect()
+}
+
+/// Address of the client that sent the request.
+///
+/// The peer address is used unless it is a trusted proxy. Then the hops
+/// listed in `Forwarded` (or, without it, `X-Forwarded-For`) are walked from
+/// the right, skipping trusted proxies, and the first other address is the
+/// client. Headers set by anyone else are ignored, since clients can put
+/// whatever they like in them.
+pub(crate) fn resolve(peer: IpAddr, headers: &HeaderMap, trusted: &[IpNet]) -> IpAddr {
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return peer;
+    }
+    let mut hops = forwarded_for(headers);
+    if hops.is_empty() {
+        hops = x_forwarded_for(headers);
+    }
+    let mut client = peer;
+    for hop in hops.iter().rev() {
+        if !is_trusted(&client) {
+            break;
+        }
+        // Obfuscated or malformed hops end the chain at the last known address
+        let Some(ip) = parse_hop(hop) else {
+            break;
+        };
+        client = ip;
+    }
+    client
+}
+
+/// `for=` values of the RFC 7239 `Forwarded` headers, in order.
+fn forwarded_for(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all("forwarded")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("for")
+                    .then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+        .collect()
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|hop| hop.trim().to_string())
+        .collect()
+}
+
+/// An address with an optional port: `192.0.2.1`, `192.0.2.1:8080`,
+/// `2001:db8::1` or `[2001:db8::1]:8080`.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    if let Ok(ip) = hop.parse::<IpAddr>() {
+        return Some(ip);
+    }
+    if let Ok(addr) = hop.parse::<SocketAddr>() {
+        return Some(addr.ip());
+    }
+    hop.strip_prefix('[')?.strip_suffix(']')?.parse().ok()
+}
+
+/// Client address of a request, taking trusted proxies into account.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ClientIp(pub IpAddr);
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, AppError> {
+        let ConnectInfo(peer) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .copied()
+            .ok_or_else(|| AppError::internal("missing connection info"))?;
+        Ok(Self(resolve(
+            peer.ip(),
+            &parts.headers,
+            &state.trusted_proxies.load(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_addresses_and_ranges() {
+        let nets =
+            parse_trusted_proxies(&["10.0.0.0/8".into(), "::1".into(), " 127.0.0.1 ".into()])
+                .unwrap();
+        assert!(nets[0].contains(&ip("10.1.2.3")));
+        assert!(nets[1].contains(&ip("::1")));
+        assert!(nets[2].contains(&ip("127.0.0.1")));
+        assert!(parse_trusted_proxies(&["proxy.local".into()]).is_err());
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let trusted = parse_trusted_proxies(&["10.0.0.1".into()]).unwrap();
+        let h = headers(&[("x-forwarded-for", "203.0.113.9")]);
+        assert_eq!(
+            resolve(ip("198.51.100.7"), &h, &trusted),
+            ip("198.51.100.7")
+        );
+        assert_eq!(resolve(ip("10.0.0.1"), &h, &[]), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn skips_trusted_hops_from_the_right() {
+        let trusted = parse_trusted_proxies(&["10.0.0.0/8".into()]).unwrap();
+        // The client may prepend anything; only the hops our proxies added count
+        let h = headers(&[("x-forwarded-for", "1.1.1.1, 203.0.113.9, 10.0.0.2")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &h, &trusted), ip("203.0.113.9"));
+        // Every hop trusted: the leftmost one is the best we know
+        let h = headers(&[
+            ("x-forwarded-for", "10.0.0.3"),
+            ("x-forwarded-for", "10.0.0.2"),
+        ]);
+        assert_eq!(resolve(ip("10.0.0.1"), &h, &trusted), ip("10.0.0.3"));
+        // A malformed hop stops the walk
+        let h = headers(&[("x-forwarded-for", "203.0.113.9, garbage")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &h, &trusted), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn prefers_the_forwarded_header() {
+        let trusted = parse_trusted_proxies(&["10.0.0.0/8".into()]).unwrap();
+        let h = headers(&[
+            (
+                "forwarded",
+                r#"for=192.0.2.60;proto=https, For="[2001:db8:cafe::17]:4711";by=10.0.0.1"#,
+            ),
+            ("x-forwarded-for", "203.0.113.9"),
+        ]);
+        assert_eq!(
+            resolve(ip("10.0.0.1"), &h, &trusted),
+            ip("2001:db8:cafe::17")
+        );
+        let h = headers(&[("forwarded", "for=192.0.2.60:8080")]);
+        assert_eq!(resolve(ip("10.0.0.1"), &h, &trusted), ip("192.0.2.60"));
+    }
+}
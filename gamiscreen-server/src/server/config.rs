@@ -1,6 +1,7 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{env, fs};
 
+use base64::Engine;
 pub use gamiscreen_shared::auth::Role;
 use gamiscreen_shared::domain::{Child, Task};
 use semver::Version;
@@ -8,29 +9,57 @@ use serde::Deserialize;
 use serde_yaml::{Mapping, Value};
 use tracing::warn;
 
-#[derive(Debug, Clone, Deserialize)]
+/// Server configuration, read from `config.yaml`.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
 pub struct AppConfig {
     pub config_version: String,
     pub tenant_id: String,
     pub children: Vec<Child>,
     pub tasks: Vec<Task>,
-    pub jwt_secret: String,
+    /// Secret of the HS256 tokens issued before signing keys existed. Only
+    /// verifies those; remove it once they have expired.
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
     pub users: Vec<UserConfig>,
     pub dev_cors_origin: Option<String>,
     pub listen_port: Option<u16>,
     #[serde(default)]
     pub push: Option<PushConfig>,
+    #[serde(default)]
+    pub savings: Option<SavingsConfig>,
+    /// Minutes a child may owe before parents are notified that a loan
+    /// reached the limit. Loans are not refused.
+    #[serde(default)]
+    pub debt_limit_minutes: Option<u32>,
+    #[serde(default)]
+    pub attachments: Option<AttachmentsConfig>,
+    #[serde(default)]
+    pub chores: Vec<ChoreConfig>,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    #[serde(default)]
+    pub metrics: Option<MetricsConfig>,
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
 pub struct UserConfig {
     pub username: String,
-    pub password_hash: String, // bcrypt hash
+    /// bcrypt hash; parents without one can only log in through OIDC.
+    #[serde(default)]
+    pub password_hash: Option<String>,
     pub role: Role,
     pub child_id: Option<String>, // required when role == child
 }
 
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Default, schemars::JsonSchema)]
 #[serde(default)]
 pub struct PushConfig {
     pub enabled: bool,
@@ -39,6 +68,232 @@ pub struct PushConfig {
     pub contact_email: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Deserialize, Default, schemars::JsonSchema)]
+#[serde(default)]
+pub struct SavingsConfig {
+    pub enabled: bool,
+    /// Interest credited once a week, as a percentage of the pot (e.g. 5.0).
+    pub weekly_interest_percent: f64,
+    /// Days after each deposit during which the child cannot withdraw.
+    pub lock_in_days: u32,
+}
+
+/// Limits for photo proof attached to task submissions.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct AttachmentsConfig {
+    /// Largest accepted photo, in bytes.
+    pub max_bytes: usize,
+    /// Photos accepted per submission.
+    pub max_per_submission: usize,
+    /// Days to keep photos after the submission is approved or discarded;
+    /// 0 deletes them immediately.
+    pub retention_days: u32,
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: 5 * 1024 * 1024,
+            max_per_submission: 3,
+            retention_days: 0,
+        }
+    }
+}
+
+/// How often a scheduled chore comes due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ChoreRepeat {
+    /// A single instance on `due_date`.
+    Once,
+    Daily,
+    /// On each of `weekdays`.
+    Weekly,
+}
+
+/// A scheduled chore: instances of a task assigned to one child, each due
+/// at `due_time` (UTC) on the days given by the recurrence rule.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+pub struct ChoreConfig {
+    pub id: String,
+    /// Task whose name and minutes apply; completing the task completes the chore.
+    pub task_id: String,
+    pub child_id: String,
+    pub repeat: ChoreRepeat,
+    #[serde(default)]
+    #[schemars(with = "Vec<String>")]
+    pub weekdays: Vec<chrono::Weekday>,
+    /// Required when `repeat` is `once`.
+    #[serde(default)]
+    pub due_date: Option<chrono::NaiveDate>,
+    pub due_time: chrono::NaiveTime,
+    /// Minutes deducted when an instance passes its due time uncompleted.
+    #[serde(default)]
+    pub penalty_minutes: u32,
+}
+
+/// Protection of logins against guessing.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct SecurityConfig {
+    /// Reverse proxies, as addresses or CIDR ranges, whose `Forwarded` or
+    /// `X-Forwarded-For` headers are believed when rate limiting by client
+    /// address.
+    pub trusted_proxies: Vec<String>,
+    /// Failed logins in a row after which an account is locked; 0 turns
+    /// lockout off.
+    pub lockout_after: u32,
+    /// Length of the first lockout; each further one doubles it.
+    pub lockout_secs: u64,
+    /// Upper bound on the lockout length.
+    pub max_lockout_secs: u64,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            trusted_proxies: Vec::new(),
+            lockout_after: 5,
+            lockout_secs: 60,
+            max_lockout_secs: 60 * 60,
+        }
+    }
+}
+
+/// HTTPS served by the server itself instead of a reverse proxy.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// PEM certificate chain, leaf certificate first.
+    pub cert_path: PathBuf,
+    /// PEM private key of the certificate.
+    pub key_path: PathBuf,
+    /// Also listen for plain HTTP on this port and redirect it to HTTPS.
+    pub redirect_http_port: Option<u16>,
+}
+
+/// An HTTP endpoint that receives server events as signed JSON POSTs.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+pub struct WebhookConfig {
+    pub id: String,
+    pub url: String,
+    /// Key for the HMAC-SHA256 signature sent with every delivery.
+    pub secret: String,
+    /// Event names to deliver; empty delivers every event.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+/// Prometheus `/metrics` endpoint.
+#[derive(Debug, Clone, PartialEq, Deserialize, Default, schemars::JsonSchema)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    /// Bearer token required to scrape; open to anyone when unset.
+    pub token: Option<String>,
+}
+
+/// Connection to an MQTT broker used to expose children to Home Assistant.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+pub struct MqttConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_mqtt_client_id")]
+    pub client_id: String,
+    /// Root of the state and command topics.
+    #[serde(default = "default_mqtt_topic_prefix")]
+    pub topic_prefix: String,
+    /// Home Assistant discovery prefix.
+    #[serde(default = "default_mqtt_discovery_prefix")]
+    pub discovery_prefix: String,
+    /// Configured user that commands received over MQTT act as; its role
+    /// decides what they may do.
+    pub user: String,
+}
+
+/// Parent login through an OpenID Connect provider, using the authorization
+/// code flow with PKCE.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+pub struct OidcConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Issuer URL; the provider is discovered from
+    /// `{issuer}/.well-known/openid-configuration`.
+    pub issuer: String,
+    pub client_id: String,
+    /// Secret of a confidential client; public clients rely on PKCE alone.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// This server's `/api/v1/auth/oidc/callback`, as registered with the
+    /// provider.
+    pub redirect_url: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    /// ID token claim listing the user's groups.
+    #[serde(default = "default_oidc_groups_claim")]
+    pub groups_claim: String,
+    /// Label of the login button in the web app.
+    #[serde(default = "default_oidc_display_name")]
+    pub display_name: String,
+    /// Which identities log in as which parent; the first match wins.
+    pub users: Vec<OidcUserMapping>,
+}
+
+/// Maps a provider subject, or members of a group, to a parent user.
+#[derive(Debug, Clone, PartialEq, Deserialize, schemars::JsonSchema)]
+pub struct OidcUserMapping {
+    /// Parent from `users` to log in as.
+    pub username: String,
+    /// `sub` claim of the identity.
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Group whose members log in as `username`.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    ["openid", "profile", "groups"].map(String::from).to_vec()
+}
+
+fn default_oidc_groups_claim() -> String {
+    "groups".to_string()
+}
+
+fn default_oidc_display_name() -> String {
+    "Single sign-on".to_string()
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "gamiscreen".to_string()
+}
+
+fn default_mqtt_topic_prefix() -> String {
+    "gamiscreen".to_string()
+}
+
+fn default_mqtt_discovery_prefix() -> String {
+    "homeassistant".to_string()
+}
+
+/// Upper bound for `attachments.max_bytes`; photos are stored in the database.
+const MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+/// Upper bound for `attachments.max_per_submission`.
+const MAX_ATTACHMENTS_PER_SUBMISSION: usize = 10;
+
 #[derive(Debug)]
 pub enum ConfigError {
     Io(std::io::Error),
@@ -72,8 +327,14 @@ impl From<serde_yaml::Error> for ConfigError {
 
 impl AppConfig {
     pub fn load() -> Result<Self, ConfigError> {
-        let path = env::var("CONFIG_PATH").unwrap_or_else(|_| "config.yaml".to_string());
-        Self::load_from_path(path)
+        Self::load_from_path(Self::path())
+    }
+
+    /// Path of the config file: `CONFIG_PATH`, or `config.yaml`.
+    pub fn path() -> PathBuf {
+        env::var_os("CONFIG_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("config.yaml"))
     }
 
     pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
@@ -81,8 +342,482 @@ impl AppConfig {
         let text = fs::read_to_string(&path)?;
         let mut cfg: AppConfig = serde_yaml::from_str(&text)?;
         apply_env_overrides(&mut cfg);
+        cfg.validate()?;
         Ok(cfg)
     }
+
+    /// Read the config at `path` as the server would, and report its issues
+    /// instead of failing on them. Unlike loading, the file is never
+    /// migrated in place.
+    pub fn check_file(path: &Path) -> Result<Vec<ConfigIssue>, ConfigError> {
+        let text = fs::read_to_string(path)?;
+        let mut doc: Value = serde_yaml::from_str(&text)?;
+        let migration = migrate_document(&mut doc)?;
+        // Parse the original text when possible, so errors point at its lines
+        let mut cfg: AppConfig = if migration.changed {
+            serde_yaml::from_value(doc)?
+        } else {
+            serde_yaml::from_str(&text)?
+        };
+        apply_env_overrides(&mut cfg);
+        let mut issues = cfg.check();
+        if migration.changed {
+            issues.push(ConfigIssue {
+                severity: IssueSeverity::Warning,
+                path: "config_version".into(),
+                message: format!(
+                    "older than {}; the server updates the file when it starts",
+                    env!("CARGO_PKG_VERSION")
+                ),
+            });
+        }
+        Ok(issues)
+    }
+
+    /// JSON Schema of `config.yaml`, for editors and linters.
+    pub fn json_schema() -> serde_json::Value {
+        schemars::schema_for!(AppConfig).to_value()
+    }
+
+    /// Savings settings, if the savings pot is enabled.
+    pub fn savings_config(&self) -> Option<&SavingsConfig> {
+        self.savings.as_ref().filter(|s| s.enabled)
+    }
+
+    /// Metrics endpoint settings, if the endpoint is enabled.
+    pub fn metrics_config(&self) -> Option<&MetricsConfig> {
+        self.metrics.as_ref().filter(|m| m.enabled)
+    }
+
+    /// MQTT bridge settings, if the bridge is enabled.
+    pub fn mqtt_config(&self) -> Option<&MqttConfig> {
+        self.mqtt.as_ref().filter(|m| m.enabled)
+    }
+
+    /// OpenID Connect settings, if OIDC login is enabled.
+    pub fn oidc_config(&self) -> Option<&OidcConfig> {
+        self.oidc.as_ref().filter(|o| o.enabled)
+    }
+
+    /// TLS settings, if the server serves HTTPS itself.
+    pub fn tls_config(&self) -> Option<&TlsConfig> {
+        self.tls.as_ref().filter(|t| t.enabled)
+    }
+
+    /// Attachment limits, falling back to the defaults when not configured.
+    pub fn attachments_config(&self) -> AttachmentsConfig {
+        self.attachments.clone().unwrap_or_default()
+    }
+
+    /// Login protection settings, falling back to the defaults when not
+    /// configured.
+    pub fn security_config(&self) -> SecurityConfig {
+        self.security.clone().unwrap_or_default()
+    }
+
+    /// Report everything wrong with the config, with the path of each
+    /// offending setting. The server refuses to start on any error.
+    pub fn check(&self) -> Vec<ConfigIssue> {
+        let mut issues = Issues::default();
+        if self.tenant_id.trim().is_empty() {
+            issues.error("tenant_id", "must not be empty");
+        }
+        self.check_children_and_tasks(&mut issues);
+        self.check_users(&mut issues);
+        self.check_push(&mut issues);
+        if let Some(attachments) = &self.attachments {
+            if !(1..=MAX_ATTACHMENT_BYTES).contains(&attachments.max_bytes) {
+                issues.error(
+                    "attachments.max_bytes",
+                    format!(
+                        "must be between 1 and {}, got {}",
+                        MAX_ATTACHMENT_BYTES, attachments.max_bytes
+                    ),
+                );
+            }
+            if !(1..=MAX_ATTACHMENTS_PER_SUBMISSION).contains(&attachments.max_per_submission) {
+                issues.error(
+                    "attachments.max_per_submission",
+                    format!(
+                        "must be between 1 and {}, got {}",
+                        MAX_ATTACHMENTS_PER_SUBMISSION, attachments.max_per_submission
+                    ),
+                );
+            }
+        }
+        self.check_chores(&mut issues);
+        self.check_webhooks(&mut issues);
+        self.check_mqtt(&mut issues);
+        self.check_oidc(&mut issues);
+        self.check_security(&mut issues);
+        self.check_tls(&mut issues);
+        if let Some(metrics) = &self.metrics
+            && metrics
+                .token
+                .as_deref()
+                .is_some_and(|t| t.trim().is_empty())
+        {
+            issues.error(
+                "metrics.token",
+                "must not be empty; omit it to leave the endpoint open",
+            );
+        }
+        if self.debt_limit_minutes == Some(0) {
+            issues.error(
+                "debt_limit_minutes",
+                "must be at least 1; omit it to turn it off",
+            );
+        }
+        if let Some(savings) = &self.savings {
+            let pct = savings.weekly_interest_percent;
+            if !pct.is_finite() || !(0.0..=100.0).contains(&pct) {
+                issues.error(
+                    "savings.weekly_interest_percent",
+                    format!("must be between 0 and 100, got {}", pct),
+                );
+            }
+        }
+        issues.0
+    }
+
+    /// Log warnings and fail on errors found by [`AppConfig::check`].
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        for issue in self.check() {
+            match issue.severity {
+                IssueSeverity::Warning => warn!("config: {issue}"),
+                IssueSeverity::Error => errors.push(issue.to_string()),
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors.join("; ")))
+        }
+    }
+
+    fn check_children_and_tasks(&self, issues: &mut Issues) {
+        let mut seen = std::collections::HashSet::new();
+        for (i, child) in self.children.iter().enumerate() {
+            if child.id.trim().is_empty() {
+                issues.error(format!("children[{i}].id"), "must not be empty");
+            } else if !seen.insert(child.id.as_str()) {
+                issues.error(
+                    format!("children[{i}].id"),
+                    format!("duplicate id '{}'", child.id),
+                );
+            }
+            let logins = self
+                .users
+                .iter()
+                .filter(|u| u.role == Role::Child && u.child_id.as_deref() == Some(&child.id))
+                .count();
+            if logins == 0 {
+                issues.warning(
+                    format!("children[{i}]"),
+                    format!(
+                        "no child user has child_id '{}', so the child cannot log in or register devices",
+                        child.id
+                    ),
+                );
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        for (i, task) in self.tasks.iter().enumerate() {
+            if task.id.trim().is_empty() {
+                issues.error(format!("tasks[{i}].id"), "must not be empty");
+            } else if !seen.insert(task.id.as_str()) {
+                issues.error(
+                    format!("tasks[{i}].id"),
+                    format!("duplicate id '{}'", task.id),
+                );
+            }
+        }
+    }
+
+    fn check_users(&self, issues: &mut Issues) {
+        let mut seen = std::collections::HashSet::new();
+        let mut child_logins = std::collections::HashSet::new();
+        for (i, user) in self.users.iter().enumerate() {
+            let path = |field: &str| format!("users[{i}].{field}");
+            if user.username.trim().is_empty() {
+                issues.error(path("username"), "must not be empty");
+            } else if !seen.insert(user.username.as_str()) {
+                issues.error(
+                    path("username"),
+                    format!("duplicate username '{}'", user.username),
+                );
+            }
+            match user.role {
+                Role::Parent => {
+                    if user.child_id.is_some() {
+                        issues.error(
+                            path("child_id"),
+                            "parents cannot have a child_id; they could not log in",
+                        );
+                    }
+                }
+                Role::Child => {
+                    if user.password_hash.is_none() {
+                        issues.error(path("password_hash"), "children need a password_hash");
+                    }
+                    match user.child_id.as_deref() {
+                        None => issues.error(path("child_id"), "children need a child_id"),
+                        Some(id) if !self.children.iter().any(|c| c.id == id) => issues.error(
+                            path("child_id"),
+                            format!("'{id}' is not a configured child"),
+                        ),
+                        Some(id) if !child_logins.insert(id) => issues.warning(
+                            path("child_id"),
+                            format!("'{id}' already has a user; devices register as the first one"),
+                        ),
+                        Some(_) => {}
+                    }
+                }
+            }
+            if let Some(hash) = &user.password_hash
+                && !hash.starts_with("$2")
+            {
+                issues.warning(
+                    path("password_hash"),
+                    "is not a bcrypt hash, so password logins will fail",
+                );
+            }
+        }
+    }
+
+    fn check_push(&self, issues: &mut Issues) {
+        let Some(push) = self.push.as_ref().filter(|p| p.enabled) else {
+            return;
+        };
+        for (field, key, len) in [
+            ("vapid_public", &push.vapid_public, 65),
+            ("vapid_private", &push.vapid_private, 32),
+        ] {
+            let path = format!("push.{field}");
+            match key.as_deref().map(str::trim) {
+                None | Some("") => {
+                    issues.error(path, "required when push is enabled");
+                }
+                Some(key) => {
+                    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                        .decode(key.trim_end_matches('='));
+                    if !decoded.is_ok_and(|bytes| bytes.len() == len) {
+                        issues.error(path, "is not a base64url-encoded P-256 VAPID key");
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_webhooks(&self, issues: &mut Issues) {
+        let mut seen = std::collections::HashSet::new();
+        for (i, hook) in self.webhooks.iter().enumerate() {
+            let path = |field: &str| format!("webhooks[{i}].{field}");
+            if !seen.insert(hook.id.as_str()) {
+                issues.error(path("id"), format!("duplicate id '{}'", hook.id));
+            }
+            if !(hook.url.starts_with("https://") || hook.url.starts_with("http://")) {
+                issues.error(path("url"), "must start with http:// or https://");
+            }
+            if hook.secret.trim().is_empty() {
+                issues.error(path("secret"), "must not be empty");
+            }
+            for (j, event) in hook.events.iter().enumerate() {
+                if !super::webhooks::EVENT_NAMES.contains(&event.as_str()) {
+                    issues.error(
+                        format!("webhooks[{i}].events[{j}]"),
+                        format!("unknown event '{event}'"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_mqtt(&self, issues: &mut Issues) {
+        let Some(mqtt) = self.mqtt_config() else {
+            return;
+        };
+        if mqtt.host.trim().is_empty() {
+            issues.error("mqtt.host", "must not be empty");
+        }
+        if !self.users.iter().any(|u| u.username == mqtt.user) {
+            issues.error(
+                "mqtt.user",
+                format!("'{}' is not a configured user", mqtt.user),
+            );
+        }
+        for (field, prefix) in [
+            ("topic_prefix", &mqtt.topic_prefix),
+            ("discovery_prefix", &mqtt.discovery_prefix),
+        ] {
+            if prefix.is_empty() || prefix.contains(['+', '#']) || prefix.ends_with('/') {
+                issues.error(
+                    format!("mqtt.{field}"),
+                    "must be non-empty, without wildcards or a trailing '/'",
+                );
+            }
+        }
+    }
+
+    fn check_oidc(&self, issues: &mut Issues) {
+        let Some(oidc) = self.oidc_config() else {
+            return;
+        };
+        for (field, url) in [
+            ("issuer", &oidc.issuer),
+            ("redirect_url", &oidc.redirect_url),
+        ] {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                issues.error(
+                    format!("oidc.{field}"),
+                    "must start with http:// or https://",
+                );
+            }
+        }
+        if oidc.client_id.trim().is_empty() {
+            issues.error("oidc.client_id", "must not be empty");
+        }
+        if !oidc.scopes.iter().any(|s| s == "openid") {
+            issues.error("oidc.scopes", "must include openid");
+        }
+        if oidc.users.is_empty() {
+            issues.error("oidc.users", "must map at least one identity");
+        }
+        for (i, mapping) in oidc.users.iter().enumerate() {
+            if mapping.subject.is_some() == mapping.group.is_some() {
+                issues.error(
+                    format!("oidc.users[{i}]"),
+                    "needs either a subject or a group",
+                );
+            }
+            if !self
+                .users
+                .iter()
+                .any(|u| u.username == mapping.username && u.role == Role::Parent)
+            {
+                issues.error(
+                    format!("oidc.users[{i}].username"),
+                    format!("'{}' is not a configured parent user", mapping.username),
+                );
+            }
+        }
+    }
+
+    fn check_security(&self, issues: &mut Issues) {
+        let Some(security) = &self.security else {
+            return;
+        };
+        if let Err(e) = super::client_ip::parse_trusted_proxies(&security.trusted_proxies) {
+            issues.error("security.trusted_proxies", e);
+        }
+        if security.lockout_after > 0
+            && !(1..=security.max_lockout_secs).contains(&security.lockout_secs)
+        {
+            issues.error(
+                "security.lockout_secs",
+                "must be positive and at most max_lockout_secs",
+            );
+        }
+    }
+
+    fn check_tls(&self, issues: &mut Issues) {
+        let Some(tls) = self.tls_config() else {
+            return;
+        };
+        for (field, path) in [("cert_path", &tls.cert_path), ("key_path", &tls.key_path)] {
+            if !path.is_file() {
+                issues.error(
+                    format!("tls.{field}"),
+                    format!("{} not found", path.display()),
+                );
+            }
+        }
+        if tls.redirect_http_port == Some(self.listen_port.unwrap_or(5151)) {
+            issues.error("tls.redirect_http_port", "must differ from listen_port");
+        }
+    }
+
+    fn check_chores(&self, issues: &mut Issues) {
+        let mut seen = std::collections::HashSet::new();
+        for (i, chore) in self.chores.iter().enumerate() {
+            let path = |field: &str| format!("chores[{i}].{field}");
+            if !seen.insert(chore.id.as_str()) {
+                issues.error(path("id"), format!("duplicate id '{}'", chore.id));
+            }
+            if !self.tasks.iter().any(|t| t.id == chore.task_id) {
+                issues.error(
+                    path("task_id"),
+                    format!("'{}' is not a configured task", chore.task_id),
+                );
+            }
+            if !self.children.iter().any(|c| c.id == chore.child_id) {
+                issues.error(
+                    path("child_id"),
+                    format!("'{}' is not a configured child", chore.child_id),
+                );
+            }
+            match chore.repeat {
+                ChoreRepeat::Once if chore.due_date.is_none() => {
+                    issues.error(path("due_date"), "one-off chores need a due_date");
+                }
+                ChoreRepeat::Weekly if chore.weekdays.is_empty() => {
+                    issues.error(path("weekdays"), "weekly chores need at least one weekday");
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// How serious a [`ConfigIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueSeverity {
+    /// The server refuses to start.
+    Error,
+    /// The server starts, but the setting probably does not do what was meant.
+    Warning,
+}
+
+/// A problem found by [`AppConfig::check`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub severity: IssueSeverity,
+    /// Path of the offending setting, e.g. `users[1].child_id`.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Default)]
+struct Issues(Vec<ConfigIssue>);
+
+impl Issues {
+    fn push(
+        &mut self,
+        severity: IssueSeverity,
+        path: impl Into<String>,
+        message: impl Into<String>,
+    ) {
+        self.0.push(ConfigIssue {
+            severity,
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(IssueSeverity::Error, path, message);
+    }
+
+    fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(IssueSeverity::Warning, path, message);
+    }
 }
 
 type MigrationFn = fn(&mut Mapping) -> bool;
@@ -93,9 +828,36 @@ const MIGRATIONS: &[(&str, MigrationFn)] =
 fn migrate_config(path: &Path) -> Result<(), ConfigError> {
     let text = fs::read_to_string(path)?;
     let mut doc: Value = serde_yaml::from_str(&text)?;
+    let migration = migrate_document(&mut doc)?;
+
+    if migration.changed {
+        let updated = serde_yaml::to_string(&doc)?;
+        if let Err(e) = fs::write(path, updated) {
+            if migration.major_update {
+                warn!(
+                    error = %e,
+                    path = ?path,
+                    "config migration for major update failed; continuing with existing config"
+                );
+            } else {
+                warn!(error = %e, path = ?path, "config migration skipped (read-only)");
+            }
+        }
+    }
 
+    Ok(())
+}
+
+#[derive(Default)]
+struct Migration {
+    changed: bool,
+    major_update: bool,
+}
+
+/// Bring a config document up to the current version in memory.
+fn migrate_document(doc: &mut Value) -> Result<Migration, ConfigError> {
     let Some(mapping) = doc.as_mapping_mut() else {
-        return Ok(());
+        return Ok(Migration::default());
     };
 
     let version_key = Value::String("config_version".to_string());
@@ -156,22 +918,10 @@ fn migrate_config(path: &Path) -> Result<(), ConfigError> {
         }
     }
 
-    if changed {
-        let updated = serde_yaml::to_string(&doc)?;
-        if let Err(e) = fs::write(path, updated) {
-            if major_update {
-                warn!(
-                    error = %e,
-                    path = ?path,
-                    "config migration for major update failed; continuing with existing config"
-                );
-            } else {
-                warn!(error = %e, path = ?path, "config migration skipped (read-only)");
-            }
-        }
-    }
-
-    Ok(())
+    Ok(Migration {
+        changed,
+        major_update,
+    })
 }
 
 fn migrate_to_0_7_0(map: &mut Mapping) -> bool {
@@ -218,6 +968,10 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
         let push = cfg.push.get_or_insert_with(Default::default);
         push.enabled = parsed;
     }
+    if let Ok(val) = env::var("METRICS_TOKEN") {
+        let metrics = cfg.metrics.get_or_insert_with(Default::default);
+        metrics.token = Some(val);
+    }
 }
 
 #[cfg(test)]
@@ -287,4 +1041,393 @@ tasks:
             Some(push_map) if push_map.get(Value::String("enabled".into())) == Some(&Value::Bool(false))
         ));
     }
+
+    #[test]
+    fn parses_savings_section_and_rejects_bad_interest() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+jwt_secret: "secret"
+users: []
+children: []
+tasks: []
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        let cfg_text = format!(
+            "{base}savings:\n  enabled: true\n  weekly_interest_percent: 5\n  lock_in_days: 7\n"
+        );
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        let savings = cfg.savings_config().expect("savings enabled");
+        assert_eq!(savings.weekly_interest_percent, 5.0);
+        assert_eq!(savings.lock_in_days, 7);
+
+        let cfg_text = format!("{base}savings:\n  enabled: true\n  weekly_interest_percent: 150\n");
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        assert!(matches!(
+            AppConfig::load_from_path(file.path()),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn attachments_default_and_reject_oversized_limit() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+jwt_secret: "secret"
+users: []
+children: []
+tasks: []
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        std::fs::write(file.path(), base).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        let attachments = cfg.attachments_config();
+        assert_eq!(attachments.max_bytes, 5 * 1024 * 1024);
+        assert_eq!(attachments.max_per_submission, 3);
+        assert_eq!(attachments.retention_days, 0);
+
+        let cfg_text = format!("{base}attachments:\n  retention_days: 7\n");
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        assert_eq!(cfg.attachments_config().retention_days, 7);
+        assert_eq!(cfg.attachments_config().max_per_submission, 3);
+
+        let cfg_text = format!("{base}attachments:\n  max_bytes: 0\n");
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        assert!(matches!(
+            AppConfig::load_from_path(file.path()),
+            Err(ConfigError::Invalid(_))
+        ));
+
+        for count in [0, 1000] {
+            let cfg_text = format!("{base}attachments:\n  max_per_submission: {count}\n");
+            std::fs::write(file.path(), cfg_text).expect("write config");
+            assert!(matches!(
+                AppConfig::load_from_path(file.path()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn parses_chores_and_rejects_incomplete_schedules() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+jwt_secret: "secret"
+users: []
+children:
+  - id: "alice"
+    display_name: "Alice"
+tasks:
+  - id: "dishes"
+    name: "Dishes"
+    minutes: 5
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        let cfg_text = format!(
+            "{base}chores:\n  - id: \"dishes-weekdays\"\n    task_id: \"dishes\"\n    child_id: \"alice\"\n    repeat: weekly\n    weekdays: [\"Mon\", \"wednesday\"]\n    due_time: \"19:00\"\n    penalty_minutes: 10\n"
+        );
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        let chore = &cfg.chores[0];
+        assert_eq!(chore.repeat, ChoreRepeat::Weekly);
+        assert_eq!(
+            chore.weekdays,
+            vec![chrono::Weekday::Mon, chrono::Weekday::Wed]
+        );
+        assert_eq!(
+            chore.due_time,
+            chrono::NaiveTime::from_hms_opt(19, 0, 0).unwrap()
+        );
+        assert_eq!(chore.penalty_minutes, 10);
+
+        for bad in [
+            "  - id: \"x\"\n    task_id: \"dishes\"\n    child_id: \"alice\"\n    repeat: once\n    due_time: \"19:00\"\n",
+            "  - id: \"x\"\n    task_id: \"dishes\"\n    child_id: \"alice\"\n    repeat: weekly\n    due_time: \"19:00\"\n",
+            "  - id: \"x\"\n    task_id: \"missing\"\n    child_id: \"alice\"\n    repeat: daily\n    due_time: \"19:00\"\n",
+        ] {
+            std::fs::write(file.path(), format!("{base}chores:\n{bad}")).expect("write config");
+            assert!(matches!(
+                AppConfig::load_from_path(file.path()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn validates_webhooks() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+jwt_secret: "secret"
+users: []
+children: []
+tasks: []
+webhooks:
+  - id: "ha"
+    secret: "s3cret"
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        let cfg_text = format!(
+            "{base}    url: \"https://example.com/hook\"\n    events: [\"reward_granted\"]\n"
+        );
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        assert_eq!(cfg.webhooks[0].events, vec!["reward_granted".to_string()]);
+
+        for bad in [
+            "    url: \"ftp://example.com/hook\"\n",
+            "    url: \"https://example.com/hook\"\n    events: [\"reward\"]\n",
+        ] {
+            std::fs::write(file.path(), format!("{base}{bad}")).expect("write config");
+            assert!(matches!(
+                AppConfig::load_from_path(file.path()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn validates_mqtt() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+jwt_secret: "secret"
+users:
+  - username: "mom"
+    password_hash: "hash"
+    role: parent
+children: []
+tasks: []
+mqtt:
+  enabled: true
+  host: "broker.local"
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        std::fs::write(file.path(), format!("{base}  user: \"mom\"\n")).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        let mqtt = cfg.mqtt_config().expect("mqtt enabled");
+        assert_eq!(mqtt.port, 1883);
+        assert_eq!(mqtt.topic_prefix, "gamiscreen");
+        assert_eq!(mqtt.discovery_prefix, "homeassistant");
+
+        for bad in [
+            "  user: \"dad\"\n",
+            "  user: \"mom\"\n  topic_prefix: \"home/#\"\n",
+            "  user: \"mom\"\n  discovery_prefix: \"homeassistant/\"\n",
+        ] {
+            std::fs::write(file.path(), format!("{base}{bad}")).expect("write config");
+            assert!(matches!(
+                AppConfig::load_from_path(file.path()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+
+        // Disabled bridges are not validated
+        std::fs::write(
+            file.path(),
+            format!(
+                "{}  user: \"dad\"\n",
+                base.replace("enabled: true", "enabled: false")
+            ),
+        )
+        .expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        assert!(cfg.mqtt_config().is_none());
+    }
+
+    #[test]
+    fn validates_oidc() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+users:
+  - username: "mom"
+    role: parent
+  - username: "kid"
+    password_hash: "hash"
+    role: child
+    child_id: "alice"
+children:
+  - id: "alice"
+    display_name: "Alice"
+tasks: []
+oidc:
+  enabled: true
+  issuer: "https://auth.example.com"
+  client_id: "gamiscreen"
+  redirect_url: "https://screen.example.com/api/v1/auth/oidc/callback"
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        std::fs::write(
+            file.path(),
+            format!("{base}  users:\n    - username: \"mom\"\n      group: \"parents\"\n"),
+        )
+        .expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        let oidc = cfg.oidc_config().expect("oidc enabled");
+        assert_eq!(oidc.scopes, vec!["openid", "profile", "groups"]);
+        assert_eq!(oidc.groups_claim, "groups");
+        assert!(cfg.users[0].password_hash.is_none());
+
+        for bad in [
+            "  users: []\n",
+            "  users:\n    - username: \"kid\"\n      group: \"parents\"\n",
+            "  users:\n    - username: \"mom\"\n",
+            "  users:\n    - username: \"mom\"\n      subject: \"a\"\n      group: \"b\"\n",
+            "  scopes: [\"profile\"]\n  users:\n    - username: \"mom\"\n      subject: \"a\"\n",
+        ] {
+            std::fs::write(file.path(), format!("{base}{bad}")).expect("write config");
+            assert!(matches!(
+                AppConfig::load_from_path(file.path()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+
+        // Children always need a password
+        let without_password = base.replace("    password_hash: \"hash\"\n", "");
+        std::fs::write(
+            file.path(),
+            format!("{without_password}  users:\n    - username: \"mom\"\n      subject: \"a\"\n"),
+        )
+        .expect("write config");
+        assert!(matches!(
+            AppConfig::load_from_path(file.path()),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn validates_security() {
+        let base = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+users: []
+children: []
+tasks: []
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        std::fs::write(file.path(), base).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        assert_eq!(cfg.security_config().lockout_after, 5);
+        assert!(cfg.security_config().trusted_proxies.is_empty());
+
+        let cfg_text = format!(
+            "{base}security:\n  trusted_proxies: [\"127.0.0.1\", \"10.0.0.0/8\"]\n  lockout_after: 0\n"
+        );
+        std::fs::write(file.path(), cfg_text).expect("write config");
+        let cfg = AppConfig::load_from_path(file.path()).expect("load config");
+        assert_eq!(cfg.security_config().trusted_proxies.len(), 2);
+        assert_eq!(cfg.security_config().max_lockout_secs, 3600);
+
+        for bad in [
+            "  trusted_proxies: [\"proxy.local\"]\n",
+            "  lockout_secs: 0\n",
+            "  lockout_secs: 600\n  max_lockout_secs: 60\n",
+        ] {
+            std::fs::write(file.path(), format!("{base}security:\n{bad}")).expect("write config");
+            assert!(matches!(
+                AppConfig::load_from_path(file.path()),
+                Err(ConfigError::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn check_reports_semantic_errors_with_paths() {
+        let text = r#"
+config_version: "0.13.0"
+tenant_id: "first"
+users:
+  - username: "parent"
+    password_hash: "$2b$12$abc"
+    role: parent
+    child_id: "alice"
+  - username: "kid"
+    password_hash: "$2b$12$abc"
+    role: child
+    child_id: "carol"
+children:
+  - id: "alice"
+    display_name: "Alice"
+tasks:
+  - id: "homework"
+    name: "Homework"
+    minutes: 10
+  - id: "homework"
+    name: "More homework"
+    minutes: 5
+push:
+  enabled: true
+  vapid_public: ""
+"#;
+        let cfg: AppConfig = serde_yaml::from_str(text).expect("parse config");
+        let issues = cfg.check();
+        let find = |path: &str| {
+            issues
+                .iter()
+                .find(|i| i.path == path)
+                .unwrap_or_else(|| panic!("no issue at {path}: {issues:?}"))
+        };
+        assert_eq!(find("users[0].child_id").severity, IssueSeverity::Error);
+        assert_eq!(find("users[1].child_id").severity, IssueSeverity::Error);
+        assert_eq!(find("tasks[1].id").severity, IssueSeverity::Error);
+        assert_eq!(find("push.vapid_public").severity, IssueSeverity::Error);
+        assert_eq!(find("push.vapid_private").severity, IssueSeverity::Error);
+        assert_eq!(find("children[0]").severity, IssueSeverity::Warning);
+        assert!(!issues.iter().any(|i| i.path == "tasks[0].id"));
+
+        let file = NamedTempFile::new().expect("tmp file");
+        std::fs::write(file.path(), text).expect("write config");
+        match AppConfig::load_from_path(file.path()) {
+            Err(ConfigError::Invalid(msg)) => {
+                assert!(
+                    msg.contains("tasks[1].id: duplicate id 'homework'"),
+                    "{msg}"
+                );
+            }
+            other => panic!("expected invalid config, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn check_file_leaves_legacy_files_alone() {
+        let legacy = r#"
+users: []
+children: []
+tasks: []
+"#;
+        let file = NamedTempFile::new().expect("tmp file");
+        std::fs::write(file.path(), legacy).expect("write config");
+        let issues = AppConfig::check_file(file.path()).expect("check config");
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.path == "config_version" && i.severity == IssueSeverity::Warning)
+        );
+        let text = std::fs::read_to_string(file.path()).expect("read config");
+        assert_eq!(text, legacy);
+
+        std::fs::write(file.path(), "tenant_id: 5\nusers: {}\n").expect("write config");
+        assert!(matches!(
+            AppConfig::check_file(file.path()),
+            Err(ConfigError::Yaml(_))
+        ));
+    }
+
+    #[test]
+    fn json_schema_covers_the_config() {
+        let schema = AppConfig::json_schema();
+        let required = schema["required"].as_array().expect("required");
+        assert!(required.iter().any(|r| r == "users"));
+        for section in ["children", "tasks", "push", "chores", "oidc", "security"] {
+            assert!(
+                schema["properties"].get(section).is_some(),
+                "missing {section}"
+            );
+        }
+    }
 }
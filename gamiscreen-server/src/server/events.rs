@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use gamiscreen_shared::api::ServerEvent;
+use tokio::sync::broadcast;
+
+/// Events kept for replay to reconnecting SSE clients.
+const REPLAY_CAPACITY: usize = 256;
+/// Capacity of the live broadcast channel.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// A server event with its position in the stream.
+#[derive(Clone, Debug)]
+pub(crate) struct Sequenced {
+    pub seq: u64,
+    pub event: ServerEvent,
+}
+
+/// What a reconnecting client missed since its `Last-Event-ID`.
+#[derive(Debug)]
+pub(crate) enum Replay {
+    /// Every missed event, oldest first (empty when nothing was missed).
+    Events(Vec<Sequenced>),
+    /// The id is unknown, from an earlier server run or already evicted;
+    /// the client has to refetch its state.
+    Gap,
+}
+
+/// Numbers every dispatched event and fans it out to live subscribers, while
+/// keeping the most recent ones so SSE clients can catch up after reconnecting.
+///
+/// Event ids are `{epoch}-{seq}`: `epoch` changes on every server start, so
+/// ids handed out by a previous process are recognised as a gap.
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    epoch: u64,
+    capacity: usize,
+    inner: Mutex<Inner>,
+    tx: broadcast::Sender<Sequenced>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    next_seq: u64,
+    buffer: VecDeque<Sequenced>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        let epoch = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        Self::new(epoch, REPLAY_CAPACITY)
+    }
+}
+
+impl EventLog {
+    pub fn new(epoch: u64, capacity: usize) -> Self {
+        let (tx, _rx) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            epoch,
+            capacity,
+            inner: Mutex::new(Inner {
+                next_seq: 1,
+                buffer: VecDeque::with_capacity(capacity),
+            }),
+            tx,
+        }
+    }
+
+    /// Assign the next id to `event`, remember it and send it to subscribers.
+    pub fn publish(&self, event: ServerEvent) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let item = Sequenced {
+            seq: inner.next_seq,
+            event,
+        };
+        inner.next_seq += 1;
+        if inner.buffer.len() == self.capacity {
+            inner.buffer.pop_front();
+        }
+        inner.buffer.push_back(item.clone());
+        // Sent under the lock so a concurrent `resume` sees each event either
+        // in its replay or on its receiver, never both or neither.
+        let _ = self.tx.send(item);
+    }
+
+    /// Subscribe to live events.
+    pub fn subscribe(&self) -> broadcast::Receiver<Sequenced> {
+        self.tx.subscribe()
+    }
+
+    /// Subscribe to live events and collect what was missed since
+    /// `last_event_id`. Live events with `seq` at or below the last replayed
+    /// one must be skipped by the caller.
+    pub fn resume(&self, last_event_id: Option<&str>) -> (broadcast::Receiver<Sequenced>, Replay) {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let rx = self.tx.subscribe();
+        let Some(last_event_id) = last_event_id else {
+            return (rx, Replay::Events(Vec::new()));
+        };
+        let Some(last) = self.parse_id(last_event_id) else {
+            return (rx, Replay::Gap);
+        };
+        let oldest = inner.buffer.front().map_or(inner.next_seq, |item| item.seq);
+        if last >= inner.next_seq || last + 1 < oldest {
+            return (rx, Replay::Gap);
+        }
+        let missed = inner
+            .buffer
+            .iter()
+            .filter(|item| item.seq > last)
+            .cloned()
+            .collect();
+        (rx, Replay::Events(missed))
+    }
+
+    /// The SSE `id` for an event.
+    pub fn event_id(&self, seq: u64) -> String {
+        format!("{}-{}", self.epoch, seq)
+    }
+
+    fn parse_id(&self, id: &str) -> Option<u64> {
+        let (epoch, seq) = id.trim().split_once('-')?;
+        if epoch.parse::<u64>().ok()? != self.epoch {
+            return None;
+        }
+        seq.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(count: u32) -> ServerEvent {
+        ServerEvent::PendingCount { count }
+    }
+
+    fn seqs(replay: Replay) -> Vec<u64> {
+        match replay {
+            Replay::Events(items) => items.into_iter().map(|i| i.seq).collect(),
+            Replay::Gap => panic!("unexpected gap"),
+        }
+    }
+
+    #[test]
+    fn fresh_subscribers_get_no_replay() {
+        let log = EventLog::new(7, 4);
+        log.publish(event(1));
+        let (_rx, replay) = log.resume(None);
+        assert!(seqs(replay).is_empty());
+    }
+
+    #[test]
+    fn replays_events_after_last_id() {
+        let log = EventLog::new(7, 4);
+        for n in 1..=3 {
+            log.publish(event(n));
+        }
+        let (_rx, replay) = log.resume(Some(&log.event_id(1)));
+        assert_eq!(seqs(replay), vec![2, 3]);
+        let (_rx, replay) = log.resume(Some("7-3"));
+        assert!(seqs(replay).is_empty());
+    }
+
+    #[test]
+    fn reports_gap_for_evicted_foreign_or_future_ids() {
+        let log = EventLog::new(7, 2);
+        for n in 1..=4 {
+            log.publish(event(n));
+        }
+        // Buffer holds 3 and 4; resuming after 2 is still complete.
+        assert_eq!(seqs(log.resume(Some("7-2")).1), vec![3, 4]);
+        assert!(matches!(log.resume(Some("7-1")).1, Replay::Gap));
+        assert!(matches!(log.resume(Some("6-3")).1, Replay::Gap));
+        assert!(matches!(log.resume(Some("7-9")).1, Replay::Gap));
+        assert!(matches!(log.resume(Some("garbage")).1, Replay::Gap));
+    }
+
+    #[test]
+    fn live_subscribers_receive_sequenced_events() {
+        let log = EventLog::new(7, 4);
+        let mut rx = log.subscribe();
+        log.publish(event(1));
+        log.publish(event(2));
+        assert_eq!(rx.try_recv().unwrap().seq, 1);
+        assert_eq!(rx.try_recv().unwrap().seq, 2);
+    }
+}
@@ -0,0 +1,292 @@
+//! Asymmetric JWT signing keys: generation, rotation and the keyring the
+//! server signs and verifies tokens with.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration as StdDuration;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{Duration, Utc};
+use gamiscreen_shared::jwt::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+    EllipticCurveKeyType, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+};
+use gamiscreen_shared::jwt::{
+    Jwk, JwkSet, JwtClaims, JwtError, KeySet, SigningAlgorithm, SigningKey,
+};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::pkcs8::{DecodePrivateKey, EncodePrivateKey};
+use rand_core::OsRng;
+
+use super::AppState;
+use super::auth::DEVICE_TOKEN_TTL_DAYS;
+use crate::storage::models::SigningKeyRow;
+use crate::storage::{StorageError, Store};
+
+/// How often the server reloads keys, picking up ones rotated by the CLI.
+const REFRESH_TICK: StdDuration = StdDuration::from_secs(60);
+
+/// Retired keys keep verifying for as long as the longest-lived token they
+/// may have signed stays valid.
+fn retired_key_retention() -> Duration {
+    Duration::days(DEVICE_TOKEN_TTL_DAYS)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyError {
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error("key {kid}: {message}")]
+    Invalid { kid: String, message: String },
+}
+
+impl KeyError {
+    fn invalid(kid: &str, message: impl ToString) -> Self {
+        Self::Invalid {
+            kid: kid.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Generate a new private key for `alg`, as PKCS#8 DER.
+fn generate(alg: SigningAlgorithm) -> Vec<u8> {
+    let der = match alg {
+        SigningAlgorithm::EdDSA => ed25519_dalek::SigningKey::generate(&mut OsRng).to_pkcs8_der(),
+        SigningAlgorithm::ES256 => p256::SecretKey::random(&mut OsRng).to_pkcs8_der(),
+    };
+    der.expect("freshly generated key encodes")
+        .as_bytes()
+        .to_vec()
+}
+
+/// The public half of a stored key, as published in the JWKS.
+fn public_jwk(kid: &str, alg: SigningAlgorithm, der: &[u8]) -> Result<Jwk, KeyError> {
+    let (key_algorithm, algorithm) = match alg {
+        SigningAlgorithm::EdDSA => {
+            let key = ed25519_dalek::SigningKey::from_pkcs8_der(der)
+                .map_err(|e| KeyError::invalid(kid, e))?;
+            let params = OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(key.verifying_key().as_bytes()),
+            };
+            (
+                KeyAlgorithm::EdDSA,
+                AlgorithmParameters::OctetKeyPair(params),
+            )
+        }
+        SigningAlgorithm::ES256 => {
+            let key =
+                p256::SecretKey::from_pkcs8_der(der).map_err(|e| KeyError::invalid(kid, e))?;
+            let point = key.public_key().to_encoded_point(false);
+            let (Some(x), Some(y)) = (point.x(), point.y()) else {
+                return Err(KeyError::invalid(kid, "public key has no coordinates"));
+            };
+            let params = EllipticCurveKeyParameters {
+                key_type: EllipticCurveKeyType::EC,
+                curve: EllipticCurve::P256,
+                x: URL_SAFE_NO_PAD.encode(x),
+                y: URL_SAFE_NO_PAD.encode(y),
+            };
+            (
+                KeyAlgorithm::ES256,
+                AlgorithmParameters::EllipticCurve(params),
+            )
+        }
+    };
+    Ok(Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm,
+    })
+}
+
+/// Create a signing key for `alg` and make it the active one. The previous
+/// key is retired but keeps verifying the tokens it signed. Returns the new
+/// key id.
+pub async fn rotate(store: &Store, alg: SigningAlgorithm) -> Result<String, KeyError> {
+    let kid = format!(
+        "{}-{}",
+        Utc::now().format("%Y%m%d"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    let der = generate(alg);
+    store.add_signing_key(&kid, alg.as_str(), &der).await?;
+    Ok(kid)
+}
+
+/// Stored signing keys, newest first, with their state.
+pub async fn list(store: &Store) -> Result<Vec<SigningKeyRow>, KeyError> {
+    Ok(store.list_signing_keys().await?)
+}
+
+/// The active signing key plus every key that may still verify tokens.
+pub struct Keyring {
+    signing: SigningKey,
+    verify: KeySet,
+    jwks: JwkSet,
+}
+
+impl Keyring {
+    /// Load keys from `store`, creating an EdDSA key first if none is active
+    /// and dropping retired keys past their retention. HS256 tokens signed
+    /// with `legacy_secret` keep verifying while it is configured.
+    pub async fn load(store: &Store, legacy_secret: Option<&str>) -> Result<Self, KeyError> {
+        let cutoff = (Utc::now() - retired_key_retention()).naive_utc();
+        let pruned = store.delete_signing_keys_retired_before(cutoff).await?;
+        if pruned > 0 {
+            tracing::info!(count = pruned, "keys: removed expired signing keys");
+        }
+
+        let mut rows = store.list_signing_keys().await?;
+        if !rows.iter().any(|r| r.retired_at.is_none()) {
+            let kid = rotate(store, SigningAlgorithm::EdDSA).await?;
+            tracing::info!(%kid, "keys: created signing key");
+            rows = store.list_signing_keys().await?;
+        }
+
+        let mut signing = None;
+        let mut jwks = JwkSet { keys: Vec::new() };
+        for row in &rows {
+            let alg: SigningAlgorithm = row
+                .alg
+                .parse()
+                .map_err(|e: JwtError| KeyError::invalid(&row.kid, e))?;
+            jwks.keys.push(public_jwk(&row.kid, alg, &row.private_key)?);
+            if row.retired_at.is_none() && signing.is_none() {
+                signing = Some(SigningKey::from_pkcs8_der(
+                    row.kid.clone(),
+                    alg,
+                    &row.private_key,
+                ));
+            }
+        }
+        let signing = signing.expect("an active key was ensured above");
+
+        let mut verify =
+            KeySet::from_jwks(&jwks).map_err(|e| KeyError::invalid(signing.kid(), e))?;
+        if let Some(secret) = legacy_secret {
+            verify = verify.with_hs256_secret(secret.as_bytes());
+        }
+        Ok(Self {
+            signing,
+            verify,
+            jwks,
+        })
+    }
+
+    pub fn signing_kid(&self) -> &str {
+        self.signing.kid()
+    }
+
+    pub fn sign(&self, claims: &JwtClaims) -> Result<String, JwtError> {
+        self.signing.sign(claims)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<JwtClaims, JwtError> {
+        self.verify.verify(token)
+    }
+
+    /// Public keys for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
+
+/// The keyring in use, loaded on first use and swapped on reload.
+#[derive(Default)]
+pub(crate) struct SharedKeyring {
+    current: RwLock<Option<Arc<Keyring>>>,
+    reload: tokio::sync::Mutex<()>,
+}
+
+impl SharedKeyring {
+    pub async fn get(
+        &self,
+        store: &Store,
+        legacy_secret: Option<&str>,
+    ) -> Result<Arc<Keyring>, KeyError> {
+        let current = self
+            .current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        match current {
+            Some(keyring) => Ok(keyring),
+            None => self.reload(store, legacy_secret).await,
+        }
+    }
+
+    pub async fn reload(
+        &self,
+        store: &Store,
+        legacy_secret: Option<&str>,
+    ) -> Result<Arc<Keyring>, KeyError> {
+        // One load at a time, so concurrent first uses create a single key.
+        let _guard = self.reload.lock().await;
+        let keyring = Arc::new(Keyring::load(store, legacy_secret).await?);
+        let previous = self
+            .current
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(keyring.clone());
+        if previous.is_none_or(|p| p.signing_kid() != keyring.signing_kid()) {
+            tracing::info!(kid = keyring.signing_kid(), "keys: signing with key");
+        }
+        Ok(keyring)
+    }
+}
+
+pub fn spawn_refresh_job(state: &AppState) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(REFRESH_TICK);
+        loop {
+            tokio::select! {
+                _ = state.shutdown.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            if let Err(e) = state.reload_keys().await {
+                tracing::error!(error = %e, "keys: reload failed");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_keys_sign_tokens_their_jwk_verifies() {
+        for alg in [SigningAlgorithm::EdDSA, SigningAlgorithm::ES256] {
+            let der = generate(alg);
+            let signing = SigningKey::from_pkcs8_der("k1", alg, &der);
+            let jwks = JwkSet {
+                keys: vec![public_jwk("k1", alg, &der).unwrap()],
+            };
+            let claims = JwtClaims {
+                sub: "parent".into(),
+                jti: "s1".into(),
+                exp: (Utc::now() + Duration::hours(1)).timestamp(),
+                role: gamiscreen_shared::auth::Role::Parent,
+                child_id: None,
+                device_id: None,
+                tenant_id: "t".into(),
+            };
+            let token = signing.sign(&claims).unwrap();
+            let verified = KeySet::from_jwks(&jwks).unwrap().verify(&token).unwrap();
+            assert_eq!(verified.jti, "s1", "{alg}");
+
+            let other = JwkSet {
+                keys: vec![public_jwk("k1", alg, &generate(alg)).unwrap()],
+            };
+            assert!(KeySet::from_jwks(&other).unwrap().verify(&token).is_err());
+        }
+    }
+}
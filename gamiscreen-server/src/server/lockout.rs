@@ -0,0 +1,122 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use gamiscreen_shared::api::{NotificationAudience, NotificationPayload};
+
+use super::config::UserConfig;
+use super::{AppError, AppState, Role};
+use crate::storage::models::NewLoginFailure;
+
+const PURGE_TICK: Duration = Duration::from_secs(60 * 60);
+/// How long failed logins are kept.
+const FAILURE_RETENTION_DAYS: i64 = 30;
+
+/// Refuse logins to `username` while it is locked after failed attempts.
+///
+/// Locked logins are refused before the password is checked, so guesses
+/// made during a lock tell nothing and do not extend it.
+pub(super) async fn check(state: &AppState, username: &str) -> Result<(), AppError> {
+    let lockout = state
+        .store
+        .get_login_lockout(username)
+        .await
+        .map_err(AppError::internal)?;
+    let now = chrono::Utc::now().naive_utc();
+    match lockout.and_then(|l| l.locked_until).filter(|t| *t > now) {
+        Some(until) => {
+            tracing::warn!(%username, %until, "login: account locked");
+            Err(AppError::TooManyRequests {
+                retry_after_secs: (until - now).num_seconds().max(0) as u64 + 1,
+            })
+        }
+        None => Ok(()),
+    }
+}
+
+/// Record a failed login of a configured user. Locks the account once it
+/// reaches `security.lockout_after` failures in a row, and tells parents
+/// when that happens to a child.
+pub(super) async fn record_failure(
+    state: &AppState,
+    user: &UserConfig,
+    ip: IpAddr,
+    reason: &'static str,
+) {
+    let security = state.config().security_config();
+    let failure = NewLoginFailure {
+        username: user.username.clone(),
+        ip: ip.to_string(),
+        reason,
+    };
+    let recorded = state
+        .store
+        .record_login_failure(
+            failure,
+            security.lockout_after,
+            chrono::Duration::seconds(security.lockout_secs as i64),
+            chrono::Duration::seconds(security.max_lockout_secs as i64),
+        )
+        .await;
+    let lockout = match recorded {
+        Ok((lockout, true)) => lockout,
+        Ok((_, false)) => return,
+        Err(e) => {
+            tracing::error!(username = %user.username, error = %e, "login: failed to record failure");
+            return;
+        }
+    };
+    let Some(until) = lockout.locked_until else {
+        return;
+    };
+    tracing::warn!(
+        username = %user.username,
+        %ip,
+        %until,
+        lockouts = lockout.lockouts,
+        "login: account locked after repeated failures"
+    );
+    if user.role == Role::Child
+        && let Some(child_id) = &user.child_id
+    {
+        state
+            .notify(
+                NotificationAudience::Parents,
+                child_id,
+                NotificationPayload::AccountLocked {
+                    username: user.username.clone(),
+                    locked_until: until.and_utc().to_rfc3339(),
+                },
+                None,
+            )
+            .await;
+    }
+}
+
+/// Reset the failure count of `username` after it logged in.
+pub(super) async fn record_success(state: &AppState, username: &str) {
+    if let Err(e) = state.store.clear_login_lockout(username).await {
+        tracing::error!(%username, error = %e, "login: failed to reset failures");
+    }
+}
+
+/// Spawn the job deleting old failed logins.
+pub fn spawn_purge_job(state: &AppState) {
+    let store = state.store.clone();
+    let shutdown = state.shutdown_token();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PURGE_TICK);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            let cutoff =
+                chrono::Utc::now().naive_utc() - chrono::Duration::days(FAILURE_RETENTION_DAYS);
+            match store.purge_login_failures(cutoff).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!(count = n, "login: purged old failed logins"),
+                Err(e) => tracing::error!(error = %e, "login: failed to purge failed logins"),
+            }
+        }
+    });
+}
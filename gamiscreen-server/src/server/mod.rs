@@ -1,72 +1,206 @@
 mod acl;
+mod agent_ws;
+mod attachments;
+mod audit;
 pub mod auth;
+mod chores;
+mod client_ip;
 mod config;
+mod events;
+pub mod keys;
+mod lockout;
+mod mqtt;
+mod oidc;
+mod openapi;
+mod pairing;
 mod push;
 mod rate_limit;
-
-use axum::extract::{Extension, Path, Query, State};
+mod reload;
+mod savings;
+mod sse_tickets;
+pub mod tls;
+pub mod totp;
+mod webhooks;
+
+use axum::extract::{DefaultBodyLimit, Extension, Path, Query, Request, State};
+use axum::handler::Handler;
 use axum::http::{HeaderName, HeaderValue, Method, StatusCode, header};
 use axum::response::Response as AxumResponse;
-use axum::response::sse::{Event, Sse};
-use axum::routing::{get, post};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{MethodRouter, get, post};
 use axum::{Json, Router, middleware};
 use bcrypt::verify;
-pub use config::{AppConfig, Role, UserConfig};
+pub use config::{
+    AppConfig, ChoreConfig, ChoreRepeat, ConfigError, ConfigIssue, IssueSeverity, MetricsConfig,
+    MqttConfig, OidcConfig, OidcUserMapping, Role, SavingsConfig, SecurityConfig, TlsConfig,
+    UserConfig, WebhookConfig,
+};
 use gamiscreen_shared::api::{ChildDto, ConfigResp};
 use gamiscreen_shared::{api, jwt};
 use mime_guess::from_path;
 use push::PushService;
+pub use reload::ReloadError;
 use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{Mutex, MutexGuard, broadcast};
+use tokio::sync::{Mutex, MutexGuard};
 use tokio_util::sync::CancellationToken;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{Span, info_span};
 use uuid::Uuid;
 
+use crate::metrics;
 use crate::server::auth::AuthCtx;
+use crate::server::client_ip::ClientIp;
 use crate::server::rate_limit::LoginRateLimiter;
+use crate::server::reload::Swap;
+use crate::storage::Inbox;
 
 const MAX_PUSH_SUBSCRIPTIONS_PER_CHILD: i64 = 10;
+const MAX_PENDING_TIME_REQUESTS_PER_CHILD: i64 = 3;
+const MAX_TIME_REQUEST_REASON_CHARS: usize = 200;
+const MAX_SUBMISSION_COMMENT_CHARS: usize = 200;
 
 type ChildCacheMap =
     std::sync::Arc<Mutex<std::collections::HashMap<String, std::sync::Arc<Mutex<Option<i32>>>>>>;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub config: AppConfig,
+    // Replaced when the config file is reloaded; see `AppState::config`
+    config: std::sync::Arc<Swap<AppConfig>>,
+    // File the config was loaded from, for reloads
+    config_path: Option<std::path::PathBuf>,
+    reload_lock: std::sync::Arc<Mutex<()>>,
     pub store: crate::storage::Store,
     // Cache of remaining minutes per child. None => needs recompute
     children_cache: ChildCacheMap,
-    // Broadcast notifications to connected websocket clients
-    notif_tx: broadcast::Sender<ServerEvent>,
+    // Numbered events for SSE, agent sockets and the MQTT bridge
+    events: std::sync::Arc<events::EventLog>,
     // Global shutdown token to allow canceling long-lived streams (e.g., SSE)
     pub shutdown: CancellationToken,
-    push: Option<PushService>,
+    push: std::sync::Arc<Swap<Option<PushService>>>,
+    webhooks: Option<webhooks::WebhookService>,
+    // OpenID Connect login, when configured
+    oidc: std::sync::Arc<Swap<Option<std::sync::Arc<oidc::OidcClient>>>>,
     // Per-IP rate limiter for login attempts
     login_limiter: std::sync::Arc<LoginRateLimiter>,
+    // Per-IP rate limiter for renewing tokens and registering devices
+    token_limiter: std::sync::Arc<LoginRateLimiter>,
+    // Proxies whose forwarding headers name the client address
+    trusted_proxies: std::sync::Arc<Swap<Vec<ipnet::IpNet>>>,
+    // JWT signing and verification keys
+    keys: std::sync::Arc<keys::SharedKeyring>,
+    // Single-use tickets that open an SSE stream
+    sse_tickets: std::sync::Arc<sse_tickets::SseTickets>,
+    // Codes parents create to pair a device with a child
+    pairing_codes: std::sync::Arc<pairing::PairingCodes>,
+    // Session ids (jti) revoked while the server runs, so streams opened by
+    // them can be closed
+    revoked_sessions: tokio::sync::broadcast::Sender<String>,
+    // Open device agent sockets, to notice devices going offline
+    agents: std::sync::Arc<agent_ws::AgentConnections>,
 }
 
 impl AppState {
     pub fn new(config: AppConfig, store: crate::storage::Store) -> Self {
-        let (notif_tx, _rx) = broadcast::channel(64);
         let push = PushService::from_config(&config);
+        let webhooks = webhooks::WebhookService::from_config(&config);
+        let oidc = oidc::OidcClient::from_config(&config).map(std::sync::Arc::new);
+        // Validated when the config was loaded
+        let trusted_proxies =
+            client_ip::parse_trusted_proxies(&config.security_config().trusted_proxies)
+                .unwrap_or_default();
         Self {
-            config,
+            config: std::sync::Arc::new(Swap::new(config)),
+            config_path: None,
+            reload_lock: Default::default(),
             store,
             children_cache: Default::default(),
-            notif_tx,
+            events: Default::default(),
             shutdown: CancellationToken::new(),
-            push,
+            push: std::sync::Arc::new(Swap::new(push)),
+            webhooks,
+            oidc: std::sync::Arc::new(Swap::new(oidc)),
             login_limiter: std::sync::Arc::new(LoginRateLimiter::default()),
+            token_limiter: std::sync::Arc::new(LoginRateLimiter::default()),
+            trusted_proxies: std::sync::Arc::new(Swap::new(trusted_proxies)),
+            keys: Default::default(),
+            sse_tickets: Default::default(),
+            pairing_codes: Default::default(),
+            revoked_sessions: tokio::sync::broadcast::channel(16).0,
+            agents: Default::default(),
         }
     }
 
+    /// Allow reloading the configuration from `path`, the file it was
+    /// loaded from.
+    pub fn with_config_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// The configuration currently in effect.
+    pub fn config(&self) -> std::sync::Arc<AppConfig> {
+        self.config.load()
+    }
+
     pub fn shutdown_token(&self) -> CancellationToken {
         self.shutdown.clone()
     }
 
+    /// The keyring tokens are signed and verified with, loaded on first use.
+    async fn keyring(&self) -> Result<std::sync::Arc<keys::Keyring>, AppError> {
+        self.keys
+            .get(&self.store, self.config().jwt_secret.as_deref())
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, "keys: failed to load signing keys");
+                AppError::internal(e)
+            })
+    }
+
+    /// Reload signing keys from storage, picking up keys rotated by
+    /// `gamiscreen-server keys rotate`.
+    pub async fn reload_keys(&self) -> Result<(), keys::KeyError> {
+        self.keys
+            .reload(&self.store, self.config().jwt_secret.as_deref())
+            .await?;
+        Ok(())
+    }
+
+    /// Delete a session and close any SSE streams it opened.
+    async fn revoke_session(&self, jti: &str) -> Result<bool, crate::storage::StorageError> {
+        let deleted = self.store.delete_session(jti).await?;
+        let _ = self.revoked_sessions.send(jti.to_string());
+        Ok(deleted)
+    }
+
+    /// Resolves once the session `jti` is revoked or has disappeared from the
+    /// store, or the server shuts down.
+    async fn session_ended(&self, jti: String) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut revoked = self.revoked_sessions.subscribe();
+        let mut check = tokio::time::interval(SSE_SESSION_CHECK_INTERVAL);
+        check.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return,
+                msg = revoked.recv() => match msg {
+                    Ok(id) if id == jti => return,
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                },
+                // Also catches sessions removed outside this process.
+                _ = check.tick() => {
+                    if let Ok(None) = self.store.get_session(&jti).await {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
     async fn child_mutex(&self, child_id: &str) -> std::sync::Arc<Mutex<Option<i32>>> {
         let mut map = self.children_cache.lock().await;
         map.entry(child_id.to_string())
@@ -133,13 +267,91 @@ impl AppState {
         Ok((effective, balance, !all_done))
     }
 
+    /// Number of items awaiting a parent decision (task submissions, transfers
+    /// and time requests).
+    async fn pending_notifications_count(&self) -> Result<u32, crate::storage::StorageError> {
+        let submissions = self.store.pending_submissions_count().await?;
+        let transfers = self.store.pending_transfers_count().await?;
+        let time_requests = self.store.pending_time_requests_count().await?;
+        let devices = self.store.pending_devices_count().await?;
+        Ok((submissions + transfers + time_requests + devices) as u32)
+    }
+
+    /// Store a notification and tell connected inbox owners about it.
+    ///
+    /// Errors are logged rather than returned: the action the notification
+    /// reports on has already been committed.
+    async fn notify(
+        &self,
+        audience: NotificationAudience,
+        child_id: &str,
+        payload: NotificationPayload,
+        source_id: Option<i32>,
+    ) {
+        let audience_str = match audience {
+            NotificationAudience::Parents => crate::storage::models::AUDIENCE_PARENTS,
+            NotificationAudience::Child => crate::storage::models::AUDIENCE_CHILD,
+        };
+        let stored = match serde_json::to_string(&payload) {
+            Ok(json) => {
+                self.store
+                    .create_notification(audience_str, child_id, payload.kind(), &json, source_id)
+                    .await
+            }
+            Err(e) => {
+                tracing::error!(error=%e, "notifications: failed to encode payload");
+                return;
+            }
+        };
+        match stored {
+            Ok(n) => self.dispatch_event(ServerEvent::NotificationCreated {
+                notification_id: n.id,
+                audience,
+                child_id: child_id.to_string(),
+                payload,
+            }),
+            Err(e) => {
+                tracing::error!(error=%e, kind=payload.kind(), %child_id, "notifications: failed to store");
+            }
+        }
+    }
+
+    /// Mark the parent notification for a handled submission or request as
+    /// resolved; see [`AppState::notify`] for error handling.
+    async fn resolve_notification(&self, kind: &str, source_id: i32) {
+        if let Err(e) = self.store.resolve_notifications(kind, source_id).await {
+            tracing::error!(error=%e, %kind, source_id, "notifications: failed to resolve");
+        }
+    }
+
+    /// Display name of a child, falling back to its id.
+    async fn child_display_name(&self, child_id: &str) -> String {
+        self.store
+            .list_children()
+            .await
+            .ok()
+            .and_then(|kids| kids.into_iter().find(|c| c.id == child_id))
+            .map(|c| c.display_name)
+            .unwrap_or_else(|| child_id.to_string())
+    }
+
     fn dispatch_event(&self, event: ServerEvent) {
-        let _ = self.notif_tx.send(event.clone());
-        if let Some(push) = &self.push {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch_server_event(self.store.clone(), &event);
+        }
+        self.events.publish(event.clone());
+        if let Some(push) = &*self.push.load() {
             push.dispatch_event(self.store.clone(), event);
         }
     }
 
+    /// Send an event that has no `ServerEvent` counterpart to subscribed webhooks.
+    fn emit_webhook(&self, event: &str, data: serde_json::Value) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.dispatch(self.store.clone(), event, data);
+        }
+    }
+
     pub async fn remaining_minutes(
         &self,
         child_id: &str,
@@ -166,55 +378,198 @@ impl AppState {
     }
 }
 
-use gamiscreen_shared::api::ServerEvent;
+use gamiscreen_shared::api::{NotificationAudience, NotificationPayload, ServerEvent};
+
+/// Start long-running background jobs (e.g. savings interest).
+///
+/// Jobs stop when the state's shutdown token is cancelled.
+pub fn spawn_background_jobs(state: &AppState) {
+    savings::spawn_interest_job(state);
+    attachments::spawn_purge_job(state);
+    chores::spawn_chore_job(state);
+    webhooks::spawn_delivery_job(state);
+    mqtt::spawn_bridge(state);
+    keys::spawn_refresh_job(state);
+    lockout::spawn_purge_job(state);
+}
 
 #[derive(Clone, Debug)]
 struct ReqId(pub String);
 
+/// Router builder that remembers every (method, path) it serves, so the
+/// OpenAPI document can be checked against the real route table.
+struct Routes {
+    router: Router<AppState>,
+    served: Vec<(Method, String)>,
+}
+
+impl Routes {
+    fn new() -> Self {
+        Self {
+            router: Router::new(),
+            served: Vec::new(),
+        }
+    }
+
+    fn get<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        self.route(Method::GET, path, get(handler))
+    }
+
+    fn post<H, T>(self, path: &str, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        self.route(Method::POST, path, post(handler))
+    }
+
+    fn route(mut self, method: Method, path: &str, route: MethodRouter<AppState>) -> Self {
+        self.served.push((method, path.to_string()));
+        self.router = self.router.route(path, route);
+        self
+    }
+
+    fn served_under(&self, prefix: &str) -> Vec<(Method, String)> {
+        self.served
+            .iter()
+            .map(|(method, path)| (method.clone(), format!("{prefix}{path}")))
+            .collect()
+    }
+}
+
 pub fn router(state: AppState) -> Router {
-    let tenant_scope = gamiscreen_shared::api::tenant_scope(&state.config.tenant_id);
+    build_router(state).0
+}
+
+/// Every (method, path) pair served by [`router`], excluding the embedded
+/// web app fallback.
+pub fn served_routes(state: &AppState) -> Vec<(Method, String)> {
+    build_router(state.clone()).1
+}
+
+fn build_router(state: AppState) -> (Router, Vec<(Method, String)>) {
+    // Photos are streamed through multipart; leave room for the form framing.
+    let attachment_limits = state.config().attachments_config();
+    let submit_body_limit = attachment_limits
+        .max_bytes
+        .saturating_mul(attachment_limits.max_per_submission)
+        .saturating_add(64 * 1024);
+    let tenant_scope = gamiscreen_shared::api::tenant_scope(&state.config().tenant_id);
     let api_v1_prefix = gamiscreen_shared::api::API_V1_PREFIX;
     let sse_path = format!("{}/sse", tenant_scope);
     let version_path = format!("{}/version", api_v1_prefix);
     let auth_login_path = format!("{}/auth/login", api_v1_prefix);
     let auth_renew_path = format!("{}/auth/renew", api_v1_prefix);
-
-    let tenant_private = Router::new()
-        .route("/children", get(api_list_children))
-        .route("/tasks", get(api_list_tasks))
-        .route("/notifications", get(api_list_notifications))
-        .route("/notifications/count", get(api_notifications_count))
-        .route(
+    let totp_path = format!("{}/auth/totp", api_v1_prefix);
+    let oidc_path = format!("{}/auth/oidc", api_v1_prefix);
+    let openapi_path = format!("{}/openapi.json", api_v1_prefix);
+
+    let tenant_private = Routes::new()
+        .get("/children", api_list_children)
+        .get("/tasks", api_list_tasks)
+        .get("/notifications", api_list_notifications)
+        .get("/notifications/count", api_notifications_count)
+        .post("/notifications/read-all", api_mark_all_notifications_read)
+        .post("/notifications/{id}/read", api_mark_notification_read)
+        .post("/notifications/{id}/dismiss", api_dismiss_notification)
+        .post(
             "/notifications/task-submissions/{id}/approve",
-            post(api_approve_submission),
+            api_approve_submission,
         )
-        .route(
+        .post(
             "/notifications/task-submissions/{id}/discard",
-            post(api_discard_submission),
+            api_discard_submission,
         )
-        .route("/children/{id}/remaining", get(api_remaining))
-        .route("/children/{id}/reward", post(api_child_reward))
-        .route("/children/{id}/reward", get(api_list_child_rewards))
-        .route("/children/{id}/usage", get(api_list_child_usage))
-        .route(
+        .post(
+            "/notifications/transfers/{id}/approve",
+            api_approve_transfer,
+        )
+        .post(
+            "/notifications/transfers/{id}/discard",
+            api_discard_transfer,
+        )
+        .post(
+            "/notifications/time-requests/{id}/approve",
+            api_approve_time_request,
+        )
+        .post(
+            "/notifications/time-requests/{id}/discard",
+            api_discard_time_request,
+        )
+        .post("/notifications/devices/{id}/approve", api_approve_device)
+        .post("/notifications/devices/{id}/discard", api_discard_device)
+        .get("/children/{id}/remaining", api_remaining)
+        .post("/children/{id}/reward", api_child_reward)
+        .get("/children/{id}/reward", api_list_child_rewards)
+        .get("/children/{id}/usage", api_list_child_usage)
+        .post("/children/{id}/transfers", api_child_transfer)
+        .get("/children/{id}/notifications", api_list_child_notifications)
+        .get(
+            "/children/{id}/notifications/count",
+            api_child_notifications_count,
+        )
+        .post(
+            "/children/{id}/notifications/read-all",
+            api_mark_all_child_notifications_read,
+        )
+        .post(
+            "/children/{id}/notifications/{notification_id}/read",
+            api_mark_child_notification_read,
+        )
+        .post(
+            "/children/{id}/notifications/{notification_id}/dismiss",
+            api_dismiss_child_notification,
+        )
+        .get("/children/{id}/time-requests", api_list_time_requests)
+        .post("/children/{id}/time-requests", api_create_time_request)
+        .get("/children/{id}/savings", api_child_savings)
+        .post("/children/{id}/savings/deposit", api_savings_deposit)
+        .post("/children/{id}/savings/withdraw", api_savings_withdraw)
+        .get("/children/{id}/savings/history", api_list_savings_history)
+        .post(
             "/children/{id}/device/{device_id}/heartbeat",
-            post(api_device_heartbeat),
+            api_device_heartbeat,
         )
-        .route(
-            "/children/{id}/push/subscriptions",
-            post(api_push_subscribe),
+        .get(
+            "/children/{id}/device/{device_id}/ws",
+            agent_ws::agent_socket,
         )
-        .route(
+        .post("/children/{id}/push/subscriptions", api_push_subscribe)
+        .post(
             "/children/{id}/push/subscriptions/unsubscribe",
-            post(api_push_unsubscribe),
+            api_push_unsubscribe,
+        )
+        .post("/children/{id}/register", api_child_register)
+        .post("/children/{id}/pairing-code", api_create_pairing_code)
+        .get("/children/{id}/devices", api_list_child_devices)
+        .get("/children/{id}/devices/{device_id}", api_get_child_device)
+        .post("/children/{id}/devices/{device_id}/block", api_block_device)
+        .post(
+            "/children/{id}/devices/{device_id}/unblock",
+            api_unblock_device,
         )
-        .route("/children/{id}/register", post(api_child_register))
-        .route("/children/{id}/tasks", get(api_list_child_tasks))
+        .get("/children/{id}/tasks", api_list_child_tasks)
+        .get("/children/{id}/submissions", api_list_child_submissions)
         .route(
+            Method::POST,
             "/children/{id}/tasks/{task_id}/submit",
-            post(api_submit_task),
+            post(api_submit_task).layer(DefaultBodyLimit::max(submit_body_limit)),
         )
-        .route("/config", get(api_config))
+        .get("/attachments/{id}", api_get_attachment)
+        .get("/audit", api_list_audit)
+        .get("/webhooks", api_list_webhooks)
+        .get("/webhooks/{id}/deliveries", api_list_webhook_deliveries)
+        .post("/webhooks/{id}/test", api_test_webhook)
+        .get("/config", api_config)
+        .post("/config/reload", api_reload_config)
+        .post("/sse/ticket", api_sse_ticket);
+    let mut served = tenant_private.served_under(&tenant_scope);
+    let tenant_private = tenant_private
+        .router
         .with_state(state.clone())
         // IMPORTANT: Last-added layer runs first on request. We want:
         // require_bearer -> enforce_acl -> set_auth_span_fields -> handler
@@ -250,13 +605,20 @@ pub fn router(state: AppState) -> Router {
         )
     });
 
-    // Public SSE route for push notifications (token passed via query)
-    let sse = Router::new()
-        .route(&sse_path, get(sse_notifications))
-        .with_state(state.clone());
-
-    let auth_router = Router::new()
-        .route(&auth_renew_path, post(api_auth_renew))
+    // Public SSE route; authenticated by a ticket in the query string
+    let sse = Routes::new().get(&sse_path, sse_notifications);
+    served.extend(sse.served_under(""));
+    let sse = sse.router.with_state(state.clone());
+
+    let auth_router = Routes::new()
+        .post(&auth_renew_path, api_auth_renew)
+        .get(&totp_path, api_totp_status)
+        .post(&format!("{totp_path}/enroll"), api_totp_enroll)
+        .post(&format!("{totp_path}/verify"), api_totp_verify)
+        .post(&format!("{totp_path}/disable"), api_totp_disable);
+    served.extend(auth_router.served_under(""));
+    let auth_router = auth_router
+        .router
         .with_state(state.clone())
         .layer(middleware::from_fn_with_state(
             state.clone(),
@@ -264,11 +626,23 @@ pub fn router(state: AppState) -> Router {
         ))
         .layer(middleware::from_fn(set_auth_span_fields));
 
-    let app = Router::new()
-        .route("/healthz", get(health))
-        .route("/api/version", get(api_version))
-        .route(&version_path, get(api_version))
-        .route(&auth_login_path, post(api_auth_login))
+    let public = Routes::new()
+        .get("/healthz", health)
+        .get("/metrics", metrics_endpoint)
+        .get("/api/version", api_version)
+        .get(&version_path, api_version)
+        .get(&openapi_path, api_openapi)
+        .get("/.well-known/jwks.json", api_jwks)
+        .post(&auth_login_path, api_auth_login)
+        .post(&format!("{api_v1_prefix}/auth/pair"), api_auth_pair)
+        .get(&format!("{api_v1_prefix}/auth/options"), api_auth_options)
+        .get(&format!("{oidc_path}/login"), api_oidc_login)
+        .get(&format!("{oidc_path}/callback"), api_oidc_callback)
+        .post(&format!("{oidc_path}/exchange"), api_oidc_exchange);
+    served.extend(public.served_under(""));
+
+    let app = public
+        .router
         .merge(auth_router)
         .merge(sse)
         .merge(private)
@@ -280,7 +654,7 @@ pub fn router(state: AppState) -> Router {
 
     // Optionally add CORS for dev if configured
 
-    if let Some(origin_cfg) = &state.config.dev_cors_origin {
+    if let Some(origin_cfg) = &state.config().dev_cors_origin {
         let mut origins: Vec<header::HeaderValue> = origin_cfg
             .split(',')
             .map(|s| s.trim())
@@ -298,9 +672,9 @@ pub fn router(state: AppState) -> Router {
             .allow_origin(AllowOrigin::list(origins))
             .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
             .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE]);
-        app.layer(cors)
+        (app.layer(cors), served)
     } else {
-        app
+        (app, served)
     }
 }
 
@@ -308,6 +682,58 @@ async fn health() -> &'static str {
     "ok"
 }
 
+/// Prometheus scrape endpoint; 404 unless enabled, and 401 without the
+/// configured bearer token.
+async fn metrics_endpoint(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> Result<AxumResponse, AppError> {
+    let config = state.config();
+    let Some(cfg) = config.metrics_config() else {
+        return Err(AppError::not_found("metrics are disabled"));
+    };
+    if let Some(token) = &cfg.token {
+        let provided = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+        if !provided.is_some_and(|p| constant_time_eq(p.as_bytes(), token.as_bytes())) {
+            return Err(AppError::unauthorized());
+        }
+    }
+
+    let m = metrics::metrics();
+    for child in &state.config().children {
+        match state.compute_child_status(&child.id).await {
+            Ok((effective, _, _)) => m
+                .remaining_minutes
+                .with_label_values(&[child.id.as_str()])
+                .set(effective as i64),
+            Err(e) => {
+                tracing::warn!(child_id = %child.id, error = ?e, "metrics: failed to compute remaining")
+            }
+        }
+    }
+    Ok(axum::response::IntoResponse::into_response((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        m.render(),
+    )))
+}
+
+/// Compare secrets without leaking the position of the first mismatch.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn api_openapi(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let tenant_scope = gamiscreen_shared::api::tenant_scope(&state.config().tenant_id);
+    Json(openapi::document(&tenant_scope))
+}
+
+async fn api_jwks(State(state): State<AppState>) -> Result<Json<jwt::JwkSet>, AppError> {
+    Ok(Json(state.keyring().await?.jwks().clone()))
+}
+
 async fn api_version() -> Result<Json<api::VersionInfoDto>, AppError> {
     let v = env!("CARGO_PKG_VERSION").to_string();
     Ok(Json(api::VersionInfoDto { version: v }))
@@ -462,9 +888,33 @@ async fn api_list_child_tasks(
         .list_tasks_with_last_done(&id)
         .await
         .map_err(AppError::internal)?;
+    let now = chrono::Utc::now().naive_utc();
+    let mut chores: std::collections::HashMap<String, Vec<api::ChoreInstanceDto>> =
+        std::collections::HashMap::new();
+    for c in state
+        .store
+        .list_chore_instances_for_child(&id, now - chrono::Duration::days(7))
+        .await
+        .map_err(AppError::internal)?
+    {
+        let open = c.status == crate::storage::models::CHORE_OPEN;
+        chores
+            .entry(c.task_id)
+            .or_default()
+            .push(api::ChoreInstanceDto {
+                id: c.id,
+                chore_id: c.chore_id,
+                due_at: c.due_at.and_utc().to_rfc3339(),
+                available: open && c.available_from <= now,
+                overdue: open && c.due_at <= now,
+                status: c.status,
+                penalty_minutes: c.penalty_minutes,
+            });
+    }
     let items = rows
         .into_iter()
         .map(|(t, last)| api::TaskWithStatusDto {
+            chores: chores.remove(&t.id).unwrap_or_default(),
             id: t.id,
             name: t.name,
             minutes: t.minutes,
@@ -520,21 +970,39 @@ struct ChildTaskPath {
 async fn api_child_reward(
     State(state): State<AppState>,
     Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
     Path(p): Path<ChildPathId>,
     Json(body): Json<api::RewardReq>,
 ) -> Result<Json<api::RewardResp>, AppError> {
+    grant_reward(&state, &auth, &req_id, &p.id, body)
+        .await
+        .map(Json)
+}
+
+/// Grant (or, for negative minutes, deduct) minutes for a child on behalf of
+/// `auth`. Shared by the HTTP endpoint and the MQTT bridge.
+async fn grant_reward(
+    state: &AppState,
+    auth: &AuthCtx,
+    req_id: &ReqId,
+    child_id: &str,
+    body: api::RewardReq,
+) -> Result<api::RewardResp, AppError> {
     // Verify the child exists before processing the reward
     let exists = state
         .store
-        .child_exists(&p.id)
+        .child_exists(child_id)
         .await
         .map_err(AppError::internal)?;
     if !exists {
-        return Err(AppError::bad_request(format!("unknown child: {}", p.id)));
+        return Err(AppError::bad_request(format!(
+            "unknown child: {}",
+            child_id
+        )));
     }
 
     // Invalidate cache for this child; compute after DB update
-    let child_mutex = state.child_mutex(&p.id).await;
+    let child_mutex = state.child_mutex(child_id).await;
     let mut child_guard = child_mutex.lock().await;
 
     // Determine minutes and description rules:
@@ -590,10 +1058,11 @@ async fn api_child_reward(
         .task_id
         .as_deref()
         .map(|tid| (tid, auth.claims.sub.as_str()));
+    let before = audit::balance_snapshot(state, child_id).await;
     let new_remaining = state
         .store
         .add_reward_minutes(
-            &p.id,
+            child_id,
             mins,
             body.task_id.as_deref(),
             Some(desc_to_store.as_str()),
@@ -603,21 +1072,96 @@ async fn api_child_reward(
         .await
         .map_err(AppError::internal)?;
     *child_guard = Some(new_remaining);
+    metrics::metrics().record_reward(mins);
 
-    let (effective, balance, blocked) = state.compute_child_status(&p.id).await?;
+    let (effective, balance, blocked) = state.compute_child_status(child_id).await?;
 
     let event = ServerEvent::RemainingUpdated {
-        child_id: p.id.clone(),
+        child_id: child_id.to_string(),
         remaining_minutes: effective,
         balance,
         blocked_by_tasks: blocked,
     };
     state.dispatch_event(event);
+    audit::record(
+        state,
+        auth,
+        req_id,
+        audit::AuditEvent {
+            action: if mins < 0 {
+                "reward.penalty"
+            } else {
+                "reward.grant"
+            },
+            child_id: Some(child_id),
+            target_id: None,
+            before,
+            after: Some(serde_json::json!({
+                "remaining_minutes": new_remaining,
+                "balance": balance,
+                "minutes": mins,
+                "task_id": body.task_id,
+                "description": desc_to_store,
+                "is_borrowed": is_borrowed,
+            })),
+        },
+    )
+    .await;
+    state.emit_webhook(
+        "reward_granted",
+        serde_json::json!({
+            "child_id": child_id,
+            "minutes": mins,
+            "task_id": body.task_id,
+            "description": desc_to_store,
+            "is_borrowed": is_borrowed,
+            "by": auth.claims.sub,
+            "remaining_minutes": effective,
+        }),
+    );
+    state
+        .notify(
+            NotificationAudience::Child,
+            child_id,
+            NotificationPayload::RewardGranted {
+                minutes: mins,
+                description: Some(desc_to_store),
+                is_borrowed,
+                by: auth.claims.sub.clone(),
+            },
+            None,
+        )
+        .await;
+    if is_borrowed {
+        notify_debt_limit(state, child_id, mins, balance).await;
+    }
 
-    Ok(Json(api::RewardResp {
+    Ok(api::RewardResp {
         remaining_minutes: effective,
         balance,
-    }))
+    })
+}
+
+/// Tell parents when lending `lent` minutes took a child's account balance
+/// to `debt_limit_minutes`. Only the loan that crosses the limit notifies.
+async fn notify_debt_limit(state: &AppState, child_id: &str, lent: i32, balance: i32) {
+    let Some(limit) = state.config().debt_limit_minutes else {
+        return;
+    };
+    let limit = i32::try_from(limit).unwrap_or(i32::MAX);
+    // A loan lowers the balance by the minutes lent
+    let before = balance.saturating_add(lent);
+    if balance > -limit || before <= -limit {
+        return;
+    }
+    state
+        .notify(
+            NotificationAudience::Parents,
+            child_id,
+            NotificationPayload::DebtLimitReached { balance, limit },
+            None,
+        )
+        .await;
 }
 
 async fn api_push_subscribe(
@@ -644,7 +1188,8 @@ async fn api_push_subscribe(
         return Err(AppError::not_found(format!("child not found: {}", p.id)));
     }
 
-    let tenant_id = state.config.tenant_id.as_str();
+    let config = state.config();
+    let tenant_id = config.tenant_id.as_str();
 
     let existing = state
         .store
@@ -691,7 +1236,7 @@ async fn api_push_unsubscribe(
     }
     state
         .store
-        .delete_push_subscription(state.config.tenant_id.as_str(), &p.id, &body.endpoint)
+        .delete_push_subscription(state.config().tenant_id.as_str(), &p.id, &body.endpoint)
         .await
         .map_err(AppError::internal)?;
     Ok(StatusCode::NO_CONTENT)
@@ -702,7 +1247,7 @@ async fn api_config(
     Extension(_auth): Extension<AuthCtx>,
 ) -> Result<Json<ConfigResp>, AppError> {
     let push_key = state
-        .config
+        .config()
         .push
         .as_ref()
         .filter(|cfg| cfg.enabled)
@@ -716,13 +1261,42 @@ async fn api_config(
     }))
 }
 
-#[derive(Deserialize)]
+/// Read the config file again and apply it without a restart.
+async fn api_reload_config(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+) -> Result<Json<api::ConfigReloadResp>, AppError> {
+    let report = state.reload_config().await.map_err(|e| match e {
+        ReloadError::Storage(e) => AppError::internal(e),
+        e => {
+            tracing::warn!(error = %e, "config: reload rejected");
+            AppError::bad_request(e.to_string())
+        }
+    })?;
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "config.reload",
+            child_id: None,
+            target_id: None,
+            before: None,
+            after: Some(reload::report_value(&report)),
+        },
+    )
+    .await;
+    Ok(Json(report))
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
 struct PageOpts {
     page: Option<usize>,
     per_page: Option<usize>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, schemars::JsonSchema)]
 struct UsageOpts {
     days: Option<u32>,
     bucket_minutes: Option<u32>,
@@ -828,154 +1402,625 @@ async fn api_list_child_usage(
     Ok(Json(dto))
 }
 
-// Use shared DTOs
-use gamiscreen_shared::api::{NotificationItemDto, NotificationsCountDto};
+fn to_rfc3339(dt: chrono::NaiveDateTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(dt, chrono::Utc).to_rfc3339()
+}
 
-async fn api_notifications_count(
+async fn savings_dto(
+    state: &AppState,
+    child_id: &str,
+    row: crate::storage::models::Balance,
+) -> Result<api::SavingsDto, AppError> {
+    let (effective, _balance, _blocked) = state.compute_child_status(child_id).await?;
+    let weekly_interest_percent = state
+        .config()
+        .savings_config()
+        .map(|c| c.weekly_interest_percent)
+        .unwrap_or(0.0);
+    Ok(api::SavingsDto {
+        child_id: child_id.to_string(),
+        savings_minutes: row.savings_minutes,
+        remaining_minutes: effective,
+        locked_until: row
+            .savings_locked_until
+            .filter(|t| *t > chrono::Utc::now().naive_utc())
+            .map(to_rfc3339),
+        weekly_interest_percent,
+        next_interest_at: row
+            .savings_interest_at
+            .map(|t| to_rfc3339(t + chrono::Duration::weeks(1))),
+    })
+}
+
+async fn api_child_savings(
     State(state): State<AppState>,
-    Extension(auth): Extension<AuthCtx>,
-) -> Result<Json<NotificationsCountDto>, AppError> {
-    if auth.claims.role != Role::Parent {
-        return Err(AppError::forbidden());
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+) -> Result<Json<api::SavingsDto>, AppError> {
+    let exists = state
+        .store
+        .child_exists(&p.id)
+        .await
+        .map_err(AppError::internal)?;
+    if !exists {
+        return Err(AppError::not_found(format!("child not found: {}", p.id)));
     }
-    let c = state
+    let row = state
         .store
-        .pending_submissions_count()
+        .get_savings(&p.id)
         .await
         .map_err(AppError::internal)?;
-    Ok(Json(NotificationsCountDto { count: c as u32 }))
-}
-
-#[derive(Deserialize)]
-struct SseQuery {
-    token: String,
+    Ok(Json(savings_dto(&state, &p.id, row).await?))
 }
 
-async fn sse_notifications(
+async fn api_savings_deposit(
     State(state): State<AppState>,
-    Query(q): Query<SseQuery>,
-) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, AppError> {
-    // Validate token from query
-    // NOTE: SSE auth does not consult or touch the sessions table; it only verifies the JWT.
-    let claims = jwt::decode_and_verify(&q.token, state.config.jwt_secret.as_bytes())
-        .map_err(|_| AppError::unauthorized())?;
-    // Access control
-    crate::server::acl::validate_ws_access_from_claims(&claims)?;
-
-    use futures::StreamExt;
-    use tokio_stream::wrappers::BroadcastStream;
-
-    // Prepare a stream that first sends initial snapshot, then relays broadcasted events
-    // Prepare initial snapshot
-    let mut init_items: Vec<ServerEvent> = Vec::new();
-    if claims.role == Role::Parent {
-        if let Ok(c) = state.store.pending_submissions_count().await {
-            init_items.push(ServerEvent::PendingCount { count: c as u32 });
-        }
-    } else if claims.role == Role::Child
-        && let Some(cid) = claims.child_id.clone()
-    {
-        let child_mutex = state.child_mutex(&cid).await;
-        let mut guard = child_mutex.lock().await;
-        if let Ok(rem) = state.remaining_minutes(&cid, &mut guard).await {
-            let balance = state.store.get_balance(&cid).await.unwrap_or(0);
-            let blocked = !state
-                .store
-                .all_required_tasks_done_today(&cid)
-                .await
-                .unwrap_or(false); // on failure, assume tasks NOT done (blocked)
-            init_items.push(ServerEvent::RemainingUpdated {
-                child_id: cid.clone(),
-                remaining_minutes: rem,
-                balance,
-                blocked_by_tasks: blocked,
-            });
-        }
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+    Json(body): Json<api::SavingsReq>,
+) -> Result<Json<api::SavingsDto>, AppError> {
+    let config = state.config();
+    let Some(cfg) = config.savings_config() else {
+        return Err(AppError::bad_request("savings are disabled"));
+    };
+    let exists = state
+        .store
+        .child_exists(&p.id)
+        .await
+        .map_err(AppError::internal)?;
+    if !exists {
+        return Err(AppError::not_found(format!("child not found: {}", p.id)));
     }
+    let lock_in = (cfg.lock_in_days > 0).then(|| chrono::Duration::days(cfg.lock_in_days.into()));
 
-    let rx = state.notif_tx.subscribe();
-    let bstream = BroadcastStream::new(rx)
-        .filter_map(move |msg| {
-            let claims2 = claims.clone();
-            futures::future::ready(match msg {
-                Ok(ev) => match (&claims.role, &ev) {
-                    (Role::Parent, _) => Some(ev),
-                    (Role::Child, ServerEvent::RemainingUpdated { child_id, .. }) => {
-                        if let Some(cid) = &claims2.child_id {
-                            if cid == child_id { Some(ev) } else { None }
-                        } else {
-                            None
-                        }
-                    }
-                    (Role::Child, ServerEvent::PendingCount { .. }) => None,
-                },
-                Err(_) => None,
-            })
-        })
-        .map(|ev| Ok(Event::default().data(serde_json::to_string(&ev).unwrap())));
+    let child_mutex = state.child_mutex(&p.id).await;
+    let mut child_guard = child_mutex.lock().await;
+    let row = state
+        .store
+        .deposit_savings(&p.id, body.minutes, lock_in)
+        .await
+        .map_err(AppError::from_storage)?;
+    *child_guard = Some(row.minutes_remaining);
+    drop(child_guard);
 
-    let init_stream = futures::stream::iter(
-        init_items
-            .into_iter()
-            .map(|ev| Ok(Event::default().data(serde_json::to_string(&ev).unwrap()))),
-    );
-    let stream = init_stream
-        .chain(bstream)
-        .take_until(state.shutdown.clone().cancelled_owned());
-    Ok(Sse::new(stream))
+    dispatch_remaining_updated(&state, &p.id).await?;
+    Ok(Json(savings_dto(&state, &p.id, row).await?))
 }
 
-async fn api_list_notifications(
+async fn api_savings_withdraw(
     State(state): State<AppState>,
     Extension(auth): Extension<AuthCtx>,
-) -> Result<Json<Vec<NotificationItemDto>>, AppError> {
-    if auth.claims.role != Role::Parent {
-        return Err(AppError::forbidden());
+    Path(p): Path<ChildPathId>,
+    Json(body): Json<api::SavingsReq>,
+) -> Result<Json<api::SavingsDto>, AppError> {
+    let exists = state
+        .store
+        .child_exists(&p.id)
+        .await
+        .map_err(AppError::internal)?;
+    if !exists {
+        return Err(AppError::not_found(format!("child not found: {}", p.id)));
     }
+    // Parents may break the lock-in period; children have to wait it out.
+    let ignore_lock = auth.claims.role == Role::Parent;
+
+    let child_mutex = state.child_mutex(&p.id).await;
+    let mut child_guard = child_mutex.lock().await;
+    let row = state
+        .store
+        .withdraw_savings(&p.id, body.minutes, ignore_lock)
+        .await
+        .map_err(AppError::from_storage)?;
+    *child_guard = Some(row.minutes_remaining);
+    drop(child_guard);
+
+    dispatch_remaining_updated(&state, &p.id).await?;
+    Ok(Json(savings_dto(&state, &p.id, row).await?))
+}
+
+async fn api_list_savings_history(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+    Query(opts): Query<PageOpts>,
+) -> Result<Json<Vec<api::SavingsTransactionDto>>, AppError> {
+    let page = opts.page.unwrap_or(1);
+    let per_page = opts.per_page.unwrap_or(10);
     let rows = state
         .store
-        .list_pending_submissions()
+        .list_savings_transactions(&p.id, page, per_page)
         .await
         .map_err(AppError::internal)?;
     let items = rows
         .into_iter()
-        .map(|(s, c, t)| NotificationItemDto {
-            id: s.id,
-            kind: "task_submission".to_string(),
-            child_id: c.id,
-            child_display_name: c.display_name,
-            task_id: t.id,
-            task_name: t.name,
-            submitted_at: chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(
-                s.submitted_at,
-                chrono::Utc,
-            )
-            .to_rfc3339(),
+        .map(|t| api::SavingsTransactionDto {
+            time: to_rfc3339(t.created_at),
+            kind: t.kind,
+            amount: t.amount,
+            savings_after: t.savings_after,
         })
         .collect();
     Ok(Json(items))
 }
 
-async fn api_approve_submission(
-    State(state): State<AppState>,
-    Extension(auth): Extension<AuthCtx>,
-    Path(id): Path<i32>,
-) -> Result<StatusCode, AppError> {
-    if auth.claims.role != Role::Parent {
-        return Err(AppError::forbidden());
-    }
-    let result = state
-        .store
-        .approve_submission(id, &auth.claims.sub)
-        .await
-        .map_err(AppError::internal)?;
-    if let Some((child_id, new_remaining)) = result {
-        let child_mutex = state.child_mutex(&child_id).await;
-        let mut child_guard = child_mutex.lock().await;
-        *child_guard = Some(new_remaining);
-        drop(child_guard);
-
-        let (effective, balance, blocked) = state.compute_child_status(&child_id).await?;
+/// Recompute a child's status and broadcast `RemainingUpdated`.
+async fn dispatch_remaining_updated(state: &AppState, child_id: &str) -> Result<(), AppError> {
+    let (effective, balance, blocked) = state.compute_child_status(child_id).await?;
+    state.dispatch_event(ServerEvent::RemainingUpdated {
+        child_id: child_id.to_string(),
+        remaining_minutes: effective,
+        balance,
+        blocked_by_tasks: blocked,
+    });
+    Ok(())
+}
+
+// Use shared DTOs
+use gamiscreen_shared::api::{NotificationItemDto, NotificationsCountDto};
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct NotificationsQuery {
+    page: Option<usize>,
+    per_page: Option<usize>,
+    #[serde(default)]
+    unread_only: bool,
+    /// Also list parent notifications that were already approved or discarded.
+    #[serde(default)]
+    include_resolved: bool,
+}
+
+#[derive(Deserialize)]
+struct ChildNotificationPath {
+    id: String,
+    notification_id: i32,
+}
+
+fn parent_inbox(auth: &AuthCtx) -> Result<Inbox, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    Ok(Inbox::parents(&auth.claims.sub))
+}
+
+async fn inbox_count(state: &AppState, inbox: &Inbox) -> Result<NotificationsCountDto, AppError> {
+    let kinds = state
+        .store
+        .unread_notification_kinds(inbox)
+        .await
+        .map_err(AppError::internal)?;
+    let mut by_kind = std::collections::BTreeMap::new();
+    for kind in kinds {
+        *by_kind.entry(kind).or_insert(0u32) += 1;
+    }
+    Ok(NotificationsCountDto {
+        count: by_kind.values().sum(),
+        by_kind,
+    })
+}
+
+async fn inbox_list(
+    state: &AppState,
+    inbox: &Inbox,
+    q: NotificationsQuery,
+) -> Result<Vec<NotificationItemDto>, AppError> {
+    let rows = state
+        .store
+        .list_notifications(
+            inbox,
+            q.page.unwrap_or(1),
+            q.per_page.unwrap_or(50),
+            q.unread_only,
+            q.include_resolved,
+        )
+        .await
+        .map_err(AppError::internal)?;
+    let submission_ids: Vec<i32> = rows
+        .iter()
+        .filter(|(n, _, _)| n.kind == "task_submission")
+        .filter_map(|(n, _, _)| n.source_id)
+        .collect();
+    let mut photos: std::collections::HashMap<i32, Vec<api::AttachmentDto>> =
+        std::collections::HashMap::new();
+    if !submission_ids.is_empty() {
+        for a in state
+            .store
+            .list_submission_attachments(submission_ids)
+            .await
+            .map_err(AppError::internal)?
+        {
+            photos
+                .entry(a.submission_id)
+                .or_default()
+                .push(api::AttachmentDto {
+                    id: a.id,
+                    content_type: a.content_type,
+                    size_bytes: a.size_bytes,
+                });
+        }
+    }
+    Ok(rows
+        .into_iter()
+        .filter_map(|(n, child, read_at)| {
+            let payload = match serde_json::from_str::<NotificationPayload>(&n.payload) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::warn!(id = n.id, error=%e, "notifications: skipping unreadable payload");
+                    return None;
+                }
+            };
+            let attachments = match (n.kind.as_str(), n.source_id) {
+                ("task_submission", Some(sid)) => photos.remove(&sid).unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            Some(NotificationItemDto {
+                id: n.id,
+                kind: n.kind,
+                child_id: child.id,
+                child_display_name: child.display_name,
+                payload,
+                created_at: to_rfc3339(n.created_at),
+                read: read_at.is_some(),
+                resolved: n.resolved_at.is_some(),
+                attachments,
+            })
+        })
+        .collect())
+}
+
+async fn api_notifications_count(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+) -> Result<Json<NotificationsCountDto>, AppError> {
+    let inbox = parent_inbox(&auth)?;
+    Ok(Json(inbox_count(&state, &inbox).await?))
+}
+
+async fn api_list_notifications(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Query(q): Query<NotificationsQuery>,
+) -> Result<Json<Vec<NotificationItemDto>>, AppError> {
+    let inbox = parent_inbox(&auth)?;
+    Ok(Json(inbox_list(&state, &inbox, q).await?))
+}
+
+async fn api_mark_notification_read(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let inbox = parent_inbox(&auth)?;
+    mark_notification(&state, &inbox, id, false).await
+}
+
+async fn api_dismiss_notification(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    let inbox = parent_inbox(&auth)?;
+    mark_notification(&state, &inbox, id, true).await
+}
+
+async fn api_mark_all_notifications_read(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+) -> Result<StatusCode, AppError> {
+    let inbox = parent_inbox(&auth)?;
+    state
+        .store
+        .mark_all_notifications_read(&inbox)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn api_child_notifications_count(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+) -> Result<Json<NotificationsCountDto>, AppError> {
+    Ok(Json(inbox_count(&state, &Inbox::child(&p.id)).await?))
+}
+
+async fn api_list_child_notifications(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+    Query(q): Query<NotificationsQuery>,
+) -> Result<Json<Vec<NotificationItemDto>>, AppError> {
+    Ok(Json(inbox_list(&state, &Inbox::child(&p.id), q).await?))
+}
+
+async fn api_mark_child_notification_read(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildNotificationPath>,
+) -> Result<StatusCode, AppError> {
+    mark_notification(&state, &Inbox::child(&p.id), p.notification_id, false).await
+}
+
+async fn api_dismiss_child_notification(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildNotificationPath>,
+) -> Result<StatusCode, AppError> {
+    mark_notification(&state, &Inbox::child(&p.id), p.notification_id, true).await
+}
+
+async fn api_mark_all_child_notifications_read(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+) -> Result<StatusCode, AppError> {
+    state
+        .store
+        .mark_all_notifications_read(&Inbox::child(&p.id))
+        .await
+        .map_err(AppError::internal)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn mark_notification(
+    state: &AppState,
+    inbox: &Inbox,
+    id: i32,
+    dismiss: bool,
+) -> Result<StatusCode, AppError> {
+    let found = state
+        .store
+        .mark_notification(inbox, id, dismiss)
+        .await
+        .map_err(AppError::internal)?;
+    if !found {
+        return Err(AppError::not_found(format!("notification not found: {id}")));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct SseQuery {
+    /// Single-use ticket from `POST {tenant}/sse/ticket`.
+    ticket: String,
+    /// Fallback for the `Last-Event-ID` header, for clients that reconnect
+    /// with a new `EventSource` and so cannot send it.
+    last_event_id: Option<String>,
+}
+
+/// Reconnect delay suggested to SSE clients.
+const SSE_RETRY: std::time::Duration = std::time::Duration::from_secs(3);
+/// How often an open SSE stream checks that its session still exists.
+const SSE_SESSION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+async fn api_sse_ticket(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+) -> Result<Json<api::SseTicketResp>, AppError> {
+    let ticket = state.sse_tickets.issue(auth.claims);
+    Ok(Json(api::SseTicketResp {
+        ticket,
+        expires_in_secs: state.sse_tickets.ttl().as_secs() as u32,
+    }))
+}
+
+async fn sse_notifications(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<SseQuery>,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>>, AppError> {
+    let claims = state
+        .sse_tickets
+        .redeem(&q.ticket)
+        .ok_or_else(AppError::unauthorized)?;
+    // The session may have been revoked since the ticket was issued.
+    if state
+        .store
+        .get_session(&claims.jti)
+        .await
+        .map_err(AppError::internal)?
+        .is_none()
+    {
+        return Err(AppError::unauthorized());
+    }
+    crate::server::acl::validate_ws_access_from_claims(&claims)?;
+
+    use futures::StreamExt;
+    use tokio_stream::wrappers::BroadcastStream;
+    use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+
+    // Subscribe before taking the snapshot so no event falls in between.
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .or(q.last_event_id.as_deref());
+    let (rx, replay) = state.events.resume(last_event_id);
+
+    // The stream sends the retry hint, missed events (or a resync), the
+    // current snapshot, then relays live events.
+    let mut init_items: Vec<ServerEvent> = Vec::new();
+    if claims.role == Role::Parent {
+        if let Ok(count) = state.pending_notifications_count().await {
+            init_items.push(ServerEvent::PendingCount { count });
+        }
+    } else if claims.role == Role::Child
+        && let Some(cid) = claims.child_id.clone()
+    {
+        let child_mutex = state.child_mutex(&cid).await;
+        let mut guard = child_mutex.lock().await;
+        if let Ok(rem) = state.remaining_minutes(&cid, &mut guard).await {
+            let balance = state.store.get_balance(&cid).await.unwrap_or(0);
+            let blocked = !state
+                .store
+                .all_required_tasks_done_today(&cid)
+                .await
+                .unwrap_or(false); // on failure, assume tasks NOT done (blocked)
+            init_items.push(ServerEvent::RemainingUpdated {
+                child_id: cid.clone(),
+                remaining_minutes: rem,
+                balance,
+                blocked_by_tasks: blocked,
+            });
+        }
+    }
+
+    let log = state.events.clone();
+    let mut head = vec![Event::default().retry(SSE_RETRY)];
+    let mut replayed_up_to = 0;
+    match replay {
+        events::Replay::Events(items) => {
+            for item in items {
+                replayed_up_to = item.seq;
+                if event_visible_to(&claims, &item.event) {
+                    head.push(sse_event(&item.event).id(log.event_id(item.seq)));
+                }
+            }
+        }
+        events::Replay::Gap => {
+            tracing::info!(username = %claims.sub, "SSE: cannot replay since Last-Event-ID; resyncing");
+            head.push(sse_event(&ServerEvent::Resync));
+        }
+    }
+    head.extend(init_items.iter().map(sse_event));
+
+    // Close the stream when its session is revoked.
+    let ended = {
+        let state = state.clone();
+        let jti = claims.jti.clone();
+        async move { state.session_ended(jti).await }
+    };
+
+    let live = BroadcastStream::new(rx).filter_map(move |msg| {
+        futures::future::ready(match msg {
+            Ok(item) if item.seq > replayed_up_to && event_visible_to(&claims, &item.event) => {
+                Some(sse_event(&item.event).id(log.event_id(item.seq)))
+            }
+            Ok(_) => None,
+            Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                tracing::warn!(missed, "SSE: subscriber lagged; resyncing");
+                Some(sse_event(&ServerEvent::Resync))
+            }
+        })
+    });
+
+    let connection = metrics::GaugeGuard::new(&metrics::metrics().sse_connections);
+    let stream = futures::stream::iter(head)
+        .chain(live)
+        .take_until(ended)
+        // Counted as open until the client goes away and the stream is dropped.
+        .map(move |event| {
+            let _ = &connection;
+            Ok(event)
+        });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn sse_event(ev: &ServerEvent) -> Event {
+    Event::default().data(serde_json::to_string(ev).expect("server event serializes"))
+}
+
+/// Whether a session with `claims` should receive `ev` over SSE or the agent
+/// socket. Children only see events about themselves.
+fn event_visible_to(claims: &jwt::JwtClaims, ev: &ServerEvent) -> bool {
+    match (&claims.role, ev) {
+        (
+            Role::Parent,
+            ServerEvent::NotificationCreated {
+                audience: NotificationAudience::Child,
+                ..
+            },
+        ) => false,
+        (Role::Parent, _) => true,
+        (Role::Child, ServerEvent::RemainingUpdated { child_id, .. })
+        | (Role::Child, ServerEvent::TimeRequestResolved { child_id, .. })
+        | (
+            Role::Child,
+            ServerEvent::NotificationCreated {
+                audience: NotificationAudience::Child,
+                child_id,
+                ..
+            },
+        ) => claims.child_id.as_ref() == Some(child_id),
+        (Role::Child, ServerEvent::PendingCount { .. })
+        | (Role::Child, ServerEvent::NotificationCreated { .. })
+        | (Role::Child, ServerEvent::DeviceConnection { .. }) => false,
+        (_, ServerEvent::Resync) => true,
+    }
+}
+
+/// Trim a parent's comment on a submission; blank comments are dropped.
+fn submission_comment(comment: Option<String>) -> Result<Option<String>, AppError> {
+    let Some(comment) = comment else {
+        return Ok(None);
+    };
+    let comment = comment.trim();
+    if comment.chars().count() > MAX_SUBMISSION_COMMENT_CHARS {
+        return Err(AppError::bad_request(format!(
+            "comment must be at most {MAX_SUBMISSION_COMMENT_CHARS} characters"
+        )));
+    }
+    Ok((!comment.is_empty()).then(|| comment.to_string()))
+}
+
+fn task_submission_dto(
+    (s, task): (
+        crate::storage::models::TaskSubmission,
+        crate::storage::models::Task,
+    ),
+) -> api::TaskSubmissionDto {
+    api::TaskSubmissionDto {
+        id: s.id,
+        child_id: s.child_id,
+        task_id: task.id,
+        task_name: task.name,
+        task_minutes: task.minutes,
+        status: s.status,
+        minutes_awarded: s.minutes_awarded,
+        comment: s.comment,
+        submitted_at: to_rfc3339(s.submitted_at),
+        resolved_by: s.resolved_by,
+        resolved_at: s.resolved_at.map(to_rfc3339),
+    }
+}
+
+async fn api_list_child_submissions(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+) -> Result<Json<Vec<api::TaskSubmissionDto>>, AppError> {
+    let rows = state
+        .store
+        .list_submissions_for_child(&p.id, 50)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(Json(rows.into_iter().map(task_submission_dto).collect()))
+}
+
+async fn api_approve_submission(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+    body: Option<Json<api::SubmissionApproveReq>>,
+) -> Result<StatusCode, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Json(body) = body.unwrap_or_default();
+    let comment = submission_comment(body.comment)?;
+    let result = state
+        .store
+        .approve_submission(id, &auth.claims.sub, body.minutes, comment.as_deref())
+        .await
+        .map_err(AppError::from_storage)?;
+    if let Some((submission, task, new_remaining)) = result {
+        metrics::metrics().record_reward(submission.minutes_awarded.unwrap_or(task.minutes));
+        let child_id = submission.child_id;
+        let child_mutex = state.child_mutex(&child_id).await;
+        let mut child_guard = child_mutex.lock().await;
+        *child_guard = Some(new_remaining);
+        drop(child_guard);
+
+        let (effective, balance, blocked) = state.compute_child_status(&child_id).await?;
         let event = ServerEvent::RemainingUpdated {
             child_id: child_id.clone(),
             remaining_minutes: effective,
@@ -983,115 +2028,1278 @@ async fn api_approve_submission(
             blocked_by_tasks: blocked,
         };
         state.dispatch_event(event);
-        if let Ok(c) = state.store.pending_submissions_count().await {
-            let event = ServerEvent::PendingCount { count: c as u32 };
+        audit::record(
+            &state,
+            &auth,
+            &req_id,
+            audit::AuditEvent {
+                action: "submission.approve",
+                child_id: Some(&child_id),
+                target_id: Some(id.to_string()),
+                before: Some(
+                    serde_json::json!({ "status": crate::storage::models::SUBMISSION_PENDING }),
+                ),
+                after: Some(serde_json::json!({
+                    "status": submission.status,
+                    "task_id": task.id,
+                    "minutes_awarded": submission.minutes_awarded,
+                    "comment": submission.comment,
+                    "remaining_minutes": new_remaining,
+                })),
+            },
+        )
+        .await;
+        state.emit_webhook(
+            "submission_reviewed",
+            serde_json::json!({
+                "child_id": child_id,
+                "submission_id": id,
+                "task_id": task.id,
+                "status": submission.status,
+                "minutes_awarded": submission.minutes_awarded,
+                "comment": submission.comment,
+                "by": auth.claims.sub,
+            }),
+        );
+        state.resolve_notification("task_submission", id).await;
+        attachments::release_submission_photos(&state, id).await;
+        state
+            .notify(
+                NotificationAudience::Child,
+                &child_id,
+                NotificationPayload::TaskReviewed {
+                    task_id: task.id,
+                    task_name: task.name,
+                    minutes: submission.minutes_awarded.unwrap_or(task.minutes),
+                    approved: true,
+                    by: auth.claims.sub.clone(),
+                    comment: submission.comment,
+                },
+                None,
+            )
+            .await;
+        if let Ok(count) = state.pending_notifications_count().await {
+            let event = ServerEvent::PendingCount { count };
             state.dispatch_event(event);
         }
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
-async fn api_discard_submission(
+async fn api_get_attachment(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(id): Path<i32>,
+) -> Result<AxumResponse, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Some((meta, data)) = state
+        .store
+        .get_submission_attachment(id)
+        .await
+        .map_err(AppError::internal)?
+    else {
+        return Err(AppError::not_found("attachment not found"));
+    };
+    Ok(axum::response::IntoResponse::into_response((
+        [
+            (header::CONTENT_TYPE, meta.content_type),
+            (header::CACHE_CONTROL, "private, no-store".to_string()),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )))
+}
+
+async fn api_discard_submission(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+    body: Option<Json<api::SubmissionRejectReq>>,
+) -> Result<StatusCode, AppError> {
+    let Json(body) = body.unwrap_or_default();
+    let comment = submission_comment(body.comment)?;
+    let rejected = state
+        .store
+        .reject_submission(id, &auth.claims.sub, comment.as_deref())
+        .await
+        .map_err(AppError::internal)?;
+    if let Some((submission, task)) = rejected {
+        audit::record(
+            &state,
+            &auth,
+            &req_id,
+            audit::AuditEvent {
+                action: "submission.reject",
+                child_id: Some(&submission.child_id),
+                target_id: Some(id.to_string()),
+                before: Some(
+                    serde_json::json!({ "status": crate::storage::models::SUBMISSION_PENDING }),
+                ),
+                after: Some(serde_json::json!({
+                    "status": submission.status,
+                    "task_id": task.id,
+                    "comment": submission.comment,
+                })),
+            },
+        )
+        .await;
+        state.emit_webhook(
+            "submission_reviewed",
+            serde_json::json!({
+                "child_id": submission.child_id,
+                "submission_id": id,
+                "task_id": task.id,
+                "status": submission.status,
+                "minutes_awarded": serde_json::Value::Null,
+                "comment": submission.comment,
+                "by": auth.claims.sub,
+            }),
+        );
+        state.resolve_notification("task_submission", id).await;
+        attachments::release_submission_photos(&state, id).await;
+        state
+            .notify(
+                NotificationAudience::Child,
+                &submission.child_id,
+                NotificationPayload::TaskReviewed {
+                    task_id: task.id,
+                    task_name: task.name,
+                    minutes: 0,
+                    approved: false,
+                    by: auth.claims.sub.clone(),
+                    comment: submission.comment,
+                },
+                None,
+            )
+            .await;
+    }
+    if let Ok(count) = state.pending_notifications_count().await {
+        let event = ServerEvent::PendingCount { count };
+        state.dispatch_event(event);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn api_submit_task(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(p): Path<ChildTaskPath>,
+    request: Request,
+) -> Result<StatusCode, AppError> {
+    // child can submit only for own id
+    if auth.claims.role != Role::Child {
+        return Err(AppError::forbidden());
+    }
+    match &auth.claims.child_id {
+        Some(cid) if cid == &p.id => {}
+        _ => return Err(AppError::forbidden()),
+    }
+    // Ensure task exists
+    let Some(task) = state
+        .store
+        .get_task_by_id(&p.task_id)
+        .await
+        .map_err(AppError::internal)?
+    else {
+        return Err(AppError::bad_request("unknown task_id"));
+    };
+    let photos = attachments::read_submission_photos(&state, request).await?;
+    let photo_count = photos.len();
+    let submission_id = state
+        .store
+        .submit_task(&p.id, &p.task_id, photos)
+        .await
+        .map_err(AppError::from_storage)?;
+    state.emit_webhook(
+        "task_submitted",
+        serde_json::json!({
+            "child_id": p.id,
+            "submission_id": submission_id,
+            "task_id": task.id,
+            "task_name": task.name,
+            "minutes": task.minutes,
+            "photos": photo_count,
+        }),
+    );
+    state
+        .notify(
+            NotificationAudience::Parents,
+            &p.id,
+            NotificationPayload::TaskSubmission {
+                submission_id,
+                task_id: task.id,
+                task_name: task.name,
+                minutes: task.minutes,
+            },
+            Some(submission_id),
+        )
+        .await;
+    if let Ok(count) = state.pending_notifications_count().await {
+        let event = ServerEvent::PendingCount { count };
+        state.dispatch_event(event);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn api_child_transfer(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(p): Path<ChildPathId>,
+    Json(body): Json<api::TransferReq>,
+) -> Result<Json<api::TransferResp>, AppError> {
+    let exists = state
+        .store
+        .child_exists(&p.id)
+        .await
+        .map_err(AppError::internal)?;
+    if !exists {
+        return Err(AppError::not_found(format!("child not found: {}", p.id)));
+    }
+    let to_exists = state
+        .store
+        .child_exists(&body.to_child_id)
+        .await
+        .map_err(AppError::internal)?;
+    if !to_exists {
+        return Err(AppError::bad_request(format!(
+            "unknown child: {}",
+            body.to_child_id
+        )));
+    }
+    let note = body
+        .note
+        .as_deref()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty());
+    // Parents move minutes directly; children need a parent to approve.
+    let approved_by = (auth.claims.role == Role::Parent).then_some(auth.claims.sub.as_str());
+    let (transfer, from_remaining, to_remaining) = state
+        .store
+        .create_transfer(
+            &p.id,
+            &body.to_child_id,
+            body.minutes,
+            note,
+            &auth.claims.sub,
+            approved_by,
+        )
+        .await
+        .map_err(AppError::from_storage)?;
+
+    if approved_by.is_some() {
+        apply_transfer_outcome(&state, &transfer, from_remaining, to_remaining).await?;
+        audit::record(
+            &state,
+            &auth,
+            &req_id,
+            audit::AuditEvent {
+                action: "transfer.create",
+                child_id: Some(&transfer.from_child_id),
+                target_id: Some(transfer.id.to_string()),
+                before: None,
+                after: Some(transfer_audit_value(
+                    &transfer,
+                    Some((from_remaining, to_remaining)),
+                )),
+            },
+        )
+        .await;
+        notify_transfer_outcome(&state, &transfer).await;
+    } else {
+        state
+            .notify(
+                NotificationAudience::Parents,
+                &transfer.from_child_id,
+                NotificationPayload::Transfer {
+                    transfer_id: transfer.id,
+                    to_child_id: transfer.to_child_id.clone(),
+                    to_child_display_name: state.child_display_name(&transfer.to_child_id).await,
+                    minutes: transfer.minutes,
+                    note: transfer.note.clone(),
+                },
+                Some(transfer.id),
+            )
+            .await;
+        if let Ok(count) = state.pending_notifications_count().await {
+            state.dispatch_event(ServerEvent::PendingCount { count });
+        }
+    }
+
+    let (effective, _balance, _blocked) = state.compute_child_status(&p.id).await?;
+    Ok(Json(api::TransferResp {
+        transfer_id: transfer.id,
+        status: transfer.status,
+        remaining_minutes: effective,
+    }))
+}
+
+/// Refresh cached remaining minutes of both children after an applied
+/// transfer and broadcast `RemainingUpdated` to each of them.
+async fn apply_transfer_outcome(
+    state: &AppState,
+    transfer: &crate::storage::models::MinuteTransfer,
+    from_remaining: i32,
+    to_remaining: i32,
+) -> Result<(), AppError> {
+    for (child_id, stored) in [
+        (&transfer.from_child_id, from_remaining),
+        (&transfer.to_child_id, to_remaining),
+    ] {
+        let child_mutex = state.child_mutex(child_id).await;
+        *child_mutex.lock().await = Some(stored);
+        dispatch_remaining_updated(state, child_id).await?;
+    }
+    Ok(())
+}
+
+/// Audit value of a resolved transfer, with both children's remaining
+/// minutes when it was applied.
+fn transfer_audit_value(
+    transfer: &crate::storage::models::MinuteTransfer,
+    remaining: Option<(i32, i32)>,
+) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "status": transfer.status,
+        "from_child_id": transfer.from_child_id,
+        "to_child_id": transfer.to_child_id,
+        "minutes": transfer.minutes,
+    });
+    if let Some((from_remaining, to_remaining)) = remaining {
+        value["from_remaining_minutes"] = from_remaining.into();
+        value["to_remaining_minutes"] = to_remaining.into();
+    }
+    value
+}
+
+/// Tell the children involved how a transfer ended: both of them when the
+/// minutes moved, only the giving child when it was denied.
+async fn notify_transfer_outcome(
+    state: &AppState,
+    transfer: &crate::storage::models::MinuteTransfer,
+) {
+    let approved = transfer.status == crate::storage::models::TRANSFER_APPROVED;
+    let payload = NotificationPayload::TransferResolved {
+        transfer_id: transfer.id,
+        from_child_id: transfer.from_child_id.clone(),
+        from_child_display_name: state.child_display_name(&transfer.from_child_id).await,
+        to_child_id: transfer.to_child_id.clone(),
+        to_child_display_name: state.child_display_name(&transfer.to_child_id).await,
+        minutes: transfer.minutes,
+        approved,
+    };
+    if approved {
+        state
+            .notify(
+                NotificationAudience::Child,
+                &transfer.to_child_id,
+                payload.clone(),
+                None,
+            )
+            .await;
+    }
+    state
+        .notify(
+            NotificationAudience::Child,
+            &transfer.from_child_id,
+            payload,
+            None,
+        )
+        .await;
+}
+
+async fn api_approve_transfer(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Some((transfer, from_remaining, to_remaining)) = state
+        .store
+        .approve_transfer(id, &auth.claims.sub)
+        .await
+        .map_err(AppError::from_storage)?
+    else {
+        return Err(AppError::not_found(format!(
+            "pending transfer not found: {id}"
+        )));
+    };
+    apply_transfer_outcome(&state, &transfer, from_remaining, to_remaining).await?;
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "transfer.approve",
+            child_id: Some(&transfer.from_child_id),
+            target_id: Some(id.to_string()),
+            before: Some(serde_json::json!({ "status": crate::storage::models::TRANSFER_PENDING })),
+            after: Some(transfer_audit_value(
+                &transfer,
+                Some((from_remaining, to_remaining)),
+            )),
+        },
+    )
+    .await;
+    state.resolve_notification("transfer", id).await;
+    notify_transfer_outcome(&state, &transfer).await;
+    if let Ok(count) = state.pending_notifications_count().await {
+        state.dispatch_event(ServerEvent::PendingCount { count });
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn api_discard_transfer(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Some(transfer) = state
+        .store
+        .deny_transfer(id, &auth.claims.sub)
+        .await
+        .map_err(AppError::internal)?
+    else {
+        return Err(AppError::not_found(format!(
+            "pending transfer not found: {id}"
+        )));
+    };
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "transfer.deny",
+            child_id: Some(&transfer.from_child_id),
+            target_id: Some(id.to_string()),
+            before: Some(serde_json::json!({ "status": crate::storage::models::TRANSFER_PENDING })),
+            after: Some(transfer_audit_value(&transfer, None)),
+        },
+    )
+    .await;
+    state.resolve_notification("transfer", id).await;
+    notify_transfer_outcome(&state, &transfer).await;
+    if let Ok(count) = state.pending_notifications_count().await {
+        state.dispatch_event(ServerEvent::PendingCount { count });
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn time_request_dto(r: crate::storage::models::TimeRequest) -> api::TimeRequestDto {
+    api::TimeRequestDto {
+        id: r.id,
+        child_id: r.child_id,
+        minutes: r.minutes,
+        reason: r.reason,
+        status: r.status,
+        is_borrowed: r.is_borrowed,
+        created_at: to_rfc3339(r.created_at),
+        resolved_at: r.resolved_at.map(to_rfc3339),
+    }
+}
+
+async fn api_create_time_request(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+    Json(body): Json<api::TimeRequestReq>,
+) -> Result<Json<api::TimeRequestDto>, AppError> {
+    // only the child itself can ask; parents grant time via the reward route
+    if auth.claims.role != Role::Child {
+        return Err(AppError::forbidden());
+    }
+    match &auth.claims.child_id {
+        Some(cid) if cid == &p.id => {}
+        _ => return Err(AppError::forbidden()),
+    }
+    let reason = body.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::bad_request("reason must not be empty"));
+    }
+    if reason.chars().count() > MAX_TIME_REQUEST_REASON_CHARS {
+        return Err(AppError::bad_request(format!(
+            "reason must be at most {MAX_TIME_REQUEST_REASON_CHARS} characters"
+        )));
+    }
+    let request = state
+        .store
+        .create_time_request(
+            &p.id,
+            body.minutes,
+            reason,
+            MAX_PENDING_TIME_REQUESTS_PER_CHILD,
+        )
+        .await
+        .map_err(AppError::from_storage)?;
+    state
+        .notify(
+            NotificationAudience::Parents,
+            &request.child_id,
+            NotificationPayload::TimeRequest {
+                request_id: request.id,
+                minutes: request.minutes,
+                reason: request.reason.clone(),
+            },
+            Some(request.id),
+        )
+        .await;
+    if let Ok(count) = state.pending_notifications_count().await {
+        state.dispatch_event(ServerEvent::PendingCount { count });
+    }
+    Ok(Json(time_request_dto(request)))
+}
+
+async fn api_list_time_requests(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildPathId>,
+) -> Result<Json<Vec<api::TimeRequestDto>>, AppError> {
+    let rows = state
+        .store
+        .list_time_requests_for_child(&p.id, 50)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(Json(rows.into_iter().map(time_request_dto).collect()))
+}
+
+fn time_request_resolved(request: &crate::storage::models::TimeRequest) -> ServerEvent {
+    ServerEvent::TimeRequestResolved {
+        child_id: request.child_id.clone(),
+        request_id: request.id,
+        minutes: request.minutes,
+        approved: request.status == crate::storage::models::TIME_REQUEST_APPROVED,
+        is_borrowed: request.is_borrowed,
+    }
+}
+
+async fn notify_time_request_outcome(
+    state: &AppState,
+    request: &crate::storage::models::TimeRequest,
+) {
+    state.resolve_notification("time_request", request.id).await;
+    state
+        .notify(
+            NotificationAudience::Child,
+            &request.child_id,
+            NotificationPayload::TimeRequestResolved {
+                request_id: request.id,
+                minutes: request.minutes,
+                approved: request.status == crate::storage::models::TIME_REQUEST_APPROVED,
+                is_borrowed: request.is_borrowed,
+            },
+            None,
+        )
+        .await;
+}
+
+async fn api_approve_time_request(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+    body: Option<Json<api::TimeRequestApproveReq>>,
+) -> Result<StatusCode, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Json(body) = body.unwrap_or_default();
+    let Some((request, new_remaining)) = state
+        .store
+        .approve_time_request(id, &auth.claims.sub, body.is_borrowed)
+        .await
+        .map_err(AppError::from_storage)?
+    else {
+        return Err(AppError::not_found(format!(
+            "pending time request not found: {id}"
+        )));
+    };
+    let child_mutex = state.child_mutex(&request.child_id).await;
+    *child_mutex.lock().await = Some(new_remaining);
+    dispatch_remaining_updated(&state, &request.child_id).await?;
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "time_request.approve",
+            child_id: Some(&request.child_id),
+            target_id: Some(id.to_string()),
+            before: Some(
+                serde_json::json!({ "status": crate::storage::models::TIME_REQUEST_PENDING }),
+            ),
+            after: Some(serde_json::json!({
+                "status": request.status,
+                "minutes": request.minutes,
+                "is_borrowed": request.is_borrowed,
+                "remaining_minutes": new_remaining,
+            })),
+        },
+    )
+    .await;
+    state.dispatch_event(time_request_resolved(&request));
+    notify_time_request_outcome(&state, &request).await;
+    if request.is_borrowed {
+        match state.store.get_balance(&request.child_id).await {
+            Ok(balance) => {
+                notify_debt_limit(&state, &request.child_id, request.minutes, balance).await
+            }
+            Err(e) => {
+                tracing::error!(error = %e, child_id = %request.child_id, "time requests: failed to load balance")
+            }
+        }
+    }
+    if let Ok(count) = state.pending_notifications_count().await {
+        state.dispatch_event(ServerEvent::PendingCount { count });
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn api_discard_time_request(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Some(request) = state
+        .store
+        .deny_time_request(id, &auth.claims.sub)
+        .await
+        .map_err(AppError::internal)?
+    else {
+        return Err(AppError::not_found(format!(
+            "pending time request not found: {id}"
+        )));
+    };
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "time_request.deny",
+            child_id: Some(&request.child_id),
+            target_id: Some(id.to_string()),
+            before: Some(
+                serde_json::json!({ "status": crate::storage::models::TIME_REQUEST_PENDING }),
+            ),
+            after: Some(serde_json::json!({
+                "status": request.status,
+                "minutes": request.minutes,
+            })),
+        },
+    )
+    .await;
+    state.dispatch_event(time_request_resolved(&request));
+    notify_time_request_outcome(&state, &request).await;
+    if let Ok(count) = state.pending_notifications_count().await {
+        state.dispatch_event(ServerEvent::PendingCount { count });
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct AuditQuery {
+    page: Option<usize>,
+    per_page: Option<usize>,
+    actor: Option<String>,
+    action: Option<String>,
+    child_id: Option<String>,
+    /// RFC 3339 timestamps bounding `created_at` (inclusive / exclusive).
+    since: Option<String>,
+    until: Option<String>,
+}
+
+fn parse_audit_time(
+    field: &str,
+    value: Option<&str>,
+) -> Result<Option<chrono::NaiveDateTime>, AppError> {
+    value
+        .map(|v| {
+            chrono::DateTime::parse_from_rfc3339(v)
+                .map(|dt| dt.naive_utc())
+                .map_err(|_| {
+                    AppError::bad_request(format!("{field} must be an RFC 3339 timestamp"))
+                })
+        })
+        .transpose()
+}
+
+async fn api_list_audit(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Query(q): Query<AuditQuery>,
+) -> Result<Json<Vec<api::AuditEntryDto>>, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let filter = crate::storage::AuditFilter {
+        since: parse_audit_time("since", q.since.as_deref())?,
+        until: parse_audit_time("until", q.until.as_deref())?,
+        actor: q.actor,
+        action: q.action,
+        child_id: q.child_id,
+    };
+    let rows = state
+        .store
+        .list_audit_entries(filter, q.page.unwrap_or(1), q.per_page.unwrap_or(50))
+        .await
+        .map_err(AppError::internal)?;
+    let parse_json = |text: Option<String>| text.and_then(|t| serde_json::from_str(&t).ok());
+    let items = rows
+        .into_iter()
+        .map(|e| api::AuditEntryDto {
+            id: e.id,
+            created_at: to_rfc3339(e.created_at),
+            actor: e.actor,
+            role: if e.role == "parent" {
+                Role::Parent
+            } else {
+                Role::Child
+            },
+            request_id: e.request_id,
+            action: e.action,
+            child_id: e.child_id,
+            target_id: e.target_id,
+            before: parse_json(e.before),
+            after: parse_json(e.after),
+        })
+        .collect();
+    Ok(Json(items))
+}
+
+fn webhook_delivery_dto(d: crate::storage::models::WebhookDelivery) -> api::WebhookDeliveryDto {
+    let pending = d.status == crate::storage::models::WEBHOOK_PENDING;
+    api::WebhookDeliveryDto {
+        id: d.id,
+        webhook_id: d.webhook_id,
+        event: d.event,
+        status: d.status,
+        attempts: d.attempts,
+        last_status_code: d.last_status_code,
+        last_error: d.last_error,
+        created_at: to_rfc3339(d.created_at),
+        next_attempt_at: pending.then(|| to_rfc3339(d.next_attempt_at)),
+        delivered_at: d.delivered_at.map(to_rfc3339),
+    }
+}
+
+/// Configured webhook with the given id.
+fn find_webhook(state: &AppState, id: &str) -> Result<WebhookConfig, AppError> {
+    state
+        .config()
+        .webhooks
+        .iter()
+        .find(|h| h.id == id)
+        .cloned()
+        .ok_or_else(|| AppError::not_found(format!("webhook not found: {id}")))
+}
+
+async fn api_list_webhooks(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+) -> Result<Json<Vec<api::WebhookDto>>, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let items = state
+        .config()
+        .webhooks
+        .iter()
+        .map(|h| api::WebhookDto {
+            id: h.id.clone(),
+            url: h.url.clone(),
+            events: h.events.clone(),
+        })
+        .collect();
+    Ok(Json(items))
+}
+
+async fn api_list_webhook_deliveries(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(id): Path<String>,
+    Query(opts): Query<PageOpts>,
+) -> Result<Json<Vec<api::WebhookDeliveryDto>>, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let hook = find_webhook(&state, &id)?;
+    let rows = state
+        .store
+        .list_webhook_deliveries(
+            &hook.id,
+            opts.page.unwrap_or(1),
+            opts.per_page.unwrap_or(50),
+        )
+        .await
+        .map_err(AppError::internal)?;
+    Ok(Json(rows.into_iter().map(webhook_delivery_dto).collect()))
+}
+
+/// Send a `ping` event to a webhook right away and report the attempt.
+async fn api_test_webhook(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Path(id): Path<String>,
+) -> Result<Json<api::WebhookDeliveryDto>, AppError> {
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let hook = find_webhook(&state, &id)?;
+    let Some(service) = &state.webhooks else {
+        return Err(AppError::not_found(format!("webhook not found: {id}")));
+    };
+    let delivery = service
+        .test_fire(&state.store, &hook.id)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found(format!("webhook not found: {id}")))?;
+    Ok(Json(webhook_delivery_dto(delivery)))
+}
+
+type MinutesGuard<'a> = MutexGuard<'a, Option<i32>>;
+
+async fn api_device_heartbeat(
+    State(state): State<AppState>,
+    Extension(_auth): Extension<AuthCtx>,
+    Path(p): Path<ChildDevicePath>,
+    Json(body): Json<api::HeartbeatReq>,
+) -> Result<Json<api::HeartbeatResp>, AppError> {
+    let resp = process_heartbeat(&state, &p.id, &p.device_id, &body.minutes).await?;
+    Ok(Json(resp))
+}
+
+/// Record usage minutes reported by a device, over HTTP or the agent socket.
+async fn process_heartbeat(
+    state: &AppState,
+    child_id: &str,
+    device_id: &str,
+    minutes: &[i64],
+) -> Result<api::HeartbeatResp, AppError> {
+    // Lock the child ID to avoid concurrent updates
+    let child_mutex = state.child_mutex(child_id).await;
+    let mut child_guard = child_mutex.lock().await;
+
+    let prev = child_guard.unwrap_or(0);
+    let new_remaining = state
+        .store
+        .process_usage_minutes(child_id, device_id, minutes)
+        .await
+        .map_err(AppError::internal)?;
+    metrics::metrics()
+        .heartbeats
+        .with_label_values(&[child_id, device_id])
+        .inc();
+    *child_guard = Some(new_remaining);
+    drop(child_guard);
+
+    let (effective, balance, blocked) = state.compute_child_status(child_id).await?;
+    let all_done = !blocked;
+
+    let prev_effective = if all_done { prev } else { 0 };
+    if effective != prev_effective {
+        let event = ServerEvent::RemainingUpdated {
+            child_id: child_id.to_string(),
+            remaining_minutes: effective,
+            balance,
+            blocked_by_tasks: blocked,
+        };
+        state.dispatch_event(event);
+    }
+
+    Ok(api::HeartbeatResp {
+        remaining_minutes: effective,
+        balance,
+        blocked_by_tasks: blocked,
+    })
+}
+
+use crate::storage::models::{
+    DEVICE_APPROVED, DEVICE_BLOCKED, DEVICE_PENDING, LOGIN_FAILURE_PASSWORD, LOGIN_FAILURE_TOTP,
+};
+
+async fn api_child_register(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(p): Path<ChildPathId>,
+    Json(body): Json<api::ClientRegisterReq>,
+) -> Result<AxumResponse, AppError> {
+    if let Err(retry_after) = state.token_limiter.check_rate_limit(client_ip) {
+        tracing::warn!(ip=%client_ip, retry_after, "register: rate limited");
+        metrics::metrics().token_rate_limited.inc();
+        return Err(AppError::TooManyRequests {
+            retry_after_secs: retry_after,
+        });
+    }
+    // Ensure child exists in DB
+    let exists = state
+        .store
+        .child_exists(&p.id)
+        .await
+        .map_err(AppError::internal)?;
+    if !exists {
+        return Err(AppError::not_found(format!("child not found: {}", p.id)));
+    }
+    // Children register only devices a parent has approved
+    if auth.claims.role == Role::Child {
+        let (device, opened) = state
+            .store
+            .request_device(&p.id, &body.device_id)
+            .await
+            .map_err(AppError::internal)?;
+        match device.status.as_str() {
+            DEVICE_APPROVED => {}
+            DEVICE_BLOCKED => {
+                tracing::warn!(child_id = %p.id, device_id = %body.device_id, "register: device is blocked");
+                return Err(AppError::forbidden());
+            }
+            _ => {
+                if opened {
+                    audit::record(
+                        &state,
+                        &auth,
+                        &req_id,
+                        audit::AuditEvent {
+                            action: "device.request",
+                            child_id: Some(&p.id),
+                            target_id: Some(body.device_id.clone()),
+                            before: None,
+                            after: Some(serde_json::json!({ "status": device.status })),
+                        },
+                    )
+                    .await;
+                    state
+                        .notify(
+                            NotificationAudience::Parents,
+                            &device.child_id,
+                            NotificationPayload::DeviceRegistration {
+                                request_id: device.id,
+                                device_id: device.device_id.clone(),
+                            },
+                            Some(device.id),
+                        )
+                        .await;
+                    if let Ok(count) = state.pending_notifications_count().await {
+                        state.dispatch_event(ServerEvent::PendingCount { count });
+                    }
+                }
+                return Ok(axum::response::IntoResponse::into_response((
+                    StatusCode::ACCEPTED,
+                    Json(device_dto(device)),
+                )));
+            }
+        }
+    }
+    let resp = register_device(
+        &state,
+        &auth,
+        &req_id,
+        "device.register",
+        p.id,
+        body.device_id,
+    )
+    .await?;
+    Ok(axum::response::IntoResponse::into_response(Json(resp)))
+}
+
+/// Issue a device token binding `device_id` to `child_id`, on behalf of the
+/// caller in `auth`.
+async fn register_device(
+    state: &AppState,
+    auth: &AuthCtx,
+    req_id: &ReqId,
+    action: &'static str,
+    child_id: String,
+    device_id: String,
+) -> Result<api::ClientRegisterResp, AppError> {
+    // Registering on behalf of a parent approves the device
+    if auth.claims.role == Role::Parent {
+        let known = state
+            .store
+            .get_device(&child_id, &device_id)
+            .await
+            .map_err(AppError::internal)?;
+        if known.is_some_and(|d| d.status == DEVICE_BLOCKED) {
+            tracing::warn!(%child_id, %device_id, "register: device is blocked");
+            return Err(AppError::forbidden());
+        }
+        state
+            .store
+            .set_device_status(&child_id, &device_id, DEVICE_APPROVED, &auth.claims.sub)
+            .await
+            .map_err(AppError::internal)?;
+    }
+    let child_username = child_username(state, &child_id)?;
+    let token = auth::issue_jwt_for_user(
+        state,
+        &child_username,
+        Role::Child,
+        Some(child_id.clone()),
+        Some(device_id.clone()),
+        &auth.claims.tenant_id,
+    )
+    .await?;
+    audit::record(
+        state,
+        auth,
+        req_id,
+        audit::AuditEvent {
+            action,
+            child_id: Some(&child_id),
+            target_id: Some(device_id.clone()),
+            before: None,
+            after: Some(serde_json::json!({ "device_id": device_id })),
+        },
+    )
+    .await;
+    state.emit_webhook(
+        "device_registered",
+        serde_json::json!({
+            "child_id": child_id,
+            "device_id": device_id,
+            "by": auth.claims.sub,
+        }),
+    );
+    Ok(api::ClientRegisterResp {
+        token,
+        child_id,
+        device_id,
+    })
+}
+
+/// Username of the child login configured for `child_id`.
+fn child_username(state: &AppState, child_id: &str) -> Result<String, AppError> {
+    state
+        .config()
+        .users
+        .iter()
+        .find(|u| u.role == Role::Child && u.child_id.as_deref() == Some(child_id))
+        .map(|u| u.username.clone())
+        .ok_or_else(|| {
+            tracing::error!(%child_id, "register: no child user configured for id");
+            AppError::internal("child login not configured")
+        })
+}
+
+fn device_dto(d: crate::storage::models::Device) -> api::DeviceDto {
+    api::DeviceDto {
+        id: d.id,
+        child_id: d.child_id,
+        device_id: d.device_id,
+        status: d.status,
+        created_at: to_rfc3339(d.created_at),
+        resolved_at: d.resolved_at.map(to_rfc3339),
+    }
+}
+
+async fn api_list_child_devices(
+    State(state): State<AppState>,
+    Path(p): Path<ChildPathId>,
+) -> Result<Json<Vec<api::DeviceDto>>, AppError> {
+    let rows = state
+        .store
+        .list_devices_for_child(&p.id)
+        .await
+        .map_err(AppError::internal)?;
+    Ok(Json(rows.into_iter().map(device_dto).collect()))
+}
+
+/// Lets a device waiting for approval poll its status.
+async fn api_get_child_device(
     State(state): State<AppState>,
-    Extension(_auth): Extension<AuthCtx>,
-    Path(id): Path<i32>,
+    Path(p): Path<ChildDevicePath>,
+) -> Result<Json<api::DeviceDto>, AppError> {
+    let device = state
+        .store
+        .get_device(&p.id, &p.device_id)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(|| AppError::not_found(format!("device not found: {}", p.device_id)))?;
+    Ok(Json(device_dto(device)))
+}
+
+async fn resolve_device_request(
+    state: &AppState,
+    auth: &AuthCtx,
+    req_id: &ReqId,
+    id: i32,
+    approve: bool,
 ) -> Result<StatusCode, AppError> {
-    state
+    if auth.claims.role != Role::Parent {
+        return Err(AppError::forbidden());
+    }
+    let Some(device) = state
         .store
-        .discard_submission(id)
+        .resolve_device_request(id, approve, &auth.claims.sub)
         .await
-        .map_err(AppError::internal)?;
-    if let Ok(c) = state.store.pending_submissions_count().await {
-        let event = ServerEvent::PendingCount { count: c as u32 };
-        state.dispatch_event(event);
+        .map_err(AppError::internal)?
+    else {
+        return Err(AppError::not_found(format!(
+            "pending device request not found: {id}"
+        )));
+    };
+    audit::record(
+        state,
+        auth,
+        req_id,
+        audit::AuditEvent {
+            action: if approve {
+                "device.approve"
+            } else {
+                "device.deny"
+            },
+            child_id: Some(&device.child_id),
+            target_id: Some(device.device_id.clone()),
+            before: Some(serde_json::json!({ "status": DEVICE_PENDING })),
+            after: Some(serde_json::json!({ "status": device.status })),
+        },
+    )
+    .await;
+    state
+        .resolve_notification("device_registration", device.id)
+        .await;
+    if let Ok(count) = state.pending_notifications_count().await {
+        state.dispatch_event(ServerEvent::PendingCount { count });
     }
     Ok(StatusCode::NO_CONTENT)
 }
 
-async fn api_submit_task(
+/// Approve a device a child asked to register; its next registration
+/// attempt gets a token.
+async fn api_approve_device(
     State(state): State<AppState>,
     Extension(auth): Extension<AuthCtx>,
-    Path(p): Path<ChildTaskPath>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
 ) -> Result<StatusCode, AppError> {
-    // child can submit only for own id
-    if auth.claims.role != Role::Child {
-        return Err(AppError::forbidden());
-    }
-    match &auth.claims.child_id {
-        Some(cid) if cid == &p.id => {}
-        _ => return Err(AppError::forbidden()),
-    }
-    // Ensure task exists
-    match state
+    resolve_device_request(&state, &auth, &req_id, id, true).await
+}
+
+async fn api_discard_device(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, AppError> {
+    resolve_device_request(&state, &auth, &req_id, id, false).await
+}
+
+/// Block a device id for the child: it cannot register, and sessions
+/// already issued to it end.
+async fn api_block_device(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Path(p): Path<ChildDevicePath>,
+) -> Result<Json<api::DeviceDto>, AppError> {
+    let exists = state
         .store
-        .get_task_by_id(&p.task_id)
+        .child_exists(&p.id)
         .await
-        .map_err(AppError::internal)?
-    {
-        Some(_) => {}
-        None => return Err(AppError::bad_request("unknown task_id")),
+        .map_err(AppError::internal)?;
+    if !exists {
+        return Err(AppError::not_found(format!("child not found: {}", p.id)));
     }
-    state
+    let (before, device) = state
         .store
-        .submit_task(&p.id, &p.task_id)
+        .set_device_status(&p.id, &p.device_id, DEVICE_BLOCKED, &auth.claims.sub)
         .await
         .map_err(AppError::internal)?;
-    if let Ok(c) = state.store.pending_submissions_count().await {
-        let event = ServerEvent::PendingCount { count: c as u32 };
-        state.dispatch_event(event);
+    if before.as_deref() == Some(DEVICE_PENDING) {
+        state
+            .resolve_notification("device_registration", device.id)
+            .await;
+        if let Ok(count) = state.pending_notifications_count().await {
+            state.dispatch_event(ServerEvent::PendingCount { count });
+        }
     }
-    Ok(StatusCode::NO_CONTENT)
+    let sessions = match child_username(&state, &p.id) {
+        Ok(username) => state
+            .store
+            .session_ids_for_device(&username, &p.device_id)
+            .await
+            .map_err(AppError::internal)?,
+        Err(_) => Vec::new(),
+    };
+    for jti in &sessions {
+        if let Err(e) = state.revoke_session(jti).await {
+            tracing::error!(%jti, error = %e, "devices: failed to revoke session");
+        }
+    }
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "device.block",
+            child_id: Some(&p.id),
+            target_id: Some(p.device_id.clone()),
+            before: before.map(|status| serde_json::json!({ "status": status })),
+            after: Some(serde_json::json!({
+                "status": device.status,
+                "revoked_sessions": sessions.len(),
+            })),
+        },
+    )
+    .await;
+    Ok(Json(device_dto(device)))
 }
 
-type MinutesGuard<'a> = MutexGuard<'a, Option<i32>>;
-
-async fn api_device_heartbeat(
+/// Lift a block. The device is forgotten and has to be registered again.
+async fn api_unblock_device(
     State(state): State<AppState>,
-    Extension(_auth): Extension<AuthCtx>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
     Path(p): Path<ChildDevicePath>,
-    Json(body): Json<api::HeartbeatReq>,
-) -> Result<Json<api::HeartbeatResp>, AppError> {
-    // Lock the child ID to avoid concurrent updates
-    let child_mutex = state.child_mutex(&p.id).await;
-    let mut child_guard = child_mutex.lock().await;
-
-    let prev = child_guard.unwrap_or(0);
-    let new_remaining = state
+) -> Result<StatusCode, AppError> {
+    let unblocked = state
         .store
-        .process_usage_minutes(&p.id, &p.device_id, &body.minutes)
+        .unblock_device(&p.id, &p.device_id)
         .await
         .map_err(AppError::internal)?;
-    *child_guard = Some(new_remaining);
-    drop(child_guard);
-
-    let (effective, balance, blocked) = state.compute_child_status(&p.id).await?;
-    let all_done = !blocked;
-
-    let prev_effective = if all_done { prev } else { 0 };
-    if effective != prev_effective {
-        let event = ServerEvent::RemainingUpdated {
-            child_id: p.id.clone(),
-            remaining_minutes: effective,
-            balance,
-            blocked_by_tasks: blocked,
-        };
-        state.dispatch_event(event);
+    if !unblocked {
+        return Err(AppError::not_found(format!(
+            "blocked device not found: {}",
+            p.device_id
+        )));
     }
-
-    Ok(Json(api::HeartbeatResp {
-        remaining_minutes: effective,
-        balance,
-        blocked_by_tasks: blocked,
-    }))
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "device.unblock",
+            child_id: Some(&p.id),
+            target_id: Some(p.device_id.clone()),
+            before: Some(serde_json::json!({ "status": DEVICE_BLOCKED })),
+            after: None,
+        },
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-async fn api_child_register(
+/// Create a code that registers one device for the child when redeemed at
+/// `POST /auth/pair`.
+async fn api_create_pairing_code(
     State(state): State<AppState>,
     Extension(auth): Extension<AuthCtx>,
     Path(p): Path<ChildPathId>,
-    Json(body): Json<api::ClientRegisterReq>,
-) -> Result<Json<api::ClientRegisterResp>, AppError> {
-    // Ensure child exists in DB
+) -> Result<Json<api::PairingCodeResp>, AppError> {
     let exists = state
         .store
         .child_exists(&p.id)
@@ -1100,52 +3308,70 @@ async fn api_child_register(
     if !exists {
         return Err(AppError::not_found(format!("child not found: {}", p.id)));
     }
-    let device_id = body.device_id.clone();
-    let child_username = state
-        .config
-        .users
-        .iter()
-        .find(|u| u.role == Role::Child && u.child_id.as_deref() == Some(p.id.as_str()))
-        .map(|u| u.username.clone())
-        .ok_or_else(|| {
-            tracing::error!(child_id = %p.id, "register: no child user configured for id");
-            AppError::internal("child login not configured")
-        })?;
-    let token = auth::issue_jwt_for_user(
+    let code = state.pairing_codes.issue(&p.id, auth.claims);
+    Ok(Json(api::PairingCodeResp {
+        code,
+        expires_in_secs: state.pairing_codes.ttl().as_secs() as u32,
+    }))
+}
+
+/// Redeem a pairing code on the new device. The registration is recorded as
+/// done by the parent who created the code.
+async fn api_auth_pair(
+    State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
+    Extension(req_id): Extension<ReqId>,
+    Json(body): Json<api::PairReq>,
+) -> Result<Json<api::ClientRegisterResp>, AppError> {
+    // Codes are short; share the login limit so they cannot be guessed
+    if let Err(retry_after) = state.login_limiter.check_rate_limit(client_ip) {
+        tracing::warn!(ip=%client_ip, retry_after, "pair: rate limited");
+        metrics::metrics().login_rate_limited.inc();
+        return Err(AppError::TooManyRequests {
+            retry_after_secs: retry_after,
+        });
+    }
+    if body.device_id.trim().is_empty() {
+        return Err(AppError::bad_request("device_id must not be empty"));
+    }
+    let Some(pairing) = state.pairing_codes.redeem(&body.code) else {
+        tracing::warn!(ip=%client_ip, "pair: unknown or expired code");
+        return Err(AppError::unauthorized());
+    };
+    let auth = AuthCtx {
+        claims: pairing.issued_by,
+    };
+    let resp = register_device(
         &state,
-        &child_username,
-        Role::Child,
-        Some(p.id.clone()),
-        Some(device_id.clone()),
-        &auth.claims.tenant_id,
+        &auth,
+        &req_id,
+        "device.pair",
+        pairing.child_id,
+        body.device_id,
     )
     .await?;
-    Ok(Json(api::ClientRegisterResp {
-        token,
-        child_id: p.id,
-        device_id,
-    }))
+    Ok(Json(resp))
 }
 
 // JwtClaims moved to auth module
 
 async fn api_auth_login(
     State(state): State<AppState>,
-    connect_info: axum::extract::ConnectInfo<std::net::SocketAddr>,
+    ClientIp(client_ip): ClientIp,
     Json(body): Json<api::AuthReq>,
 ) -> Result<Json<api::AuthResp>, AppError> {
     // Rate-limit by client IP
-    let client_ip = connect_info.0.ip();
     if let Err(retry_after) = state.login_limiter.check_rate_limit(client_ip) {
         tracing::warn!(ip=%client_ip, retry_after, "login: rate limited");
+        metrics::metrics().login_rate_limited.inc();
         return Err(AppError::TooManyRequests {
             retry_after_secs: retry_after,
         });
     }
 
     // Find user in config
-    let user = state
-        .config
+    let config = state.config();
+    let user = config
         .users
         .iter()
         .find(|u| u.username == body.username)
@@ -1153,11 +3379,18 @@ async fn api_auth_login(
             tracing::warn!(username=%body.username, "login: unknown username");
             AppError::unauthorized()
         })?;
-    if !verify(&body.password, &user.password_hash).map_err(|e| {
+    let Some(password_hash) = user.password_hash.as_deref() else {
+        tracing::warn!(username=%body.username, "login: user has no password, only OIDC");
+        return Err(AppError::unauthorized());
+    };
+    // Per-account limit, for guessing spread over many addresses
+    lockout::check(&state, &user.username).await?;
+    if !verify(&body.password, password_hash).map_err(|e| {
         tracing::error!(username=%body.username, error=%e, "login: bcrypt verify failed");
         AppError::internal(e)
     })? {
-        tracing::warn!(username=%body.username, "login: invalid password");
+        tracing::warn!(username=%body.username, ip=%client_ip, "login: invalid password");
+        lockout::record_failure(&state, user, client_ip, LOGIN_FAILURE_PASSWORD).await;
         return Err(AppError::unauthorized());
     }
     // For child role, ensure child_id provided
@@ -1165,13 +3398,33 @@ async fn api_auth_login(
         tracing::error!(username=%body.username, "login: child user missing child_id in config");
         return Err(AppError::internal("child user missing child_id"));
     }
+    // Second factor for users who enabled TOTP
+    let credential = state
+        .store
+        .get_totp(&user.username)
+        .await
+        .map_err(AppError::internal)?;
+    if let Some(credential) = credential.filter(|c| c.enabled_at.is_some()) {
+        let Some(code) = body.totp_code.as_deref().filter(|c| !c.trim().is_empty()) else {
+            return Err(AppError::TotpRequired);
+        };
+        let valid = totp::verify_login_code(&state.store, &user.username, &credential.secret, code)
+            .await
+            .map_err(AppError::internal)?;
+        if !valid {
+            tracing::warn!(username=%body.username, ip=%client_ip, "login: invalid totp code");
+            lockout::record_failure(&state, user, client_ip, LOGIN_FAILURE_TOTP).await;
+            return Err(AppError::unauthorized());
+        }
+    }
+    lockout::record_success(&state, &user.username).await;
     let token = auth::issue_jwt_for_user(
         &state,
         &user.username,
         user.role,
         user.child_id.clone(),
         None,
-        &state.config.tenant_id,
+        &state.config().tenant_id,
     )
     .await?;
     Ok(Json(api::AuthResp { token }))
@@ -1179,8 +3432,16 @@ async fn api_auth_login(
 
 async fn api_auth_renew(
     State(state): State<AppState>,
+    ClientIp(client_ip): ClientIp,
     Extension(auth): Extension<AuthCtx>,
 ) -> Result<Json<api::AuthResp>, AppError> {
+    if let Err(retry_after) = state.token_limiter.check_rate_limit(client_ip) {
+        tracing::warn!(ip=%client_ip, retry_after, "renew: rate limited");
+        metrics::metrics().token_rate_limited.inc();
+        return Err(AppError::TooManyRequests {
+            retry_after_secs: retry_after,
+        });
+    }
     let claims = auth.claims;
     let token = auth::issue_jwt_for_user(
         &state,
@@ -1192,7 +3453,7 @@ async fn api_auth_renew(
     )
     .await?;
 
-    match state.store.delete_session(&claims.jti).await {
+    match state.revoke_session(&claims.jti).await {
         Ok(true) => {}
         Ok(false) => {
             tracing::warn!(jti = %claims.jti, "auth renew: previous session missing");
@@ -1209,6 +3470,292 @@ async fn api_auth_renew(
     Ok(Json(api::AuthResp { token }))
 }
 
+async fn api_auth_options(State(state): State<AppState>) -> Json<api::AuthOptionsDto> {
+    Json(api::AuthOptionsDto {
+        oidc_name: state
+            .oidc
+            .load()
+            .as_deref()
+            .map(|o| o.display_name().to_string()),
+    })
+}
+
+fn oidc_client(state: &AppState) -> Result<std::sync::Arc<oidc::OidcClient>, AppError> {
+    Option::clone(&state.oidc.load())
+        .ok_or_else(|| AppError::not_found("OIDC login is not configured"))
+}
+
+async fn api_oidc_login(State(state): State<AppState>) -> Result<AxumResponse, AppError> {
+    let client = oidc_client(&state)?;
+    let (url, login_state) = client.authorization_url().await.map_err(|e| {
+        tracing::error!(error = %e, "oidc: cannot start login");
+        AppError::internal(e)
+    })?;
+    Ok(axum::response::IntoResponse::into_response((
+        [(header::SET_COOKIE, client.state_cookie(&login_state))],
+        axum::response::Redirect::to(&url),
+    )))
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+struct OidcCallbackQuery {
+    /// Authorization code issued by the provider.
+    code: Option<String>,
+    /// Identifies the login started by `GET /auth/oidc/login`.
+    state: Option<String>,
+    /// Set by the provider instead of `code` when the login failed.
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// The provider redirects the browser here. It is sent on to the web app's
+/// login page with a single-use code for `POST /auth/oidc/exchange`, or with
+/// the reason the login failed. The login must have been started in the
+/// same browser, shown by the state cookie set by `GET /auth/oidc/login`.
+async fn api_oidc_callback(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Query(q): Query<OidcCallbackQuery>,
+) -> AxumResponse {
+    let client = Option::clone(&state.oidc.load());
+    let target = match oidc_callback_user(client.clone(), &headers, q).await {
+        Ok((client, username)) => {
+            format!("/#login?oidc_code={}", client.issue_handoff(&username))
+        }
+        Err(message) => format!(
+            "/#login?oidc_error={}",
+            percent_encoding::utf8_percent_encode(&message, percent_encoding::NON_ALPHANUMERIC)
+        ),
+    };
+    let redirect = axum::response::Redirect::to(&target);
+    match client {
+        Some(client) => axum::response::IntoResponse::into_response((
+            [(header::SET_COOKIE, client.clear_state_cookie())],
+            redirect,
+        )),
+        None => axum::response::IntoResponse::into_response(redirect),
+    }
+}
+
+async fn oidc_callback_user(
+    client: Option<std::sync::Arc<oidc::OidcClient>>,
+    headers: &axum::http::HeaderMap,
+    q: OidcCallbackQuery,
+) -> Result<(std::sync::Arc<oidc::OidcClient>, String), String> {
+    let client = client.ok_or("OIDC login is not configured")?;
+    if let Some(error) = q.error {
+        tracing::warn!(%error, description = ?q.error_description, "oidc: provider refused login");
+        return Err(q.error_description.unwrap_or(error));
+    }
+    let (Some(code), Some(login_state)) = (q.code, q.state) else {
+        return Err("incomplete response from the identity provider".into());
+    };
+    if !oidc::state_cookie_matches(headers, &login_state) {
+        tracing::warn!("oidc: callback without the login's state cookie");
+        return Err("the login was started in another browser".into());
+    }
+    let identity = client.complete(&code, &login_state).await.map_err(|e| {
+        tracing::warn!(error = %e, "oidc: login failed");
+        e.to_string()
+    })?;
+    let Some(username) = client.username_for(&identity) else {
+        tracing::warn!(
+            subject = %identity.subject,
+            groups = ?identity.groups,
+            "oidc: identity is not mapped to a parent"
+        );
+        return Err("this account is not allowed to log in".into());
+    };
+    tracing::info!(subject = %identity.subject, %username, "oidc: login");
+    let username = username.to_string();
+    Ok((client, username))
+}
+
+async fn api_oidc_exchange(
+    State(state): State<AppState>,
+    Json(body): Json<api::OidcExchangeReq>,
+) -> Result<Json<api::AuthResp>, AppError> {
+    let username = oidc_client(&state)?
+        .redeem_handoff(&body.code)
+        .ok_or_else(AppError::unauthorized)?;
+    let token = auth::issue_jwt_for_user(
+        &state,
+        &username,
+        Role::Parent,
+        None,
+        None,
+        &state.config().tenant_id,
+    )
+    .await?;
+    Ok(Json(api::AuthResp { token }))
+}
+
+/// Two-factor settings belong to parents logged in with their password, not
+/// to device tokens.
+fn require_parent_login(auth: &AuthCtx) -> Result<(), AppError> {
+    if auth.claims.role == Role::Parent && auth.claims.device_id.is_none() {
+        Ok(())
+    } else {
+        Err(AppError::forbidden())
+    }
+}
+
+async fn api_totp_status(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+) -> Result<Json<api::TotpStatusDto>, AppError> {
+    require_parent_login(&auth)?;
+    let username = &auth.claims.sub;
+    let credential = state
+        .store
+        .get_totp(username)
+        .await
+        .map_err(AppError::internal)?;
+    let enabled = credential.is_some_and(|c| c.enabled_at.is_some());
+    let recovery_codes_left = if enabled {
+        state
+            .store
+            .count_totp_recovery_codes(username)
+            .await
+            .map_err(AppError::internal)?
+            .try_into()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    Ok(Json(api::TotpStatusDto {
+        enabled,
+        recovery_codes_left,
+    }))
+}
+
+async fn api_totp_enroll(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+) -> Result<Json<api::TotpEnrollResp>, AppError> {
+    require_parent_login(&auth)?;
+    let username = &auth.claims.sub;
+    let secret = totp::generate_secret();
+    let started = state
+        .store
+        .start_totp_enrollment(username, &secret)
+        .await
+        .map_err(AppError::internal)?;
+    if !started {
+        return Err(AppError::bad_request(
+            "two-factor authentication is already enabled",
+        ));
+    }
+    Ok(Json(api::TotpEnrollResp {
+        secret: totp::encode_secret(&secret),
+        otpauth_uri: totp::otpauth_uri(username, &secret),
+    }))
+}
+
+async fn api_totp_verify(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Json(body): Json<api::TotpCodeReq>,
+) -> Result<Json<api::TotpRecoveryCodesResp>, AppError> {
+    require_parent_login(&auth)?;
+    let username = &auth.claims.sub;
+    let pending = state
+        .store
+        .get_totp(username)
+        .await
+        .map_err(AppError::internal)?
+        .filter(|c| c.enabled_at.is_none())
+        .ok_or_else(|| AppError::bad_request("no two-factor enrollment in progress"))?;
+    let now = chrono::Utc::now().timestamp();
+    let step = totp::matching_step(&pending.secret, body.code.trim(), now)
+        .ok_or_else(|| AppError::bad_request("invalid code"))?;
+    let recovery_codes = totp::generate_recovery_codes();
+    let hashes = recovery_codes
+        .iter()
+        .map(|c| totp::hash_recovery_code(c))
+        .collect();
+    let enabled = state
+        .store
+        .enable_totp(username, step, hashes)
+        .await
+        .map_err(AppError::internal)?;
+    if !enabled {
+        return Err(AppError::bad_request(
+            "no two-factor enrollment in progress",
+        ));
+    }
+
+    // Sessions opened with just the password no longer count
+    let sessions = state
+        .store
+        .session_ids_for_user(username)
+        .await
+        .map_err(AppError::internal)?;
+    let others: Vec<&String> = sessions.iter().filter(|j| **j != auth.claims.jti).collect();
+    for jti in &others {
+        if let Err(e) = state.revoke_session(jti).await {
+            tracing::error!(%jti, error = %e, "totp: failed to revoke session");
+        }
+    }
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "totp.enable",
+            child_id: None,
+            target_id: Some(username.clone()),
+            before: None,
+            after: Some(serde_json::json!({ "revoked_sessions": others.len() })),
+        },
+    )
+    .await;
+    Ok(Json(api::TotpRecoveryCodesResp { recovery_codes }))
+}
+
+async fn api_totp_disable(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthCtx>,
+    Extension(req_id): Extension<ReqId>,
+    Json(body): Json<api::TotpCodeReq>,
+) -> Result<StatusCode, AppError> {
+    require_parent_login(&auth)?;
+    let username = &auth.claims.sub;
+    let credential = state
+        .store
+        .get_totp(username)
+        .await
+        .map_err(AppError::internal)?
+        .filter(|c| c.enabled_at.is_some())
+        .ok_or_else(|| AppError::bad_request("two-factor authentication is not enabled"))?;
+    let valid = totp::verify_login_code(&state.store, username, &credential.secret, &body.code)
+        .await
+        .map_err(AppError::internal)?;
+    if !valid {
+        return Err(AppError::bad_request("invalid code"));
+    }
+    state
+        .store
+        .delete_totp(username)
+        .await
+        .map_err(AppError::internal)?;
+    audit::record(
+        &state,
+        &auth,
+        &req_id,
+        audit::AuditEvent {
+            action: "totp.disable",
+            child_id: None,
+            target_id: Some(username.clone()),
+            before: None,
+            after: None,
+        },
+    )
+    .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[derive(Debug, Serialize)]
 struct ErrorBody {
     error: String,
@@ -1218,9 +3765,13 @@ struct ErrorBody {
 pub enum AppError {
     BadRequest(String),
     Unauthorized,
+    /// Password accepted, but the user has TOTP enabled and sent no code.
+    TotpRequired,
     Forbidden,
     NotFound(String),
-    TooManyRequests { retry_after_secs: u64 },
+    TooManyRequests {
+        retry_after_secs: u64,
+    },
     Internal(String),
 }
 
@@ -1240,6 +3791,13 @@ impl AppError {
     fn internal<E: std::fmt::Display>(e: E) -> Self {
         Self::Internal(e.to_string())
     }
+    /// Map a storage error, surfacing rejected input as 400 rather than 500.
+    fn from_storage(e: crate::storage::StorageError) -> Self {
+        match e {
+            crate::storage::StorageError::InvalidInput(m) => Self::BadRequest(m),
+            other => Self::internal(other),
+        }
+    }
 }
 
 impl axum::response::IntoResponse for AppError {
@@ -1253,6 +3811,13 @@ impl axum::response::IntoResponse for AppError {
                 None,
                 None,
             ),
+            AppError::TotpRequired => (
+                StatusCode::UNAUTHORIZED,
+                "totp_required".into(),
+                "totp_required",
+                None,
+                None,
+            ),
             AppError::Forbidden => (
                 StatusCode::FORBIDDEN,
                 "forbidden".into(),
@@ -0,0 +1,599 @@
+use std::time::Duration;
+
+use axum::http::Method;
+use gamiscreen_shared::api::{self, ServerEvent};
+use gamiscreen_shared::jwt::JwtClaims;
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use super::auth::AuthCtx;
+use super::config::MqttConfig;
+use super::{AppState, ReqId, acl, grant_reward};
+
+/// Capacity of the client's request queue.
+const REQUEST_CAP: usize = 256;
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+/// Pause before reconnecting after the connection to the broker fails.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How long to keep flushing on shutdown so the `offline` status goes out.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// Topic layout for one bridge, derived from the config.
+#[derive(Clone)]
+struct Topics {
+    prefix: String,
+    discovery: String,
+    /// Home Assistant node id, unique per tenant.
+    node: String,
+}
+
+impl Topics {
+    fn new(cfg: &MqttConfig, tenant_id: &str) -> Self {
+        Self {
+            prefix: cfg.topic_prefix.clone(),
+            discovery: cfg.discovery_prefix.clone(),
+            node: format!("gamiscreen_{}", object_id(tenant_id)),
+        }
+    }
+
+    /// `online`/`offline`, retained; `offline` is also the last will.
+    fn status(&self) -> String {
+        format!("{}/status", self.prefix)
+    }
+
+    fn pending_count(&self) -> String {
+        format!("{}/pending_count", self.prefix)
+    }
+
+    fn child_state(&self, child_id: &str) -> String {
+        format!("{}/children/{}/state", self.prefix, child_id)
+    }
+
+    fn reward_command(&self, child_id: &str) -> String {
+        format!("{}/children/{}/reward/set", self.prefix, child_id)
+    }
+
+    fn clear_command(&self, child_id: &str) -> String {
+        format!("{}/children/{}/clear/set", self.prefix, child_id)
+    }
+
+    fn discovery_config(&self, component: &str, object: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/config",
+            self.discovery,
+            component,
+            self.node,
+            object_id(object)
+        )
+    }
+
+    /// Parse a command topic into the child id and the command.
+    fn parse_command<'a>(&self, topic: &'a str) -> Option<(&'a str, Command)> {
+        let rest = topic
+            .strip_prefix(&self.prefix)?
+            .strip_prefix("/children/")?;
+        let (child_id, command) = rest.split_once('/')?;
+        let command = match command {
+            "reward/set" => Command::Reward,
+            "clear/set" => Command::Clear,
+            _ => return None,
+        };
+        (!child_id.is_empty()).then_some((child_id, command))
+    }
+}
+
+/// Home Assistant object ids may only contain `[a-zA-Z0-9_-]`.
+fn object_id(raw: &str) -> String {
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Reward,
+    /// Deduct the child's remaining minutes. They are gone, unlike a lock
+    /// that could be lifted.
+    Clear,
+}
+
+/// Payload of a reward command; the same fields as a reward request,
+/// with the child taken from the topic.
+#[derive(Debug, Deserialize)]
+struct RewardCommand {
+    task_id: Option<String>,
+    minutes: Option<i32>,
+    description: Option<String>,
+    #[serde(default)]
+    is_borrowed: Option<bool>,
+}
+
+/// Spawn the MQTT bridge if it is enabled: publishes children's state and
+/// the pending count to retained topics, announces entities through Home
+/// Assistant discovery and applies reward and clear commands as the
+/// configured user.
+pub fn spawn_bridge(state: &AppState) {
+    let Some(cfg) = state.config().mqtt_config().cloned() else {
+        return;
+    };
+    let topics = Topics::new(&cfg, &state.config().tenant_id);
+    let mut options = MqttOptions::new(cfg.client_id.clone(), cfg.host.clone(), cfg.port);
+    options.set_keep_alive(KEEP_ALIVE);
+    options.set_last_will(LastWill::new(
+        topics.status(),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &cfg.username {
+        options.set_credentials(username.clone(), cfg.password.clone().unwrap_or_default());
+    }
+    let (client, eventloop) = AsyncClient::new(options, REQUEST_CAP);
+
+    tokio::spawn(forward_events(
+        state.clone(),
+        client.clone(),
+        topics.clone(),
+    ));
+    tokio::spawn(run_eventloop(state.clone(), cfg, client, eventloop, topics));
+}
+
+/// Drive the connection: announce on every (re)connect and handle commands.
+async fn run_eventloop(
+    state: AppState,
+    cfg: MqttConfig,
+    client: AsyncClient,
+    mut eventloop: EventLoop,
+    topics: Topics,
+) {
+    let shutdown = state.shutdown_token();
+    loop {
+        let event = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            event = eventloop.poll() => event,
+        };
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                info!(host = %cfg.host, port = cfg.port, "mqtt: connected");
+                tokio::spawn(announce(state.clone(), client.clone(), topics.clone()));
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                let Some((child_id, command)) = topics.parse_command(&publish.topic) else {
+                    continue;
+                };
+                let state = state.clone();
+                let cfg = cfg.clone();
+                let child_id = child_id.to_string();
+                tokio::spawn(async move {
+                    if let Err(e) =
+                        handle_command(&state, &cfg, &child_id, command, &publish.payload).await
+                    {
+                        warn!(child_id = %child_id, ?command, error = ?e, "mqtt: command rejected");
+                    }
+                });
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!(error = %e, "mqtt: connection error; reconnecting");
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+                }
+            }
+        }
+    }
+
+    // Going offline on purpose: the broker only sends the last will on a
+    // dropped connection.
+    let _ = client.try_publish(topics.status(), QoS::AtLeastOnce, true, "offline");
+    let _ = client.try_disconnect();
+    let _ = tokio::time::timeout(SHUTDOWN_GRACE, async {
+        while eventloop.poll().await.is_ok() {}
+    })
+    .await;
+}
+
+/// Subscribe to the command topics and publish discovery configs and the
+/// current state of every child.
+async fn announce(state: AppState, client: AsyncClient, topics: Topics) {
+    let result: Result<(), rumqttc::ClientError> = async {
+        for filter in [
+            format!("{}/children/+/reward/set", topics.prefix),
+            format!("{}/children/+/clear/set", topics.prefix),
+        ] {
+            client.subscribe(filter, QoS::AtLeastOnce).await?;
+        }
+        for (topic, config) in discovery_configs(&state, &topics) {
+            client
+                .publish(topic, QoS::AtLeastOnce, true, config.to_string())
+                .await?;
+        }
+        client
+            .publish(topics.status(), QoS::AtLeastOnce, true, "online")
+            .await?;
+        publish_snapshot(&state, &client, &topics).await
+    }
+    .await;
+    if let Err(e) = result {
+        warn!(error = %e, "mqtt: failed to announce");
+    }
+}
+
+/// Publish the state of every child and the pending count.
+async fn publish_snapshot(
+    state: &AppState,
+    client: &AsyncClient,
+    topics: &Topics,
+) -> Result<(), rumqttc::ClientError> {
+    for child in &state.config().children {
+        match state.compute_child_status(&child.id).await {
+            Ok((remaining_minutes, balance, blocked_by_tasks)) => {
+                publish_child_state(
+                    client,
+                    topics,
+                    &child.id,
+                    remaining_minutes,
+                    balance,
+                    blocked_by_tasks,
+                )
+                .await?;
+            }
+            Err(e) => warn!(child_id = %child.id, error = ?e, "mqtt: failed to compute state"),
+        }
+    }
+    match state.pending_notifications_count().await {
+        Ok(count) => publish_pending_count(client, topics, count).await?,
+        Err(e) => warn!(error = %e, "mqtt: failed to count pending items"),
+    }
+    Ok(())
+}
+
+async fn publish_child_state(
+    client: &AsyncClient,
+    topics: &Topics,
+    child_id: &str,
+    remaining_minutes: i32,
+    balance: i32,
+    blocked_by_tasks: bool,
+) -> Result<(), rumqttc::ClientError> {
+    let payload = json!({
+        "remaining_minutes": remaining_minutes,
+        "balance": balance,
+        "blocked_by_tasks": blocked_by_tasks,
+    });
+    client
+        .publish(
+            topics.child_state(child_id),
+            QoS::AtLeastOnce,
+            true,
+            payload.to_string(),
+        )
+        .await
+}
+
+async fn publish_pending_count(
+    client: &AsyncClient,
+    topics: &Topics,
+    count: u32,
+) -> Result<(), rumqttc::ClientError> {
+    client
+        .publish(
+            topics.pending_count(),
+            QoS::AtLeastOnce,
+            true,
+            count.to_string(),
+        )
+        .await
+}
+
+/// Relay `RemainingUpdated` and `PendingCount` events to their retained
+/// topics. While disconnected, publishes queue up in the client.
+async fn forward_events(state: AppState, client: AsyncClient, topics: Topics) {
+    let shutdown = state.shutdown_token();
+    let mut rx = state.events.subscribe();
+    loop {
+        let event = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            event = rx.recv() => event.map(|item| item.event),
+        };
+        let result = match event {
+            Ok(ServerEvent::RemainingUpdated {
+                child_id,
+                remaining_minutes,
+                balance,
+                blocked_by_tasks,
+            }) => {
+                publish_child_state(
+                    &client,
+                    &topics,
+                    &child_id,
+                    remaining_minutes,
+                    balance,
+                    blocked_by_tasks,
+                )
+                .await
+            }
+            Ok(ServerEvent::PendingCount { count }) => {
+                publish_pending_count(&client, &topics, count).await
+            }
+            Ok(_) => Ok(()),
+            // Missed some events: publish everything again.
+            Err(RecvError::Lagged(_)) => publish_snapshot(&state, &client, &topics).await,
+            Err(RecvError::Closed) => break,
+        };
+        if let Err(e) = result {
+            warn!(error = %e, "mqtt: failed to publish state");
+        }
+    }
+}
+
+/// Discovery topics and configs for the hub device and every child.
+fn discovery_configs(state: &AppState, topics: &Topics) -> Vec<(String, Value)> {
+    let availability = topics.status();
+    let hub = json!({
+        "identifiers": [topics.node],
+        "name": "GamiScreen",
+        "manufacturer": "GamiScreen",
+    });
+    let mut out = vec![(
+        topics.discovery_config("sensor", "pending_count"),
+        json!({
+            "name": "Pending approvals",
+            "unique_id": format!("{}_pending_count", topics.node),
+            "state_topic": topics.pending_count(),
+            "availability_topic": availability,
+            "icon": "mdi:bell-alert",
+            "device": hub,
+        }),
+    )];
+
+    for child in &state.config().children {
+        let id = object_id(&child.id);
+        let device = json!({
+            "identifiers": [format!("{}_{}", topics.node, id)],
+            "name": child.display_name,
+            "manufacturer": "GamiScreen",
+            "via_device": topics.node,
+        });
+        let state_topic = topics.child_state(&child.id);
+        let unique = |suffix: &str| format!("{}_{}_{}", topics.node, id, suffix);
+
+        out.push((
+            topics.discovery_config("sensor", &format!("{id}_remaining")),
+            json!({
+                "name": "Remaining screen time",
+                "unique_id": unique("remaining"),
+                "state_topic": state_topic,
+                "value_template": "{{ value_json.remaining_minutes }}",
+                "unit_of_measurement": "min",
+                "icon": "mdi:timer-sand",
+                "availability_topic": availability,
+                "device": device,
+            }),
+        ));
+        out.push((
+            topics.discovery_config("sensor", &format!("{id}_balance")),
+            json!({
+                "name": "Balance",
+                "unique_id": unique("balance"),
+                "state_topic": state_topic,
+                "value_template": "{{ value_json.balance }}",
+                "unit_of_measurement": "min",
+                "availability_topic": availability,
+                "device": device,
+            }),
+        ));
+        out.push((
+            topics.discovery_config("binary_sensor", &format!("{id}_blocked")),
+            json!({
+                "name": "Blocked by required tasks",
+                "unique_id": unique("blocked"),
+                "state_topic": state_topic,
+                "value_template": "{{ 'ON' if value_json.blocked_by_tasks else 'OFF' }}",
+                "availability_topic": availability,
+                "device": device,
+            }),
+        ));
+        for task in &state.config().tasks {
+            let task_id = object_id(&task.id);
+            out.push((
+                topics.discovery_config("button", &format!("{id}_task_{task_id}")),
+                json!({
+                    "name": format!("{} (+{} min)", task.name, task.minutes),
+                    "unique_id": unique(&format!("task_{task_id}")),
+                    "command_topic": topics.reward_command(&child.id),
+                    "payload_press": json!({ "task_id": task.id }).to_string(),
+                    "availability_topic": availability,
+                    "device": device,
+                }),
+            ));
+        }
+        out.push((
+            topics.discovery_config("button", &format!("{id}_clear")),
+            json!({
+                "name": "Clear remaining time",
+                "unique_id": unique("clear"),
+                "command_topic": topics.clear_command(&child.id),
+                "payload_press": "CLEAR",
+                "icon": "mdi:timer-off",
+                "availability_topic": availability,
+                "device": device,
+            }),
+        ));
+    }
+    out
+}
+
+/// Apply a command as the configured user, subject to the same access
+/// rules as `POST /children/{id}/reward`.
+async fn handle_command(
+    state: &AppState,
+    cfg: &MqttConfig,
+    child_id: &str,
+    command: Command,
+    payload: &[u8],
+) -> Result<(), super::AppError> {
+    let config = state.config();
+    let user = config
+        .users
+        .iter()
+        .find(|u| u.username == cfg.user)
+        .ok_or_else(super::AppError::unauthorized)?;
+    let auth = AuthCtx {
+        claims: JwtClaims {
+            sub: user.username.clone(),
+            jti: "mqtt".to_string(),
+            exp: 0,
+            role: user.role,
+            child_id: user.child_id.clone(),
+            device_id: None,
+            tenant_id: config.tenant_id.clone(),
+        },
+    };
+    acl::authorize(
+        &auth.claims,
+        &Method::POST,
+        &["children", child_id, "reward"],
+    )?;
+    let req_id = ReqId(format!("mqtt-{}", uuid::Uuid::new_v4()));
+
+    let body = match command {
+        Command::Reward => {
+            let cmd: RewardCommand = serde_json::from_slice(payload)
+                .map_err(|e| super::AppError::bad_request(format!("invalid payload: {e}")))?;
+            api::RewardReq {
+                child_id: child_id.to_string(),
+                task_id: cmd.task_id,
+                minutes: cmd.minutes,
+                description: cmd.description,
+                is_borrowed: cmd.is_borrowed,
+            }
+        }
+        // Takes away whatever time is left, as a penalty.
+        Command::Clear => {
+            let remaining = state
+                .store
+                .get_remaining(child_id)
+                .await
+                .map_err(super::AppError::internal)?;
+            if remaining <= 0 {
+                return Ok(());
+            }
+            api::RewardReq {
+                child_id: child_id.to_string(),
+                task_id: None,
+                minutes: Some(-remaining),
+                description: Some("Cleared from Home Assistant".to_string()),
+                is_borrowed: None,
+            }
+        }
+    };
+    let resp = grant_reward(state, &auth, &req_id, child_id, body).await?;
+    info!(
+        child_id = %child_id,
+        ?command,
+        remaining_minutes = resp.remaining_minutes,
+        "mqtt: command applied"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::AppConfig;
+
+    fn config(user: &str) -> AppConfig {
+        let yaml = format!(
+            r#"
+config_version: "0.14.0"
+tenant_id: "home"
+dev_cors_origin: null
+listen_port: null
+users:
+  - username: "parent"
+    role: parent
+  - username: "alice"
+    role: child
+    child_id: "alice"
+children:
+  - id: "alice"
+    display_name: "Alice"
+tasks: []
+mqtt:
+  enabled: true
+  host: "localhost"
+  user: "{user}"
+"#
+        );
+        serde_yaml::from_str(&yaml).expect("parse config")
+    }
+
+    #[test]
+    fn parses_command_topics() {
+        let topics = Topics::new(config("parent").mqtt.as_ref().unwrap(), "home");
+
+        assert_eq!(
+            topics.parse_command("gamiscreen/children/alice/reward/set"),
+            Some(("alice", Command::Reward))
+        );
+        assert_eq!(
+            topics.parse_command("gamiscreen/children/alice/clear/set"),
+            Some(("alice", Command::Clear))
+        );
+        assert_eq!(
+            topics
+                .parse_command(&topics.reward_command("bob"))
+                .map(|c| c.0),
+            Some("bob")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_command_topics() {
+        let topics = Topics::new(config("parent").mqtt.as_ref().unwrap(), "home");
+
+        for topic in [
+            "other/children/alice/reward/set",
+            "gamiscreenx/children/alice/reward/set",
+            "gamiscreen/children//reward/set",
+            "gamiscreen/children/alice/bob/reward/set",
+            "gamiscreen/children/alice/state",
+            "gamiscreen/children/alice/reward",
+        ] {
+            assert_eq!(topics.parse_command(topic), None, "{topic}");
+        }
+    }
+
+    #[test]
+    fn object_id_replaces_unsupported_characters() {
+        assert_eq!(object_id("alice-1_b"), "alice-1_b");
+        assert_eq!(object_id("a.b/c d+é"), "a_b_c_d__");
+    }
+
+    #[tokio::test]
+    async fn handle_command_rejects_child_user() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+        let cfg = config("alice");
+        let mqtt = cfg.mqtt.clone().unwrap();
+        let state = AppState::new(cfg, store);
+
+        let err = handle_command(&state, &mqtt, "alice", Command::Clear, b"")
+            .await
+            .expect_err("child user must not apply commands");
+        assert!(matches!(err, crate::server::AppError::Forbidden), "{err:?}");
+    }
+}
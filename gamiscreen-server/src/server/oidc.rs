@@ -0,0 +1,402 @@
+//! Parent login through an OpenID Connect provider: the authorization code
+//! flow with PKCE, validation of the returned ID token and the mapping of
+//! provider identities to configured parents.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use gamiscreen_shared::jwt::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+
+use super::config::{AppConfig, OidcConfig};
+
+/// How long the provider has to send the browser back after login starts.
+const LOGIN_TTL: Duration = Duration::from_secs(10 * 60);
+/// How long the web app has to exchange the code it is redirected with.
+const HANDOFF_TTL: Duration = Duration::from_secs(60);
+/// Provider metadata and keys are refetched after this long, or sooner when
+/// a token names an unknown key.
+const PROVIDER_TTL: Duration = Duration::from_secs(60 * 60);
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Holds a hash of the login state in the browser that started the login,
+/// so a callback carrying someone else's state is refused.
+const STATE_COOKIE: &str = "gamiscreen_oidc_state";
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("provider request failed: {0}")]
+    Http(String),
+    #[error("provider returned an error: {0}")]
+    Provider(String),
+    #[error("unknown or expired login")]
+    UnknownState,
+    #[error("invalid ID token: {0}")]
+    InvalidToken(String),
+}
+
+fn invalid_token(e: impl ToString) -> OidcError {
+    OidcError::InvalidToken(e.to_string())
+}
+
+/// An identity confirmed by the provider.
+#[derive(Debug)]
+pub struct Identity {
+    pub subject: String,
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Clone)]
+struct Provider {
+    metadata: Discovery,
+    jwks: JwkSet,
+    fetched_at: Instant,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdClaims {
+    sub: String,
+    nonce: Option<String>,
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A login waiting for the provider to redirect back.
+struct PendingLogin {
+    verifier: String,
+    nonce: String,
+}
+
+/// Single-use values that expire, keyed by a random string.
+struct Expiring<T> {
+    entries: Mutex<HashMap<String, (T, Instant)>>,
+    ttl: Duration,
+}
+
+impl<T> Expiring<T> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    fn insert(&self, value: T) -> String {
+        let key = random_token();
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        entries.insert(key.clone(), (value, now + self.ttl));
+        key
+    }
+
+    fn take(&self, key: &str) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let (value, expires_at) = entries.remove(key)?;
+        (expires_at > Instant::now()).then_some(value)
+    }
+}
+
+fn state_hash(state: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(state.as_bytes()))
+}
+
+/// Whether the request's cookies include the state cookie for `state`.
+pub fn state_cookie_matches(headers: &axum::http::HeaderMap, state: &str) -> bool {
+    let expected = state_hash(state);
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .any(|(name, value)| name == STATE_COOKIE && value == expected)
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Client for the configured provider.
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    provider: tokio::sync::RwLock<Option<Provider>>,
+    pending: Expiring<PendingLogin>,
+    handoffs: Expiring<String>,
+}
+
+impl OidcClient {
+    /// A client for the provider in `config`, if OIDC login is enabled.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        let config = config.oidc_config()?.clone();
+        let http = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .ok()?;
+        Some(Self {
+            config,
+            http,
+            provider: Default::default(),
+            pending: Expiring::new(LOGIN_TTL),
+            handoffs: Expiring::new(HANDOFF_TTL),
+        })
+    }
+
+    pub fn display_name(&self) -> &str {
+        &self.config.display_name
+    }
+
+    /// Start a login: the provider URL to send the browser to, and the
+    /// state that identifies the login.
+    pub async fn authorization_url(&self) -> Result<(String, String), OidcError> {
+        let provider = self.provider(false).await?;
+        let verifier = random_token();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        let nonce = random_token();
+        let state = self.pending.insert(PendingLogin {
+            verifier,
+            nonce: nonce.clone(),
+        });
+        let scope = self.config.scopes.join(" ");
+        let url = reqwest::Url::parse_with_params(
+            &provider.metadata.authorization_endpoint,
+            [
+                ("response_type", "code"),
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("scope", scope.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Provider(format!("invalid authorization_endpoint: {e}")))?;
+        Ok((url.into(), state))
+    }
+
+    /// `Set-Cookie` value binding the login `state` to the browser, sent only
+    /// to the callback.
+    pub fn state_cookie(&self, state: &str) -> String {
+        format!(
+            "{STATE_COOKIE}={}; Path={}; Max-Age={}; HttpOnly; Secure; SameSite=Lax",
+            state_hash(state),
+            self.callback_path(),
+            LOGIN_TTL.as_secs()
+        )
+    }
+
+    /// `Set-Cookie` value removing the state cookie.
+    pub fn clear_state_cookie(&self) -> String {
+        format!(
+            "{STATE_COOKIE}=; Path={}; Max-Age=0; HttpOnly; Secure; SameSite=Lax",
+            self.callback_path()
+        )
+    }
+
+    /// Path of `redirect_url`, where the provider sends the browser back.
+    fn callback_path(&self) -> String {
+        reqwest::Url::parse(&self.config.redirect_url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_else(|_| "/".into())
+    }
+
+    /// Finish the login identified by `state`, redeeming `code` at the token
+    /// endpoint and validating the ID token it returns.
+    pub async fn complete(&self, code: &str, state: &str) -> Result<Identity, OidcError> {
+        let pending = self.pending.take(state).ok_or(OidcError::UnknownState)?;
+        let provider = self.provider(false).await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_url.as_str()),
+            ("code_verifier", pending.verifier.as_str()),
+        ];
+        let mut req = self.http.post(&provider.metadata.token_endpoint);
+        match &self.config.client_secret {
+            // client_secret_basic; both parts are form-encoded first (RFC 6749 2.3.1)
+            Some(secret) => {
+                let encode = |s: &str| utf8_percent_encode(s, NON_ALPHANUMERIC).to_string();
+                req = req.basic_auth(encode(&self.config.client_id), Some(encode(secret)));
+            }
+            None => form.push(("client_id", self.config.client_id.as_str())),
+        }
+        let resp = req
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| OidcError::Http(e.to_string()))?;
+        let status = resp.status();
+        let body = resp
+            .bytes()
+            .await
+            .map_err(|e| OidcError::Http(e.to_string()))?;
+        if !status.is_success() {
+            let message = match serde_json::from_slice::<ErrorResponse>(&body) {
+                Ok(e) => match e.error_description {
+                    Some(d) => format!("{}: {}", e.error, d),
+                    None => e.error,
+                },
+                Err(_) => format!("token endpoint returned {status}"),
+            };
+            return Err(OidcError::Provider(message));
+        }
+        let tokens: TokenResponse =
+            serde_json::from_slice(&body).map_err(|e| OidcError::Provider(e.to_string()))?;
+        let id_token = tokens
+            .id_token
+            .ok_or_else(|| OidcError::Provider("token response has no id_token".into()))?;
+        self.verify_id_token(provider, &id_token, &pending.nonce)
+            .await
+    }
+
+    async fn verify_id_token(
+        &self,
+        mut provider: Provider,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<Identity, OidcError> {
+        let header = jsonwebtoken::decode_header(id_token).map_err(invalid_token)?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(invalid_token("symmetric algorithms are not accepted"));
+        }
+        let find_key = |jwks: &JwkSet| match header.kid.as_deref() {
+            Some(kid) => jwks.find(kid).cloned(),
+            None if jwks.keys.len() == 1 => jwks.keys.first().cloned(),
+            None => None,
+        };
+        let jwk = match find_key(&provider.jwks) {
+            Some(jwk) => jwk,
+            // The provider may have rotated its keys since they were fetched.
+            None => {
+                provider = self.provider(true).await?;
+                find_key(&provider.jwks).ok_or_else(|| invalid_token("signed with unknown key"))?
+            }
+        };
+        let key = DecodingKey::from_jwk(&jwk).map_err(invalid_token)?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&provider.metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = jsonwebtoken::decode::<IdClaims>(id_token, &key, &validation)
+            .map_err(invalid_token)?
+            .claims;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid_token("nonce does not match"));
+        }
+        let groups = match claims.other.get(&self.config.groups_claim) {
+            Some(serde_json::Value::Array(values)) => values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+            Some(serde_json::Value::String(group)) => vec![group.clone()],
+            _ => Vec::new(),
+        };
+        Ok(Identity {
+            subject: claims.sub,
+            groups,
+        })
+    }
+
+    /// The parent `identity` logs in as, if any mapping matches.
+    pub fn username_for(&self, identity: &Identity) -> Option<&str> {
+        self.config
+            .users
+            .iter()
+            .find(|m| {
+                m.subject.as_deref() == Some(identity.subject.as_str())
+                    || m.group
+                        .as_ref()
+                        .is_some_and(|g| identity.groups.contains(g))
+            })
+            .map(|m| m.username.as_str())
+    }
+
+    /// A short-lived code the web app trades for a token for `username`,
+    /// so the token itself never appears in a URL.
+    pub fn issue_handoff(&self, username: &str) -> String {
+        self.handoffs.insert(username.to_string())
+    }
+
+    pub fn redeem_handoff(&self, code: &str) -> Option<String> {
+        self.handoffs.take(code)
+    }
+
+    async fn provider(&self, refresh: bool) -> Result<Provider, OidcError> {
+        if !refresh
+            && let Some(provider) = self.provider.read().await.as_ref()
+            && provider.fetched_at.elapsed() < PROVIDER_TTL
+        {
+            return Ok(provider.clone());
+        }
+        let mut cached = self.provider.write().await;
+        let issuer = self.config.issuer.trim_end_matches('/');
+        let metadata: Discovery = self
+            .get_json(&format!("{issuer}/.well-known/openid-configuration"))
+            .await?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(OidcError::Provider(format!(
+                "discovery document is for issuer {}",
+                metadata.issuer
+            )));
+        }
+        let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+        let provider = Provider {
+            metadata,
+            jwks,
+            fetched_at: Instant::now(),
+        };
+        *cached = Some(provider.clone());
+        Ok(provider)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, OidcError> {
+        let resp = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| OidcError::Http(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(OidcError::Http(format!("{url} returned {}", resp.status())));
+        }
+        let body = resp
+            .bytes()
+            .await
+            .map_err(|e| OidcError::Http(e.to_string()))?;
+        serde_json::from_slice(&body).map_err(|e| OidcError::Provider(format!("{url}: {e}")))
+    }
+}
@@ -0,0 +1,1078 @@
+use std::collections::BTreeMap;
+
+use axum::http::Method;
+use gamiscreen_shared::api;
+use schemars::generate::{SchemaGenerator, SchemaSettings};
+use schemars::{JsonSchema, Schema};
+use serde_json::{Map, Value, json};
+
+use super::{AuditQuery, NotificationsQuery, OidcCallbackQuery, PageOpts, SseQuery, UsageOpts};
+
+/// How an operation is authenticated.
+#[derive(Clone, Copy)]
+enum Auth {
+    Public,
+    /// Session or device JWT in `Authorization: Bearer`.
+    Bearer,
+    /// Single-use ticket in the `ticket` query parameter (EventSource cannot
+    /// set headers).
+    SseTicket,
+    /// `metrics.token`, when configured.
+    MetricsToken,
+}
+
+/// Collects operations and the schemas they reference.
+struct Spec {
+    generator: SchemaGenerator,
+    paths: BTreeMap<String, Map<String, Value>>,
+}
+
+/// One operation under construction; added to the spec by `done`.
+struct Op<'a> {
+    spec: &'a mut Spec,
+    method: Method,
+    path: String,
+    auth: Auth,
+    value: Map<String, Value>,
+    parameters: Vec<Value>,
+    success: (&'static str, Value),
+    has_input: bool,
+    errors: Vec<&'static str>,
+}
+
+impl Spec {
+    fn new() -> Self {
+        let mut settings = SchemaSettings::draft2020_12();
+        settings.definitions_path = "/components/schemas".into();
+        Self {
+            generator: settings.into_generator(),
+            paths: BTreeMap::new(),
+        }
+    }
+
+    fn op(&mut self, method: Method, path: &str, id: &str, summary: &str, tag: &str) -> Op<'_> {
+        let mut value = Map::new();
+        value.insert("operationId".into(), json!(id));
+        value.insert("summary".into(), json!(summary));
+        value.insert("tags".into(), json!([tag]));
+        Op {
+            spec: self,
+            method,
+            path: path.to_string(),
+            auth: Auth::Bearer,
+            value,
+            parameters: Vec::new(),
+            success: ("204", json!({ "description": "Done" })),
+            has_input: false,
+            errors: Vec::new(),
+        }
+    }
+
+    fn schema<T: JsonSchema>(&mut self) -> Value {
+        self.generator.subschema_for::<T>().to_value()
+    }
+}
+
+impl Op<'_> {
+    fn auth(mut self, auth: Auth) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    fn description(mut self, text: &str) -> Self {
+        self.value.insert("description".into(), json!(text));
+        self
+    }
+
+    /// Query parameters taken from the fields of `Q`.
+    fn query<Q: JsonSchema>(mut self) -> Self {
+        let schema: Schema = self.spec.generator.root_schema_for::<Q>();
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+            for (name, prop) in props {
+                let mut prop = prop.clone();
+                let description = prop
+                    .as_object_mut()
+                    .and_then(|p| p.remove("description"))
+                    .unwrap_or(Value::Null);
+                let mut param = json!({
+                    "name": name,
+                    "in": "query",
+                    "required": required.contains(&name.as_str()),
+                    "schema": prop,
+                });
+                if !description.is_null() {
+                    param["description"] = description;
+                }
+                self.parameters.push(param);
+            }
+        }
+        self.has_input = true;
+        self
+    }
+
+    fn body<T: JsonSchema>(self) -> Self {
+        self.json_body::<T>(true)
+    }
+
+    /// A JSON body the client may leave out entirely.
+    fn optional_body<T: JsonSchema>(self) -> Self {
+        self.json_body::<T>(false)
+    }
+
+    fn json_body<T: JsonSchema>(mut self, required: bool) -> Self {
+        let schema = self.spec.schema::<T>();
+        self.value.insert(
+            "requestBody".into(),
+            json!({
+                "required": required,
+                "content": { "application/json": { "schema": schema } },
+            }),
+        );
+        self.has_input = true;
+        self
+    }
+
+    fn raw_body(mut self, body: Value) -> Self {
+        self.value.insert("requestBody".into(), body);
+        self.has_input = true;
+        self
+    }
+
+    /// `200` with a JSON body of type `T`.
+    fn returns<T: JsonSchema>(mut self) -> Self {
+        let schema = self.spec.schema::<T>();
+        self.success = (
+            "200",
+            json!({
+                "description": "OK",
+                "content": { "application/json": { "schema": schema } },
+            }),
+        );
+        self
+    }
+
+    fn returns_raw(mut self, response: Value) -> Self {
+        self.success = ("200", response);
+        self
+    }
+
+    fn redirects(mut self, description: &str) -> Self {
+        self.success = (
+            "303",
+            json!({
+                "description": description,
+                "headers": { "Location": { "schema": { "type": "string" } } },
+            }),
+        );
+        self
+    }
+
+    fn upgrades(mut self, description: &str) -> Self {
+        self.success = ("101", json!({ "description": description }));
+        self
+    }
+
+    fn error(mut self, response: &'static str) -> Self {
+        self.errors.push(response);
+        self
+    }
+
+    fn done(mut self) {
+        // Path parameters come from the template; ids of database rows are integers.
+        let segments: Vec<&str> = self.path.split('/').collect();
+        for (i, segment) in segments.iter().enumerate() {
+            let Some(name) = segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) else {
+                continue;
+            };
+            let parent = i.checked_sub(1).map(|p| segments[p]).unwrap_or("");
+            let integer = name == "notification_id"
+                || (name == "id" && !matches!(parent, "children" | "webhooks"));
+            self.parameters.push(json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": if integer { "integer" } else { "string" } },
+            }));
+            self.errors.push("NotFound");
+        }
+        if self.has_input || !self.parameters.is_empty() {
+            self.errors.push("BadRequest");
+        }
+        match self.auth {
+            Auth::Public => {
+                self.value.insert("security".into(), json!([]));
+            }
+            Auth::Bearer => {
+                self.value
+                    .insert("security".into(), json!([{ "bearerAuth": [] }]));
+                self.errors.extend(["Unauthorized", "Forbidden"]);
+            }
+            Auth::SseTicket => {
+                self.value
+                    .insert("security".into(), json!([{ "sseTicket": [] }]));
+                self.errors.extend(["Unauthorized", "Forbidden"]);
+            }
+            Auth::MetricsToken => {
+                self.value
+                    .insert("security".into(), json!([{ "metricsToken": [] }, {}]));
+                self.errors.extend(["Unauthorized", "NotFound"]);
+            }
+        }
+        self.errors.push("InternalError");
+
+        if !self.parameters.is_empty() {
+            self.value
+                .insert("parameters".into(), Value::Array(self.parameters));
+        }
+        let mut responses = Map::new();
+        responses.insert(self.success.0.into(), self.success.1);
+        for error in self.errors {
+            responses.insert(
+                error_status(error).into(),
+                json!({ "$ref": format!("#/components/responses/{error}") }),
+            );
+        }
+        self.value
+            .insert("responses".into(), Value::Object(responses));
+
+        let method = self.method.as_str().to_ascii_lowercase();
+        self.spec
+            .paths
+            .entry(self.path)
+            .or_default()
+            .insert(method, Value::Object(self.value));
+    }
+}
+
+fn error_status(response: &str) -> &'static str {
+    match response {
+        "BadRequest" => "400",
+        "Unauthorized" => "401",
+        "Forbidden" => "403",
+        "NotFound" => "404",
+        "TooManyRequests" => "429",
+        _ => "500",
+    }
+}
+
+/// OpenAPI 3.1 description of every route served by `router()`.
+pub(super) fn document(tenant_scope: &str) -> Value {
+    use Method as M;
+
+    let v1 = api::API_V1_PREFIX;
+    let t = |path: &str| format!("{tenant_scope}{path}");
+    let mut spec = Spec::new();
+
+    // Meta
+    spec.op(M::GET, "/healthz", "health", "Liveness probe", "meta")
+        .auth(Auth::Public)
+        .returns_raw(json!({
+            "description": "Always `ok`",
+            "content": { "text/plain": { "schema": { "type": "string" } } },
+        }))
+        .done();
+    spec.op(M::GET, "/metrics", "metrics", "Prometheus metrics", "meta")
+        .auth(Auth::MetricsToken)
+        .description(
+            "404 unless `metrics.enabled`; needs the bearer token when `metrics.token` is set.",
+        )
+        .returns_raw(json!({
+            "description": "Prometheus text exposition format",
+            "content": { "text/plain": { "schema": { "type": "string" } } },
+        }))
+        .done();
+    for (path, id) in [
+        ("/api/version".to_string(), "legacyVersion"),
+        (format!("{v1}/version"), "version"),
+    ] {
+        spec.op(M::GET, &path, id, "Server version", "meta")
+            .auth(Auth::Public)
+            .returns::<api::VersionInfoDto>()
+            .done();
+    }
+    spec.op(
+        M::GET,
+        &format!("{v1}/openapi.json"),
+        "openapi",
+        "This document",
+        "meta",
+    )
+    .auth(Auth::Public)
+    .returns_raw(json!({
+        "description": "OpenAPI 3.1 document",
+        "content": { "application/json": { "schema": { "type": "object" } } },
+    }))
+    .done();
+
+    // Auth
+    spec.op(
+        M::GET,
+        "/.well-known/jwks.json",
+        "jwks",
+        "Public keys that verify issued tokens",
+        "auth",
+    )
+    .auth(Auth::Public)
+    .description(
+        "JSON Web Key Set with the active signing key and retired keys whose tokens may still be \
+         valid. Tokens name their key in the `kid` header.",
+    )
+    .returns_raw(json!({
+        "description": "JSON Web Key Set",
+        "content": { "application/json": { "schema": { "type": "object" } } },
+    }))
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/login"),
+        "login",
+        "Log in with a configured user",
+        "auth",
+    )
+    .auth(Auth::Public)
+    .body::<api::AuthReq>()
+    .returns::<api::AuthResp>()
+    .description(
+        "Users with two-factor authentication enabled must also send `totp_code`; without it the \
+         login fails with 401 and error `totp_required`. Repeated failures lock the account for a \
+         while, answered with 429.",
+    )
+    .error("Unauthorized")
+    .error("TooManyRequests")
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/pair"),
+        "pairDevice",
+        "Register a device with a pairing code",
+        "devices",
+    )
+    .auth(Auth::Public)
+    .body::<api::PairReq>()
+    .returns::<api::ClientRegisterResp>()
+    .description(
+        "Redeems a code from `createPairingCode` for a device token of that child. Codes work \
+         once; attempts count towards the login rate limit.",
+    )
+    .error("Unauthorized")
+    .error("TooManyRequests")
+    .done();
+    spec.op(
+        M::GET,
+        &format!("{v1}/auth/options"),
+        "authOptions",
+        "Login methods besides the password",
+        "auth",
+    )
+    .auth(Auth::Public)
+    .returns::<api::AuthOptionsDto>()
+    .done();
+    spec.op(
+        M::GET,
+        &format!("{v1}/auth/oidc/login"),
+        "oidcLogin",
+        "Start an OpenID Connect login",
+        "auth",
+    )
+    .auth(Auth::Public)
+    .description("404 unless `oidc.enabled`.")
+    .redirects("To the identity provider, with a PKCE challenge")
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::GET,
+        &format!("{v1}/auth/oidc/callback"),
+        "oidcCallback",
+        "Return from the identity provider",
+        "auth",
+    )
+    .auth(Auth::Public)
+    .query::<OidcCallbackQuery>()
+    .description(
+        "Redirects to the web app's login page with `oidc_code` for `oidcExchange`, or \
+         `oidc_error` when the login failed or the identity is not mapped to a parent.",
+    )
+    .redirects("To the web app")
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/oidc/exchange"),
+        "oidcExchange",
+        "Trade the code from an OpenID Connect login for a token",
+        "auth",
+    )
+    .auth(Auth::Public)
+    .description("Codes work once and expire after 60 seconds.")
+    .body::<api::OidcExchangeReq>()
+    .returns::<api::AuthResp>()
+    .error("Unauthorized")
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/renew"),
+        "renewToken",
+        "Exchange a valid token for a fresh one",
+        "auth",
+    )
+    .returns::<api::AuthResp>()
+    .error("TooManyRequests")
+    .done();
+    spec.op(
+        M::GET,
+        &format!("{v1}/auth/totp"),
+        "totpStatus",
+        "Two-factor authentication status of the calling parent",
+        "auth",
+    )
+    .returns::<api::TotpStatusDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/totp/enroll"),
+        "totpEnroll",
+        "Start two-factor enrollment",
+        "auth",
+    )
+    .description(
+        "Creates a new TOTP secret for the calling parent, replacing an unconfirmed one. It takes \
+         effect once confirmed through `totpVerify`.",
+    )
+    .returns::<api::TotpEnrollResp>()
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/totp/verify"),
+        "totpVerify",
+        "Confirm two-factor enrollment with a code",
+        "auth",
+    )
+    .description(
+        "Enables TOTP, returns the recovery codes (shown only once) and signs out the parent's \
+         other sessions.",
+    )
+    .body::<api::TotpCodeReq>()
+    .returns::<api::TotpRecoveryCodesResp>()
+    .done();
+    spec.op(
+        M::POST,
+        &format!("{v1}/auth/totp/disable"),
+        "totpDisable",
+        "Turn off two-factor authentication",
+        "auth",
+    )
+    .description("Needs a current code or an unused recovery code.")
+    .body::<api::TotpCodeReq>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/sse/ticket"),
+        "sseTicket",
+        "Get a single-use ticket for the event stream",
+        "events",
+    )
+    .returns::<api::SseTicketResp>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/sse"),
+        "events",
+        "Server-sent event stream",
+        "events",
+    )
+    .auth(Auth::SseTicket)
+    .query::<SseQuery>()
+    .description(
+        "Each event's `data` is a JSON `ServerEvent`. Parents receive all events, children only \
+         their own. Events carry an `id`; reconnecting with `Last-Event-ID` (or the \
+         `last_event_id` parameter) replays what was missed, or sends a `resync` event when that \
+         is no longer possible. Every connection needs a fresh ticket, and the stream ends when \
+         the session that issued it is revoked.",
+    )
+    .returns_raw(json!({
+        "description": "Event stream",
+        "content": {
+            "text/event-stream": { "schema": spec_ref::<api::ServerEvent>() },
+        },
+    }))
+    .done();
+    spec.schema::<api::ServerEvent>();
+
+    // Family
+    spec.op(
+        M::GET,
+        &t("/children"),
+        "listChildren",
+        "List children",
+        "family",
+    )
+    .returns::<Vec<api::ChildDto>>()
+    .done();
+    spec.op(M::GET, &t("/tasks"), "listTasks", "List tasks", "family")
+        .returns::<Vec<api::TaskDto>>()
+        .done();
+    spec.op(
+        M::GET,
+        &t("/config"),
+        "getConfig",
+        "Client configuration",
+        "family",
+    )
+    .returns::<api::ConfigResp>()
+    .done();
+
+    // Parent notifications
+    spec.op(
+        M::GET,
+        &t("/notifications"),
+        "listNotifications",
+        "List parent notifications",
+        "notifications",
+    )
+    .query::<NotificationsQuery>()
+    .returns::<Vec<api::NotificationItemDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/notifications/count"),
+        "countNotifications",
+        "Count unread parent notifications",
+        "notifications",
+    )
+    .returns::<api::NotificationsCountDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/read-all"),
+        "readAllNotifications",
+        "Mark all parent notifications read",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/{id}/read"),
+        "readNotification",
+        "Mark a parent notification read",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/{id}/dismiss"),
+        "dismissNotification",
+        "Dismiss a parent notification",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/task-submissions/{id}/approve"),
+        "approveSubmission",
+        "Approve a task submission, optionally with fewer minutes",
+        "notifications",
+    )
+    .optional_body::<api::SubmissionApproveReq>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/task-submissions/{id}/discard"),
+        "rejectSubmission",
+        "Reject a task submission",
+        "notifications",
+    )
+    .optional_body::<api::SubmissionRejectReq>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/transfers/{id}/approve"),
+        "approveTransfer",
+        "Approve a minute transfer",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/transfers/{id}/discard"),
+        "denyTransfer",
+        "Deny a minute transfer",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/time-requests/{id}/approve"),
+        "approveTimeRequest",
+        "Approve a request for more time",
+        "notifications",
+    )
+    .optional_body::<api::TimeRequestApproveReq>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/time-requests/{id}/discard"),
+        "denyTimeRequest",
+        "Deny a request for more time",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/devices/{id}/approve"),
+        "approveDevice",
+        "Approve a device registration request",
+        "notifications",
+    )
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::POST,
+        &t("/notifications/devices/{id}/discard"),
+        "denyDevice",
+        "Deny a device registration request",
+        "notifications",
+    )
+    .error("NotFound")
+    .done();
+
+    // Children
+    spec.op(
+        M::GET,
+        &t("/children/{id}/remaining"),
+        "getRemaining",
+        "Remaining minutes, balance and task block",
+        "children",
+    )
+    .returns::<api::RemainingDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/reward"),
+        "grantReward",
+        "Grant or deduct minutes",
+        "children",
+    )
+    .body::<api::RewardReq>()
+    .returns::<api::RewardResp>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/reward"),
+        "listRewards",
+        "Reward history",
+        "children",
+    )
+    .query::<PageOpts>()
+    .returns::<Vec<api::RewardHistoryItemDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/usage"),
+        "getUsage",
+        "Screen-time usage in buckets",
+        "children",
+    )
+    .query::<UsageOpts>()
+    .returns::<api::UsageSeriesDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/transfers"),
+        "createTransfer",
+        "Transfer minutes to a sibling",
+        "children",
+    )
+    .body::<api::TransferReq>()
+    .returns::<api::TransferResp>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/tasks"),
+        "listChildTasks",
+        "Tasks with the child's completion status and chores",
+        "children",
+    )
+    .returns::<Vec<api::TaskWithStatusDto>>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/tasks/{task_id}/submit"),
+        "submitTask",
+        "Submit a task for approval",
+        "children",
+    )
+    .raw_body(json!({
+        "required": false,
+        "content": {
+            "multipart/form-data": {
+                "schema": {
+                    "type": "object",
+                    "properties": {
+                        (super::attachments::PHOTO_FIELD): {
+                            "type": "array",
+                            "items": { "type": "string", "contentMediaType": "image/*" },
+                        },
+                    },
+                },
+            },
+        },
+    }))
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/submissions"),
+        "listSubmissions",
+        "Task submission history",
+        "children",
+    )
+    .returns::<Vec<api::TaskSubmissionDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/attachments/{id}"),
+        "getAttachment",
+        "Download a submission photo",
+        "children",
+    )
+    .returns_raw(json!({
+        "description": "The photo",
+        "content": { "image/*": { "schema": { "type": "string", "contentMediaType": "image/*" } } },
+    }))
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/time-requests"),
+        "listTimeRequests",
+        "List requests for more time",
+        "children",
+    )
+    .returns::<Vec<api::TimeRequestDto>>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/time-requests"),
+        "createTimeRequest",
+        "Ask a parent for more time",
+        "children",
+    )
+    .body::<api::TimeRequestReq>()
+    .returns::<api::TimeRequestDto>()
+    .done();
+
+    // Child notifications
+    spec.op(
+        M::GET,
+        &t("/children/{id}/notifications"),
+        "listChildNotifications",
+        "List a child's notifications",
+        "notifications",
+    )
+    .query::<NotificationsQuery>()
+    .returns::<Vec<api::NotificationItemDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/notifications/count"),
+        "countChildNotifications",
+        "Count a child's unread notifications",
+        "notifications",
+    )
+    .returns::<api::NotificationsCountDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/notifications/read-all"),
+        "readAllChildNotifications",
+        "Mark all of a child's notifications read",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/notifications/{notification_id}/read"),
+        "readChildNotification",
+        "Mark a child notification read",
+        "notifications",
+    )
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/notifications/{notification_id}/dismiss"),
+        "dismissChildNotification",
+        "Dismiss a child notification",
+        "notifications",
+    )
+    .done();
+
+    // Savings
+    spec.op(
+        M::GET,
+        &t("/children/{id}/savings"),
+        "getSavings",
+        "Savings pot",
+        "savings",
+    )
+    .returns::<api::SavingsDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/savings/deposit"),
+        "depositSavings",
+        "Move minutes into savings",
+        "savings",
+    )
+    .body::<api::SavingsReq>()
+    .returns::<api::SavingsDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/savings/withdraw"),
+        "withdrawSavings",
+        "Move minutes out of savings",
+        "savings",
+    )
+    .body::<api::SavingsReq>()
+    .returns::<api::SavingsDto>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/savings/history"),
+        "listSavingsHistory",
+        "Savings transactions",
+        "savings",
+    )
+    .query::<PageOpts>()
+    .returns::<Vec<api::SavingsTransactionDto>>()
+    .done();
+
+    // Devices
+    spec.op(
+        M::POST,
+        &t("/children/{id}/register"),
+        "registerDevice",
+        "Register a device and obtain its token",
+        "devices",
+    )
+    .body::<api::ClientRegisterReq>()
+    .returns::<api::ClientRegisterResp>()
+    .description(
+        "Parents register devices directly. A child registering a device it has not used \
+         before gets `202 Accepted` with the pending `DeviceDto` instead of a token; once a \
+         parent approves the request, registering again returns the token. Blocked devices \
+         get `403`.",
+    )
+    .error("NotFound")
+    .error("TooManyRequests")
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/devices"),
+        "listChildDevices",
+        "Devices known for the child",
+        "devices",
+    )
+    .returns::<Vec<api::DeviceDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/devices/{device_id}"),
+        "getChildDevice",
+        "Approval status of one device",
+        "devices",
+    )
+    .returns::<api::DeviceDto>()
+    .description("Lets a device poll whether its registration request was approved.")
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/devices/{device_id}/block"),
+        "blockDevice",
+        "Block a device and revoke its sessions",
+        "devices",
+    )
+    .returns::<api::DeviceDto>()
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/devices/{device_id}/unblock"),
+        "unblockDevice",
+        "Allow a blocked device to register again",
+        "devices",
+    )
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/pairing-code"),
+        "createPairingCode",
+        "Create a code that registers a device for the child",
+        "devices",
+    )
+    .returns::<api::PairingCodeResp>()
+    .description("The code is entered on the device with `gamiscreen-client login --code`.")
+    .error("NotFound")
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/device/{device_id}/heartbeat"),
+        "heartbeat",
+        "Report used minutes",
+        "devices",
+    )
+    .body::<api::HeartbeatReq>()
+    .returns::<api::HeartbeatResp>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/children/{id}/device/{device_id}/ws"),
+        "agentSocket",
+        "Device agent WebSocket",
+        "devices",
+    )
+    .description(
+        "Upgrades to a WebSocket for the device named in the token. The agent sends JSON \
+         `AgentMessage` frames (heartbeats); the server replies with `AgentServerMessage` \
+         frames carrying heartbeat acks, the child's events and lock/unlock commands. The \
+         server pings periodically and drops agents that stop answering.",
+    )
+    .upgrades("Switching to the WebSocket protocol")
+    .done();
+    spec.schema::<api::AgentMessage>();
+    spec.schema::<api::AgentServerMessage>();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/push/subscriptions"),
+        "subscribePush",
+        "Register a Web Push subscription",
+        "devices",
+    )
+    .body::<api::PushSubscribeReq>()
+    .returns::<api::PushSubscribeResp>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/children/{id}/push/subscriptions/unsubscribe"),
+        "unsubscribePush",
+        "Remove a Web Push subscription",
+        "devices",
+    )
+    .body::<api::PushUnsubscribeReq>()
+    .done();
+
+    // Administration
+    spec.op(
+        M::GET,
+        &t("/audit"),
+        "listAudit",
+        "Audit log of parent actions",
+        "admin",
+    )
+    .query::<AuditQuery>()
+    .returns::<Vec<api::AuditEntryDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/webhooks"),
+        "listWebhooks",
+        "Configured webhooks",
+        "admin",
+    )
+    .returns::<Vec<api::WebhookDto>>()
+    .done();
+    spec.op(
+        M::GET,
+        &t("/webhooks/{id}/deliveries"),
+        "listWebhookDeliveries",
+        "Webhook delivery log",
+        "admin",
+    )
+    .query::<PageOpts>()
+    .returns::<Vec<api::WebhookDeliveryDto>>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/webhooks/{id}/test"),
+        "testWebhook",
+        "Send a ping to a webhook",
+        "admin",
+    )
+    .returns::<api::WebhookDeliveryDto>()
+    .done();
+    spec.op(
+        M::POST,
+        &t("/config/reload"),
+        "reloadConfig",
+        "Reload the config file",
+        "admin",
+    )
+    .description(
+        "Reads the config file again and applies it without a restart. An invalid file is \
+         rejected with 400 and the running configuration is kept. Settings that only take \
+         effect on restart (`tenant_id`, `listen_port`, `jwt_secret`, `dev_cors_origin`, \
+         `mqtt`, `webhooks`) keep their running values and are listed in `restart_required`.",
+    )
+    .returns::<api::ConfigReloadResp>()
+    .error("BadRequest")
+    .done();
+
+    let mut schemas = spec.generator.take_definitions(true);
+    schemas.insert(
+        "Error".into(),
+        json!({
+            "type": "object",
+            "properties": { "error": { "type": "string" } },
+            "required": ["error"],
+        }),
+    );
+    let error = |description: &str| {
+        json!({
+            "description": description,
+            "content": {
+                "application/json": { "schema": { "$ref": "#/components/schemas/Error" } },
+            },
+        })
+    };
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "GamiScreen API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": spec.paths,
+        "components": {
+            "schemas": schemas,
+            "securitySchemes": {
+                "bearerAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+                "sseTicket": { "type": "apiKey", "in": "query", "name": "ticket" },
+                "metricsToken": { "type": "http", "scheme": "bearer" },
+            },
+            "responses": {
+                "BadRequest": error("Invalid input"),
+                "Unauthorized": error("Missing or invalid credentials"),
+                "Forbidden": error("Not allowed for this user"),
+                "NotFound": error("Unknown resource"),
+                "TooManyRequests": {
+                    "description": "Rate limited",
+                    "headers": { "Retry-After": { "schema": { "type": "integer" } } },
+                    "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/Error" } },
+                    },
+                },
+                "InternalError": error("Server error"),
+            },
+        },
+    })
+}
+
+/// `$ref` to a component schema, for schemas added separately.
+fn spec_ref<T: JsonSchema>() -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", T::schema_name()) })
+}
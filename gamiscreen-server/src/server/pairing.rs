@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use gamiscreen_shared::jwt::JwtClaims;
+use rand_core::{OsRng, RngCore};
+
+/// How long a parent has to enter a code on the new device.
+const CODE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Letters and digits that cannot be confused with each other when read off
+/// a screen; 32 symbols, so each carries 5 bits.
+const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LEN: usize = 8;
+
+/// Single-use codes a parent creates to pair a new device with a child.
+///
+/// The device redeems a code for a device token without anyone typing a
+/// password on it. Codes expire after a short TTL, and redemption shares the
+/// login rate limit, which leaves guessing one of the 2^40 codes hopeless.
+#[derive(Debug)]
+pub(crate) struct PairingCodes {
+    codes: Mutex<HashMap<String, Pairing>>,
+    ttl: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Pairing {
+    pub child_id: String,
+    /// Session of the parent who created the code, to attribute the
+    /// registration to.
+    pub issued_by: JwtClaims,
+    expires_at: Instant,
+}
+
+impl Default for PairingCodes {
+    fn default() -> Self {
+        Self::new(CODE_TTL)
+    }
+}
+
+/// Uppercase the code and drop separators, so `abcd efgh` matches
+/// `ABCD-EFGH`.
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+impl PairingCodes {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            codes: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Issue a code, formatted `XXXX-XXXX`, pairing a device with `child_id`.
+    pub fn issue(&self, child_id: &str, issued_by: JwtClaims) -> String {
+        let now = Instant::now();
+        let mut map = self.codes.lock().unwrap_or_else(|e| e.into_inner());
+        map.retain(|_, p| p.expires_at > now);
+        let code = loop {
+            let mut bytes = [0u8; CODE_LEN];
+            OsRng.fill_bytes(&mut bytes);
+            let code: String = bytes
+                .iter()
+                .map(|b| ALPHABET[*b as usize % ALPHABET.len()] as char)
+                .collect();
+            if !map.contains_key(&code) {
+                break code;
+            }
+        };
+        map.insert(
+            code.clone(),
+            Pairing {
+                child_id: child_id.to_string(),
+                issued_by,
+                expires_at: now + self.ttl,
+            },
+        );
+        format!("{}-{}", &code[..CODE_LEN / 2], &code[CODE_LEN / 2..])
+    }
+
+    /// Consume `code`, returning what it pairs with if it is known and has
+    /// not expired.
+    pub fn redeem(&self, code: &str) -> Option<Pairing> {
+        let mut map = self.codes.lock().unwrap_or_else(|e| e.into_inner());
+        let p = map.remove(&normalize(code))?;
+        (p.expires_at > Instant::now()).then_some(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gamiscreen_shared::auth::Role;
+
+    use super::*;
+
+    fn parent() -> JwtClaims {
+        JwtClaims {
+            sub: "parent".into(),
+            jti: "s1".into(),
+            role: Role::Parent,
+            child_id: None,
+            device_id: None,
+            tenant_id: "t".into(),
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn codes_are_single_use_and_forgive_formatting() {
+        let codes = PairingCodes::default();
+        let code = codes.issue("alice", parent());
+        assert_eq!(code.len(), CODE_LEN + 1);
+        assert_eq!(code.as_bytes()[CODE_LEN / 2], b'-');
+        let typed = code.replace('-', " ").to_lowercase();
+        let pairing = codes.redeem(&typed).unwrap();
+        assert_eq!(pairing.child_id, "alice");
+        assert_eq!(pairing.issued_by.sub, "parent");
+        assert!(codes.redeem(&code).is_none());
+    }
+
+    #[test]
+    fn expired_codes_are_rejected_and_pruned() {
+        let codes = PairingCodes::new(Duration::ZERO);
+        let code = codes.issue("alice", parent());
+        codes.issue("bob", parent());
+        assert!(codes.redeem(&code).is_none());
+        assert_eq!(codes.codes.lock().unwrap().len(), 1);
+    }
+}
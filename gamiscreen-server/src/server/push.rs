@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
-use gamiscreen_shared::api::ServerEvent;
+use gamiscreen_shared::api::{NotificationAudience, ServerEvent};
 use tokio::sync::Mutex;
 use tracing::{info, warn};
 use web_push::{
@@ -98,6 +98,29 @@ impl PushServiceInner {
                 let payload = Arc::new(serde_json::to_vec(&event).map_err(|e| e.to_string())?);
                 self.send_to_subscriptions(store, subs, payload).await
             }
+            ServerEvent::NotificationCreated {
+                audience: NotificationAudience::Child,
+                child_id,
+                ..
+            } => {
+                let subs = store
+                    .list_push_subscriptions_for_child(&self.tenant_id, child_id)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if subs.is_empty() {
+                    return Ok(());
+                }
+                let payload = Arc::new(serde_json::to_vec(&event).map_err(|e| e.to_string())?);
+                self.send_to_subscriptions(store, subs, payload).await
+            }
+            // Parents learn about new items through PendingCount; the time
+            // request outcome reaches the child as a notification above.
+            // Device connection changes are only for open parent sessions,
+            // and resyncs only concern a single stream.
+            ServerEvent::NotificationCreated { .. }
+            | ServerEvent::TimeRequestResolved { .. }
+            | ServerEvent::DeviceConnection { .. }
+            | ServerEvent::Resync => Ok(()),
             ServerEvent::PendingCount { .. } => {
                 let subs = store
                     .list_all_push_subscriptions(&self.tenant_id)
@@ -163,6 +186,7 @@ impl PushServiceInner {
         match result {
             Ok(()) => {
                 info!(endpoint = %endpoint, "push: delivered");
+                crate::metrics::metrics().record_push_delivery(true);
                 if let Err(e) = store
                     .mark_push_delivery_result(subscription.id, true, None)
                     .await
@@ -173,6 +197,7 @@ impl PushServiceInner {
             Err(err) => {
                 let err_str = err.to_string();
                 warn!(endpoint = %endpoint, error = %err_str, "push: send failed");
+                crate::metrics::metrics().record_push_delivery(false);
 
                 if let Err(e) = store
                     .mark_push_delivery_result(subscription.id, false, Some(&err_str))
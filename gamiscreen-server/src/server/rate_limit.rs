@@ -15,10 +15,11 @@ const WINDOW_SECS: u64 = 60;
 /// evicted to prevent unbounded memory growth. Thread-safe via `std::sync::Mutex`
 /// (non-async — held only briefly for HashMap operations).
 ///
-/// **Note:** When the server is behind a reverse proxy, `ConnectInfo<SocketAddr>`
-/// returns the proxy's IP, not the real client IP. In that case all clients share
-/// one rate-limit bucket. For proxy-aware IP extraction, consider
-/// `axum-client-ip` or configure the proxy to pass the real IP in a trusted header.
+/// **Note:** Callers pass the address from [`super::client_ip::ClientIp`]. Behind a
+/// reverse proxy that is only the real client address when the proxy is listed in
+/// `security.trusted_proxies`; otherwise all clients share the proxy's bucket.
+/// Guessing one account's password from many addresses is caught by the
+/// per-account lockout instead (`server/lockout.rs`).
 #[derive(Debug)]
 pub(crate) struct LoginRateLimiter {
     attempts: Mutex<HashMap<IpAddr, Vec<Instant>>>,
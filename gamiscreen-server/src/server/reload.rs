@@ -0,0 +1,192 @@
+use std::sync::{Arc, RwLock};
+
+use gamiscreen_shared::api::ConfigReloadResp;
+
+use super::config::{AppConfig, ConfigError};
+use super::push::PushService;
+use super::{AppState, audit, client_ip, oidc};
+use crate::storage::StorageError;
+
+/// A value that can be replaced while readers keep using the one they
+/// loaded.
+pub(crate) struct Swap<T>(RwLock<Arc<T>>);
+
+impl<T> Swap<T> {
+    pub fn new(value: T) -> Self {
+        Self(RwLock::new(Arc::new(value)))
+    }
+
+    pub fn load(&self) -> Arc<T> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn store(&self, value: T) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(value);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReloadError {
+    #[error("the server was not started from a config file")]
+    NoConfigFile,
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// Keep the running value of a setting the file changed, noting its `name`.
+fn pin<T: PartialEq + Clone>(
+    name: &str,
+    running: &T,
+    next: &mut T,
+    restart_required: &mut Vec<String>,
+) {
+    if running != next {
+        *next = running.clone();
+        restart_required.push(name.to_string());
+    }
+}
+
+/// Settings read once at startup: the routes (including the submission body
+/// limit), listeners, CORS layer, signing keys, MQTT bridge and webhook
+/// delivery are built from them.
+fn pin_restart_only(running: &AppConfig, next: &mut AppConfig) -> Vec<String> {
+    let mut restart_required = Vec::new();
+    let r = &mut restart_required;
+    pin("tenant_id", &running.tenant_id, &mut next.tenant_id, r);
+    pin(
+        "listen_port",
+        &running.listen_port,
+        &mut next.listen_port,
+        r,
+    );
+    pin("jwt_secret", &running.jwt_secret, &mut next.jwt_secret, r);
+    pin(
+        "dev_cors_origin",
+        &running.dev_cors_origin,
+        &mut next.dev_cors_origin,
+        r,
+    );
+    pin("mqtt", &running.mqtt, &mut next.mqtt, r);
+    pin("webhooks", &running.webhooks, &mut next.webhooks, r);
+    pin("tls", &running.tls, &mut next.tls, r);
+    let limits = running.attachments_config();
+    let mut next_limits = next.attachments_config();
+    pin(
+        "attachments.max_bytes",
+        &limits.max_bytes,
+        &mut next_limits.max_bytes,
+        r,
+    );
+    pin(
+        "attachments.max_per_submission",
+        &limits.max_per_submission,
+        &mut next_limits.max_per_submission,
+        r,
+    );
+    next.attachments = if next_limits == limits {
+        running.attachments.clone()
+    } else {
+        Some(next_limits)
+    };
+    restart_required
+}
+
+/// Top-level settings that differ between `running` and `next`.
+fn changed_sections(running: &AppConfig, next: &AppConfig) -> Vec<String> {
+    [
+        ("children", running.children != next.children),
+        ("tasks", running.tasks != next.tasks),
+        ("users", running.users != next.users),
+        ("push", running.push != next.push),
+        ("savings", running.savings != next.savings),
+        (
+            "debt_limit_minutes",
+            running.debt_limit_minutes != next.debt_limit_minutes,
+        ),
+        ("attachments", running.attachments != next.attachments),
+        ("chores", running.chores != next.chores),
+        ("metrics", running.metrics != next.metrics),
+        ("oidc", running.oidc != next.oidc),
+        ("security", running.security != next.security),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+impl AppState {
+    /// Read the config file again and apply it.
+    ///
+    /// The file is validated first; an invalid file leaves the running
+    /// configuration untouched. Restart-only settings keep their running
+    /// values and are reported in `restart_required`.
+    pub async fn reload_config(&self) -> Result<ConfigReloadResp, ReloadError> {
+        let path = self.config_path.clone().ok_or(ReloadError::NoConfigFile)?;
+        // One reload at a time, so two of them cannot interleave their swaps
+        let _guard = self.reload_lock.lock().await;
+        let mut next = AppConfig::load_from_path(&path)?;
+        let running = self.config();
+        let restart_required = pin_restart_only(&running, &mut next);
+        let changed = changed_sections(&running, &next);
+
+        if running.children != next.children || running.tasks != next.tasks {
+            self.store
+                .seed_from_config(&next.children, &next.tasks)
+                .await?;
+        }
+        if running.push != next.push {
+            self.push.store(PushService::from_config(&next));
+        }
+        if running.oidc != next.oidc {
+            self.oidc
+                .store(oidc::OidcClient::from_config(&next).map(Arc::new));
+        }
+        if running.security != next.security {
+            // Validated when the file was loaded
+            let trusted = client_ip::parse_trusted_proxies(&next.security_config().trusted_proxies)
+                .unwrap_or_default();
+            self.trusted_proxies.store(trusted);
+        }
+        self.config.store(next);
+
+        tracing::info!(
+            path = %path.display(),
+            changed = ?changed,
+            restart_required = ?restart_required,
+            "config: reloaded"
+        );
+        Ok(ConfigReloadResp {
+            changed,
+            restart_required,
+        })
+    }
+
+    /// [`reload_config`](Self::reload_config) for a SIGHUP, recorded in the
+    /// audit log as `config.reload` by the system.
+    pub async fn reload_config_on_signal(&self) -> Result<ConfigReloadResp, ReloadError> {
+        let report = self.reload_config().await?;
+        audit::record_system(
+            self,
+            audit::AuditEvent {
+                action: "config.reload",
+                child_id: None,
+                target_id: None,
+                before: None,
+                after: Some(report_value(&report)),
+            },
+        )
+        .await;
+        Ok(report)
+    }
+}
+
+/// The `after` value of a `config.reload` audit entry.
+pub(super) fn report_value(report: &ConfigReloadResp) -> serde_json::Value {
+    serde_json::json!({
+        "changed": report.changed,
+        "restart_required": report.restart_required,
+    })
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use super::AppState;
+
+/// How often the interest job checks for pots that are due.
+const INTEREST_TICK: Duration = Duration::from_secs(60 * 60);
+
+/// Spawn the weekly savings interest job.
+///
+/// The job wakes up hourly and, while savings are enabled with a positive
+/// rate, credits interest to every pot whose last payment is at least a week
+/// old; missed weeks are caught up on startup.
+pub fn spawn_interest_job(state: &AppState) {
+    let state = state.clone();
+    let shutdown = state.shutdown_token();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(INTEREST_TICK);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            // Read on every tick so a config reload takes effect
+            let weekly_percent = match state.config().savings_config() {
+                Some(cfg) if cfg.weekly_interest_percent > 0.0 => cfg.weekly_interest_percent,
+                _ => continue,
+            };
+            let now = chrono::Utc::now().naive_utc();
+            match state
+                .store
+                .apply_savings_interest(weekly_percent, now)
+                .await
+            {
+                Ok(0) => {}
+                Ok(n) => tracing::info!(pots = n, "savings: credited weekly interest"),
+                Err(e) => tracing::error!(error = %e, "savings: interest job failed"),
+            }
+        }
+    });
+}
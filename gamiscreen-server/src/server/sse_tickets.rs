@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use gamiscreen_shared::jwt::JwtClaims;
+
+/// How long an unused ticket stays valid.
+const TICKET_TTL: Duration = Duration::from_secs(30);
+
+/// Single-use tickets for opening the SSE stream.
+///
+/// `EventSource` cannot send an `Authorization` header, so clients trade their
+/// bearer token for a ticket and pass that in the query string instead. A
+/// ticket carries the claims of the session that asked for it, expires after
+/// a short TTL and is consumed by the first stream that presents it, so one
+/// that leaks into access logs or browser history is worthless.
+#[derive(Debug)]
+pub(crate) struct SseTickets {
+    tickets: Mutex<HashMap<String, Ticket>>,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+struct Ticket {
+    claims: JwtClaims,
+    expires_at: Instant,
+}
+
+impl Default for SseTickets {
+    fn default() -> Self {
+        Self::new(TICKET_TTL)
+    }
+}
+
+impl SseTickets {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            tickets: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Issue a ticket for the session identified by `claims`.
+    pub fn issue(&self, claims: JwtClaims) -> String {
+        let now = Instant::now();
+        let ticket = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let mut map = self.tickets.lock().unwrap_or_else(|e| e.into_inner());
+        // Drop tickets nobody redeemed so the map stays small.
+        map.retain(|_, t| t.expires_at > now);
+        map.insert(
+            ticket.clone(),
+            Ticket {
+                claims,
+                expires_at: now + self.ttl,
+            },
+        );
+        ticket
+    }
+
+    /// Consume `ticket`, returning the claims it was issued for if it is
+    /// known and has not expired.
+    pub fn redeem(&self, ticket: &str) -> Option<JwtClaims> {
+        let mut map = self.tickets.lock().unwrap_or_else(|e| e.into_inner());
+        let t = map.remove(ticket)?;
+        (t.expires_at > Instant::now()).then_some(t.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gamiscreen_shared::auth::Role;
+
+    use super::*;
+
+    fn claims(jti: &str) -> JwtClaims {
+        JwtClaims {
+            sub: "parent".into(),
+            jti: jti.into(),
+            role: Role::Parent,
+            child_id: None,
+            device_id: None,
+            tenant_id: "t".into(),
+            exp: 0,
+        }
+    }
+
+    #[test]
+    fn tickets_are_single_use() {
+        let tickets = SseTickets::default();
+        let ticket = tickets.issue(claims("s1"));
+        assert_eq!(tickets.redeem(&ticket).map(|c| c.jti), Some("s1".into()));
+        assert!(tickets.redeem(&ticket).is_none());
+    }
+
+    #[test]
+    fn unknown_tickets_are_rejected() {
+        let tickets = SseTickets::default();
+        tickets.issue(claims("s1"));
+        assert!(tickets.redeem("nope").is_none());
+    }
+
+    #[test]
+    fn expired_tickets_are_rejected_and_pruned() {
+        let tickets = SseTickets::new(Duration::ZERO);
+        let ticket = tickets.issue(claims("s1"));
+        tickets.issue(claims("s2"));
+        assert!(tickets.redeem(&ticket).is_none());
+        assert_eq!(tickets.tickets.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,253 @@
+//! HTTPS served with rustls: the certificate is reloaded from its files
+//! without dropping connections, and plain HTTP can be redirected to it.
+
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use axum::Router;
+use axum::extract::Request;
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum_server::Handle;
+use axum_server::tls_rustls::RustlsConfig;
+use tokio_util::sync::CancellationToken;
+
+use super::config::TlsConfig;
+
+/// How often the certificate files are checked for changes.
+const WATCH_TICK: Duration = Duration::from_secs(30);
+
+#[derive(Debug, thiserror::Error)]
+#[error("load TLS certificate {} and key {}: {source}", cert_path.display(), key_path.display())]
+pub struct TlsError {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    source: io::Error,
+}
+
+/// The certificate served over HTTPS, shared with the listener.
+#[derive(Clone)]
+pub struct Certificates {
+    rustls: RustlsConfig,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl Certificates {
+    pub async fn load(tls: &TlsConfig) -> Result<Self, TlsError> {
+        let rustls = RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+            .await
+            .map_err(|source| TlsError {
+                cert_path: tls.cert_path.clone(),
+                key_path: tls.key_path.clone(),
+                source,
+            })?;
+        Ok(Self {
+            rustls,
+            cert_path: tls.cert_path.clone(),
+            key_path: tls.key_path.clone(),
+        })
+    }
+
+    pub fn rustls_config(&self) -> RustlsConfig {
+        self.rustls.clone()
+    }
+
+    /// Read the files again. When they fail to load, the certificate in use
+    /// is kept.
+    pub async fn reload(&self) -> Result<(), TlsError> {
+        self.rustls
+            .reload_from_pem_file(&self.cert_path, &self.key_path)
+            .await
+            .map_err(|source| TlsError {
+                cert_path: self.cert_path.clone(),
+                key_path: self.key_path.clone(),
+                source,
+            })?;
+        tracing::info!(cert = %self.cert_path.display(), "tls: certificate reloaded");
+        Ok(())
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        Some((modified_at(&self.cert_path)?, modified_at(&self.key_path)?))
+    }
+
+    /// Reload the certificate when its files change, e.g. after a renewal.
+    pub fn spawn_watch(&self, shutdown: CancellationToken) {
+        let certs = self.clone();
+        tokio::spawn(async move {
+            let mut loaded = certs.modified();
+            let mut changed = loaded;
+            let mut ticker = tokio::time::interval(WATCH_TICK);
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = ticker.tick() => {}
+                }
+                let current = certs.modified();
+                if current.is_none() || current == loaded {
+                    continue;
+                }
+                // Renewals write the certificate and key one after the other;
+                // wait until neither changed for a tick.
+                if current != changed {
+                    changed = current;
+                    continue;
+                }
+                loaded = current;
+                if let Err(e) = certs.reload().await {
+                    tracing::error!(error = %e, "tls: reload failed, keeping the current certificate");
+                }
+            }
+        });
+    }
+}
+
+/// Serve `app` over HTTPS on `addr` until `shutdown` is cancelled, plus the
+/// HTTP redirect listener when `redirect_http_port` is set.
+pub async fn serve(
+    addr: SocketAddr,
+    app: Router,
+    certs: Certificates,
+    redirect_http_port: Option<u16>,
+    shutdown: CancellationToken,
+) -> io::Result<()> {
+    if let Some(http_port) = redirect_http_port {
+        let http_addr = SocketAddr::new(addr.ip(), http_port);
+        let listener = tokio::net::TcpListener::bind(http_addr).await?;
+        tracing::info!(addr = %http_addr, "tls: redirecting HTTP to HTTPS");
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, redirect_router(addr.port()))
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .await
+            {
+                tracing::error!(error = %e, "tls: redirect listener failed");
+            }
+        });
+    }
+
+    let handle = Handle::new();
+    let stop = handle.clone();
+    tokio::spawn(async move {
+        shutdown.cancelled().await;
+        stop.graceful_shutdown(None);
+    });
+    axum_server::bind_rustls(addr, certs.rustls_config())
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Routes sending every request to the same URL over HTTPS on `https_port`.
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |req: Request| async move { redirect_to_https(&req, https_port) })
+}
+
+fn redirect_to_https(req: &Request, https_port: u16) -> Response {
+    let Some(host) = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .map(host_without_port)
+        .filter(|h| !h.is_empty())
+    else {
+        return (StatusCode::BAD_REQUEST, "missing Host header").into_response();
+    };
+    let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+    let location = if https_port == 443 {
+        format!("https://{host}{path}")
+    } else {
+        format!("https://{host}:{https_port}{path}")
+    };
+    Redirect::permanent(&location).into_response()
+}
+
+fn host_without_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        // IPv6 literal, e.g. [::1]:80
+        return host.find(']').map_or(host, |end| &host[..=end]);
+    }
+    host.rsplit_once(':').map_or(host, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+
+    use super::*;
+
+    fn redirect(host: Option<&str>, uri: &str, https_port: u16) -> Response {
+        let mut req = Request::builder().uri(uri);
+        if let Some(host) = host {
+            req = req.header(header::HOST, host);
+        }
+        redirect_to_https(&req.body(Body::empty()).unwrap(), https_port)
+    }
+
+    fn location(resp: &Response) -> &str {
+        resp.headers()[header::LOCATION].to_str().unwrap()
+    }
+
+    #[test]
+    fn redirects_to_the_same_url_over_https() {
+        let resp = redirect(Some("screen.local:8080"), "/api/v1/version?x=1", 8443);
+        assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            location(&resp),
+            "https://screen.local:8443/api/v1/version?x=1"
+        );
+
+        let resp = redirect(Some("[fd00::1]:80"), "/", 443);
+        assert_eq!(location(&resp), "https://[fd00::1]/");
+
+        let resp = redirect(Some("screen.local"), "/", 443);
+        assert_eq!(location(&resp), "https://screen.local/");
+
+        let resp = redirect(None, "/", 443);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    fn write_self_signed(dir: &Path, name: &str) -> TlsConfig {
+        let cert = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.signing_key.serialize_pem()).unwrap();
+        TlsConfig {
+            enabled: true,
+            cert_path,
+            key_path,
+            redirect_http_port: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reload_keeps_the_certificate_when_the_files_are_broken() {
+        let dir = tempfile::tempdir().unwrap();
+        let tls = write_self_signed(dir.path(), "first.local");
+        let certs = Certificates::load(&tls).await.unwrap();
+        let first = certs.rustls_config().get_inner();
+
+        std::fs::write(&tls.key_path, "not a key").unwrap();
+        assert!(certs.reload().await.is_err());
+        assert!(std::sync::Arc::ptr_eq(
+            &first,
+            &certs.rustls_config().get_inner()
+        ));
+
+        write_self_signed(dir.path(), "second.local");
+        certs.reload().await.unwrap();
+        assert!(!std::sync::Arc::ptr_eq(
+            &first,
+            &certs.rustls_config().get_inner()
+        ));
+    }
+}
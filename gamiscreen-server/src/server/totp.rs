@@ -0,0 +1,180 @@
+//! Time-based one-time passwords (RFC 6238) as a second login factor for
+//! parents, plus the single-use recovery codes issued alongside them.
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use rand_core::{OsRng, RngCore};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::storage::{StorageError, Store};
+
+/// Issuer shown by authenticator apps.
+const ISSUER: &str = "GamiScreen";
+/// Seconds per time step, as assumed by common authenticator apps.
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Steps either side of now that are still accepted, for clock drift.
+const SKEW_STEPS: i64 = 1;
+/// 160-bit secrets, the size RFC 4226 recommends for HMAC-SHA1.
+const SECRET_LEN: usize = 20;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+/// The secret in the base32 form authenticator apps accept for manual entry.
+pub fn encode_secret(secret: &[u8]) -> String {
+    BASE32_NOPAD.encode(secret)
+}
+
+/// `otpauth://` URI for enrolling `username` by QR code.
+pub fn otpauth_uri(username: &str, secret: &[u8]) -> String {
+    let label = utf8_percent_encode(&format!("{ISSUER}:{username}"), NON_ALPHANUMERIC).to_string();
+    format!(
+        "otpauth://totp/{label}?secret={}&issuer={ISSUER}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}",
+        encode_secret(secret)
+    )
+}
+
+fn hotp(secret: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    // Dynamic truncation, RFC 4226 section 5.3
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    binary % 10u32.pow(DIGITS)
+}
+
+/// The code an authenticator app shows at `unix_secs`.
+pub fn code_at(secret: &[u8], unix_secs: i64) -> String {
+    format!(
+        "{:0width$}",
+        hotp(secret, unix_secs.div_euclid(STEP_SECS)),
+        width = DIGITS as usize
+    )
+}
+
+/// The time step `code` is valid for at `unix_secs`, if any.
+pub fn matching_step(secret: &[u8], code: &str, unix_secs: i64) -> Option<i64> {
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let now = unix_secs.div_euclid(STEP_SECS);
+    (now - SKEW_STEPS..=now + SKEW_STEPS).find(|&step| hotp(secret, step) == code)
+}
+
+/// Fresh recovery codes, formatted like `abcd-efgh`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            OsRng.fill_bytes(&mut bytes);
+            let code = BASE32_NOPAD.encode(&bytes).to_ascii_lowercase();
+            format!("{}-{}", &code[..4], &code[4..])
+        })
+        .collect()
+}
+
+/// Digest stored for a recovery code. Dashes, spaces and case are ignored so
+/// codes can be typed the way they were written down.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+/// Check a login code for `username`, who has TOTP enabled with `secret`.
+///
+/// Six digits are taken as an authenticator code, which is accepted once;
+/// anything else as a recovery code, which is spent on success.
+pub async fn verify_login_code(
+    store: &Store,
+    username: &str,
+    secret: &[u8],
+    code: &str,
+) -> Result<bool, StorageError> {
+    let code = code.trim();
+    if code.len() == DIGITS as usize && code.bytes().all(|b| b.is_ascii_digit()) {
+        let now = chrono::Utc::now().timestamp();
+        return match matching_step(secret, code, now) {
+            Some(step) => store.accept_totp_step(username, step).await,
+            None => Ok(false),
+        };
+    }
+    store
+        .use_totp_recovery_code(username, &hash_recovery_code(code))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B, SHA-1 seed, truncated to six digits.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn codes_match_rfc_6238_vectors() {
+        for (time, code) in [
+            (59, 287082),
+            (1111111109, 81804),
+            (1111111111, 50471),
+            (1234567890, 5924),
+            (2000000000, 279037),
+        ] {
+            assert_eq!(hotp(RFC_SECRET, time / STEP_SECS), code, "t={time}");
+        }
+    }
+
+    #[test]
+    fn codes_are_accepted_within_one_step_of_drift() {
+        let now = 1_111_111_111;
+        let step = now / STEP_SECS;
+        let code = |s: i64| code_at(RFC_SECRET, s * STEP_SECS);
+        assert_eq!(matching_step(RFC_SECRET, &code(step), now), Some(step));
+        assert_eq!(
+            matching_step(RFC_SECRET, &code(step - 1), now),
+            Some(step - 1)
+        );
+        assert_eq!(
+            matching_step(RFC_SECRET, &code(step + 1), now),
+            Some(step + 1)
+        );
+        assert_eq!(matching_step(RFC_SECRET, &code(step - 2), now), None);
+        assert_eq!(matching_step(RFC_SECRET, "12345", now), None);
+        assert_eq!(matching_step(RFC_SECRET, "abcdef", now), None);
+    }
+
+    #[test]
+    fn recovery_codes_hash_ignoring_formatting() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        let code = &codes[0];
+        assert_eq!(code.len(), 9);
+        let typed = code.replace('-', " ").to_uppercase();
+        assert_eq!(hash_recovery_code(&typed), hash_recovery_code(code));
+        assert_ne!(hash_recovery_code(&codes[1]), hash_recovery_code(code));
+    }
+
+    #[test]
+    fn otpauth_uri_names_issuer_and_user() {
+        let uri = otpauth_uri("mom dad", RFC_SECRET);
+        assert!(uri.starts_with("otpauth://totp/GamiScreen%3Amom%20dad?secret="));
+        assert!(uri.contains(&format!("secret={}", encode_secret(RFC_SECRET))));
+    }
+}
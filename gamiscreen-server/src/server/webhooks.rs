@@ -0,0 +1,339 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use gamiscreen_shared::api::ServerEvent;
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use tokio::sync::Notify;
+use tracing::warn;
+
+use super::AppState;
+use super::config::{AppConfig, WebhookConfig};
+use crate::storage::models::{NewWebhookDelivery, WEBHOOK_FAILED, WebhookDelivery};
+use crate::storage::{StorageError, Store};
+
+/// Event names a webhook can subscribe to. The first five are the `type`
+/// tags of the broadcast `ServerEvent`s.
+pub const EVENT_NAMES: &[&str] = &[
+    "pending_count",
+    "remaining_updated",
+    "notification_created",
+    "time_request_resolved",
+    "device_connection",
+    "task_submitted",
+    "submission_reviewed",
+    "reward_granted",
+    "device_registered",
+];
+
+/// Event sent by the test-fire endpoint, regardless of the event filter.
+const PING_EVENT: &str = "ping";
+
+/// Attempts per delivery before it is marked failed.
+const MAX_ATTEMPTS: i32 = 8;
+/// Delay before the first retry; doubled for each further attempt.
+const RETRY_BASE_SECS: i64 = 15;
+const RETRY_MAX_SECS: i64 = 60 * 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Fallback wake-up for retries; new deliveries wake the job at once.
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const PURGE_TICK: Duration = Duration::from_secs(60 * 60);
+/// Finished deliveries are kept in the log for this many days.
+const DELIVERY_LOG_DAYS: i64 = 7;
+const BATCH_SIZE: i64 = 50;
+
+#[derive(Clone)]
+pub struct WebhookService {
+    inner: Arc<WebhookServiceInner>,
+}
+
+struct WebhookServiceInner {
+    hooks: Vec<WebhookConfig>,
+    client: reqwest::Client,
+    wake: Notify,
+}
+
+impl WebhookService {
+    pub fn from_config(cfg: &AppConfig) -> Option<Self> {
+        if cfg.webhooks.is_empty() {
+            return None;
+        }
+        let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!(error = %e, "webhooks: failed to build HTTP client; disabling webhooks");
+                return None;
+            }
+        };
+        Some(Self {
+            inner: Arc::new(WebhookServiceInner {
+                hooks: cfg.webhooks.clone(),
+                client,
+                wake: Notify::new(),
+            }),
+        })
+    }
+
+    /// Queue a `ServerEvent` under its `type` tag.
+    pub fn dispatch_server_event(&self, store: Store, event: &ServerEvent) {
+        let data = match serde_json::to_value(event) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(error = %e, "webhooks: failed to encode event");
+                return;
+            }
+        };
+        if let Some(name) = data.get("type").and_then(Value::as_str) {
+            let name = name.to_string();
+            self.dispatch(store, &name, data);
+        }
+    }
+
+    /// Queue `data` as `event` for every webhook subscribed to it.
+    pub fn dispatch(&self, store: Store, event: &str, data: Value) {
+        let body = envelope(event, data).to_string();
+        let now = Utc::now().naive_utc();
+        let deliveries: Vec<NewWebhookDelivery> = self
+            .inner
+            .hooks
+            .iter()
+            .filter(|h| h.events.is_empty() || h.events.iter().any(|e| e == event))
+            .map(|h| NewWebhookDelivery {
+                webhook_id: h.id.clone(),
+                event: event.to_string(),
+                payload: body.clone(),
+                next_attempt_at: now,
+            })
+            .collect();
+        if deliveries.is_empty() {
+            return;
+        }
+        let inner = self.inner.clone();
+        tokio::spawn(async move {
+            match store.enqueue_webhook_deliveries(deliveries).await {
+                Ok(_) => inner.wake.notify_one(),
+                Err(e) => warn!(error = %e, "webhooks: failed to queue deliveries"),
+            }
+        });
+    }
+
+    /// Queue a `ping` for one webhook and attempt it right away. Returns
+    /// `None` for an unknown webhook id.
+    pub async fn test_fire(
+        &self,
+        store: &Store,
+        webhook_id: &str,
+    ) -> Result<Option<WebhookDelivery>, StorageError> {
+        let Some(hook) = self.inner.hooks.iter().find(|h| h.id == webhook_id) else {
+            return Ok(None);
+        };
+        let body = envelope(PING_EVENT, json!({ "webhook_id": hook.id })).to_string();
+        // Keep the delivery job away from it until the attempt below is recorded.
+        let hold = chrono::Duration::from_std(REQUEST_TIMEOUT * 2).expect("small duration");
+        let queued = store
+            .enqueue_webhook_deliveries(vec![NewWebhookDelivery {
+                webhook_id: hook.id.clone(),
+                event: PING_EVENT.to_string(),
+                payload: body,
+                next_attempt_at: Utc::now().naive_utc() + hold,
+            }])
+            .await?;
+        match queued.into_iter().next() {
+            Some(delivery) => Ok(Some(self.inner.attempt(store, hook, delivery).await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// JSON body shared by all deliveries of one event.
+fn envelope(event: &str, data: Value) -> Value {
+    json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "event": event,
+        "created_at": Utc::now().to_rfc3339(),
+        "data": data,
+    })
+}
+
+/// Hex-encoded HMAC-SHA256 of `"{timestamp}.{body}"`, sent as
+/// `X-Gamiscreen-Signature: sha256=<hex>`.
+fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Delay before the next attempt after `attempts` failed ones.
+fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exp = (attempts - 1).clamp(0, 16) as u32;
+    chrono::Duration::seconds((RETRY_BASE_SECS << exp).min(RETRY_MAX_SECS))
+}
+
+impl WebhookServiceInner {
+    /// POST a delivery once and record the outcome.
+    async fn attempt(
+        &self,
+        store: &Store,
+        hook: &WebhookConfig,
+        delivery: WebhookDelivery,
+    ) -> Result<WebhookDelivery, StorageError> {
+        let timestamp = Utc::now().timestamp();
+        let signature = sign(&hook.secret, timestamp, &delivery.payload);
+        let result = self
+            .client
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Gamiscreen-Event", &delivery.event)
+            .header("X-Gamiscreen-Delivery", delivery.id.to_string())
+            .header("X-Gamiscreen-Timestamp", timestamp.to_string())
+            .header("X-Gamiscreen-Signature", format!("sha256={signature}"))
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+        let (status_code, error) = match result {
+            Ok(resp) if resp.status().is_success() => (Some(resp.status().as_u16() as i32), None),
+            Ok(resp) => (
+                Some(resp.status().as_u16() as i32),
+                Some(format!("HTTP {}", resp.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let attempts = delivery.attempts + 1;
+        let retry_at = (error.is_some() && attempts < MAX_ATTEMPTS)
+            .then(|| Utc::now().naive_utc() + retry_delay(attempts));
+        let updated = store
+            .record_webhook_attempt(delivery.id, status_code, error, retry_at)
+            .await?;
+        if updated.status == WEBHOOK_FAILED {
+            warn!(
+                webhook_id = %hook.id,
+                delivery_id = updated.id,
+                error = ?updated.last_error,
+                "webhooks: giving up on delivery"
+            );
+        }
+        Ok(updated)
+    }
+
+    /// Attempt every pending delivery that is due.
+    async fn deliver_due(&self, store: &Store) -> Result<(), StorageError> {
+        loop {
+            let due = store
+                .due_webhook_deliveries(Utc::now().naive_utc(), BATCH_SIZE)
+                .await?;
+            let count = due.len() as i64;
+            for delivery in due {
+                match self.hooks.iter().find(|h| h.id == delivery.webhook_id) {
+                    Some(hook) => {
+                        self.attempt(store, hook, delivery).await?;
+                    }
+                    None => {
+                        store
+                            .record_webhook_attempt(
+                                delivery.id,
+                                None,
+                                Some("webhook no longer configured".to_string()),
+                                None,
+                            )
+                            .await?;
+                    }
+                }
+            }
+            if count < BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Spawn the job that sends queued webhook deliveries, retrying failed
+/// ones with exponential backoff, and prunes the delivery log.
+pub fn spawn_delivery_job(state: &AppState) {
+    let Some(service) = state.webhooks.clone() else {
+        return;
+    };
+    let store = state.store.clone();
+    let shutdown = state.shutdown_token();
+    tokio::spawn(async move {
+        let inner = service.inner;
+        let mut purge = tokio::time::interval(PURGE_TICK);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = inner.wake.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                _ = purge.tick() => {
+                    let cutoff = Utc::now().naive_utc() - chrono::Duration::days(DELIVERY_LOG_DAYS);
+                    match store.purge_webhook_deliveries(cutoff).await {
+                        Ok(0) => {}
+                        Ok(n) => tracing::info!(count = n, "webhooks: pruned delivery log"),
+                        Err(e) => tracing::error!(error = %e, "webhooks: failed to prune delivery log"),
+                    }
+                }
+            }
+            if let Err(e) = inner.deliver_due(&store).await {
+                tracing::error!(error = %e, "webhooks: delivery pass failed");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use gamiscreen_shared::api::{NotificationAudience, NotificationPayload};
+
+    use super::*;
+
+    #[test]
+    fn event_names_cover_every_broadcast_server_event() {
+        let events = [
+            ServerEvent::PendingCount { count: 1 },
+            ServerEvent::RemainingUpdated {
+                child_id: "alice".into(),
+                remaining_minutes: 5,
+                balance: 0,
+                blocked_by_tasks: false,
+            },
+            ServerEvent::NotificationCreated {
+                notification_id: 1,
+                audience: NotificationAudience::Parents,
+                child_id: "alice".into(),
+                payload: NotificationPayload::AccountLocked {
+                    username: "alice".into(),
+                    locked_until: "2026-01-01T00:00:00Z".into(),
+                },
+            },
+            ServerEvent::TimeRequestResolved {
+                child_id: "alice".into(),
+                request_id: 1,
+                minutes: 5,
+                approved: true,
+                is_borrowed: false,
+            },
+            ServerEvent::DeviceConnection {
+                child_id: "alice".into(),
+                device_id: "laptop".into(),
+                connected: true,
+            },
+        ];
+        for event in &events {
+            // A new variant must be added above, or here if it is per-stream
+            match event {
+                ServerEvent::PendingCount { .. }
+                | ServerEvent::RemainingUpdated { .. }
+                | ServerEvent::NotificationCreated { .. }
+                | ServerEvent::TimeRequestResolved { .. }
+                | ServerEvent::DeviceConnection { .. } => {}
+                ServerEvent::Resync => unreachable!("sent to a single stream only"),
+            }
+            let data = serde_json::to_value(event).unwrap();
+            let name = data["type"].as_str().unwrap();
+            assert!(EVENT_NAMES.contains(&name), "{name} missing");
+        }
+    }
+}
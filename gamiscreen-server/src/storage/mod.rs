@@ -1,7 +1,7 @@
 pub mod models;
 pub mod schema;
 
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool};
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
@@ -35,6 +35,44 @@ pub enum StorageError {
     InvalidInput(String),
 }
 
+/// Optional filters for [`Store::list_audit_entries`]; `None` matches all.
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub child_id: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+/// Whose notifications a query targets and whose read state applies.
+#[derive(Debug, Clone)]
+pub struct Inbox {
+    audience: String,
+    child_id: Option<String>,
+    recipient: String,
+}
+
+impl Inbox {
+    /// Notifications for parents, with read state of the given parent user.
+    pub fn parents(username: &str) -> Self {
+        Self {
+            audience: models::AUDIENCE_PARENTS.to_string(),
+            child_id: None,
+            recipient: username.to_string(),
+        }
+    }
+
+    /// A child's own notifications; read state is shared by all of its devices.
+    pub fn child(child_id: &str) -> Self {
+        Self {
+            audience: models::AUDIENCE_CHILD.to_string(),
+            child_id: Some(child_id.to_string()),
+            recipient: child_id.to_string(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Store {
     pool: Pool<ConnectionManager<SqliteConnection>>,
@@ -372,7 +410,7 @@ impl Store {
         tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            record_task_done_inner(&mut conn, &child, &task, &user)
+            record_task_done_inner(&mut conn, &child, &task, &user, None)
         })
         .await?
     }
@@ -418,428 +456,2280 @@ impl Store {
     }
 
     // Task submissions (pending approvals)
-    pub async fn submit_task(&self, child: &str, task: &str) -> Result<(), StorageError> {
-        use models::NewTaskSubmission;
-        use schema::task_submissions;
+    /// Record a task submission together with its photo attachments
+    /// (content type and bytes). Returns the submission id.
+    pub async fn submit_task(
+        &self,
+        child: &str,
+        task: &str,
+        attachments: Vec<(&'static str, Vec<u8>)>,
+    ) -> Result<i32, StorageError> {
+        use models::{NewSubmissionAttachment, NewTaskSubmission};
+        use schema::{submission_attachments, task_submissions};
         let pool = self.pool.clone();
         let c = child.to_string();
         let t = task.to_string();
-        tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
+        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            let rec = NewTaskSubmission {
-                child_id: &c,
-                task_id: &t,
-            };
-            diesel::insert_into(task_submissions::table)
-                .values(&rec)
-                .execute(&mut conn)?;
-            Ok(())
+            conn.immediate_transaction(|conn| {
+                let rec = NewTaskSubmission {
+                    child_id: &c,
+                    task_id: &t,
+                };
+                let submission_id: i32 = diesel::insert_into(task_submissions::table)
+                    .values(&rec)
+                    .returning(task_submissions::id)
+                    .get_result(conn)?;
+                for (content_type, data) in &attachments {
+                    let size_bytes = i32::try_from(data.len()).map_err(|_| {
+                        StorageError::InvalidInput("attachment too large".to_string())
+                    })?;
+                    diesel::insert_into(submission_attachments::table)
+                        .values(&NewSubmissionAttachment {
+                            submission_id,
+                            child_id: &c,
+                            content_type,
+                            size_bytes,
+                            data,
+                        })
+                        .execute(conn)?;
+                }
+                Ok(submission_id)
+            })
         })
         .await?
     }
 
-    pub async fn list_pending_submissions(
+    /// Attachment metadata for the given submissions, oldest first.
+    pub async fn list_submission_attachments(
         &self,
-    ) -> Result<Vec<(models::TaskSubmission, Child, Task)>, StorageError> {
+        submission_ids: Vec<i32>,
+    ) -> Result<Vec<models::SubmissionAttachmentMeta>, StorageError> {
+        use schema::submission_attachments::dsl as sa;
         let pool = self.pool.clone();
         tokio::task::spawn_blocking(
-            move || -> Result<Vec<(models::TaskSubmission, Child, Task)>, StorageError> {
+            move || -> Result<Vec<models::SubmissionAttachmentMeta>, StorageError> {
                 let mut conn = pool.get()?;
                 configure_sqlite_conn(&mut conn)?;
-                use crate::storage::schema::{children, task_submissions, tasks};
-                let rows = task_submissions::table
-                    .inner_join(children::table.on(children::id.eq(task_submissions::child_id)))
-                    .inner_join(tasks::table.on(tasks::id.eq(task_submissions::task_id)))
-                    .order(task_submissions::submitted_at.desc())
-                    .select((
-                        (
-                            task_submissions::id,
-                            task_submissions::child_id,
-                            task_submissions::task_id,
-                            task_submissions::submitted_at,
-                        ),
-                        (children::id, children::display_name),
-                        (tasks::id, tasks::name, tasks::minutes, tasks::required),
-                    ))
-                    .load::<(models::TaskSubmission, Child, Task)>(&mut conn)?;
-                Ok(rows)
+                Ok(sa::submission_attachments
+                    .filter(sa::submission_id.eq_any(&submission_ids))
+                    .order(sa::id.asc())
+                    .select(models::SubmissionAttachmentMeta::as_select())
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Fetch one attachment with its contents.
+    pub async fn get_submission_attachment(
+        &self,
+        id: i32,
+    ) -> Result<Option<(models::SubmissionAttachmentMeta, Vec<u8>)>, StorageError> {
+        use schema::submission_attachments::dsl as sa;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<(models::SubmissionAttachmentMeta, Vec<u8>)>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(sa::submission_attachments
+                    .filter(sa::id.eq(id))
+                    .select((models::SubmissionAttachmentMeta::as_select(), sa::data))
+                    .first(&mut conn)
+                    .optional()?)
             },
         )
         .await?
     }
 
+    /// Schedule the attachments of a resolved submission for deletion.
+    ///
+    /// With `keep_until` unset they are deleted right away. Returns the number
+    /// of attachments affected.
+    pub async fn release_submission_attachments(
+        &self,
+        submission_id: i32,
+        keep_until: Option<NaiveDateTime>,
+    ) -> Result<usize, StorageError> {
+        use schema::submission_attachments::dsl as sa;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            let target = sa::submission_attachments.filter(sa::submission_id.eq(submission_id));
+            Ok(match keep_until {
+                Some(at) => diesel::update(target)
+                    .set(sa::delete_after.eq(at))
+                    .execute(&mut conn)?,
+                None => diesel::delete(target).execute(&mut conn)?,
+            })
+        })
+        .await?
+    }
+
+    /// Delete attachments whose retention period ended before `now`.
+    pub async fn purge_expired_attachments(
+        &self,
+        now: NaiveDateTime,
+    ) -> Result<usize, StorageError> {
+        use schema::submission_attachments::dsl as sa;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(
+                diesel::delete(sa::submission_attachments.filter(sa::delete_after.le(now)))
+                    .execute(&mut conn)?,
+            )
+        })
+        .await?
+    }
+
     pub async fn pending_submissions_count(&self) -> Result<i64, StorageError> {
         use schema::task_submissions::dsl as ts;
         let pool = self.pool.clone();
         tokio::task::spawn_blocking(move || -> Result<i64, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            Ok(ts::task_submissions.count().get_result(&mut conn)?)
+            Ok(ts::task_submissions
+                .filter(ts::status.eq(models::SUBMISSION_PENDING))
+                .count()
+                .get_result(&mut conn)?)
         })
         .await?
     }
 
-    /// Approve a task submission: insert reward, record completion, delete submission.
-    /// Returns (child_id, new_remaining) if a submission was found.
+    /// Approve a pending task submission: insert the reward and record the
+    /// completion, keeping the submission as history.
+    ///
+    /// `minutes` overrides the task's credit for partial completion; it must be
+    /// between 1 and the task's minutes. Returns the updated submission, its
+    /// task and the child's new remaining minutes if a pending submission was
+    /// found.
     pub async fn approve_submission(
         &self,
         submission_id: i32,
         approver: &str,
-    ) -> Result<Option<(String, i32)>, StorageError> {
+        minutes: Option<i32>,
+        comment: Option<&str>,
+    ) -> Result<Option<(models::TaskSubmission, Task, i32)>, StorageError> {
         let pool = self.pool.clone();
         let approver = approver.to_string();
-        tokio::task::spawn_blocking(move || -> Result<Option<(String, i32)>, StorageError> {
-            use crate::storage::schema::{balances, rewards, task_submissions, tasks};
-            let mut conn = pool.get()?;
-            configure_sqlite_conn(&mut conn)?;
-            let mut result: Option<(String, i32)> = None;
-            conn.immediate_transaction(|conn| -> Result<(), StorageError> {
-                let rec: Option<(String, String, i32, String)> = task_submissions::table
-                    .inner_join(tasks::table.on(tasks::id.eq(task_submissions::task_id)))
-                    .filter(task_submissions::id.eq(submission_id))
-                    .select((
-                        task_submissions::child_id,
-                        task_submissions::task_id,
-                        tasks::minutes,
-                        tasks::name,
+        let comment = comment.map(str::to_string);
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<(models::TaskSubmission, Task, i32)>, StorageError> {
+                use crate::storage::schema::task_submissions;
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    let Some((_, task)) = find_submission_inner(conn, submission_id)? else {
+                        return Ok(None);
+                    };
+                    let awarded = match minutes {
+                        Some(m) if m == task.minutes => task.minutes,
+                        Some(m) if task.minutes > 0 && (1..task.minutes).contains(&m) => m,
+                        Some(_) if task.minutes <= 0 => {
+                            return Err(StorageError::InvalidInput(
+                                "minutes can only be adjusted for rewarding tasks".to_string(),
+                            ));
+                        }
+                        Some(_) => {
+                            return Err(StorageError::InvalidInput(format!(
+                                "minutes must be between 1 and {}",
+                                task.minutes
+                            )));
+                        }
+                        None => task.minutes,
+                    };
+                    let description = if awarded == task.minutes {
+                        task.name.clone()
+                    } else {
+                        format!(
+                            "{} (partial: {} of {} min)",
+                            task.name, awarded, task.minutes
+                        )
+                    };
+                    let submission: models::TaskSubmission = diesel::update(
+                        task_submissions::table.filter(task_submissions::id.eq(submission_id)),
+                    )
+                    .set((
+                        task_submissions::status.eq(models::SUBMISSION_APPROVED),
+                        task_submissions::minutes_awarded.eq(Some(awarded)),
+                        task_submissions::comment.eq(comment.as_deref()),
+                        task_submissions::resolved_by.eq(Some(&approver)),
+                        task_submissions::resolved_at.eq(Some(Utc::now().naive_utc())),
                     ))
-                    .first::<(String, String, i32, String)>(conn)
-                    .optional()?;
-                let Some((child_id, task_id, mins, task_name)) = rec else {
-                    return Ok(());
-                };
-
-                // Read current account_balance for debt tracking
-                let account_balance: i32 = balances::table
-                    .filter(balances::child_id.eq(&child_id))
-                    .select(balances::account_balance)
-                    .first(conn)?;
-
-                let new_reward = NewReward {
-                    child_id: &child_id,
-                    task_id: Some(&task_id),
-                    minutes: mins,
-                    description: Some(&task_name),
-                    is_borrowed: false,
-                };
-                let reward_id: i32 = diesel::insert_into(rewards::table)
-                    .values(&new_reward)
-                    .returning(rewards::id)
+                    .returning(models::TaskSubmission::as_returning())
                     .get_result(conn)?;
+                    let new_remaining = grant_reward_inner(
+                        conn,
+                        &NewReward {
+                            child_id: &submission.child_id,
+                            task_id: Some(&task.id),
+                            minutes: awarded,
+                            description: Some(&description),
+                            is_borrowed: false, // task approvals are never borrowed
+                        },
+                    )?;
+                    record_task_done_inner(
+                        conn,
+                        &submission.child_id,
+                        &task.id,
+                        &approver,
+                        Some(awarded),
+                    )?;
+                    Ok(Some((submission, task, new_remaining)))
+                })
+            },
+        )
+        .await?
+    }
 
-                let (rem_delta, bal_delta) = apply_reward_to_balance(
-                    conn,
-                    &child_id,
-                    mins,
-                    false, // task approvals are never borrowed
-                    account_balance,
-                    reward_id,
-                )?;
-
-                diesel::update(balances::table.filter(balances::child_id.eq(&child_id)))
+    /// Reject a pending task submission without a reward, keeping it as
+    /// history with the parent's optional comment. Returns the updated
+    /// submission and its task if a pending submission was found.
+    pub async fn reject_submission(
+        &self,
+        submission_id: i32,
+        by: &str,
+        comment: Option<&str>,
+    ) -> Result<Option<(models::TaskSubmission, Task)>, StorageError> {
+        use schema::task_submissions;
+        let pool = self.pool.clone();
+        let by = by.to_string();
+        let comment = comment.map(str::to_string);
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<(models::TaskSubmission, Task)>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    let Some((_, task)) = find_submission_inner(conn, submission_id)? else {
+                        return Ok(None);
+                    };
+                    let submission = diesel::update(
+                        task_submissions::table.filter(task_submissions::id.eq(submission_id)),
+                    )
                     .set((
-                        balances::minutes_remaining.eq(balances::minutes_remaining + rem_delta),
-                        balances::account_balance.eq(balances::account_balance + bal_delta),
+                        task_submissions::status.eq(models::SUBMISSION_REJECTED),
+                        task_submissions::comment.eq(comment.as_deref()),
+                        task_submissions::resolved_by.eq(Some(&by)),
+                        task_submissions::resolved_at.eq(Some(Utc::now().naive_utc())),
                     ))
-                    .execute(conn)?;
+                    .returning(models::TaskSubmission::as_returning())
+                    .get_result(conn)?;
+                    Ok(Some((submission, task)))
+                })
+            },
+        )
+        .await?
+    }
 
-                let new_remaining: i32 = balances::table
-                    .filter(balances::child_id.eq(&child_id))
-                    .select(balances::minutes_remaining)
-                    .first(conn)?;
-                record_task_done_inner(conn, &child_id, &task_id, &approver)?;
-                diesel::delete(
-                    task_submissions::table.filter(task_submissions::id.eq(submission_id)),
-                )
-                .execute(conn)?;
-                result = Some((child_id, new_remaining));
-                Ok(())
-            })?;
-            Ok(result)
-        })
+    /// Most recent task submissions of a child with their tasks, newest first.
+    pub async fn list_submissions_for_child(
+        &self,
+        child_id: &str,
+        limit: i64,
+    ) -> Result<Vec<(models::TaskSubmission, Task)>, StorageError> {
+        use schema::{task_submissions, tasks};
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<(models::TaskSubmission, Task)>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(task_submissions::table
+                    .inner_join(tasks::table.on(tasks::id.eq(task_submissions::task_id)))
+                    .filter(task_submissions::child_id.eq(&child))
+                    .order(task_submissions::id.desc())
+                    .limit(limit.clamp(1, 1000))
+                    .select((models::TaskSubmission::as_select(), Task::as_select()))
+                    .load(&mut conn)?)
+            },
+        )
         .await?
     }
 
-    pub async fn discard_submission(&self, submission_id: i32) -> Result<(), StorageError> {
-        use schema::task_submissions::dsl as ts;
+    /// Record a minute transfer between two children.
+    ///
+    /// With `approved_by` set (parent-initiated) the transfer is applied
+    /// immediately; otherwise it is stored as pending for parent approval.
+    /// Returns the transfer plus the stored remaining minutes of the giving and
+    /// receiving child (unchanged while pending).
+    pub async fn create_transfer(
+        &self,
+        from_child: &str,
+        to_child: &str,
+        mins: i32,
+        note: Option<&str>,
+        requested_by: &str,
+        approved_by: Option<&str>,
+    ) -> Result<(models::MinuteTransfer, i32, i32), StorageError> {
+        use models::NewMinuteTransfer;
+        use schema::{balances, minute_transfers};
+        if mins <= 0 {
+            return Err(StorageError::InvalidInput(
+                "minutes must be positive".to_string(),
+            ));
+        }
+        if from_child == to_child {
+            return Err(StorageError::InvalidInput(
+                "cannot transfer minutes to the same child".to_string(),
+            ));
+        }
         let pool = self.pool.clone();
-        tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
+        let from = from_child.to_string();
+        let to = to_child.to_string();
+        let note = note.map(|s| s.to_string());
+        let requested_by = requested_by.to_string();
+        let approved_by = approved_by.map(|s| s.to_string());
+        tokio::task::spawn_blocking(
+            move || -> Result<(models::MinuteTransfer, i32, i32), StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    // Validate up front so children cannot queue transfers they cannot cover
+                    let available: i32 = balances::table
+                        .filter(balances::child_id.eq(&from))
+                        .select(balances::minutes_remaining)
+                        .first(conn)?;
+                    if available < mins {
+                        return Err(StorageError::InvalidInput(format!(
+                            "not enough minutes to transfer ({} available)",
+                            available.max(0)
+                        )));
+                    }
+                    let now = Utc::now().naive_utc();
+                    let status = if approved_by.is_some() {
+                        models::TRANSFER_APPROVED
+                    } else {
+                        models::TRANSFER_PENDING
+                    };
+                    let transfer: models::MinuteTransfer =
+                        diesel::insert_into(minute_transfers::table)
+                            .values(&NewMinuteTransfer {
+                                from_child_id: &from,
+                                to_child_id: &to,
+                                minutes: mins,
+                                note: note.as_deref(),
+                                requested_by: &requested_by,
+                                status,
+                                resolved_by: approved_by.as_deref(),
+                                resolved_at: approved_by.as_ref().map(|_| now),
+                            })
+                            .returning(models::MinuteTransfer::as_returning())
+                            .get_result(conn)?;
+                    if approved_by.is_some() {
+                        let (from_rem, to_rem) = apply_transfer_inner(conn, &from, &to, mins)?;
+                        Ok((transfer, from_rem, to_rem))
+                    } else {
+                        let to_rem: i32 = balances::table
+                            .filter(balances::child_id.eq(&to))
+                            .select(balances::minutes_remaining)
+                            .first(conn)?;
+                        Ok((transfer, available, to_rem))
+                    }
+                })
+            },
+        )
+        .await?
+    }
+
+    pub async fn pending_transfers_count(&self) -> Result<i64, StorageError> {
+        use schema::minute_transfers;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<i64, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            let _ = diesel::delete(ts::task_submissions.filter(ts::id.eq(submission_id)))
-                .execute(&mut conn)?;
-            Ok(())
+            Ok(minute_transfers::table
+                .filter(minute_transfers::status.eq(models::TRANSFER_PENDING))
+                .count()
+                .get_result(&mut conn)?)
         })
         .await?
     }
 
-    pub async fn list_rewards_for_child(
+    /// Approve a pending transfer, moving the minutes in the same transaction.
+    /// Returns the transfer and the new stored remaining minutes of both
+    /// children, or `None` if no pending transfer with this id exists.
+    pub async fn approve_transfer(
         &self,
-        child: &str,
-        page: usize,
-        per_page: usize,
-    ) -> Result<Vec<models::Reward>, StorageError> {
+        transfer_id: i32,
+        approver: &str,
+    ) -> Result<Option<(models::MinuteTransfer, i32, i32)>, StorageError> {
+        use schema::minute_transfers;
         let pool = self.pool.clone();
-        let child = child.to_string();
-        let page = page.max(1);
-        let per_page = per_page.clamp(1, 1000) as i64;
-        let offset = ((page as i64) - 1) * per_page;
-        tokio::task::spawn_blocking(move || -> Result<Vec<models::Reward>, StorageError> {
-            let mut conn = pool.get()?;
-            configure_sqlite_conn(&mut conn)?;
-            use crate::storage::schema::rewards;
-            // Only read from rewards; description is stored at creation time
-            Ok(rewards::table
-                .filter(rewards::child_id.eq(&child))
-                .order(rewards::created_at.desc())
-                .offset(offset)
-                .limit(per_page)
-                .select((
-                    rewards::id,
-                    rewards::child_id,
-                    rewards::task_id,
-                    rewards::minutes,
-                    rewards::description,
-                    rewards::created_at,
-                    rewards::is_borrowed,
-                ))
-                .load::<models::Reward>(&mut conn)?)
-        })
+        let approver = approver.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<(models::MinuteTransfer, i32, i32)>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    let Some(transfer) = minute_transfers::table
+                        .filter(minute_transfers::id.eq(transfer_id))
+                        .filter(minute_transfers::status.eq(models::TRANSFER_PENDING))
+                        .select(models::MinuteTransfer::as_select())
+                        .first(conn)
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+                    let (from_rem, to_rem) = apply_transfer_inner(
+                        conn,
+                        &transfer.from_child_id,
+                        &transfer.to_child_id,
+                        transfer.minutes,
+                    )?;
+                    let transfer = diesel::update(
+                        minute_transfers::table.filter(minute_transfers::id.eq(transfer_id)),
+                    )
+                    .set((
+                        minute_transfers::status.eq(models::TRANSFER_APPROVED),
+                        minute_transfers::resolved_by.eq(Some(&approver)),
+                        minute_transfers::resolved_at.eq(Some(Utc::now().naive_utc())),
+                    ))
+                    .returning(models::MinuteTransfer::as_returning())
+                    .get_result(conn)?;
+                    Ok(Some((transfer, from_rem, to_rem)))
+                })
+            },
+        )
         .await?
     }
 
-    pub async fn get_task_by_id(&self, id_: &str) -> Result<Option<Task>, StorageError> {
-        use schema::tasks::dsl::*;
+    /// Deny a pending transfer. Returns `None` if it was not pending.
+    pub async fn deny_transfer(
+        &self,
+        transfer_id: i32,
+        by: &str,
+    ) -> Result<Option<models::MinuteTransfer>, StorageError> {
+        use schema::minute_transfers;
         let pool = self.pool.clone();
-        let tid = id_.to_string();
-        tokio::task::spawn_blocking(move || -> Result<Option<Task>, StorageError> {
-            let mut conn = pool.get()?;
-            configure_sqlite_conn(&mut conn)?;
-            Ok(tasks
-                .filter(id.eq(&tid))
-                .first::<Task>(&mut conn)
+        let by = by.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<models::MinuteTransfer>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(diesel::update(
+                    minute_transfers::table
+                        .filter(minute_transfers::id.eq(transfer_id))
+                        .filter(minute_transfers::status.eq(models::TRANSFER_PENDING)),
+                )
+                .set((
+                    minute_transfers::status.eq(models::TRANSFER_DENIED),
+                    minute_transfers::resolved_by.eq(Some(&by)),
+                    minute_transfers::resolved_at.eq(Some(Utc::now().naive_utc())),
+                ))
+                .returning(models::MinuteTransfer::as_returning())
+                .get_result(&mut conn)
                 .optional()?)
-        })
+            },
+        )
         .await?
     }
 
-    /// Add a reward and optionally record task completion in a single transaction.
-    ///
-    /// When `task_completion` is `Some((task_id, approved_by))`, the task is marked
-    /// done inside the same transaction as the reward, ensuring atomicity.
-    pub async fn add_reward_minutes(
+    /// Queue a child's request for extra minutes. Each child may have at most
+    /// `max_pending` requests waiting for a parent at any time.
+    pub async fn create_time_request(
         &self,
         child_id: &str,
         mins: i32,
-        task: Option<&str>,
-        description: Option<&str>,
-        is_borrowed: bool,
-        task_completion: Option<(&str, &str)>,
-    ) -> Result<i32, StorageError> {
-        use schema::{balances, rewards};
+        reason: &str,
+        max_pending: i64,
+    ) -> Result<models::TimeRequest, StorageError> {
+        use models::NewTimeRequest;
+        use schema::time_requests;
+        if mins <= 0 {
+            return Err(StorageError::InvalidInput(
+                "minutes must be positive".to_string(),
+            ));
+        }
         let pool = self.pool.clone();
         let child = child_id.to_string();
-        let task_opt = task.map(|s| s.to_string());
-        let description_opt = description.map(|s| s.to_string());
-        let completion_opt = task_completion.map(|(tid, user)| (tid.to_string(), user.to_string()));
-        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+        let reason = reason.to_string();
+        tokio::task::spawn_blocking(move || -> Result<models::TimeRequest, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            conn.immediate_transaction(|conn| -> Result<i32, StorageError> {
-                // Read current account_balance for debt tracking
-                let account_balance: i32 = balances::table
-                    .filter(balances::child_id.eq(&child))
-                    .select(balances::account_balance)
-                    .first(conn)?;
-
-                // Insert reward row — is_borrowed is a display flag for "(lent)" labels in UI
-                let new_reward = NewReward {
-                    child_id: &child,
-                    task_id: task_opt.as_deref(),
-                    minutes: mins,
-                    description: description_opt.as_deref(),
-                    is_borrowed,
-                };
-                let reward_id: i32 = diesel::insert_into(rewards::table)
-                    .values(&new_reward)
-                    .returning(rewards::id)
+            conn.immediate_transaction(|conn| {
+                let pending: i64 = time_requests::table
+                    .filter(time_requests::child_id.eq(&child))
+                    .filter(time_requests::status.eq(models::TIME_REQUEST_PENDING))
+                    .count()
                     .get_result(conn)?;
-
-                let (rem_delta, bal_delta) = apply_reward_to_balance(
-                    conn,
-                    &child,
-                    mins,
-                    is_borrowed,
-                    account_balance,
-                    reward_id,
-                )?;
-
-                diesel::update(balances::table.filter(balances::child_id.eq(&child)))
-                    .set((
-                        balances::minutes_remaining.eq(balances::minutes_remaining + rem_delta),
-                        balances::account_balance.eq(balances::account_balance + bal_delta),
-                    ))
-                    .execute(conn)?;
-
-                if let Some((ref tid, ref user)) = completion_opt {
-                    record_task_done_inner(conn, &child, tid, user)?;
+                if pending >= max_pending {
+                    return Err(StorageError::InvalidInput(
+                        "too many pending time requests".to_string(),
+                    ));
                 }
-
-                let new_remaining: i32 = balances::table
-                    .filter(balances::child_id.eq(&child))
-                    .select(balances::minutes_remaining)
-                    .first(conn)?;
-                Ok(new_remaining)
+                Ok(diesel::insert_into(time_requests::table)
+                    .values(&NewTimeRequest {
+                        child_id: &child,
+                        minutes: mins,
+                        reason: &reason,
+                    })
+                    .returning(models::TimeRequest::as_returning())
+                    .get_result(conn)?)
             })
         })
         .await?
     }
 
-    pub async fn process_usage_minutes(
-        &self,
-        child: &str,
-        device: &str,
-        minutes: &[i64],
-    ) -> Result<i32, StorageError> {
-        use schema::{balances, usage_minutes};
+    pub async fn pending_time_requests_count(&self) -> Result<i64, StorageError> {
+        use schema::time_requests;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<i64, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(time_requests::table
+                .filter(time_requests::status.eq(models::TIME_REQUEST_PENDING))
+                .count()
+                .get_result(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Most recent time requests of a child, newest first.
+    pub async fn list_time_requests_for_child(
+        &self,
+        child_id: &str,
+        limit: i64,
+    ) -> Result<Vec<models::TimeRequest>, StorageError> {
+        use schema::time_requests;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Vec<models::TimeRequest>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(time_requests::table
+                .filter(time_requests::child_id.eq(&child))
+                .order(time_requests::id.desc())
+                .limit(limit.clamp(1, 1000))
+                .select(models::TimeRequest::as_select())
+                .load(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Approve a pending time request, granting the minutes as a reward (or a
+    /// loan when `is_borrowed`) in the same transaction. Returns the request and
+    /// the child's new stored remaining minutes, or `None` if no pending
+    /// request with this id exists.
+    pub async fn approve_time_request(
+        &self,
+        request_id: i32,
+        approver: &str,
+        is_borrowed: bool,
+    ) -> Result<Option<(models::TimeRequest, i32)>, StorageError> {
+        use schema::time_requests;
+        let pool = self.pool.clone();
+        let approver = approver.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<(models::TimeRequest, i32)>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    let Some(request) = time_requests::table
+                        .filter(time_requests::id.eq(request_id))
+                        .filter(time_requests::status.eq(models::TIME_REQUEST_PENDING))
+                        .select(models::TimeRequest::as_select())
+                        .first(conn)
+                        .optional()?
+                    else {
+                        return Ok(None);
+                    };
+                    let description = format!("Extra time: {}", request.reason);
+                    let new_remaining = grant_reward_inner(
+                        conn,
+                        &NewReward {
+                            child_id: &request.child_id,
+                            task_id: None,
+                            minutes: request.minutes,
+                            description: Some(&description),
+                            is_borrowed,
+                        },
+                    )?;
+                    let request = diesel::update(
+                        time_requests::table.filter(time_requests::id.eq(request_id)),
+                    )
+                    .set((
+                        time_requests::status.eq(models::TIME_REQUEST_APPROVED),
+                        time_requests::is_borrowed.eq(is_borrowed),
+                        time_requests::resolved_by.eq(Some(&approver)),
+                        time_requests::resolved_at.eq(Some(Utc::now().naive_utc())),
+                    ))
+                    .returning(models::TimeRequest::as_returning())
+                    .get_result(conn)?;
+                    Ok(Some((request, new_remaining)))
+                })
+            },
+        )
+        .await?
+    }
+
+    /// Deny a pending time request. Returns `None` if it was not pending.
+    pub async fn deny_time_request(
+        &self,
+        request_id: i32,
+        by: &str,
+    ) -> Result<Option<models::TimeRequest>, StorageError> {
+        use schema::time_requests;
+        let pool = self.pool.clone();
+        let by = by.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<models::TimeRequest>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(diesel::update(
+                    time_requests::table
+                        .filter(time_requests::id.eq(request_id))
+                        .filter(time_requests::status.eq(models::TIME_REQUEST_PENDING)),
+                )
+                .set((
+                    time_requests::status.eq(models::TIME_REQUEST_DENIED),
+                    time_requests::resolved_by.eq(Some(&by)),
+                    time_requests::resolved_at.eq(Some(Utc::now().naive_utc())),
+                ))
+                .returning(models::TimeRequest::as_returning())
+                .get_result(&mut conn)
+                .optional()?)
+            },
+        )
+        .await?
+    }
+
+    pub async fn get_device(
+        &self,
+        child_id: &str,
+        device_id: &str,
+    ) -> Result<Option<models::Device>, StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        let device = device_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Option<models::Device>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(devices::table
+                .filter(devices::child_id.eq(&child))
+                .filter(devices::device_id.eq(&device))
+                .select(models::Device::as_select())
+                .first(&mut conn)
+                .optional()?)
+        })
+        .await?
+    }
+
+    /// Ask for `device_id` to be approved for `child_id`. Returns the device
+    /// and whether a new request was opened: devices that are pending,
+    /// approved or blocked are returned unchanged, while unknown and denied
+    /// ones become pending.
+    pub async fn request_device(
+        &self,
+        child_id: &str,
+        device_id: &str,
+    ) -> Result<(models::Device, bool), StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        let device = device_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<(models::Device, bool), StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| {
+                let existing: Option<models::Device> = devices::table
+                    .filter(devices::child_id.eq(&child))
+                    .filter(devices::device_id.eq(&device))
+                    .select(models::Device::as_select())
+                    .first(conn)
+                    .optional()?;
+                match existing {
+                    Some(d) if d.status != models::DEVICE_DENIED => Ok((d, false)),
+                    Some(d) => {
+                        let reopened = diesel::update(devices::table.find(d.id))
+                            .set((
+                                devices::status.eq(models::DEVICE_PENDING),
+                                devices::created_at.eq(Utc::now().naive_utc()),
+                                devices::resolved_by.eq(None::<String>),
+                                devices::resolved_at.eq(None::<NaiveDateTime>),
+                            ))
+                            .returning(models::Device::as_returning())
+                            .get_result(conn)?;
+                        Ok((reopened, true))
+                    }
+                    None => {
+                        let created = diesel::insert_into(devices::table)
+                            .values((
+                                devices::child_id.eq(&child),
+                                devices::device_id.eq(&device),
+                                devices::status.eq(models::DEVICE_PENDING),
+                            ))
+                            .returning(models::Device::as_returning())
+                            .get_result(conn)?;
+                        Ok((created, true))
+                    }
+                }
+            })
+        })
+        .await?
+    }
+
+    /// Set the status of `device_id` for `child_id`, creating the device if
+    /// needed. Returns the previous status, if the device was known, and the
+    /// updated device.
+    pub async fn set_device_status(
+        &self,
+        child_id: &str,
+        device_id: &str,
+        status: &'static str,
+        by: &str,
+    ) -> Result<(Option<String>, models::Device), StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        let device = device_id.to_string();
+        let by = by.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<(Option<String>, models::Device), StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    let before: Option<String> = devices::table
+                        .filter(devices::child_id.eq(&child))
+                        .filter(devices::device_id.eq(&device))
+                        .select(devices::status)
+                        .first(conn)
+                        .optional()?;
+                    let now = Utc::now().naive_utc();
+                    let updated = diesel::insert_into(devices::table)
+                        .values((
+                            devices::child_id.eq(&child),
+                            devices::device_id.eq(&device),
+                            devices::status.eq(status),
+                            devices::resolved_by.eq(Some(&by)),
+                            devices::resolved_at.eq(Some(now)),
+                        ))
+                        .on_conflict((devices::child_id, devices::device_id))
+                        .do_update()
+                        .set((
+                            devices::status.eq(status),
+                            devices::resolved_by.eq(Some(&by)),
+                            devices::resolved_at.eq(Some(now)),
+                        ))
+                        .returning(models::Device::as_returning())
+                        .get_result(conn)?;
+                    Ok((before, updated))
+                })
+            },
+        )
+        .await?
+    }
+
+    /// Approve or deny the pending device request `id`. `None` if there is
+    /// no such pending request.
+    pub async fn resolve_device_request(
+        &self,
+        id: i32,
+        approve: bool,
+        by: &str,
+    ) -> Result<Option<models::Device>, StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        let by = by.to_string();
+        let status = if approve {
+            models::DEVICE_APPROVED
+        } else {
+            models::DEVICE_DENIED
+        };
+        tokio::task::spawn_blocking(move || -> Result<Option<models::Device>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(diesel::update(
+                devices::table
+                    .filter(devices::id.eq(id))
+                    .filter(devices::status.eq(models::DEVICE_PENDING)),
+            )
+            .set((
+                devices::status.eq(status),
+                devices::resolved_by.eq(Some(&by)),
+                devices::resolved_at.eq(Some(Utc::now().naive_utc())),
+            ))
+            .returning(models::Device::as_returning())
+            .get_result(&mut conn)
+            .optional()?)
+        })
+        .await?
+    }
+
+    /// Forget a blocked device, so it has to be registered again. Returns
+    /// whether it was blocked.
+    pub async fn unblock_device(
+        &self,
+        child_id: &str,
+        device_id: &str,
+    ) -> Result<bool, StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        let device = device_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            let deleted = diesel::delete(
+                devices::table
+                    .filter(devices::child_id.eq(&child))
+                    .filter(devices::device_id.eq(&device))
+                    .filter(devices::status.eq(models::DEVICE_BLOCKED)),
+            )
+            .execute(&mut conn)?;
+            Ok(deleted > 0)
+        })
+        .await?
+    }
+
+    /// Devices of a child, newest first.
+    pub async fn list_devices_for_child(
+        &self,
+        child_id: &str,
+    ) -> Result<Vec<models::Device>, StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Vec<models::Device>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(devices::table
+                .filter(devices::child_id.eq(&child))
+                .order((devices::created_at.desc(), devices::id.desc()))
+                .select(models::Device::as_select())
+                .load(&mut conn)?)
+        })
+        .await?
+    }
+
+    pub async fn pending_devices_count(&self) -> Result<i64, StorageError> {
+        use schema::devices;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<i64, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(devices::table
+                .filter(devices::status.eq(models::DEVICE_PENDING))
+                .count()
+                .get_result(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Store a notification for `audience` about `child_id`.
+    pub async fn create_notification(
+        &self,
+        audience: &str,
+        child_id: &str,
+        kind: &str,
+        payload: &str,
+        source_id: Option<i32>,
+    ) -> Result<models::Notification, StorageError> {
+        use models::NewNotification;
+        use schema::notifications;
+        let pool = self.pool.clone();
+        let audience = audience.to_string();
+        let child = child_id.to_string();
+        let kind = kind.to_string();
+        let payload = payload.to_string();
+        tokio::task::spawn_blocking(move || -> Result<models::Notification, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(diesel::insert_into(notifications::table)
+                .values(&NewNotification {
+                    audience: &audience,
+                    child_id: &child,
+                    kind: &kind,
+                    payload: &payload,
+                    source_id,
+                })
+                .returning(models::Notification::as_returning())
+                .get_result(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Mark the parent notification pointing at `source_id` as handled.
+    pub async fn resolve_notifications(
+        &self,
+        kind: &str,
+        source_id: i32,
+    ) -> Result<usize, StorageError> {
+        use schema::notifications;
+        let pool = self.pool.clone();
+        let kind = kind.to_string();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(diesel::update(
+                notifications::table
+                    .filter(notifications::audience.eq(models::AUDIENCE_PARENTS))
+                    .filter(notifications::kind.eq(&kind))
+                    .filter(notifications::source_id.eq(source_id))
+                    .filter(notifications::resolved_at.is_null()),
+            )
+            .set(notifications::resolved_at.eq(Some(Utc::now().naive_utc())))
+            .execute(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// List notifications in an inbox, newest first, skipping the ones the
+    /// recipient dismissed. Returns each notification with its child and the
+    /// time the recipient read it.
+    pub async fn list_notifications(
+        &self,
+        inbox: &Inbox,
+        page: usize,
+        per_page: usize,
+        unread_only: bool,
+        include_resolved: bool,
+    ) -> Result<Vec<(models::Notification, Child, Option<NaiveDateTime>)>, StorageError> {
+        let pool = self.pool.clone();
+        let inbox = inbox.clone();
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 1000) as i64;
+        let offset = ((page as i64) - 1) * per_page;
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<(models::Notification, Child, Option<NaiveDateTime>)>, StorageError> {
+                use schema::{children, notification_receipts, notifications};
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                let mut query = notifications::table
+                    .inner_join(children::table)
+                    .left_join(
+                        notification_receipts::table.on(notification_receipts::notification_id
+                            .eq(notifications::id)
+                            .and(notification_receipts::recipient.eq(&inbox.recipient))),
+                    )
+                    .filter(notifications::audience.eq(&inbox.audience))
+                    .filter(notification_receipts::dismissed_at.is_null())
+                    .into_boxed();
+                if let Some(child) = &inbox.child_id {
+                    query = query.filter(notifications::child_id.eq(child));
+                }
+                if unread_only {
+                    query = query.filter(notification_receipts::read_at.is_null());
+                }
+                if !include_resolved {
+                    query = query.filter(notifications::resolved_at.is_null());
+                }
+                Ok(query
+                    .order(notifications::id.desc())
+                    .offset(offset)
+                    .limit(per_page)
+                    .select((
+                        models::Notification::as_select(),
+                        Child::as_select(),
+                        notification_receipts::read_at.nullable(),
+                    ))
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Kinds of the unread, unresolved notifications in an inbox (one entry
+    /// per notification).
+    pub async fn unread_notification_kinds(
+        &self,
+        inbox: &Inbox,
+    ) -> Result<Vec<String>, StorageError> {
+        let pool = self.pool.clone();
+        let inbox = inbox.clone();
+        tokio::task::spawn_blocking(move || -> Result<Vec<String>, StorageError> {
+            use schema::{notification_receipts, notifications};
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            let mut query = notifications::table
+                .left_join(
+                    notification_receipts::table.on(notification_receipts::notification_id
+                        .eq(notifications::id)
+                        .and(notification_receipts::recipient.eq(&inbox.recipient))),
+                )
+                .filter(notifications::audience.eq(&inbox.audience))
+                .filter(notifications::resolved_at.is_null())
+                .filter(notification_receipts::read_at.is_null())
+                .filter(notification_receipts::dismissed_at.is_null())
+                .into_boxed();
+            if let Some(child) = &inbox.child_id {
+                query = query.filter(notifications::child_id.eq(child));
+            }
+            Ok(query.select(notifications::kind).load(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Mark one notification as read (or dismissed) for the inbox recipient.
+    /// Returns false if the notification is not in this inbox.
+    pub async fn mark_notification(
+        &self,
+        inbox: &Inbox,
+        notification_id: i32,
+        dismiss: bool,
+    ) -> Result<bool, StorageError> {
+        use schema::notifications;
+        let pool = self.pool.clone();
+        let inbox = inbox.clone();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| {
+                let mut query = notifications::table
+                    .filter(notifications::id.eq(notification_id))
+                    .filter(notifications::audience.eq(&inbox.audience))
+                    .into_boxed();
+                if let Some(child) = &inbox.child_id {
+                    query = query.filter(notifications::child_id.eq(child));
+                }
+                let found: i64 = query.count().get_result(conn)?;
+                if found == 0 {
+                    return Ok(false);
+                }
+                upsert_receipt_inner(conn, notification_id, &inbox.recipient, dismiss)?;
+                Ok(true)
+            })
+        })
+        .await?
+    }
+
+    /// Mark every unread notification in an inbox as read. Returns how many
+    /// notifications changed.
+    pub async fn mark_all_notifications_read(&self, inbox: &Inbox) -> Result<usize, StorageError> {
+        let pool = self.pool.clone();
+        let inbox = inbox.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            use schema::{notification_receipts, notifications};
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| {
+                let mut query = notifications::table
+                    .left_join(
+                        notification_receipts::table.on(notification_receipts::notification_id
+                            .eq(notifications::id)
+                            .and(notification_receipts::recipient.eq(&inbox.recipient))),
+                    )
+                    .filter(notifications::audience.eq(&inbox.audience))
+                    .filter(notification_receipts::read_at.is_null())
+                    .filter(notification_receipts::dismissed_at.is_null())
+                    .into_boxed();
+                if let Some(child) = &inbox.child_id {
+                    query = query.filter(notifications::child_id.eq(child));
+                }
+                let ids: Vec<i32> = query.select(notifications::id).load(conn)?;
+                for id in &ids {
+                    upsert_receipt_inner(conn, *id, &inbox.recipient, false)?;
+                }
+                Ok(ids.len())
+            })
+        })
+        .await?
+    }
+
+    pub async fn list_rewards_for_child(
+        &self,
+        child: &str,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<models::Reward>, StorageError> {
+        let pool = self.pool.clone();
+        let child = child.to_string();
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 1000) as i64;
+        let offset = ((page as i64) - 1) * per_page;
+        tokio::task::spawn_blocking(move || -> Result<Vec<models::Reward>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            use crate::storage::schema::rewards;
+            // Only read from rewards; description is stored at creation time
+            Ok(rewards::table
+                .filter(rewards::child_id.eq(&child))
+                .order(rewards::created_at.desc())
+                .offset(offset)
+                .limit(per_page)
+                .select((
+                    rewards::id,
+                    rewards::child_id,
+                    rewards::task_id,
+                    rewards::minutes,
+                    rewards::description,
+                    rewards::created_at,
+                    rewards::is_borrowed,
+                ))
+                .load::<models::Reward>(&mut conn)?)
+        })
+        .await?
+    }
+
+    pub async fn get_task_by_id(&self, id_: &str) -> Result<Option<Task>, StorageError> {
+        use schema::tasks::dsl::*;
+        let pool = self.pool.clone();
+        let tid = id_.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Option<Task>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(tasks
+                .filter(id.eq(&tid))
+                .first::<Task>(&mut conn)
+                .optional()?)
+        })
+        .await?
+    }
+
+    /// Add a reward and optionally record task completion in a single transaction.
+    ///
+    /// When `task_completion` is `Some((task_id, approved_by))`, the task is marked
+    /// done inside the same transaction as the reward, ensuring atomicity.
+    pub async fn add_reward_minutes(
+        &self,
+        child_id: &str,
+        mins: i32,
+        task: Option<&str>,
+        description: Option<&str>,
+        is_borrowed: bool,
+        task_completion: Option<(&str, &str)>,
+    ) -> Result<i32, StorageError> {
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        let task_opt = task.map(|s| s.to_string());
+        let description_opt = description.map(|s| s.to_string());
+        let completion_opt = task_completion.map(|(tid, user)| (tid.to_string(), user.to_string()));
+        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| -> Result<i32, StorageError> {
+                // is_borrowed is a display flag for "(lent)" labels in UI
+                let new_remaining = grant_reward_inner(
+                    conn,
+                    &NewReward {
+                        child_id: &child,
+                        task_id: task_opt.as_deref(),
+                        minutes: mins,
+                        description: description_opt.as_deref(),
+                        is_borrowed,
+                    },
+                )?;
+
+                if let Some((ref tid, ref user)) = completion_opt {
+                    record_task_done_inner(conn, &child, tid, user, Some(mins))?;
+                }
+                Ok(new_remaining)
+            })
+        })
+        .await?
+    }
+
+    pub async fn process_usage_minutes(
+        &self,
+        child: &str,
+        device: &str,
+        minutes: &[i64],
+    ) -> Result<i32, StorageError> {
+        use schema::{balances, usage_minutes};
+
+        use crate::storage::models::NewUsageMinute;
+        if minutes.is_empty() {
+            return Err(StorageError::InvalidInput(
+                "no minutes provided".to_string(),
+            ));
+        }
+        // Validate timestamp bounds to prevent retroactive inflation or
+        // future-timestamp poisoning of screen-time accounting.
+        let now_minute = Utc::now().timestamp() / 60;
+        let window_past = 7 * 24 * 60; // 7 days back
+        let window_future = 5; // 5 minutes forward (clock skew tolerance)
+        if minutes
+            .iter()
+            .any(|&m| m < now_minute - window_past || m > now_minute + window_future)
+        {
+            return Err(StorageError::InvalidInput(
+                "minute timestamp out of acceptable range".to_string(),
+            ));
+        }
+        let pool = self.pool.clone();
+        let child_owned = child.to_string();
+        let device_owned = device.to_string();
+        let minutes_vec = minutes.to_vec();
+        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| -> Result<i32, StorageError> {
+                let mut new_count = 0i32;
+                for m in &minutes_vec {
+                    let row = NewUsageMinute {
+                        child_id: &child_owned,
+                        minute_ts: *m,
+                        device_id: &device_owned,
+                    };
+                    let inserted = diesel::insert_into(usage_minutes::table)
+                        .values(&row)
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                    new_count += inserted as i32;
+                }
+                if new_count > 0 {
+                    diesel::update(balances::table.filter(balances::child_id.eq(&child_owned)))
+                        .set(
+                            balances::minutes_remaining.eq(balances::minutes_remaining - new_count),
+                        )
+                        .execute(conn)?;
+                }
+                let new_remaining: i32 = balances::table
+                    .filter(balances::child_id.eq(&child_owned))
+                    .select(balances::minutes_remaining)
+                    .first(conn)?;
+                Ok(new_remaining)
+            })
+        })
+        .await?
+    }
+
+    pub async fn list_usage_minutes(
+        &self,
+        child: &str,
+        minute_from: i64,
+        minute_to: i64,
+    ) -> Result<Vec<i64>, StorageError> {
+        use schema::usage_minutes::dsl as um;
+        if minute_to <= minute_from {
+            return Ok(Vec::new());
+        }
+        let pool = self.pool.clone();
+        let child_owned = child.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Vec<i64>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(um::usage_minutes
+                .filter(um::child_id.eq(&child_owned))
+                .filter(um::minute_ts.ge(minute_from))
+                .filter(um::minute_ts.lt(minute_to))
+                .select(um::minute_ts)
+                .distinct()
+                .order(um::minute_ts.asc())
+                .load::<i64>(&mut conn)?)
+        })
+        .await?
+    }
+
+    pub async fn get_remaining(&self, child_id: &str) -> Result<i32, StorageError> {
+        use schema::balances;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(balances::table
+                .filter(balances::child_id.eq(&child))
+                .select(balances::minutes_remaining)
+                .first(&mut conn)?)
+        })
+        .await?
+    }
+
+    pub async fn get_balance(&self, child_id: &str) -> Result<i32, StorageError> {
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            get_balance_inner(&mut conn, &child)
+        })
+        .await?
+    }
+
+    pub async fn all_required_tasks_done_today(
+        &self,
+        child_id: &str,
+    ) -> Result<bool, StorageError> {
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            all_required_tasks_done_today_inner(&mut conn, &child)
+        })
+        .await?
+    }
+
+    /// Fetch the balance row for a child, including the savings pot.
+    pub async fn get_savings(&self, child_id: &str) -> Result<models::Balance, StorageError> {
+        use schema::balances;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<models::Balance, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(balances::table
+                .filter(balances::child_id.eq(&child))
+                .select(models::Balance::as_select())
+                .first(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Move minutes from `minutes_remaining` into the savings pot.
+    ///
+    /// When `lock_in` is set, the pot cannot be withdrawn by the child until
+    /// `lock_in` after this deposit (each deposit extends the lock).
+    pub async fn deposit_savings(
+        &self,
+        child_id: &str,
+        mins: i32,
+        lock_in: Option<chrono::Duration>,
+    ) -> Result<models::Balance, StorageError> {
+        use schema::balances;
+        if mins <= 0 {
+            return Err(StorageError::InvalidInput(
+                "minutes must be positive".to_string(),
+            ));
+        }
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<models::Balance, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| -> Result<models::Balance, StorageError> {
+                let now = Utc::now().naive_utc();
+                let current: models::Balance = balances::table
+                    .filter(balances::child_id.eq(&child))
+                    .select(models::Balance::as_select())
+                    .first(conn)?;
+                if current.minutes_remaining < mins {
+                    return Err(StorageError::InvalidInput(format!(
+                        "not enough minutes to deposit ({} available)",
+                        current.minutes_remaining.max(0)
+                    )));
+                }
+                let locked_until = match lock_in {
+                    Some(d) => current.savings_locked_until.max(Some(now + d)),
+                    None => current.savings_locked_until,
+                };
+                // Interest is counted from the moment the pot becomes non-empty
+                let interest_at = current.savings_interest_at.or(Some(now));
+                let savings_after = current.savings_minutes + mins;
+                diesel::update(balances::table.filter(balances::child_id.eq(&child)))
+                    .set((
+                        balances::minutes_remaining.eq(balances::minutes_remaining - mins),
+                        balances::savings_minutes.eq(savings_after),
+                        balances::savings_locked_until.eq(locked_until),
+                        balances::savings_interest_at.eq(interest_at),
+                    ))
+                    .execute(conn)?;
+                record_savings_transaction(conn, &child, "deposit", mins, savings_after, now)?;
+                Ok(balances::table
+                    .filter(balances::child_id.eq(&child))
+                    .select(models::Balance::as_select())
+                    .first(conn)?)
+            })
+        })
+        .await?
+    }
+
+    /// Move minutes from the savings pot back into `minutes_remaining`.
+    ///
+    /// Withdrawals during the lock-in period are rejected unless
+    /// `ignore_lock` is set (parents may break the lock).
+    pub async fn withdraw_savings(
+        &self,
+        child_id: &str,
+        mins: i32,
+        ignore_lock: bool,
+    ) -> Result<models::Balance, StorageError> {
+        use schema::balances;
+        if mins <= 0 {
+            return Err(StorageError::InvalidInput(
+                "minutes must be positive".to_string(),
+            ));
+        }
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(move || -> Result<models::Balance, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| -> Result<models::Balance, StorageError> {
+                let now = Utc::now().naive_utc();
+                let current: models::Balance = balances::table
+                    .filter(balances::child_id.eq(&child))
+                    .select(models::Balance::as_select())
+                    .first(conn)?;
+                if current.savings_minutes < mins {
+                    return Err(StorageError::InvalidInput(format!(
+                        "not enough minutes in savings ({} available)",
+                        current.savings_minutes
+                    )));
+                }
+                if !ignore_lock
+                    && let Some(until) = current.savings_locked_until
+                    && until > now
+                {
+                    return Err(StorageError::InvalidInput(format!(
+                        "savings are locked until {}",
+                        chrono::DateTime::<Utc>::from_naive_utc_and_offset(until, Utc).to_rfc3339()
+                    )));
+                }
+                let savings_after = current.savings_minutes - mins;
+                // An emptied pot forgets its lock and interest anchor
+                let (locked_until, interest_at) = if savings_after == 0 {
+                    (None, None)
+                } else {
+                    (current.savings_locked_until, current.savings_interest_at)
+                };
+                diesel::update(balances::table.filter(balances::child_id.eq(&child)))
+                    .set((
+                        balances::minutes_remaining.eq(balances::minutes_remaining + mins),
+                        balances::savings_minutes.eq(savings_after),
+                        balances::savings_locked_until.eq(locked_until),
+                        balances::savings_interest_at.eq(interest_at),
+                    ))
+                    .execute(conn)?;
+                record_savings_transaction(conn, &child, "withdraw", -mins, savings_after, now)?;
+                Ok(balances::table
+                    .filter(balances::child_id.eq(&child))
+                    .select(models::Balance::as_select())
+                    .first(conn)?)
+            })
+        })
+        .await?
+    }
+
+    pub async fn list_savings_transactions(
+        &self,
+        child_id: &str,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<models::SavingsTransaction>, StorageError> {
+        use schema::savings_transactions;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 1000) as i64;
+        let offset = ((page as i64) - 1) * per_page;
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<models::SavingsTransaction>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(savings_transactions::table
+                    .filter(savings_transactions::child_id.eq(&child))
+                    .order(savings_transactions::id.desc())
+                    .offset(offset)
+                    .limit(per_page)
+                    .select(models::SavingsTransaction::as_select())
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Bring stored chore instances in line with the configured chores.
+    ///
+    /// Inserts `upcoming` instances that do not exist yet and drops open
+    /// instances of chores missing from `chore_ids`. Open instances due after
+    /// `now` that are no longer in `upcoming` (the schedule changed) are
+    /// dropped too, and the rest take the configured child, task, start and
+    /// penalty. Returns the number of instances inserted.
+    pub async fn sync_chore_instances(
+        &self,
+        chore_ids: Vec<String>,
+        upcoming: Vec<models::NewChoreInstance>,
+        now: NaiveDateTime,
+    ) -> Result<usize, StorageError> {
+        use schema::chore_instances;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| -> Result<usize, StorageError> {
+                diesel::delete(
+                    chore_instances::table
+                        .filter(chore_instances::status.eq(models::CHORE_OPEN))
+                        .filter(chore_instances::chore_id.ne_all(&chore_ids)),
+                )
+                .execute(conn)?;
+                let open: Vec<(i32, String, NaiveDateTime)> = chore_instances::table
+                    .filter(chore_instances::status.eq(models::CHORE_OPEN))
+                    .filter(chore_instances::due_at.gt(now))
+                    .select((
+                        chore_instances::id,
+                        chore_instances::chore_id,
+                        chore_instances::due_at,
+                    ))
+                    .load(conn)?;
+                for (id, chore_id, due_at) in open {
+                    let target = chore_instances::table.find(id);
+                    match upcoming
+                        .iter()
+                        .find(|u| u.chore_id == chore_id && u.due_at == due_at)
+                    {
+                        Some(u) => {
+                            diesel::update(target)
+                                .set((
+                                    chore_instances::child_id.eq(&u.child_id),
+                                    chore_instances::task_id.eq(&u.task_id),
+                                    chore_instances::available_from.eq(u.available_from),
+                                    chore_instances::penalty_minutes.eq(u.penalty_minutes),
+                                ))
+                                .execute(conn)?;
+                        }
+                        None => {
+                            diesel::delete(target).execute(conn)?;
+                        }
+                    }
+                }
+                let mut inserted = 0;
+                for instance in &upcoming {
+                    inserted += diesel::insert_or_ignore_into(chore_instances::table)
+                        .values(instance)
+                        .execute(conn)?;
+                }
+                Ok(inserted)
+            })
+        })
+        .await?
+    }
+
+    /// Mark open chore instances due at or before `now` as missed, deducting
+    /// their penalty through the regular reward path.
+    ///
+    /// Returns each missed instance with its task and, when a penalty was
+    /// applied, the child's new remaining minutes.
+    pub async fn apply_overdue_chores(
+        &self,
+        now: NaiveDateTime,
+    ) -> Result<Vec<(models::ChoreInstance, Task, Option<i32>)>, StorageError> {
+        use schema::{chore_instances, tasks};
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<(models::ChoreInstance, Task, Option<i32>)>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    let overdue: Vec<(models::ChoreInstance, Task)> = chore_instances::table
+                        .inner_join(tasks::table)
+                        .filter(chore_instances::status.eq(models::CHORE_OPEN))
+                        .filter(chore_instances::due_at.le(now))
+                        .order(chore_instances::due_at.asc())
+                        .select((models::ChoreInstance::as_select(), Task::as_select()))
+                        .load(conn)?;
+                    let mut missed = Vec::with_capacity(overdue.len());
+                    for (mut instance, task) in overdue {
+                        diesel::update(chore_instances::table.find(instance.id))
+                            .set(chore_instances::status.eq(models::CHORE_MISSED))
+                            .execute(conn)?;
+                        instance.status = models::CHORE_MISSED.to_string();
+                        let new_remaining = if instance.penalty_minutes > 0 {
+                            let description = format!("Missed chore: {}", task.name);
+                            Some(grant_reward_inner(
+                                conn,
+                                &NewReward {
+                                    child_id: &instance.child_id,
+                                    task_id: None,
+                                    minutes: -instance.penalty_minutes,
+                                    description: Some(&description),
+                                    is_borrowed: false,
+                                },
+                            )?)
+                        } else {
+                            None
+                        };
+                        missed.push((instance, task, new_remaining));
+                    }
+                    Ok(missed)
+                })
+            },
+        )
+        .await?
+    }
+
+    /// Open chore instances of a child plus those missed since `missed_since`,
+    /// ordered by due time.
+    pub async fn list_chore_instances_for_child(
+        &self,
+        child_id: &str,
+        missed_since: NaiveDateTime,
+    ) -> Result<Vec<models::ChoreInstance>, StorageError> {
+        use schema::chore_instances;
+        let pool = self.pool.clone();
+        let child = child_id.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<models::ChoreInstance>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(chore_instances::table
+                    .filter(chore_instances::child_id.eq(&child))
+                    .filter(
+                        chore_instances::status
+                            .eq(models::CHORE_OPEN)
+                            .or(chore_instances::status
+                                .eq(models::CHORE_MISSED)
+                                .and(chore_instances::due_at.ge(missed_since))),
+                    )
+                    .order(chore_instances::due_at.asc())
+                    .select(models::ChoreInstance::as_select())
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Queue deliveries and return them with their ids.
+    pub async fn enqueue_webhook_deliveries(
+        &self,
+        deliveries: Vec<models::NewWebhookDelivery>,
+    ) -> Result<Vec<models::WebhookDelivery>, StorageError> {
+        use schema::webhook_deliveries;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<models::WebhookDelivery>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    deliveries
+                        .iter()
+                        .map(|d| {
+                            Ok(diesel::insert_into(webhook_deliveries::table)
+                                .values(d)
+                                .returning(models::WebhookDelivery::as_returning())
+                                .get_result(conn)?)
+                        })
+                        .collect()
+                })
+            },
+        )
+        .await?
+    }
+
+    /// Pending deliveries whose next attempt is due at `now`, oldest first.
+    pub async fn due_webhook_deliveries(
+        &self,
+        now: NaiveDateTime,
+        limit: i64,
+    ) -> Result<Vec<models::WebhookDelivery>, StorageError> {
+        use schema::webhook_deliveries;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<models::WebhookDelivery>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(webhook_deliveries::table
+                    .filter(webhook_deliveries::status.eq(models::WEBHOOK_PENDING))
+                    .filter(webhook_deliveries::next_attempt_at.le(now))
+                    .order(webhook_deliveries::id.asc())
+                    .limit(limit)
+                    .select(models::WebhookDelivery::as_select())
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Record the outcome of a delivery attempt.
+    ///
+    /// A failed attempt stays pending until `retry_at` when given and is
+    /// marked failed otherwise.
+    pub async fn record_webhook_attempt(
+        &self,
+        id: i32,
+        status_code: Option<i32>,
+        error: Option<String>,
+        retry_at: Option<NaiveDateTime>,
+    ) -> Result<models::WebhookDelivery, StorageError> {
+        use schema::webhook_deliveries;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<models::WebhookDelivery, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            let now = Utc::now().naive_utc();
+            let (status, delivered_at, next_attempt_at) = match (&error, retry_at) {
+                (None, _) => (models::WEBHOOK_DELIVERED, Some(now), now),
+                (Some(_), Some(at)) => (models::WEBHOOK_PENDING, None, at),
+                (Some(_), None) => (models::WEBHOOK_FAILED, None, now),
+            };
+            Ok(diesel::update(webhook_deliveries::table.find(id))
+                .set((
+                    webhook_deliveries::status.eq(status),
+                    webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+                    webhook_deliveries::last_status_code.eq(status_code),
+                    webhook_deliveries::last_error.eq(error),
+                    webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+                    webhook_deliveries::delivered_at.eq(delivered_at),
+                ))
+                .returning(models::WebhookDelivery::as_returning())
+                .get_result(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Delivery log of one webhook, newest first.
+    pub async fn list_webhook_deliveries(
+        &self,
+        webhook_id: &str,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<models::WebhookDelivery>, StorageError> {
+        use schema::webhook_deliveries;
+        let pool = self.pool.clone();
+        let hook = webhook_id.to_string();
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 1000) as i64;
+        let offset = ((page as i64) - 1) * per_page;
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<models::WebhookDelivery>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(webhook_deliveries::table
+                    .filter(webhook_deliveries::webhook_id.eq(&hook))
+                    .order(webhook_deliveries::id.desc())
+                    .offset(offset)
+                    .limit(per_page)
+                    .select(models::WebhookDelivery::as_select())
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Delete finished deliveries created before `cutoff`. Returns the number
+    /// of rows removed.
+    pub async fn purge_webhook_deliveries(
+        &self,
+        cutoff: NaiveDateTime,
+    ) -> Result<usize, StorageError> {
+        use schema::webhook_deliveries;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(diesel::delete(
+                webhook_deliveries::table
+                    .filter(webhook_deliveries::status.ne(models::WEBHOOK_PENDING))
+                    .filter(webhook_deliveries::created_at.lt(cutoff)),
+            )
+            .execute(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Append an entry to the audit log.
+    pub async fn record_audit(&self, entry: models::NewAuditEntry) -> Result<(), StorageError> {
+        use schema::audit_log;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            diesel::insert_into(audit_log::table)
+                .values(&entry)
+                .execute(&mut conn)?;
+            Ok(())
+        })
+        .await?
+    }
+
+    /// Audit entries matching `filter`, newest first.
+    pub async fn list_audit_entries(
+        &self,
+        filter: AuditFilter,
+        page: usize,
+        per_page: usize,
+    ) -> Result<Vec<models::AuditEntry>, StorageError> {
+        use schema::audit_log;
+        let pool = self.pool.clone();
+        let page = page.max(1);
+        let per_page = per_page.clamp(1, 1000) as i64;
+        let offset = ((page as i64) - 1) * per_page;
+        tokio::task::spawn_blocking(move || -> Result<Vec<models::AuditEntry>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            let mut query = audit_log::table.into_boxed();
+            if let Some(actor) = filter.actor {
+                query = query.filter(audit_log::actor.eq(actor));
+            }
+            if let Some(action) = filter.action {
+                query = query.filter(audit_log::action.eq(action));
+            }
+            if let Some(child) = filter.child_id {
+                query = query.filter(audit_log::child_id.eq(child));
+            }
+            if let Some(since) = filter.since {
+                query = query.filter(audit_log::created_at.ge(since));
+            }
+            if let Some(until) = filter.until {
+                query = query.filter(audit_log::created_at.lt(until));
+            }
+            Ok(query
+                .order(audit_log::id.desc())
+                .offset(offset)
+                .limit(per_page)
+                .select(models::AuditEntry::as_select())
+                .load(&mut conn)?)
+        })
+        .await?
+    }
 
-        use crate::storage::models::NewUsageMinute;
-        if minutes.is_empty() {
-            return Err(StorageError::InvalidInput(
-                "no minutes provided".to_string(),
-            ));
-        }
-        // Validate timestamp bounds to prevent retroactive inflation or
-        // future-timestamp poisoning of screen-time accounting.
-        let now_minute = Utc::now().timestamp() / 60;
-        let window_past = 7 * 24 * 60; // 7 days back
-        let window_future = 5; // 5 minutes forward (clock skew tolerance)
-        if minutes
-            .iter()
-            .any(|&m| m < now_minute - window_past || m > now_minute + window_future)
-        {
-            return Err(StorageError::InvalidInput(
-                "minute timestamp out of acceptable range".to_string(),
-            ));
-        }
+    /// Credit weekly interest to every non-empty savings pot that is due.
+    ///
+    /// Interest compounds once per full week since `savings_interest_at`, so a
+    /// server that was offline catches up on missed weeks. Fractional minutes
+    /// are rounded down. Returns the number of pots that received interest.
+    pub async fn apply_savings_interest(
+        &self,
+        weekly_percent: f64,
+        now: chrono::NaiveDateTime,
+    ) -> Result<usize, StorageError> {
+        use schema::balances;
         let pool = self.pool.clone();
-        let child_owned = child.to_string();
-        let device_owned = device.to_string();
-        let minutes_vec = minutes.to_vec();
-        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            conn.immediate_transaction(|conn| -> Result<i32, StorageError> {
-                let mut new_count = 0i32;
-                for m in &minutes_vec {
-                    let row = NewUsageMinute {
-                        child_id: &child_owned,
-                        minute_ts: *m,
-                        device_id: &device_owned,
+            conn.immediate_transaction(|conn| -> Result<usize, StorageError> {
+                let week = chrono::Duration::weeks(1);
+                let due: Vec<models::Balance> = balances::table
+                    .filter(balances::savings_minutes.gt(0))
+                    .filter(balances::savings_interest_at.le(now - week))
+                    .select(models::Balance::as_select())
+                    .load(conn)?;
+                let mut credited = 0usize;
+                for row in due {
+                    let Some(mut anchor) = row.savings_interest_at else {
+                        continue;
                     };
-                    let inserted = diesel::insert_into(usage_minutes::table)
-                        .values(&row)
-                        .on_conflict_do_nothing()
+                    let mut savings = row.savings_minutes;
+                    let mut total = 0i32;
+                    while anchor + week <= now {
+                        anchor += week;
+                        let interest = (savings as f64 * weekly_percent / 100.0).floor() as i32;
+                        if interest > 0 {
+                            savings += interest;
+                            total += interest;
+                            record_savings_transaction(
+                                conn,
+                                &row.child_id,
+                                "interest",
+                                interest,
+                                savings,
+                                anchor,
+                            )?;
+                        }
+                    }
+                    diesel::update(balances::table.filter(balances::child_id.eq(&row.child_id)))
+                        .set((
+                            balances::savings_minutes.eq(savings),
+                            balances::savings_interest_at.eq(Some(anchor)),
+                        ))
                         .execute(conn)?;
-                    new_count += inserted as i32;
+                    if total > 0 {
+                        credited += 1;
+                    }
                 }
-                if new_count > 0 {
-                    diesel::update(balances::table.filter(balances::child_id.eq(&child_owned)))
+                Ok(credited)
+            })
+        })
+        .await?
+    }
+
+    /// Signing keys, newest first.
+    pub async fn list_signing_keys(&self) -> Result<Vec<models::SigningKeyRow>, StorageError> {
+        use schema::signing_keys;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(
+            move || -> Result<Vec<models::SigningKeyRow>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(signing_keys::table
+                    .order((signing_keys::created_at.desc(), signing_keys::kid.desc()))
+                    .select(models::SigningKeyRow::as_select())
+                    .load(&mut conn)?)
+            },
+        )
+        .await?
+    }
+
+    /// Store a new signing key and retire the one it replaces.
+    pub async fn add_signing_key(
+        &self,
+        kid: &str,
+        alg: &str,
+        private_key: &[u8],
+    ) -> Result<(), StorageError> {
+        use schema::signing_keys;
+        let pool = self.pool.clone();
+        let kid = kid.to_string();
+        let alg = alg.to_string();
+        let private_key = private_key.to_vec();
+        tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| {
+                let now = Utc::now().naive_utc();
+                diesel::update(signing_keys::table.filter(signing_keys::retired_at.is_null()))
+                    .set(signing_keys::retired_at.eq(Some(now)))
+                    .execute(conn)?;
+                diesel::insert_into(signing_keys::table)
+                    .values(&models::NewSigningKey {
+                        kid: &kid,
+                        alg: &alg,
+                        private_key: &private_key,
+                    })
+                    .execute(conn)?;
+                Ok(())
+            })
+        })
+        .await?
+    }
+
+    /// Delete keys retired before `cutoff`; returns how many were removed.
+    pub async fn delete_signing_keys_retired_before(
+        &self,
+        cutoff: NaiveDateTime,
+    ) -> Result<usize, StorageError> {
+        use schema::signing_keys;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(
+                diesel::delete(signing_keys::table.filter(signing_keys::retired_at.lt(cutoff)))
+                    .execute(&mut conn)?,
+            )
+        })
+        .await?
+    }
+
+    /// Lockout state of `username`, if it failed to log in since its last
+    /// successful login.
+    pub async fn get_login_lockout(
+        &self,
+        username: &str,
+    ) -> Result<Option<models::LoginLockout>, StorageError> {
+        use schema::login_lockouts;
+        let pool = self.pool.clone();
+        let username = username.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<models::LoginLockout>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(login_lockouts::table
+                    .find(&username)
+                    .select(models::LoginLockout::as_select())
+                    .first(&mut conn)
+                    .optional()?)
+            },
+        )
+        .await?
+    }
+
+    /// Record a failed login and count it against the account. The
+    /// `lockout_after`-th failure in a row locks the account for `lockout`,
+    /// doubled for every earlier lock and capped at `max_lockout`; a zero
+    /// `lockout_after` never locks. Returns the updated state and whether
+    /// this failure locked the account.
+    pub async fn record_login_failure(
+        &self,
+        failure: models::NewLoginFailure,
+        lockout_after: u32,
+        lockout: chrono::Duration,
+        max_lockout: chrono::Duration,
+    ) -> Result<(models::LoginLockout, bool), StorageError> {
+        use schema::{login_failures, login_lockouts};
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(
+            move || -> Result<(models::LoginLockout, bool), StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                conn.immediate_transaction(|conn| {
+                    diesel::insert_into(login_failures::table)
+                        .values(&failure)
+                        .execute(conn)?;
+                    let counted = diesel::insert_into(login_lockouts::table)
+                        .values((
+                            login_lockouts::username.eq(&failure.username),
+                            login_lockouts::failed_attempts.eq(1),
+                        ))
+                        .on_conflict(login_lockouts::username)
+                        .do_update()
                         .set(
-                            balances::minutes_remaining.eq(balances::minutes_remaining - new_count),
+                            login_lockouts::failed_attempts.eq(login_lockouts::failed_attempts + 1),
                         )
-                        .execute(conn)?;
-                }
-                let new_remaining: i32 = balances::table
-                    .filter(balances::child_id.eq(&child_owned))
-                    .select(balances::minutes_remaining)
-                    .first(conn)?;
-                Ok(new_remaining)
-            })
+                        .returning(models::LoginLockout::as_returning())
+                        .get_result(conn)?;
+                    if lockout_after == 0 || counted.failed_attempts < lockout_after as i32 {
+                        return Ok((counted, false));
+                    }
+                    // 2^lockouts, saturating long before the cap matters
+                    let factor = 1i32
+                        .checked_shl(counted.lockouts as u32)
+                        .unwrap_or(i32::MAX);
+                    let length = lockout
+                        .checked_mul(factor)
+                        .map_or(max_lockout, |l| l.min(max_lockout));
+                    let locked = diesel::update(login_lockouts::table.find(&failure.username))
+                        .set((
+                            login_lockouts::failed_attempts.eq(0),
+                            login_lockouts::lockouts.eq(counted.lockouts + 1),
+                            login_lockouts::locked_until.eq(Some(Utc::now().naive_utc() + length)),
+                        ))
+                        .returning(models::LoginLockout::as_returning())
+                        .get_result(conn)?;
+                    Ok((locked, true))
+                })
+            },
+        )
+        .await?
+    }
+
+    /// Forget the failures of `username` after it logged in.
+    pub async fn clear_login_lockout(&self, username: &str) -> Result<(), StorageError> {
+        use schema::login_lockouts;
+        let pool = self.pool.clone();
+        let username = username.to_string();
+        tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            diesel::delete(login_lockouts::table.find(&username)).execute(&mut conn)?;
+            Ok(())
         })
         .await?
     }
 
-    pub async fn list_usage_minutes(
+    /// Delete failed logins recorded before `cutoff`. Returns the number of
+    /// rows removed.
+    pub async fn purge_login_failures(&self, cutoff: NaiveDateTime) -> Result<usize, StorageError> {
+        use schema::login_failures;
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> Result<usize, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(
+                diesel::delete(login_failures::table.filter(login_failures::created_at.lt(cutoff)))
+                    .execute(&mut conn)?,
+            )
+        })
+        .await?
+    }
+
+    /// TOTP credential of `username`, enabled or still being enrolled.
+    pub async fn get_totp(
         &self,
-        child: &str,
-        minute_from: i64,
-        minute_to: i64,
-    ) -> Result<Vec<i64>, StorageError> {
-        use schema::usage_minutes::dsl as um;
-        if minute_to <= minute_from {
-            return Ok(Vec::new());
-        }
+        username: &str,
+    ) -> Result<Option<models::TotpCredential>, StorageError> {
+        use schema::totp_credentials;
         let pool = self.pool.clone();
-        let child_owned = child.to_string();
-        tokio::task::spawn_blocking(move || -> Result<Vec<i64>, StorageError> {
+        let username = username.to_string();
+        tokio::task::spawn_blocking(
+            move || -> Result<Option<models::TotpCredential>, StorageError> {
+                let mut conn = pool.get()?;
+                configure_sqlite_conn(&mut conn)?;
+                Ok(totp_credentials::table
+                    .find(&username)
+                    .select(models::TotpCredential::as_select())
+                    .first(&mut conn)
+                    .optional()?)
+            },
+        )
+        .await?
+    }
+
+    /// Start a TOTP enrollment with `secret`, replacing any unconfirmed one.
+    /// Returns false if `username` already has TOTP enabled.
+    pub async fn start_totp_enrollment(
+        &self,
+        username: &str,
+        secret: &[u8],
+    ) -> Result<bool, StorageError> {
+        use schema::totp_credentials;
+        let pool = self.pool.clone();
+        let username = username.to_string();
+        let secret = secret.to_vec();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            Ok(um::usage_minutes
-                .filter(um::child_id.eq(&child_owned))
-                .filter(um::minute_ts.ge(minute_from))
-                .filter(um::minute_ts.lt(minute_to))
-                .select(um::minute_ts)
-                .distinct()
-                .order(um::minute_ts.asc())
-                .load::<i64>(&mut conn)?)
+            conn.immediate_transaction(|conn| {
+                let enabled = totp_credentials::table
+                    .find(&username)
+                    .select(totp_credentials::enabled_at)
+                    .first::<Option<NaiveDateTime>>(conn)
+                    .optional()?
+                    .flatten()
+                    .is_some();
+                if enabled {
+                    return Ok(false);
+                }
+                diesel::delete(totp_credentials::table.find(&username)).execute(conn)?;
+                diesel::insert_into(totp_credentials::table)
+                    .values(&models::NewTotpCredential {
+                        username: &username,
+                        secret: &secret,
+                    })
+                    .execute(conn)?;
+                Ok(true)
+            })
         })
         .await?
     }
 
-    pub async fn get_remaining(&self, child_id: &str) -> Result<i32, StorageError> {
-        use schema::balances;
+    /// Confirm a pending enrollment: enable TOTP, mark `step` as used and
+    /// replace the recovery codes with `code_hashes`. Returns false if there
+    /// is no pending enrollment.
+    pub async fn enable_totp(
+        &self,
+        username: &str,
+        step: i64,
+        code_hashes: Vec<String>,
+    ) -> Result<bool, StorageError> {
+        use schema::{totp_credentials, totp_recovery_codes};
         let pool = self.pool.clone();
-        let child = child_id.to_string();
-        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+        let username = username.to_string();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            Ok(balances::table
-                .filter(balances::child_id.eq(&child))
-                .select(balances::minutes_remaining)
-                .first(&mut conn)?)
+            conn.immediate_transaction(|conn| {
+                let updated = diesel::update(
+                    totp_credentials::table
+                        .find(&username)
+                        .filter(totp_credentials::enabled_at.is_null()),
+                )
+                .set((
+                    totp_credentials::enabled_at.eq(Some(Utc::now().naive_utc())),
+                    totp_credentials::last_step.eq(Some(step)),
+                ))
+                .execute(conn)?;
+                if updated == 0 {
+                    return Ok(false);
+                }
+                diesel::delete(
+                    totp_recovery_codes::table.filter(totp_recovery_codes::username.eq(&username)),
+                )
+                .execute(conn)?;
+                let rows: Vec<_> = code_hashes
+                    .iter()
+                    .map(|h| models::NewTotpRecoveryCode {
+                        username: &username,
+                        code_hash: h,
+                    })
+                    .collect();
+                diesel::insert_into(totp_recovery_codes::table)
+                    .values(&rows)
+                    .execute(conn)?;
+                Ok(true)
+            })
         })
         .await?
     }
 
-    pub async fn get_balance(&self, child_id: &str) -> Result<i32, StorageError> {
+    /// Record `step` as used if it is newer than the last accepted one.
+    /// Returns false for a replayed or older code.
+    pub async fn accept_totp_step(&self, username: &str, step: i64) -> Result<bool, StorageError> {
+        use schema::totp_credentials;
         let pool = self.pool.clone();
-        let child = child_id.to_string();
-        tokio::task::spawn_blocking(move || -> Result<i32, StorageError> {
+        let username = username.to_string();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            get_balance_inner(&mut conn, &child)
+            let updated = diesel::update(
+                totp_credentials::table
+                    .find(&username)
+                    .filter(totp_credentials::enabled_at.is_not_null())
+                    .filter(
+                        totp_credentials::last_step
+                            .is_null()
+                            .or(totp_credentials::last_step.lt(step)),
+                    ),
+            )
+            .set(totp_credentials::last_step.eq(Some(step)))
+            .execute(&mut conn)?;
+            Ok(updated > 0)
         })
         .await?
     }
 
-    pub async fn all_required_tasks_done_today(
+    /// Spend the unused recovery code with digest `code_hash`. Returns false
+    /// if there is no such code.
+    pub async fn use_totp_recovery_code(
         &self,
-        child_id: &str,
+        username: &str,
+        code_hash: &str,
     ) -> Result<bool, StorageError> {
+        use schema::totp_recovery_codes;
         let pool = self.pool.clone();
-        let child = child_id.to_string();
+        let username = username.to_string();
+        let code_hash = code_hash.to_string();
         tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            all_required_tasks_done_today_inner(&mut conn, &child)
+            let updated = diesel::update(
+                totp_recovery_codes::table
+                    .filter(totp_recovery_codes::username.eq(&username))
+                    .filter(totp_recovery_codes::code_hash.eq(&code_hash))
+                    .filter(totp_recovery_codes::used_at.is_null()),
+            )
+            .set(totp_recovery_codes::used_at.eq(Some(Utc::now().naive_utc())))
+            .execute(&mut conn)?;
+            Ok(updated > 0)
+        })
+        .await?
+    }
+
+    /// Number of recovery codes `username` has not used yet.
+    pub async fn count_totp_recovery_codes(&self, username: &str) -> Result<i64, StorageError> {
+        use schema::totp_recovery_codes;
+        let pool = self.pool.clone();
+        let username = username.to_string();
+        tokio::task::spawn_blocking(move || -> Result<i64, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(totp_recovery_codes::table
+                .filter(totp_recovery_codes::username.eq(&username))
+                .filter(totp_recovery_codes::used_at.is_null())
+                .count()
+                .get_result(&mut conn)?)
+        })
+        .await?
+    }
+
+    /// Remove the TOTP credential and recovery codes of `username`. Returns
+    /// false if there was nothing to remove.
+    pub async fn delete_totp(&self, username: &str) -> Result<bool, StorageError> {
+        use schema::{totp_credentials, totp_recovery_codes};
+        let pool = self.pool.clone();
+        let username = username.to_string();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            conn.immediate_transaction(|conn| {
+                diesel::delete(
+                    totp_recovery_codes::table.filter(totp_recovery_codes::username.eq(&username)),
+                )
+                .execute(conn)?;
+                let deleted =
+                    diesel::delete(totp_credentials::table.find(&username)).execute(conn)?;
+                Ok(deleted > 0)
+            })
         })
         .await?
     }
 
     // Session helpers for JWT inactivity windows
-    pub async fn create_session(&self, jti_: &str, username_: &str) -> Result<(), StorageError> {
+    pub async fn create_session(
+        &self,
+        jti_: &str,
+        username_: &str,
+        device_id_: Option<&str>,
+    ) -> Result<(), StorageError> {
         use schema::sessions;
         let pool = self.pool.clone();
         let j = jti_.to_string();
         let u = username_.to_string();
+        let d = device_id_.map(str::to_string);
         tokio::task::spawn_blocking(move || -> Result<(), StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
             let new = NewSession {
                 jti: &j,
                 username: &u,
+                device_id: d.as_deref(),
             };
             diesel::insert_into(sessions::table)
                 .values(&new)
@@ -853,27 +2743,65 @@ impl Store {
     pub async fn get_session(&self, jti_: &str) -> Result<Option<Session>, StorageError> {
         use schema::sessions::dsl::*;
         let pool = self.pool.clone();
-        let j = jti_.to_string();
-        tokio::task::spawn_blocking(move || -> Result<Option<Session>, StorageError> {
+        let j = jti_.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Option<Session>, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            Ok(sessions
+                .filter(jti.eq(&j))
+                .first::<Session>(&mut conn)
+                .optional()?)
+        })
+        .await?
+    }
+
+    pub async fn delete_session(&self, jti_: &str) -> Result<bool, StorageError> {
+        use schema::sessions::dsl::*;
+        let pool = self.pool.clone();
+        let j = jti_.to_string();
+        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
+            let mut conn = pool.get()?;
+            configure_sqlite_conn(&mut conn)?;
+            let deleted = diesel::delete(sessions.filter(jti.eq(&j))).execute(&mut conn)?;
+            Ok(deleted > 0)
+        })
+        .await?
+    }
+
+    /// Ids (jti) of the sessions `username` is logged in with.
+    pub async fn session_ids_for_user(&self, username: &str) -> Result<Vec<String>, StorageError> {
+        use schema::sessions;
+        let pool = self.pool.clone();
+        let username = username.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Vec<String>, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            Ok(sessions
-                .filter(jti.eq(&j))
-                .first::<Session>(&mut conn)
-                .optional()?)
+            Ok(sessions::table
+                .filter(sessions::username.eq(&username))
+                .select(sessions::jti)
+                .load(&mut conn)?)
         })
         .await?
     }
 
-    pub async fn delete_session(&self, jti_: &str) -> Result<bool, StorageError> {
-        use schema::sessions::dsl::*;
+    /// Ids (jti) of the sessions of `username` bound to `device`.
+    pub async fn session_ids_for_device(
+        &self,
+        username: &str,
+        device: &str,
+    ) -> Result<Vec<String>, StorageError> {
+        use schema::sessions;
         let pool = self.pool.clone();
-        let j = jti_.to_string();
-        tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
+        let username = username.to_string();
+        let device = device.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Vec<String>, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
-            let deleted = diesel::delete(sessions.filter(jti.eq(&j))).execute(&mut conn)?;
-            Ok(deleted > 0)
+            Ok(sessions::table
+                .filter(sessions::username.eq(&username))
+                .filter(sessions::device_id.eq(&device))
+                .select(sessions::jti)
+                .load(&mut conn)?)
         })
         .await?
     }
@@ -883,23 +2811,31 @@ impl Store {
     ///
     /// This combines the idle timeout check and the `last_used_at` update into
     /// a single atomic UPDATE, eliminating the race condition between checking
-    /// and updating the session.
+    /// and updating the session. `device` is the device the token is bound to;
+    /// it is recorded for sessions created before sessions tracked devices.
     pub async fn touch_session_with_cutoff(
         &self,
         jti_: &str,
         cutoff: chrono::NaiveDateTime,
+        device: Option<&str>,
     ) -> Result<bool, StorageError> {
         use schema::sessions::dsl::*;
         let pool = self.pool.clone();
         let j = jti_.to_string();
+        let device = device.map(str::to_string);
         tokio::task::spawn_blocking(move || -> Result<bool, StorageError> {
             let mut conn = pool.get()?;
             configure_sqlite_conn(&mut conn)?;
             let now = Utc::now().naive_utc();
-            let updated =
-                diesel::update(sessions.filter(jti.eq(&j)).filter(last_used_at.ge(cutoff)))
+            let target = sessions.filter(jti.eq(&j)).filter(last_used_at.ge(cutoff));
+            let updated = match &device {
+                Some(d) => diesel::update(target)
+                    .set((last_used_at.eq(now), device_id.eq(Some(d))))
+                    .execute(&mut conn)?,
+                None => diesel::update(target)
                     .set(last_used_at.eq(now))
-                    .execute(&mut conn)?;
+                    .execute(&mut conn)?,
+            };
             Ok(updated > 0)
         })
         .await?
@@ -911,6 +2847,7 @@ fn record_task_done_inner(
     child_id: &str,
     task_id: &str,
     by_username: &str,
+    minutes_awarded: Option<i32>,
 ) -> Result<(), StorageError> {
     use models::NewTaskCompletion;
     use schema::task_completions;
@@ -918,10 +2855,40 @@ fn record_task_done_inner(
         child_id,
         task_id,
         by_username,
+        minutes_awarded,
     };
     diesel::insert_into(task_completions::table)
         .values(&rec)
         .execute(conn)?;
+    complete_chore_inner(conn, child_id, task_id)
+}
+
+/// Mark the earliest open chore instance of this task that can already be
+/// completed as done. Does nothing when the task is not a scheduled chore.
+fn complete_chore_inner(
+    conn: &mut SqliteConnection,
+    child_id: &str,
+    task_id: &str,
+) -> Result<(), StorageError> {
+    use schema::chore_instances;
+    let now = Utc::now().naive_utc();
+    let next: Option<i32> = chore_instances::table
+        .filter(chore_instances::child_id.eq(child_id))
+        .filter(chore_instances::task_id.eq(task_id))
+        .filter(chore_instances::status.eq(models::CHORE_OPEN))
+        .filter(chore_instances::available_from.le(now))
+        .order(chore_instances::due_at.asc())
+        .select(chore_instances::id)
+        .first(conn)
+        .optional()?;
+    if let Some(id) = next {
+        diesel::update(chore_instances::table.find(id))
+            .set((
+                chore_instances::status.eq(models::CHORE_DONE),
+                chore_instances::completed_at.eq(Some(now)),
+            ))
+            .execute(conn)?;
+    }
     Ok(())
 }
 
@@ -974,6 +2941,93 @@ fn all_required_tasks_done_today_inner(
         .all(|tid| completed_task_ids.contains(tid)))
 }
 
+/// Look up a pending task submission together with its task.
+fn find_submission_inner(
+    conn: &mut SqliteConnection,
+    submission_id: i32,
+) -> Result<Option<(models::TaskSubmission, Task)>, StorageError> {
+    use schema::{task_submissions, tasks};
+    Ok(task_submissions::table
+        .inner_join(tasks::table.on(tasks::id.eq(task_submissions::task_id)))
+        .filter(task_submissions::id.eq(submission_id))
+        .filter(task_submissions::status.eq(models::SUBMISSION_PENDING))
+        .select((models::TaskSubmission::as_select(), Task::as_select()))
+        .first(conn)
+        .optional()?)
+}
+
+/// Insert a reward row and apply it to the child's balance.
+///
+/// Returns the child's new stored remaining minutes.
+fn grant_reward_inner(
+    conn: &mut SqliteConnection,
+    new_reward: &NewReward<'_>,
+) -> Result<i32, StorageError> {
+    use schema::{balances, rewards};
+    let child_id = new_reward.child_id;
+    // Read current account_balance for debt tracking
+    let account_balance: i32 = balances::table
+        .filter(balances::child_id.eq(child_id))
+        .select(balances::account_balance)
+        .first(conn)?;
+    let reward_id: i32 = diesel::insert_into(rewards::table)
+        .values(new_reward)
+        .returning(rewards::id)
+        .get_result(conn)?;
+    let (rem_delta, bal_delta) = apply_reward_to_balance(
+        conn,
+        child_id,
+        new_reward.minutes,
+        new_reward.is_borrowed,
+        account_balance,
+        reward_id,
+    )?;
+    diesel::update(balances::table.filter(balances::child_id.eq(child_id)))
+        .set((
+            balances::minutes_remaining.eq(balances::minutes_remaining + rem_delta),
+            balances::account_balance.eq(balances::account_balance + bal_delta),
+        ))
+        .execute(conn)?;
+    Ok(balances::table
+        .filter(balances::child_id.eq(child_id))
+        .select(balances::minutes_remaining)
+        .first(conn)?)
+}
+
+/// Record that `recipient` read (and optionally dismissed) a notification.
+fn upsert_receipt_inner(
+    conn: &mut SqliteConnection,
+    notification_id: i32,
+    recipient: &str,
+    dismiss: bool,
+) -> Result<(), StorageError> {
+    use models::NotificationReceipt;
+    use schema::notification_receipts;
+    let now = Utc::now().naive_utc();
+    let receipt = NotificationReceipt {
+        notification_id,
+        recipient: recipient.to_string(),
+        read_at: Some(now),
+        dismissed_at: dismiss.then_some(now),
+    };
+    let insert = diesel::insert_into(notification_receipts::table)
+        .values(&receipt)
+        .on_conflict((
+            notification_receipts::notification_id,
+            notification_receipts::recipient,
+        ));
+    // Receipts are only created on read, so an existing one already has read_at
+    if dismiss {
+        insert
+            .do_update()
+            .set(notification_receipts::dismissed_at.eq(Some(now)))
+            .execute(conn)?;
+    } else {
+        insert.do_nothing().execute(conn)?;
+    }
+    Ok(())
+}
+
 /// Apply reward/penalty logic to balances and record balance transactions.
 ///
 /// Returns `(remaining_delta, balance_delta)` — the changes to apply to the
@@ -1031,7 +3085,72 @@ fn apply_reward_to_balance(
     }
 }
 
+/// Move `mins` from one child's `minutes_remaining` to another's.
+///
+/// Transfers are gifts of already-earned time: `account_balance` (debt) is
+/// left untouched on both sides. Returns the new stored remaining minutes of
+/// the giving and receiving child.
+fn apply_transfer_inner(
+    conn: &mut SqliteConnection,
+    from_child: &str,
+    to_child: &str,
+    mins: i32,
+) -> Result<(i32, i32), StorageError> {
+    use schema::balances;
+    let available: i32 = balances::table
+        .filter(balances::child_id.eq(from_child))
+        .select(balances::minutes_remaining)
+        .first(conn)?;
+    if available < mins {
+        return Err(StorageError::InvalidInput(format!(
+            "not enough minutes to transfer ({} available)",
+            available.max(0)
+        )));
+    }
+    let from_rem = available - mins;
+    diesel::update(balances::table.filter(balances::child_id.eq(from_child)))
+        .set(balances::minutes_remaining.eq(from_rem))
+        .execute(conn)?;
+    let updated = diesel::update(balances::table.filter(balances::child_id.eq(to_child)))
+        .set(balances::minutes_remaining.eq(balances::minutes_remaining + mins))
+        .execute(conn)?;
+    if updated == 0 {
+        return Err(StorageError::InvalidInput(format!(
+            "unknown child: {}",
+            to_child
+        )));
+    }
+    let to_rem: i32 = balances::table
+        .filter(balances::child_id.eq(to_child))
+        .select(balances::minutes_remaining)
+        .first(conn)?;
+    Ok((from_rem, to_rem))
+}
+
+fn record_savings_transaction(
+    conn: &mut SqliteConnection,
+    child_id: &str,
+    kind: &str,
+    amount: i32,
+    savings_after: i32,
+    created_at: chrono::NaiveDateTime,
+) -> Result<(), StorageError> {
+    use models::NewSavingsTransaction;
+    use schema::savings_transactions;
+    diesel::insert_into(savings_transactions::table)
+        .values(&NewSavingsTransaction {
+            child_id,
+            kind,
+            amount,
+            savings_after,
+            created_at,
+        })
+        .execute(conn)?;
+    Ok(())
+}
+
 fn configure_sqlite_conn(conn: &mut SqliteConnection) -> Result<(), diesel::result::Error> {
+    conn.set_instrumentation(QueryTimer::default());
     // Enable WAL for better read/write concurrency and set a busy timeout
     // Ignore the result rows; Diesel's execute is fine for PRAGMAs
     diesel::sql_query("PRAGMA journal_mode=WAL;").execute(conn)?;
@@ -1040,6 +3159,31 @@ fn configure_sqlite_conn(conn: &mut SqliteConnection) -> Result<(), diesel::resu
     Ok(())
 }
 
+/// Records the duration of every statement run on a connection.
+#[derive(Default)]
+struct QueryTimer {
+    started: Option<std::time::Instant>,
+}
+
+impl diesel::connection::Instrumentation for QueryTimer {
+    fn on_connection_event(&mut self, event: diesel::connection::InstrumentationEvent<'_>) {
+        use diesel::connection::InstrumentationEvent;
+        match event {
+            InstrumentationEvent::StartQuery { .. } => {
+                self.started = Some(std::time::Instant::now());
+            }
+            InstrumentationEvent::FinishQuery { .. } => {
+                if let Some(started) = self.started.take() {
+                    crate::metrics::metrics()
+                        .db_query_seconds
+                        .observe(started.elapsed().as_secs_f64());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use diesel::sqlite::SqliteConnection;
@@ -1119,7 +3263,8 @@ mod tests {
                 child_id TEXT NOT NULL,
                 task_id TEXT NOT NULL,
                 by_username TEXT NOT NULL,
-                done_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+                done_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                minutes_awarded INTEGER
             )",
         )
         .execute(&mut conn)
@@ -1406,4 +3551,461 @@ mod tests {
             "should reference a reward"
         );
     }
+
+    #[tokio::test]
+    async fn savings_deposit_interest_and_withdraw() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+
+        let child = gamiscreen_shared::domain::Child {
+            id: "kid1".into(),
+            display_name: "Kid".into(),
+        };
+        store.seed_from_config(&[child], &[]).await.expect("seed");
+        store
+            .add_reward_minutes("kid1", 30, None, Some("earned"), false, None)
+            .await
+            .expect("earn 30 min");
+
+        // Cannot deposit more than is spendable
+        let err = store.deposit_savings("kid1", 31, None).await.unwrap_err();
+        assert!(matches!(err, StorageError::InvalidInput(_)));
+
+        let row = store
+            .deposit_savings("kid1", 20, None)
+            .await
+            .expect("deposit");
+        assert_eq!(row.minutes_remaining, 10);
+        assert_eq!(row.savings_minutes, 20);
+        let anchor = row.savings_interest_at.expect("interest anchor set");
+
+        // Nothing is due before a full week has passed
+        let credited = store
+            .apply_savings_interest(10.0, anchor + chrono::Duration::days(6))
+            .await
+            .expect("interest");
+        assert_eq!(credited, 0);
+
+        // Two missed weeks compound: 20 -> 22 -> 24 (2.2 rounds down)
+        let credited = store
+            .apply_savings_interest(10.0, anchor + chrono::Duration::days(15))
+            .await
+            .expect("interest");
+        assert_eq!(credited, 1);
+        let row = store.get_savings("kid1").await.expect("savings");
+        assert_eq!(row.savings_minutes, 24);
+        assert_eq!(
+            row.savings_interest_at,
+            Some(anchor + chrono::Duration::weeks(2))
+        );
+
+        let row = store
+            .withdraw_savings("kid1", 24, false)
+            .await
+            .expect("withdraw");
+        assert_eq!(row.minutes_remaining, 34);
+        assert_eq!(row.savings_minutes, 0);
+        assert_eq!(row.savings_interest_at, None, "empty pot resets anchor");
+
+        let ledger = store
+            .list_savings_transactions("kid1", 1, 10)
+            .await
+            .expect("ledger");
+        let kinds: Vec<(&str, i32)> = ledger.iter().map(|t| (t.kind.as_str(), t.amount)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("withdraw", -24),
+                ("interest", 2),
+                ("interest", 2),
+                ("deposit", 20)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn savings_lock_in_blocks_child_withdrawal() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+
+        let child = gamiscreen_shared::domain::Child {
+            id: "kid1".into(),
+            display_name: "Kid".into(),
+        };
+        store.seed_from_config(&[child], &[]).await.expect("seed");
+        store
+            .add_reward_minutes("kid1", 10, None, Some("earned"), false, None)
+            .await
+            .expect("earn 10 min");
+        let row = store
+            .deposit_savings("kid1", 10, Some(chrono::Duration::days(3)))
+            .await
+            .expect("deposit");
+        assert!(row.savings_locked_until.is_some());
+
+        let err = store.withdraw_savings("kid1", 5, false).await.unwrap_err();
+        assert!(matches!(err, StorageError::InvalidInput(_)));
+
+        let row = store
+            .withdraw_savings("kid1", 5, true)
+            .await
+            .expect("override lock");
+        assert_eq!(row.savings_minutes, 5);
+        assert_eq!(row.minutes_remaining, 5);
+    }
+
+    #[tokio::test]
+    async fn submission_attachments_follow_retention() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+
+        let child = gamiscreen_shared::domain::Child {
+            id: "kid1".into(),
+            display_name: "Kid".into(),
+        };
+        let task = gamiscreen_shared::domain::Task {
+            id: "room".into(),
+            name: "Clean room".into(),
+            minutes: 10,
+            required: false,
+        };
+        store
+            .seed_from_config(&[child], &[task])
+            .await
+            .expect("seed");
+
+        let kept = store
+            .submit_task("kid1", "room", vec![("image/png", vec![1, 2, 3])])
+            .await
+            .expect("submit with photo");
+        let dropped = store
+            .submit_task("kid1", "room", vec![("image/jpeg", vec![4, 5])])
+            .await
+            .expect("submit with photo");
+        let photos = store
+            .list_submission_attachments(vec![kept, dropped])
+            .await
+            .expect("list");
+        assert_eq!(photos.len(), 2);
+        let (meta, data) = store
+            .get_submission_attachment(photos[0].id)
+            .await
+            .expect("get")
+            .expect("attachment exists");
+        assert_eq!(meta.content_type, "image/png");
+        assert_eq!(meta.size_bytes, 3);
+        assert_eq!(data, vec![1, 2, 3]);
+
+        // Released without retention: gone at once
+        store
+            .release_submission_attachments(dropped, None)
+            .await
+            .expect("release");
+        // Released with retention: kept until the deadline passes
+        let now = Utc::now().naive_utc();
+        let keep_until = now + chrono::Duration::days(7);
+        store
+            .release_submission_attachments(kept, Some(keep_until))
+            .await
+            .expect("release");
+        let remaining = store
+            .list_submission_attachments(vec![kept, dropped])
+            .await
+            .expect("list");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].submission_id, kept);
+
+        assert_eq!(
+            store.purge_expired_attachments(now).await.expect("purge"),
+            0
+        );
+        assert_eq!(
+            store
+                .purge_expired_attachments(keep_until)
+                .await
+                .expect("purge"),
+            1
+        );
+        assert!(
+            store
+                .get_submission_attachment(photos[0].id)
+                .await
+                .expect("get")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn chore_instances_complete_and_penalize() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+
+        let child = gamiscreen_shared::domain::Child {
+            id: "kid1".into(),
+            display_name: "Kid".into(),
+        };
+        let task = gamiscreen_shared::domain::Task {
+            id: "dishes".into(),
+            name: "Dishes".into(),
+            minutes: 5,
+            required: false,
+        };
+        store
+            .seed_from_config(&[child], &[task])
+            .await
+            .expect("seed");
+        store
+            .add_reward_minutes("kid1", 20, None, Some("earned"), false, None)
+            .await
+            .expect("earn 20 min");
+
+        let now = Utc::now().naive_utc();
+        let instance = |due_in_hours: i64| models::NewChoreInstance {
+            chore_id: "dishes-daily".into(),
+            child_id: "kid1".into(),
+            task_id: "dishes".into(),
+            available_from: now + chrono::Duration::hours(due_in_hours - 24),
+            due_at: now + chrono::Duration::hours(due_in_hours),
+            penalty_minutes: 7,
+        };
+        let upcoming = vec![instance(2), instance(26)];
+        let inserted = store
+            .sync_chore_instances(vec!["dishes-daily".into()], upcoming.clone(), now)
+            .await
+            .expect("sync");
+        assert_eq!(inserted, 2);
+        let inserted = store
+            .sync_chore_instances(vec!["dishes-daily".into()], upcoming, now)
+            .await
+            .expect("sync again");
+        assert_eq!(inserted, 0, "instances are created once");
+
+        // Completing the task only counts for the instance already available
+        store
+            .record_task_done("kid1", "dishes", "parent")
+            .await
+            .expect("done");
+        let list = store
+            .list_chore_instances_for_child("kid1", now)
+            .await
+            .expect("list");
+        assert_eq!(list.len(), 1, "done instances are not listed");
+        assert_eq!(list[0].status, models::CHORE_OPEN);
+
+        // The remaining one is missed once its due time passes
+        let missed = store
+            .apply_overdue_chores(now + chrono::Duration::hours(27))
+            .await
+            .expect("overdue");
+        assert_eq!(missed.len(), 1);
+        assert_eq!(missed[0].0.status, models::CHORE_MISSED);
+        assert_eq!(missed[0].2, Some(20 - 7));
+        assert_eq!(store.get_remaining("kid1").await.expect("remaining"), 13);
+
+        // Dropping the chore from the config leaves history alone
+        store
+            .sync_chore_instances(Vec::new(), Vec::new(), now)
+            .await
+            .expect("sync removed");
+        let list = store
+            .list_chore_instances_for_child("kid1", now)
+            .await
+            .expect("list");
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].status, models::CHORE_MISSED);
+    }
+
+    #[tokio::test]
+    async fn chore_instances_follow_schedule_changes() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+        let child = gamiscreen_shared::domain::Child {
+            id: "kid1".into(),
+            display_name: "Kid".into(),
+        };
+        let task = gamiscreen_shared::domain::Task {
+            id: "dishes".into(),
+            name: "Dishes".into(),
+            minutes: 5,
+            required: false,
+        };
+        store
+            .seed_from_config(&[child], &[task])
+            .await
+            .expect("seed");
+
+        let now = chrono::NaiveDate::from_ymd_opt(2026, 3, 2)
+            .unwrap()
+            .and_hms_opt(19, 0, 0)
+            .unwrap();
+        let instance = |day: u32, hour: u32, penalty_minutes: i32| models::NewChoreInstance {
+            chore_id: "dishes-daily".into(),
+            child_id: "kid1".into(),
+            task_id: "dishes".into(),
+            available_from: now - chrono::Duration::days(1),
+            due_at: chrono::NaiveDate::from_ymd_opt(2026, 3, day)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+            penalty_minutes,
+        };
+        let ids = || vec!["dishes-daily".to_string()];
+        // Due at 18:00; today's instance is overdue but not settled yet
+        let inserted = store
+            .sync_chore_instances(
+                ids(),
+                vec![instance(2, 18, 5), instance(3, 18, 5), instance(4, 18, 5)],
+                now,
+            )
+            .await
+            .expect("sync");
+        assert_eq!(inserted, 3);
+
+        // Moved to 20:00: the future 18:00 instances are replaced, not doubled
+        let inserted = store
+            .sync_chore_instances(ids(), vec![instance(2, 20, 5), instance(3, 20, 5)], now)
+            .await
+            .expect("sync moved");
+        assert_eq!(inserted, 2);
+        let due: Vec<_> = store
+            .list_chore_instances_for_child("kid1", now)
+            .await
+            .expect("list")
+            .into_iter()
+            .map(|i| (i.due_at, i.penalty_minutes))
+            .collect();
+        assert_eq!(
+            due,
+            vec![
+                (instance(2, 18, 5).due_at, 5),
+                (instance(2, 20, 5).due_at, 5),
+                (instance(3, 20, 5).due_at, 5),
+            ]
+        );
+
+        // A new penalty applies to the instances still to come
+        let inserted = store
+            .sync_chore_instances(ids(), vec![instance(2, 20, 9), instance(3, 20, 9)], now)
+            .await
+            .expect("sync penalty");
+        assert_eq!(inserted, 0);
+        let penalties: Vec<_> = store
+            .list_chore_instances_for_child("kid1", now)
+            .await
+            .expect("list")
+            .into_iter()
+            .map(|i| i.penalty_minutes)
+            .collect();
+        assert_eq!(penalties, vec![5, 9, 9]);
+    }
+
+    #[tokio::test]
+    async fn device_requests_move_between_states() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+        let child = gamiscreen_shared::domain::Child {
+            id: "kid1".into(),
+            display_name: "Kid".into(),
+        };
+        store.seed_from_config(&[child], &[]).await.expect("seed");
+
+        let (device, opened) = store.request_device("kid1", "pc").await.expect("request");
+        assert!(opened);
+        assert_eq!(device.status, models::DEVICE_PENDING);
+        let (_, opened) = store.request_device("kid1", "pc").await.expect("request");
+        assert!(!opened, "one request per device");
+        assert_eq!(store.pending_devices_count().await.expect("count"), 1);
+
+        let denied = store
+            .resolve_device_request(device.id, false, "parent")
+            .await
+            .expect("deny")
+            .expect("pending request");
+        assert_eq!(denied.status, models::DEVICE_DENIED);
+        assert!(
+            store
+                .resolve_device_request(device.id, true, "parent")
+                .await
+                .expect("approve")
+                .is_none(),
+            "only pending requests are resolved"
+        );
+        let (device, opened) = store.request_device("kid1", "pc").await.expect("request");
+        assert!(opened, "denied devices can ask again");
+        assert_eq!(device.status, models::DEVICE_PENDING);
+
+        let (before, device) = store
+            .set_device_status("kid1", "pc", models::DEVICE_BLOCKED, "parent")
+            .await
+            .expect("block");
+        assert_eq!(before.as_deref(), Some(models::DEVICE_PENDING));
+        assert_eq!(device.status, models::DEVICE_BLOCKED);
+        let (device, opened) = store.request_device("kid1", "pc").await.expect("request");
+        assert!(!opened, "blocked devices stay blocked");
+        assert_eq!(device.status, models::DEVICE_BLOCKED);
+        assert!(store.unblock_device("kid1", "pc").await.expect("unblock"));
+        assert!(!store.unblock_device("kid1", "pc").await.expect("unblock"));
+        assert!(store.get_device("kid1", "pc").await.expect("get").is_none());
+    }
+
+    #[tokio::test]
+    async fn login_lockouts_double_up_to_the_cap() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join("test.db");
+        let store = crate::storage::Store::connect_sqlite(db_path.to_str().unwrap())
+            .await
+            .expect("connect");
+        let fail = || {
+            store.record_login_failure(
+                models::NewLoginFailure {
+                    username: "kid".into(),
+                    ip: "192.0.2.1".into(),
+                    reason: models::LOGIN_FAILURE_PASSWORD,
+                },
+                2,
+                chrono::Duration::minutes(1),
+                chrono::Duration::minutes(3),
+            )
+        };
+        let lock_minutes = |l: &models::LoginLockout| {
+            (l.locked_until.expect("locked") - Utc::now().naive_utc()).num_seconds() as f64 / 60.0
+        };
+
+        let (state, locked) = fail().await.expect("fail");
+        assert!(!locked);
+        assert_eq!(state.failed_attempts, 1);
+        let (state, locked) = fail().await.expect("fail");
+        assert!(locked);
+        assert_eq!(state.failed_attempts, 0);
+        assert!((0.9..=1.0).contains(&lock_minutes(&state)));
+
+        fail().await.expect("fail");
+        let (state, _) = fail().await.expect("fail");
+        assert!((1.9..=2.0).contains(&lock_minutes(&state)));
+        fail().await.expect("fail");
+        let (state, _) = fail().await.expect("fail");
+        assert_eq!(state.lockouts, 3);
+        assert!((2.9..=3.0).contains(&lock_minutes(&state)), "capped");
+
+        store.clear_login_lockout("kid").await.expect("clear");
+        assert!(store.get_login_lockout("kid").await.expect("get").is_none());
+    }
 }
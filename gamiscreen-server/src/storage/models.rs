@@ -2,8 +2,11 @@ use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 use crate::storage::schema::{
-    balance_transactions, balances, children, push_subscriptions, rewards, task_completions,
-    task_submissions, tasks, usage_minutes,
+    audit_log, balance_transactions, balances, children, chore_instances, devices, login_failures,
+    login_lockouts, minute_transfers, notification_receipts, notifications, push_subscriptions,
+    rewards, savings_transactions, signing_keys, submission_attachments, task_completions,
+    task_submissions, tasks, time_requests, totp_credentials, totp_recovery_codes, usage_minutes,
+    webhook_deliveries,
 };
 
 #[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
@@ -72,6 +75,7 @@ pub struct Session {
     pub username: String,
     pub issued_at: NaiveDateTime,
     pub last_used_at: NaiveDateTime,
+    pub device_id: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -79,6 +83,7 @@ pub struct Session {
 pub struct NewSession<'a> {
     pub jti: &'a str,
     pub username: &'a str,
+    pub device_id: Option<&'a str>,
 }
 
 #[derive(Debug, Clone, Queryable, Identifiable, Associations, Selectable)]
@@ -91,6 +96,7 @@ pub struct TaskCompletion {
     pub task_id: String,
     pub by_username: String,
     pub done_at: NaiveDateTime,
+    pub minutes_awarded: Option<i32>,
 }
 
 #[derive(Insertable)]
@@ -99,6 +105,7 @@ pub struct NewTaskCompletion<'a> {
     pub child_id: &'a str,
     pub task_id: &'a str,
     pub by_username: &'a str,
+    pub minutes_awarded: Option<i32>,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -118,8 +125,18 @@ pub struct TaskSubmission {
     pub child_id: String,
     pub task_id: String,
     pub submitted_at: NaiveDateTime,
+    pub status: String,
+    pub minutes_awarded: Option<i32>,
+    pub comment: Option<String>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
 }
 
+/// Status values stored in `task_submissions.status`.
+pub const SUBMISSION_PENDING: &str = "pending";
+pub const SUBMISSION_APPROVED: &str = "approved";
+pub const SUBMISSION_REJECTED: &str = "rejected";
+
 #[derive(Insertable)]
 #[diesel(table_name = task_submissions)]
 pub struct NewTaskSubmission<'a> {
@@ -162,6 +179,9 @@ pub struct Balance {
     pub child_id: String,
     pub minutes_remaining: i32,
     pub account_balance: i32,
+    pub savings_minutes: i32,
+    pub savings_locked_until: Option<NaiveDateTime>,
+    pub savings_interest_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
@@ -183,3 +203,317 @@ pub struct NewBalanceTransaction<'a> {
     pub description: Option<&'a str>,
     pub related_reward_id: Option<i32>,
 }
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = savings_transactions)]
+pub struct SavingsTransaction {
+    pub id: i32,
+    pub child_id: String,
+    /// One of `deposit`, `withdraw` or `interest`.
+    pub kind: String,
+    /// Signed change to the savings pot (negative for withdrawals).
+    pub amount: i32,
+    /// Savings pot after this entry was applied.
+    pub savings_after: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = savings_transactions)]
+pub struct NewSavingsTransaction<'a> {
+    pub child_id: &'a str,
+    pub kind: &'a str,
+    pub amount: i32,
+    pub savings_after: i32,
+    pub created_at: NaiveDateTime,
+}
+
+/// Status values stored in `minute_transfers.status`.
+pub const TRANSFER_PENDING: &str = "pending";
+pub const TRANSFER_APPROVED: &str = "approved";
+pub const TRANSFER_DENIED: &str = "denied";
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = minute_transfers)]
+pub struct MinuteTransfer {
+    pub id: i32,
+    pub from_child_id: String,
+    pub to_child_id: String,
+    pub minutes: i32,
+    pub note: Option<String>,
+    pub requested_by: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = minute_transfers)]
+pub struct NewMinuteTransfer<'a> {
+    pub from_child_id: &'a str,
+    pub to_child_id: &'a str,
+    pub minutes: i32,
+    pub note: Option<&'a str>,
+    pub requested_by: &'a str,
+    pub status: &'a str,
+    pub resolved_by: Option<&'a str>,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+/// Status values stored in `time_requests.status`.
+pub const TIME_REQUEST_PENDING: &str = "pending";
+pub const TIME_REQUEST_APPROVED: &str = "approved";
+pub const TIME_REQUEST_DENIED: &str = "denied";
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = time_requests)]
+pub struct TimeRequest {
+    pub id: i32,
+    pub child_id: String,
+    pub minutes: i32,
+    pub reason: String,
+    pub status: String,
+    pub is_borrowed: bool,
+    pub created_at: NaiveDateTime,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = time_requests)]
+pub struct NewTimeRequest<'a> {
+    pub child_id: &'a str,
+    pub minutes: i32,
+    pub reason: &'a str,
+}
+
+/// Status values stored in `devices.status`.
+pub const DEVICE_PENDING: &str = "pending";
+pub const DEVICE_APPROVED: &str = "approved";
+pub const DEVICE_DENIED: &str = "denied";
+pub const DEVICE_BLOCKED: &str = "blocked";
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = devices)]
+pub struct Device {
+    pub id: i32,
+    pub child_id: String,
+    pub device_id: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+/// Status values stored in `chore_instances.status`.
+pub const CHORE_OPEN: &str = "open";
+pub const CHORE_DONE: &str = "done";
+pub const CHORE_MISSED: &str = "missed";
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = chore_instances)]
+pub struct ChoreInstance {
+    pub id: i32,
+    pub chore_id: String,
+    pub child_id: String,
+    pub task_id: String,
+    pub available_from: NaiveDateTime,
+    pub due_at: NaiveDateTime,
+    pub penalty_minutes: i32,
+    pub status: String,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = chore_instances)]
+pub struct NewChoreInstance {
+    pub chore_id: String,
+    pub child_id: String,
+    pub task_id: String,
+    pub available_from: NaiveDateTime,
+    pub due_at: NaiveDateTime,
+    pub penalty_minutes: i32,
+}
+
+/// Attachment metadata, without the file contents.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = submission_attachments)]
+pub struct SubmissionAttachmentMeta {
+    pub id: i32,
+    pub submission_id: i32,
+    pub child_id: String,
+    pub content_type: String,
+    pub size_bytes: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = submission_attachments)]
+pub struct NewSubmissionAttachment<'a> {
+    pub submission_id: i32,
+    pub child_id: &'a str,
+    pub content_type: &'a str,
+    pub size_bytes: i32,
+    pub data: &'a [u8],
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = audit_log)]
+pub struct AuditEntry {
+    pub id: i32,
+    pub created_at: NaiveDateTime,
+    pub actor: String,
+    pub role: String,
+    pub request_id: Option<String>,
+    pub action: String,
+    pub child_id: Option<String>,
+    pub target_id: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = audit_log)]
+pub struct NewAuditEntry {
+    pub actor: String,
+    pub role: String,
+    pub request_id: Option<String>,
+    pub action: String,
+    pub child_id: Option<String>,
+    pub target_id: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Status values stored in `webhook_deliveries.status`.
+pub const WEBHOOK_PENDING: &str = "pending";
+pub const WEBHOOK_DELIVERED: &str = "delivered";
+pub const WEBHOOK_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub next_attempt_at: NaiveDateTime,
+    pub delivered_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub webhook_id: String,
+    pub event: String,
+    pub payload: String,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = signing_keys)]
+#[diesel(primary_key(kid))]
+pub struct SigningKeyRow {
+    pub kid: String,
+    pub alg: String,
+    pub private_key: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub retired_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = signing_keys)]
+pub struct NewSigningKey<'a> {
+    pub kid: &'a str,
+    pub alg: &'a str,
+    pub private_key: &'a [u8],
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = totp_credentials)]
+#[diesel(primary_key(username))]
+pub struct TotpCredential {
+    pub username: String,
+    pub secret: Vec<u8>,
+    pub created_at: NaiveDateTime,
+    pub enabled_at: Option<NaiveDateTime>,
+    pub last_step: Option<i64>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = totp_credentials)]
+pub struct NewTotpCredential<'a> {
+    pub username: &'a str,
+    pub secret: &'a [u8],
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = totp_recovery_codes)]
+pub struct NewTotpRecoveryCode<'a> {
+    pub username: &'a str,
+    pub code_hash: &'a str,
+}
+
+/// Values stored in `login_failures.reason`.
+pub const LOGIN_FAILURE_PASSWORD: &str = "password";
+pub const LOGIN_FAILURE_TOTP: &str = "totp";
+
+#[derive(Insertable)]
+#[diesel(table_name = login_failures)]
+pub struct NewLoginFailure {
+    pub username: String,
+    pub ip: String,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = login_lockouts)]
+#[diesel(primary_key(username))]
+pub struct LoginLockout {
+    pub username: String,
+    pub failed_attempts: i32,
+    pub lockouts: i32,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+/// Values stored in `notifications.audience`.
+pub const AUDIENCE_PARENTS: &str = "parents";
+pub const AUDIENCE_CHILD: &str = "child";
+
+#[derive(Debug, Clone, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = notifications)]
+pub struct Notification {
+    pub id: i32,
+    pub audience: String,
+    pub child_id: String,
+    pub kind: String,
+    pub payload: String,
+    pub source_id: Option<i32>,
+    pub created_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = notifications)]
+pub struct NewNotification<'a> {
+    pub audience: &'a str,
+    pub child_id: &'a str,
+    pub kind: &'a str,
+    pub payload: &'a str,
+    pub source_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = notification_receipts)]
+pub struct NotificationReceipt {
+    pub notification_id: i32,
+    pub recipient: String,
+    pub read_at: Option<NaiveDateTime>,
+    pub dismissed_at: Option<NaiveDateTime>,
+}
@@ -4,6 +4,20 @@ diesel::table! {
         child_id -> Text,
         minutes_remaining -> Integer,
         account_balance -> Integer,
+        savings_minutes -> Integer,
+        savings_locked_until -> Nullable<Timestamp>,
+        savings_interest_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    savings_transactions (id) {
+        id -> Integer,
+        child_id -> Text,
+        kind -> Text,
+        amount -> Integer,
+        savings_after -> Integer,
+        created_at -> Timestamp,
     }
 }
 
@@ -18,6 +32,18 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    devices (id) {
+        id -> Integer,
+        child_id -> Text,
+        device_id -> Text,
+        status -> Text,
+        created_at -> Timestamp,
+        resolved_by -> Nullable<Text>,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     children (id) {
         id -> Text,
@@ -52,6 +78,55 @@ diesel::table! {
         username -> Text,
         issued_at -> Timestamp,
         last_used_at -> Timestamp,
+        device_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    signing_keys (kid) {
+        kid -> Text,
+        alg -> Text,
+        private_key -> Binary,
+        created_at -> Timestamp,
+        retired_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    totp_credentials (username) {
+        username -> Text,
+        secret -> Binary,
+        created_at -> Timestamp,
+        enabled_at -> Nullable<Timestamp>,
+        last_step -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    login_failures (id) {
+        id -> Integer,
+        username -> Text,
+        ip -> Text,
+        reason -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    login_lockouts (username) {
+        username -> Text,
+        failed_attempts -> Integer,
+        lockouts -> Integer,
+        locked_until -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    totp_recovery_codes (id) {
+        id -> Integer,
+        username -> Text,
+        code_hash -> Text,
+        used_at -> Nullable<Timestamp>,
     }
 }
 
@@ -70,6 +145,7 @@ diesel::table! {
         task_id -> Text,
         by_username -> Text,
         done_at -> Timestamp,
+        minutes_awarded -> Nullable<Integer>,
     }
 }
 
@@ -79,6 +155,11 @@ diesel::table! {
         child_id -> Text,
         task_id -> Text,
         submitted_at -> Timestamp,
+        status -> Text,
+        minutes_awarded -> Nullable<Integer>,
+        comment -> Nullable<Text>,
+        resolved_by -> Nullable<Text>,
+        resolved_at -> Nullable<Timestamp>,
     }
 }
 
@@ -97,21 +178,153 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    minute_transfers (id) {
+        id -> Integer,
+        from_child_id -> Text,
+        to_child_id -> Text,
+        minutes -> Integer,
+        note -> Nullable<Text>,
+        requested_by -> Text,
+        status -> Text,
+        created_at -> Timestamp,
+        resolved_by -> Nullable<Text>,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    time_requests (id) {
+        id -> Integer,
+        child_id -> Text,
+        minutes -> Integer,
+        reason -> Text,
+        status -> Text,
+        is_borrowed -> Bool,
+        created_at -> Timestamp,
+        resolved_by -> Nullable<Text>,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    chore_instances (id) {
+        id -> Integer,
+        chore_id -> Text,
+        child_id -> Text,
+        task_id -> Text,
+        available_from -> Timestamp,
+        due_at -> Timestamp,
+        penalty_minutes -> Integer,
+        status -> Text,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    submission_attachments (id) {
+        id -> Integer,
+        submission_id -> Integer,
+        child_id -> Text,
+        content_type -> Text,
+        size_bytes -> Integer,
+        data -> Binary,
+        created_at -> Timestamp,
+        delete_after -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    notifications (id) {
+        id -> Integer,
+        audience -> Text,
+        child_id -> Text,
+        kind -> Text,
+        payload -> Text,
+        source_id -> Nullable<Integer>,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    notification_receipts (notification_id, recipient) {
+        notification_id -> Integer,
+        recipient -> Text,
+        read_at -> Nullable<Timestamp>,
+        dismissed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    audit_log (id) {
+        id -> Integer,
+        created_at -> Timestamp,
+        actor -> Text,
+        role -> Text,
+        request_id -> Nullable<Text>,
+        action -> Text,
+        child_id -> Nullable<Text>,
+        target_id -> Nullable<Text>,
+        before -> Nullable<Text>,
+        after -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Integer,
+        webhook_id -> Text,
+        event -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Integer,
+        last_status_code -> Nullable<Integer>,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        next_attempt_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::joinable!(rewards -> children (child_id));
 diesel::joinable!(rewards -> tasks (task_id));
 diesel::joinable!(push_subscriptions -> children (child_id));
 diesel::joinable!(balance_transactions -> children (child_id));
 diesel::joinable!(balance_transactions -> rewards (related_reward_id));
+diesel::joinable!(savings_transactions -> children (child_id));
+diesel::joinable!(time_requests -> children (child_id));
+diesel::joinable!(devices -> children (child_id));
+diesel::joinable!(notifications -> children (child_id));
+diesel::joinable!(submission_attachments -> children (child_id));
+diesel::joinable!(chore_instances -> children (child_id));
+diesel::joinable!(chore_instances -> tasks (task_id));
+diesel::joinable!(notification_receipts -> notifications (notification_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_log,
     balances,
     balance_transactions,
     children,
+    chore_instances,
+    devices,
+    login_failures,
+    login_lockouts,
+    minute_transfers,
+    notification_receipts,
+    notifications,
     rewards,
     tasks,
     sessions,
+    signing_keys,
     task_completions,
     task_submissions,
+    totp_credentials,
+    totp_recovery_codes,
     push_subscriptions,
+    savings_transactions,
+    submission_attachments,
+    time_requests,
     usage_minutes,
+    webhook_deliveries,
 );
@@ -13,7 +13,12 @@ use serde::de::DeserializeOwned;
 use serde_json::Value;
 
 const LOGIN_PATH: &str = "/api/v1/auth/login";
+const PAIR_PATH: &str = "/api/v1/auth/pair";
 const RENEW_PATH: &str = "/api/v1/auth/renew";
+const TOTP_PATH: &str = "/api/v1/auth/totp";
+const TOTP_ENROLL_PATH: &str = "/api/v1/auth/totp/enroll";
+const TOTP_VERIFY_PATH: &str = "/api/v1/auth/totp/verify";
+const TOTP_DISABLE_PATH: &str = "/api/v1/auth/totp/disable";
 const TENANT_ID: &str = "test-tenant";
 
 struct TestServer {
@@ -54,6 +59,7 @@ impl TestServer {
                 Some(to_value(&api::AuthReq {
                     username: username.to_string(),
                     password: password.to_string(),
+                    totp_code: None,
                 })),
                 StatusCode::OK,
             )
@@ -86,6 +92,60 @@ impl TestServer {
         (status, bytes)
     }
 
+    /// POST a multipart form built from `(field name, bytes)` parts.
+    async fn post_multipart(
+        &self,
+        path: &str,
+        token: &str,
+        parts: &[(&str, &[u8])],
+    ) -> (StatusCode, Vec<u8>) {
+        let boundary = "gamiscreen-test-boundary";
+        let mut body = Vec::new();
+        for (name, data) in parts {
+            body.extend_from_slice(
+                format!(
+                    "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"{name}.bin\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+        let resp = self
+            .client
+            .post(format!("{}{}", self.base, path))
+            .bearer_auth(token)
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        let status = resp.status();
+        (status, resp.bytes().await.unwrap().to_vec())
+    }
+
+    /// GET returning the status, content type and raw body.
+    async fn get_bytes(&self, path: &str, token: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.base, path))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        let status = resp.status();
+        let content_type = resp
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        (status, content_type, resp.bytes().await.unwrap().to_vec())
+    }
+
     async fn request_expect_json<T: DeserializeOwned>(
         &self,
         method: &str,
@@ -194,16 +254,41 @@ impl Drop for TestServer {
     }
 }
 
-async fn start_server(
-    tmp_db: &Path,
-) -> Result<(SocketAddr, tokio::task::JoinHandle<()>), std::io::Error> {
+fn default_tasks() -> Vec<Task> {
+    vec![
+        Task {
+            id: "homework".into(),
+            name: "Homework".into(),
+            minutes: 2,
+            required: false,
+        },
+        Task {
+            id: "chores".into(),
+            name: "Chores".into(),
+            minutes: 1,
+            required: false,
+        },
+    ]
+}
+
+fn test_config(tasks: Vec<Task>) -> server::AppConfig {
     let parent_pwd = "secret123";
     let child_pwd = "kidpass";
     let parent_hash = bcrypt::hash(parent_pwd, bcrypt::DEFAULT_COST).unwrap();
     let child_hash = bcrypt::hash(child_pwd, bcrypt::DEFAULT_COST).unwrap();
-    let config = server::AppConfig {
+    server::AppConfig {
         config_version: env!("CARGO_PKG_VERSION").to_string(),
         push: None,
+        savings: None,
+        attachments: None,
+        chores: Vec::new(),
+        webhooks: Vec::new(),
+        mqtt: None,
+        metrics: None,
+        oidc: None,
+        security: None,
+        tls: None,
+        debt_limit_minutes: None,
         tenant_id: TENANT_ID.into(),
         children: vec![
             Child {
@@ -215,105 +300,52 @@ async fn start_server(
                 display_name: "Bob".into(),
             },
         ],
-        tasks: vec![
-            Task {
-                id: "homework".into(),
-                name: "Homework".into(),
-                minutes: 2,
-                required: false,
-            },
-            Task {
-                id: "chores".into(),
-                name: "Chores".into(),
-                minutes: 1,
-                required: false,
-            },
-        ],
-        jwt_secret: "testsecret".into(),
+        tasks,
+        jwt_secret: Some("testsecret".into()),
         users: vec![
             server::UserConfig {
                 username: "parent".into(),
-                password_hash: parent_hash,
+                password_hash: Some(parent_hash),
                 role: server::Role::Parent,
                 child_id: None,
             },
             server::UserConfig {
                 username: "alice".into(),
-                password_hash: child_hash,
+                password_hash: Some(child_hash),
                 role: server::Role::Child,
                 child_id: Some("alice".into()),
             },
         ],
         dev_cors_origin: None,
         listen_port: None,
-    };
-
-    let store = storage::Store::connect_sqlite(tmp_db.to_str().unwrap())
-        .await
-        .expect("db");
-    store
-        .seed_from_config(&config.children, &config.tasks)
-        .await
-        .expect("seed");
-
-    let state = server::AppState::new(config, store);
-    let app = server::router(state);
+    }
+}
 
-    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
-    let addr = listener.local_addr()?;
-    let handle = tokio::spawn(async move {
-        axum::serve(
-            listener,
-            app.into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .await
-        .unwrap();
-    });
-    Ok((addr, handle))
+async fn start_server(
+    tmp_db: &Path,
+) -> Result<(SocketAddr, tokio::task::JoinHandle<()>), std::io::Error> {
+    start_server_with_config(tmp_db, test_config(default_tasks())).await
 }
 
 async fn start_server_with_tasks(
     tmp_db: &Path,
     tasks: Vec<Task>,
 ) -> Result<(SocketAddr, tokio::task::JoinHandle<()>), std::io::Error> {
-    let parent_pwd = "secret123";
-    let child_pwd = "kidpass";
-    let parent_hash = bcrypt::hash(parent_pwd, bcrypt::DEFAULT_COST).unwrap();
-    let child_hash = bcrypt::hash(child_pwd, bcrypt::DEFAULT_COST).unwrap();
-    let config = server::AppConfig {
-        config_version: env!("CARGO_PKG_VERSION").to_string(),
-        push: None,
-        tenant_id: TENANT_ID.into(),
-        children: vec![
-            Child {
-                id: "alice".into(),
-                display_name: "Alice".into(),
-            },
-            Child {
-                id: "bob".into(),
-                display_name: "Bob".into(),
-            },
-        ],
-        tasks,
-        jwt_secret: "testsecret".into(),
-        users: vec![
-            server::UserConfig {
-                username: "parent".into(),
-                password_hash: parent_hash,
-                role: server::Role::Parent,
-                child_id: None,
-            },
-            server::UserConfig {
-                username: "alice".into(),
-                password_hash: child_hash,
-                role: server::Role::Child,
-                child_id: Some("alice".into()),
-            },
-        ],
-        dev_cors_origin: None,
-        listen_port: None,
-    };
+    start_server_with_config(tmp_db, test_config(tasks)).await
+}
 
+async fn start_server_with_config(
+    tmp_db: &Path,
+    config: server::AppConfig,
+) -> Result<(SocketAddr, tokio::task::JoinHandle<()>), std::io::Error> {
+    start_server_with_config_file(tmp_db, config, None).await
+}
+
+async fn start_server_with_config_file(
+    tmp_db: &Path,
+    config: server::AppConfig,
+    config_path: Option<&Path>,
+) -> Result<(SocketAddr, tokio::task::JoinHandle<()>), std::io::Error> {
     let store = storage::Store::connect_sqlite(tmp_db.to_str().unwrap())
         .await
         .expect("db");
@@ -322,7 +354,11 @@ async fn start_server_with_tasks(
         .await
         .expect("seed");
 
-    let state = server::AppState::new(config, store);
+    let mut state = server::AppState::new(config, store);
+    if let Some(path) = config_path {
+        state = state.with_config_path(path);
+    }
+    server::spawn_background_jobs(&state);
     let app = server::router(state);
 
     let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0)).await?;
@@ -358,6 +394,51 @@ impl TestServer {
             _tempdir: dir,
         })
     }
+
+    async fn spawn_with_config(config: server::AppConfig) -> Option<Self> {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let (addr, handle) = match start_server_with_config(&db_path, config).await {
+            Ok(v) => v,
+            Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                eprintln!("Skipping test due to sandbox restrictions: {e}");
+                return None;
+            }
+            Err(e) => panic!("failed to start server: {e}"),
+        };
+        Some(Self {
+            base: format!("http://{}", addr),
+            client: Client::new(),
+            handle,
+            db_path: db_path.clone(),
+            _tempdir: dir,
+        })
+    }
+}
+
+impl TestServer {
+    /// Serve the config file at `config_path`, so it can be reloaded.
+    async fn spawn_from_file(config_path: &Path) -> Option<Self> {
+        let config = server::AppConfig::load_from_path(config_path).expect("load config");
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let (addr, handle) =
+            match start_server_with_config_file(&db_path, config, Some(config_path)).await {
+                Ok(v) => v,
+                Err(e) if e.kind() == ErrorKind::PermissionDenied => {
+                    eprintln!("Skipping test due to sandbox restrictions: {e}");
+                    return None;
+                }
+                Err(e) => panic!("failed to start server: {e}"),
+            };
+        Some(Self {
+            base: format!("http://{}", addr),
+            client: Client::new(),
+            handle,
+            db_path: db_path.clone(),
+            _tempdir: dir,
+        })
+    }
 }
 
 fn tenant_path(suffix: &str) -> String {
@@ -666,8 +747,16 @@ async fn parent_access_control() {
             StatusCode::OK,
         )
         .await;
-    let submission_id = notifications[0].id;
-    assert_eq!(notifications[0].task_id, "homework");
+    let api::NotificationPayload::TaskSubmission {
+        submission_id,
+        ref task_id,
+        ..
+    } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
+    assert_eq!(task_id, "homework");
+    assert_eq!(notifications[0].kind, "task_submission");
     assert_eq!(notifications[0].child_id, "alice");
 
     server
@@ -723,7 +812,13 @@ async fn parent_access_control() {
             StatusCode::OK,
         )
         .await;
-    let discard_id = notifications[0].id;
+    let api::NotificationPayload::TaskSubmission {
+        submission_id: discard_id,
+        ..
+    } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
 
     server
         .request_expect_status(
@@ -833,7 +928,7 @@ async fn child_access_control() {
         .request_expect_json(
             "POST",
             &tenant_path("children/alice/register"),
-            Some(&child_token),
+            Some(&parent_token),
             Some(to_value(&api::ClientRegisterReq {
                 child_id: None,
                 device_id: "dev1".to_string(),
@@ -900,6 +995,18 @@ async fn child_access_control() {
             None,
             Some(&child_token),
         ),
+        (
+            "POST",
+            tenant_path("notifications/devices/1/approve"),
+            None,
+            Some(&child_token),
+        ),
+        (
+            "POST",
+            tenant_path("children/alice/devices/dev1/block"),
+            None,
+            Some(&child_token),
+        ),
         (
             "POST",
             tenant_path("notifications/task-submissions/1/discard"),
@@ -1018,13 +1125,13 @@ async fn device_session_idle_timeout() {
         return;
     };
 
-    // Login as a child and register a device
-    let child_token = server.login("alice", "kidpass").await;
+    // Register a device for the child
+    let parent_token = server.login("parent", "secret123").await;
     let register_resp: api::ClientRegisterResp = server
         .request_expect_json(
             "POST",
             &tenant_path("children/alice/register"),
-            Some(&child_token),
+            Some(&parent_token),
             Some(to_value(&api::ClientRegisterReq {
                 child_id: None,
                 device_id: "test-device".to_string(),
@@ -1120,13 +1227,13 @@ async fn authenticated_request_advances_last_used_at_for_device() {
         return;
     };
 
-    // Login as a child and register a device
-    let child_token = server.login("alice", "kidpass").await;
+    // Register a device for the child
+    let parent_token = server.login("parent", "secret123").await;
     let register_resp: api::ClientRegisterResp = server
         .request_expect_json(
             "POST",
             &tenant_path("children/alice/register"),
-            Some(&child_token),
+            Some(&parent_token),
             Some(to_value(&api::ClientRegisterReq {
                 child_id: None,
                 device_id: "test-device".to_string(),
@@ -1193,6 +1300,7 @@ async fn login_rate_limit_enforced() {
                 Some(to_value(&api::AuthReq {
                     username: "wrong".to_string(),
                     password: "wrong".to_string(),
+                    totp_code: None,
                 })),
             )
             .await;
@@ -1206,6 +1314,7 @@ async fn login_rate_limit_enforced() {
             Some(to_value(&api::AuthReq {
                 username: "parent".to_string(),
                 password: "secret123".to_string(),
+                totp_code: None,
             })),
             StatusCode::TOO_MANY_REQUESTS,
         )
@@ -1311,6 +1420,48 @@ async fn test_borrowing_flow() {
     assert_eq!(earn_resp3.balance, 0);
 }
 
+#[tokio::test]
+async fn test_borrowing_past_the_debt_limit_notifies_parents() {
+    let mut config = test_config(Vec::new());
+    config.debt_limit_minutes = Some(15);
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let debt_notifications = || async {
+        let items: Vec<api::NotificationItemDto> = server
+            .request_expect_json(
+                "GET",
+                &tenant_path("notifications"),
+                Some(&parent),
+                None,
+                StatusCode::OK,
+            )
+            .await;
+        items
+            .into_iter()
+            .filter(|n| n.kind == "debt_limit_reached")
+            .collect::<Vec<_>>()
+    };
+
+    let lend = reward_req("alice", None, Some(10), None, Some(true));
+    parent_reward(&server, &parent, "alice", &lend).await;
+    assert!(debt_notifications().await.is_empty());
+
+    // Only the loan that crosses the limit notifies
+    parent_reward(&server, &parent, "alice", &lend).await;
+    parent_reward(&server, &parent, "alice", &lend).await;
+    let items = debt_notifications().await;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].child_id, "alice");
+    match &items[0].payload {
+        api::NotificationPayload::DebtLimitReached { balance, limit } => {
+            assert_eq!((*balance, *limit), (-20, 15));
+        }
+        other => panic!("unexpected payload: {other:?}"),
+    }
+}
+
 #[tokio::test]
 async fn test_penalty_borrow_earn_remaining_converges_to_balance() {
     // Test: penalty -> borrow -> earn with new account_balance system.
@@ -1528,7 +1679,7 @@ async fn get_remaining(server: &TestServer, token: &str, child_id: &str) -> api:
 
 async fn register_device(
     server: &TestServer,
-    child_token: &str,
+    parent_token: &str,
     child_id: &str,
     device_id: &str,
 ) -> api::ClientRegisterResp {
@@ -1536,7 +1687,7 @@ async fn register_device(
         .request_expect_json(
             "POST",
             &tenant_path(&format!("children/{child_id}/register")),
-            Some(child_token),
+            Some(parent_token),
             Some(to_value(&api::ClientRegisterReq {
                 child_id: None,
                 device_id: device_id.to_string(),
@@ -1608,7 +1759,6 @@ async fn test_scenario_fresh_start_normal_day() {
         return;
     };
     let parent = server.login("parent", "secret123").await;
-    let child = server.login("alice", "kidpass").await;
 
     // Fresh start: zero everything
     let rem = get_remaining(&server, &parent, "alice").await;
@@ -1628,7 +1778,7 @@ async fn test_scenario_fresh_start_normal_day() {
     assert_eq!(resp.balance, 0); // no borrowing
 
     // Register device and send heartbeat for 2 past minutes
-    let dev = register_device(&server, &child, "alice", "laptop1").await;
+    let dev = register_device(&server, &parent, "alice", "laptop1").await;
     let m = now_minute();
     let hb = send_heartbeat(&server, &dev.token, "alice", "laptop1", &[m - 2, m - 1]).await;
     assert_eq!(hb.remaining_minutes, 0);
@@ -1671,7 +1821,6 @@ async fn test_scenario_borrowing_time() {
         return;
     };
     let parent = server.login("parent", "secret123").await;
-    let child = server.login("alice", "kidpass").await;
 
     // Borrow 20
     let resp = parent_reward(
@@ -1685,7 +1834,7 @@ async fn test_scenario_borrowing_time() {
     assert_eq!(resp.balance, -20); // debt from borrowing
 
     // Use 10 minutes via heartbeat
-    let dev = register_device(&server, &child, "alice", "dev1").await;
+    let dev = register_device(&server, &parent, "alice", "dev1").await;
     let m = now_minute();
     let timestamps: Vec<i64> = (1..=10).map(|i| m - i).collect();
     let hb = send_heartbeat(&server, &dev.token, "alice", "dev1", &timestamps).await;
@@ -1929,7 +2078,6 @@ async fn test_scenario_heartbeat_idempotent() {
         return;
     };
     let parent = server.login("parent", "secret123").await;
-    let child = server.login("alice", "kidpass").await;
 
     // Earn 10 custom
     parent_reward(
@@ -1940,7 +2088,7 @@ async fn test_scenario_heartbeat_idempotent() {
     )
     .await;
 
-    let dev = register_device(&server, &child, "alice", "pc1").await;
+    let dev = register_device(&server, &parent, "alice", "pc1").await;
     let m = now_minute() - 5;
 
     // First heartbeat with minute m
@@ -2050,3 +2198,3439 @@ async fn test_scenario_reward_history_borrowed_vs_earned() {
         "earned reward should have is_borrowed=false"
     );
 }
+
+#[tokio::test]
+async fn test_savings_deposit_withdraw_and_lock_in() {
+    let mut config = test_config(default_tasks());
+    config.savings = Some(server::SavingsConfig {
+        enabled: true,
+        weekly_interest_percent: 5.0,
+        lock_in_days: 7,
+    });
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(30), None, None),
+    )
+    .await;
+
+    // Child cannot save more than is spendable
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/savings/deposit"),
+            Some(&child),
+            Some(to_value(&api::SavingsReq { minutes: 31 })),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+
+    let savings: api::SavingsDto = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("children/alice/savings/deposit"),
+            Some(&child),
+            Some(to_value(&api::SavingsReq { minutes: 20 })),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(savings.savings_minutes, 20);
+    assert_eq!(savings.remaining_minutes, 10);
+    assert_eq!(savings.weekly_interest_percent, 5.0);
+    assert!(savings.locked_until.is_some(), "deposit starts lock-in");
+    assert!(savings.next_interest_at.is_some());
+    assert_eq!(
+        get_remaining(&server, &parent, "alice")
+            .await
+            .remaining_minutes,
+        10
+    );
+
+    // Child cannot touch another child's savings
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("children/bob/savings"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+
+    // Lock-in applies to the child...
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/savings/withdraw"),
+            Some(&child),
+            Some(to_value(&api::SavingsReq { minutes: 5 })),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+
+    // ...but a parent may break it
+    let savings: api::SavingsDto = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("children/alice/savings/withdraw"),
+            Some(&parent),
+            Some(to_value(&api::SavingsReq { minutes: 5 })),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(savings.savings_minutes, 15);
+    assert_eq!(savings.remaining_minutes, 15);
+
+    let history: Vec<api::SavingsTransactionDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children/alice/savings/history"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let entries: Vec<(&str, i32, i32)> = history
+        .iter()
+        .map(|t| (t.kind.as_str(), t.amount, t.savings_after))
+        .collect();
+    assert_eq!(entries, vec![("withdraw", -5, 15), ("deposit", 20, 20)]);
+}
+
+#[tokio::test]
+async fn test_savings_disabled_rejects_deposit() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(10), None, None),
+    )
+    .await;
+
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/savings/deposit"),
+            Some(&child),
+            Some(to_value(&api::SavingsReq { minutes: 5 })),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    let savings: api::SavingsDto = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children/alice/savings"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(savings.savings_minutes, 0);
+    assert_eq!(savings.remaining_minutes, 10);
+}
+
+#[tokio::test]
+async fn test_sibling_transfer_flow() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(20), None, None),
+    )
+    .await;
+
+    let transfer_path = tenant_path("children/alice/transfers");
+    let transfer = |minutes: i32| {
+        to_value(&api::TransferReq {
+            to_child_id: "bob".into(),
+            minutes,
+            note: Some("thanks for the help".into()),
+        })
+    };
+
+    // Cannot give more than is available, nor give on behalf of a sibling
+    server
+        .request_expect_status(
+            "POST",
+            &transfer_path,
+            Some(&child),
+            Some(transfer(21)),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/bob/transfers"),
+            Some(&child),
+            Some(to_value(&api::TransferReq {
+                to_child_id: "alice".into(),
+                minutes: 1,
+                note: None,
+            })),
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+
+    // Child-initiated transfer waits for a parent
+    let resp: api::TransferResp = server
+        .request_expect_json(
+            "POST",
+            &transfer_path,
+            Some(&child),
+            Some(transfer(5)),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(resp.status, "pending");
+    assert_eq!(resp.remaining_minutes, 20);
+
+    let count: api::NotificationsCountDto = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications/count"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(count.count, 1);
+    let notifications: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, "transfer");
+    let api::NotificationPayload::Transfer {
+        transfer_id,
+        minutes,
+        ref to_child_id,
+        ref note,
+        ..
+    } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
+    assert_eq!(transfer_id, resp.transfer_id);
+    assert_eq!(minutes, 5);
+    assert_eq!(to_child_id, "bob");
+    assert_eq!(note.as_deref(), Some("thanks for the help"));
+
+    // Children cannot approve their own transfers
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/transfers/{}/approve",
+                resp.transfer_id
+            )),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/transfers/{}/approve",
+                resp.transfer_id
+            )),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        get_remaining(&server, &parent, "alice")
+            .await
+            .remaining_minutes,
+        15
+    );
+    assert_eq!(
+        get_remaining(&server, &parent, "bob")
+            .await
+            .remaining_minutes,
+        5
+    );
+
+    // Approving twice is rejected
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/transfers/{}/approve",
+                resp.transfer_id
+            )),
+            Some(&parent),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+
+    // Discarded transfers do not move minutes
+    let pending: api::TransferResp = server
+        .request_expect_json(
+            "POST",
+            &transfer_path,
+            Some(&child),
+            Some(transfer(3)),
+            StatusCode::OK,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/transfers/{}/discard",
+                pending.transfer_id
+            )),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        get_remaining(&server, &parent, "alice")
+            .await
+            .remaining_minutes,
+        15
+    );
+
+    // Parent-initiated transfers apply immediately
+    let direct: api::TransferResp = server
+        .request_expect_json(
+            "POST",
+            &transfer_path,
+            Some(&parent),
+            Some(transfer(10)),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(direct.status, "approved");
+    assert_eq!(direct.remaining_minutes, 5);
+    let bob = get_remaining(&server, &parent, "bob").await;
+    assert_eq!(bob.remaining_minutes, 15);
+    assert_eq!(bob.balance, 0, "gifts do not touch the debt balance");
+
+    let count: api::NotificationsCountDto = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications/count"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(count.count, 0);
+}
+
+#[tokio::test]
+async fn test_time_request_flow() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    let requests_path = tenant_path("children/alice/time-requests");
+    let ask = |minutes: i32, reason: &str| {
+        to_value(&api::TimeRequestReq {
+            minutes,
+            reason: reason.into(),
+        })
+    };
+
+    // Only the child itself may ask, and it must say why
+    server
+        .request_expect_status(
+            "POST",
+            &requests_path,
+            Some(&parent),
+            Some(ask(10, "finish the level")),
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/bob/time-requests"),
+            Some(&child),
+            Some(ask(10, "finish the level")),
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &requests_path,
+            Some(&child),
+            Some(ask(10, "   ")),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &requests_path,
+            Some(&child),
+            Some(ask(0, "finish the level")),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+
+    let loan: api::TimeRequestDto = server
+        .request_expect_json(
+            "POST",
+            &requests_path,
+            Some(&child),
+            Some(ask(10, "finish the level")),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(loan.status, "pending");
+
+    let notifications: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, "time_request");
+    let api::NotificationPayload::TimeRequest {
+        request_id,
+        minutes,
+        ref reason,
+    } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
+    assert_eq!(request_id, loan.id);
+    assert_eq!(minutes, 10);
+    assert_eq!(reason, "finish the level");
+
+    // Children cannot approve their own requests
+    let approve_path = tenant_path(&format!("notifications/time-requests/{}/approve", loan.id));
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            Some(to_value(&api::TimeRequestApproveReq { is_borrowed: true })),
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    let remaining = get_remaining(&server, &parent, "alice").await;
+    assert_eq!(remaining.remaining_minutes, 10);
+    assert_eq!(remaining.balance, -10);
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+
+    // A gift is approved without a body; a denied request grants nothing
+    let gift: api::TimeRequestDto = server
+        .request_expect_json(
+            "POST",
+            &requests_path,
+            Some(&child),
+            Some(ask(5, "movie ends soon")),
+            StatusCode::OK,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!("notifications/time-requests/{}/approve", gift.id)),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    let denied: api::TimeRequestDto = server
+        .request_expect_json(
+            "POST",
+            &requests_path,
+            Some(&child),
+            Some(ask(60, "please")),
+            StatusCode::OK,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/time-requests/{}/discard",
+                denied.id
+            )),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    // Gifted minutes repay the earlier loan first, like any other earnings
+    let remaining = get_remaining(&server, &parent, "alice").await;
+    assert_eq!(remaining.remaining_minutes, 10);
+    assert_eq!(remaining.balance, -5);
+
+    let history: Vec<api::TimeRequestDto> = server
+        .request_expect_json("GET", &requests_path, Some(&child), None, StatusCode::OK)
+        .await;
+    let statuses: Vec<(&str, bool)> = history
+        .iter()
+        .map(|r| (r.status.as_str(), r.is_borrowed))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![("denied", false), ("approved", false), ("approved", true)]
+    );
+    assert!(history.iter().all(|r| r.resolved_at.is_some()));
+
+    // Pending requests per child are capped
+    for _ in 0..3 {
+        server
+            .request_expect_status(
+                "POST",
+                &requests_path,
+                Some(&child),
+                Some(ask(5, "again")),
+                StatusCode::OK,
+            )
+            .await;
+    }
+    server
+        .request_expect_status(
+            "POST",
+            &requests_path,
+            Some(&child),
+            Some(ask(5, "again")),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    let count: api::NotificationsCountDto = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications/count"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(count.count, 3);
+}
+
+#[tokio::test]
+async fn test_notification_inbox() {
+    let mut config = test_config(default_tasks());
+    let mut second_parent = config.users[0].clone();
+    second_parent.username = "parent2".into();
+    config.users.push(second_parent);
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let other_parent = server.login("parent2", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    let count = |token: String, path: &'static str| {
+        let server = &server;
+        async move {
+            server
+                .request_expect_json::<api::NotificationsCountDto>(
+                    "GET",
+                    &tenant_path(path),
+                    Some(&token),
+                    None,
+                    StatusCode::OK,
+                )
+                .await
+        }
+    };
+
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/tasks/homework/submit"),
+            Some(&child),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    let parent_count = count(parent.clone(), "notifications/count").await;
+    assert_eq!(parent_count.count, 1);
+    assert_eq!(parent_count.by_kind.get("task_submission"), Some(&1));
+
+    // Read state is tracked per parent
+    let notifications: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let notification = &notifications[0];
+    assert!(!notification.read && !notification.resolved);
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!("notifications/{}/read", notification.id)),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(count(parent.clone(), "notifications/count").await.count, 0);
+    assert_eq!(
+        count(other_parent.clone(), "notifications/count")
+            .await
+            .count,
+        1
+    );
+    let unread: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications?unread_only=true"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert!(unread.is_empty());
+
+    // Children have no access to the parents' inbox
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!("notifications/{}/read", notification.id)),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "children/alice/notifications/{}/read",
+                notification.id
+            )),
+            Some(&child),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+
+    // Approving resolves the parent notification and tells the child
+    let api::NotificationPayload::TaskSubmission { submission_id, .. } = notification.payload
+    else {
+        panic!("unexpected payload: {:?}", notification.payload);
+    };
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/task-submissions/{submission_id}/approve"
+            )),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        count(other_parent.clone(), "notifications/count")
+            .await
+            .count,
+        0
+    );
+    let history: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications?include_resolved=true"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(history.len(), 1);
+    assert!(history[0].resolved);
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(15), Some("movie night"), None),
+    )
+    .await;
+    let child_count = count(child.clone(), "children/alice/notifications/count").await;
+    assert_eq!(child_count.count, 2);
+    assert_eq!(child_count.by_kind.get("task_reviewed"), Some(&1));
+    assert_eq!(child_count.by_kind.get("reward_granted"), Some(&1));
+
+    let inbox_path = tenant_path("children/alice/notifications");
+    let first_page: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &format!("{inbox_path}?per_page=1"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(first_page.len(), 1);
+    assert_eq!(first_page[0].kind, "reward_granted");
+    let second_page: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &format!("{inbox_path}?per_page=1&page=2"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(second_page.len(), 1);
+    match &second_page[0].payload {
+        api::NotificationPayload::TaskReviewed {
+            task_id,
+            approved,
+            by,
+            ..
+        } => {
+            assert_eq!(task_id, "homework");
+            assert!(*approved);
+            assert_eq!(by, "parent");
+        }
+        other => panic!("unexpected payload: {other:?}"),
+    }
+
+    // Parents may look at a child's inbox but not change it; siblings may not look
+    let as_parent: Vec<api::NotificationItemDto> = server
+        .request_expect_json("GET", &inbox_path, Some(&parent), None, StatusCode::OK)
+        .await;
+    assert_eq!(as_parent.len(), 2);
+    server
+        .request_expect_status(
+            "POST",
+            &format!("{inbox_path}/read-all"),
+            Some(&parent),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("children/bob/notifications"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+
+    server
+        .request_expect_status(
+            "POST",
+            &format!("{inbox_path}/{}/dismiss", first_page[0].id),
+            Some(&child),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &format!("{inbox_path}/read-all"),
+            Some(&child),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        count(child.clone(), "children/alice/notifications/count")
+            .await
+            .count,
+        0
+    );
+    let remaining: Vec<api::NotificationItemDto> = server
+        .request_expect_json("GET", &inbox_path, Some(&child), None, StatusCode::OK)
+        .await;
+    assert_eq!(remaining.len(), 1);
+    assert!(remaining[0].read);
+    assert_eq!(remaining[0].kind, "task_reviewed");
+}
+
+#[tokio::test]
+async fn test_submission_photo_proof() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    let submit_path = tenant_path("children/alice/tasks/homework/submit");
+    let png: &[u8] = b"\x89PNG\r\n\x1a\nnot really a picture";
+
+    // Only images, only in the photo field, at most three per submission
+    let (status, _) = server
+        .post_multipart(&submit_path, &child, &[("photo", b"<html></html>")])
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server
+        .post_multipart(&submit_path, &child, &[("file", png)])
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = server
+        .post_multipart(&submit_path, &child, &[("photo", png); 4])
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let pending: api::NotificationsCountDto = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications/count"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(pending.count, 0, "rejected uploads create no submission");
+
+    let (status, body) = server
+        .post_multipart(&submit_path, &child, &[("photo", png)])
+        .await;
+    assert_eq!(
+        status,
+        StatusCode::NO_CONTENT,
+        "{}",
+        String::from_utf8_lossy(&body)
+    );
+
+    let notifications: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(notifications.len(), 1);
+    let attachment = &notifications[0].attachments[0];
+    assert_eq!(attachment.content_type, "image/png");
+    assert_eq!(attachment.size_bytes as usize, png.len());
+
+    let photo_path = tenant_path(&format!("attachments/{}", attachment.id));
+    let (status, content_type, data) = server.get_bytes(&photo_path, &parent).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("image/png"));
+    assert_eq!(data, png);
+    let (status, _, _) = server.get_bytes(&photo_path, &child).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Without a retention period the photo goes away with the submission
+    let api::NotificationPayload::TaskSubmission { submission_id, .. } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/task-submissions/{submission_id}/approve"
+            )),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    let (status, _, _) = server.get_bytes(&photo_path, &parent).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_submission_partial_credit_and_rejection() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    let submit = |task: &'static str| {
+        let server = &server;
+        let child = child.clone();
+        async move {
+            server
+                .request_expect_status(
+                    "POST",
+                    &tenant_path(&format!("children/alice/tasks/{task}/submit")),
+                    Some(&child),
+                    None,
+                    StatusCode::NO_CONTENT,
+                )
+                .await;
+        }
+    };
+    let history = |token: String| {
+        let server = &server;
+        async move {
+            server
+                .request_expect_json::<Vec<api::TaskSubmissionDto>>(
+                    "GET",
+                    &tenant_path("children/alice/submissions"),
+                    Some(&token),
+                    None,
+                    StatusCode::OK,
+                )
+                .await
+        }
+    };
+
+    // Homework is worth 2 minutes; grant half of it
+    submit("homework").await;
+    let homework_id = history(child.clone()).await[0].id;
+    let approve_path = tenant_path(&format!(
+        "notifications/task-submissions/{homework_id}/approve"
+    ));
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            Some(to_value(&api::SubmissionApproveReq {
+                minutes: Some(3),
+                comment: None,
+            })),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            Some(to_value(&api::SubmissionApproveReq {
+                minutes: Some(1),
+                comment: Some("Only half of it".into()),
+            })),
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        get_remaining(&server, &child, "alice")
+            .await
+            .remaining_minutes,
+        1
+    );
+    // Already resolved: a second approval grants nothing
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        get_remaining(&server, &child, "alice")
+            .await
+            .remaining_minutes,
+        1
+    );
+
+    submit("chores").await;
+    let chores_id = history(child.clone()).await[0].id;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/task-submissions/{chores_id}/discard"
+            )),
+            Some(&parent),
+            Some(to_value(&api::SubmissionRejectReq {
+                comment: Some("  The bed is not made  ".into()),
+            })),
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    assert_eq!(
+        get_remaining(&server, &child, "alice")
+            .await
+            .remaining_minutes,
+        1
+    );
+
+    // Both outcomes stay in the history
+    let rows = history(parent.clone()).await;
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].task_id, "chores");
+    assert_eq!(rows[0].status, "rejected");
+    assert_eq!(rows[0].minutes_awarded, None);
+    assert_eq!(rows[0].comment.as_deref(), Some("The bed is not made"));
+    assert_eq!(rows[0].resolved_by.as_deref(), Some("parent"));
+    assert_eq!(rows[1].task_id, "homework");
+    assert_eq!(rows[1].status, "approved");
+    assert_eq!(rows[1].task_minutes, 2);
+    assert_eq!(rows[1].minutes_awarded, Some(1));
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("children/bob/submissions"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+
+    // The child is told about both decisions, with the comments
+    let inbox: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children/alice/notifications"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let reviews: Vec<(i32, bool, Option<String>)> = inbox
+        .into_iter()
+        .filter_map(|n| match n.payload {
+            api::NotificationPayload::TaskReviewed {
+                minutes,
+                approved,
+                comment,
+                ..
+            } => Some((minutes, approved, comment)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        reviews,
+        vec![
+            (0, false, Some("The bed is not made".to_string())),
+            (1, true, Some("Only half of it".to_string())),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_scheduled_chore_listed_and_completed() {
+    let mut config = test_config(default_tasks());
+    let due_time = (Utc::now() + chrono::Duration::hours(2)).time();
+    config.chores = vec![server::ChoreConfig {
+        id: "chores-daily".into(),
+        task_id: "chores".into(),
+        child_id: "alice".into(),
+        repeat: server::ChoreRepeat::Daily,
+        weekdays: Vec::new(),
+        due_date: None,
+        due_time,
+        penalty_minutes: 5,
+    }];
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+
+    let chores_of = |tasks: Vec<api::TaskWithStatusDto>| {
+        tasks
+            .into_iter()
+            .find(|t| t.id == "chores")
+            .expect("chores task listed")
+            .chores
+    };
+    // The scheduling pass runs right after startup
+    let mut instances = Vec::new();
+    for _ in 0..50 {
+        let tasks: Vec<api::TaskWithStatusDto> = server
+            .request_expect_json(
+                "GET",
+                &tenant_path("children/alice/tasks"),
+                Some(&child),
+                None,
+                StatusCode::OK,
+            )
+            .await;
+        instances = chores_of(tasks);
+        if !instances.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert!(instances.len() >= 7, "a week of instances is scheduled");
+    let next = &instances[0];
+    assert_eq!(next.status, "open");
+    assert!(next.available, "today's instance can be completed");
+    assert!(!next.overdue);
+    assert_eq!(next.penalty_minutes, 5);
+    assert!(
+        !instances[1].available,
+        "tomorrow's instance is not yet due"
+    );
+    let first_id = next.id;
+
+    // Completing the task completes the current instance only
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", Some("chores"), None, None, None),
+    )
+    .await;
+    let tasks: Vec<api::TaskWithStatusDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children/alice/tasks"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let instances = chores_of(tasks);
+    assert!(instances.iter().all(|c| c.id != first_id));
+    assert!(instances.iter().all(|c| !c.available && !c.overdue));
+    assert_eq!(
+        get_remaining(&server, &parent, "alice")
+            .await
+            .remaining_minutes,
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_audit_log_records_parent_actions() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+
+    // A caller-supplied request id is kept with the entry
+    let resp = server
+        .client
+        .post(format!(
+            "{}{}",
+            server.base,
+            tenant_path("children/alice/reward")
+        ))
+        .bearer_auth(&parent)
+        .header("x-request-id", "audit-test-1")
+        .json(&reward_req("alice", None, Some(10), Some("Bonus"), None))
+        .send()
+        .await
+        .expect("reward request");
+    assert_eq!(resp.status(), StatusCode::OK);
+    parent_reward(
+        &server,
+        &parent,
+        "bob",
+        &reward_req("bob", None, Some(-3), Some("Penalty"), None),
+    )
+    .await;
+
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/tasks/homework/submit"),
+            Some(&child),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    let submissions: Vec<api::TaskSubmissionDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children/alice/submissions"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!(
+                "notifications/task-submissions/{}/discard",
+                submissions[0].id
+            )),
+            Some(&parent),
+            Some(to_value(&api::SubmissionRejectReq {
+                comment: Some("Not yet".into()),
+            })),
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+
+    let entries: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(
+        actions,
+        vec!["submission.reject", "reward.penalty", "reward.grant"]
+    );
+    assert!(entries.iter().all(|e| e.actor == "parent"));
+    assert!(
+        entries
+            .iter()
+            .all(|e| e.role == gamiscreen_shared::auth::Role::Parent)
+    );
+
+    let grant = &entries[2];
+    assert_eq!(grant.request_id.as_deref(), Some("audit-test-1"));
+    assert_eq!(grant.child_id.as_deref(), Some("alice"));
+    let before = grant.before.as_ref().expect("before value");
+    let after = grant.after.as_ref().expect("after value");
+    assert_eq!(
+        after["remaining_minutes"].as_i64().unwrap(),
+        before["remaining_minutes"].as_i64().unwrap() + 10
+    );
+    assert_eq!(after["description"], "Bonus");
+
+    let reject = &entries[0];
+    assert_eq!(reject.target_id, Some(submissions[0].id.to_string()));
+    assert_eq!(reject.before.as_ref().unwrap()["status"], "pending");
+    assert_eq!(reject.after.as_ref().unwrap()["status"], "rejected");
+    assert_eq!(reject.after.as_ref().unwrap()["comment"], "Not yet");
+
+    // Filters
+    let bob_entries: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?child_id=bob"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(bob_entries.len(), 1);
+    assert_eq!(bob_entries[0].action, "reward.penalty");
+    let grants: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?action=reward.grant&actor=parent"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(grants.len(), 1);
+    let future: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?since=2999-01-01T00:00:00Z"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert!(future.is_empty());
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("audit?since=yesterday"),
+            Some(&parent),
+            None,
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+
+    // Parents only
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("audit"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+}
+
+type ReceivedHooks = std::sync::Arc<tokio::sync::Mutex<Vec<(axum::http::HeaderMap, String)>>>;
+
+/// Start an HTTP endpoint that records webhook POSTs; `/ok` answers 200 and
+/// `/fail` answers 500.
+async fn spawn_webhook_receiver() -> (SocketAddr, ReceivedHooks) {
+    use axum::extract::State;
+    use axum::routing::post;
+
+    let received: ReceivedHooks = Default::default();
+    let record = |status: StatusCode| {
+        move |State(received): State<ReceivedHooks>,
+              headers: axum::http::HeaderMap,
+              body: String| async move {
+            received.lock().await.push((headers, body));
+            status
+        }
+    };
+    let app = axum::Router::new()
+        .route("/ok", post(record(StatusCode::OK)))
+        .route("/fail", post(record(StatusCode::INTERNAL_SERVER_ERROR)))
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))
+        .await
+        .expect("bind webhook receiver");
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (addr, received)
+}
+
+#[tokio::test]
+async fn test_webhooks_deliver_signed_events() {
+    use hmac::{Hmac, Mac};
+
+    let (addr, received) = spawn_webhook_receiver().await;
+    let hook = |id: &str, path: &str, events: &[&str]| server::WebhookConfig {
+        id: id.into(),
+        url: format!("http://{addr}/{path}"),
+        secret: format!("{id}-secret"),
+        events: events.iter().map(|e| e.to_string()).collect(),
+    };
+    let mut config = test_config(default_tasks());
+    config.webhooks = vec![
+        hook("all", "ok", &[]),
+        hook("rewards", "ok", &["reward_granted"]),
+        hook("broken", "fail", &["device_registered"]),
+    ];
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(5), Some("Bonus"), None),
+    )
+    .await;
+
+    // remaining_updated, reward_granted and notification_created for "all",
+    // reward_granted for "rewards"
+    let mut deliveries = Vec::new();
+    for _ in 0..100 {
+        deliveries = received.lock().await.clone();
+        if deliveries.len() >= 4 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(deliveries.len(), 4, "unexpected deliveries: {deliveries:?}");
+    let mut events = Vec::new();
+    for (headers, body) in &deliveries {
+        let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+        let event = header("x-gamiscreen-event");
+        let payload: Value = serde_json::from_str(body).expect("JSON body");
+        assert_eq!(payload["event"], event.as_str());
+        // Both webhooks share the receiver; the signature tells them apart
+        let signature = header("x-gamiscreen-signature");
+        let signed_by = ["all", "rewards"].into_iter().find(|id| {
+            let mut mac =
+                Hmac::<sha2::Sha256>::new_from_slice(format!("{id}-secret").as_bytes()).unwrap();
+            mac.update(format!("{}.{}", header("x-gamiscreen-timestamp"), body).as_bytes());
+            signature == format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+        });
+        let signed_by = signed_by.expect("valid signature");
+        if event == "reward_granted" {
+            assert_eq!(payload["data"]["minutes"], 5);
+            assert_eq!(payload["data"]["by"], "parent");
+        }
+        events.push((signed_by, event));
+    }
+    events.sort();
+    assert_eq!(
+        events,
+        vec![
+            ("all", "notification_created".to_string()),
+            ("all", "remaining_updated".to_string()),
+            ("all", "reward_granted".to_string()),
+            ("rewards", "reward_granted".to_string()),
+        ]
+    );
+
+    let hooks: Vec<api::WebhookDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("webhooks"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(hooks.len(), 3);
+    let log: Vec<api::WebhookDeliveryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("webhooks/rewards/deliveries"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].event, "reward_granted");
+    assert_eq!(log[0].status, "delivered");
+    assert_eq!(log[0].last_status_code, Some(200));
+
+    // Test-fire reports the attempt; failures stay queued for a retry
+    let ping: api::WebhookDeliveryDto = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("webhooks/all/test"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(ping.event, "ping");
+    assert_eq!(ping.status, "delivered");
+    let ping: api::WebhookDeliveryDto = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("webhooks/broken/test"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(ping.status, "pending");
+    assert_eq!(ping.attempts, 1);
+    assert_eq!(ping.last_status_code, Some(500));
+    assert!(ping.next_attempt_at.is_some());
+
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("webhooks/missing/test"),
+            Some(&parent),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("webhooks"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+}
+
+/// Needs an MQTT broker (e.g. a local Mosquitto) at `GAMISCREEN_TEST_MQTT`,
+/// given as `host:port`; skipped otherwise.
+#[tokio::test]
+async fn test_mqtt_bridge_publishes_state_and_applies_commands() {
+    use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+
+    let Ok(broker) = std::env::var("GAMISCREEN_TEST_MQTT") else {
+        eprintln!("Skipping MQTT test: GAMISCREEN_TEST_MQTT is not set");
+        return;
+    };
+    let (host, port) = broker.rsplit_once(':').expect("host:port");
+    let port: u16 = port.parse().expect("port");
+    let prefix = format!("gs-test-{}", uuid::Uuid::new_v4().simple());
+
+    let mut config = test_config(default_tasks());
+    config.mqtt = Some(server::MqttConfig {
+        enabled: true,
+        host: host.into(),
+        port,
+        username: None,
+        password: None,
+        client_id: format!("{prefix}-server"),
+        topic_prefix: prefix.clone(),
+        discovery_prefix: format!("{prefix}-ha"),
+        user: "parent".into(),
+    });
+
+    let (client, mut eventloop) =
+        AsyncClient::new(MqttOptions::new(format!("{prefix}-client"), host, port), 64);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            match eventloop.poll().await {
+                Ok(Event::Incoming(Packet::Publish(p))) => {
+                    let payload = String::from_utf8_lossy(&p.payload).to_string();
+                    if tx.send((p.topic, payload)).is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => panic!("mqtt test client: {e}"),
+            }
+        }
+    });
+    client
+        .subscribe(format!("{prefix}/#"), QoS::AtLeastOnce)
+        .await
+        .unwrap();
+    client
+        .subscribe(format!("{prefix}-ha/#"), QoS::AtLeastOnce)
+        .await
+        .unwrap();
+
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+
+    let state_topic = format!("{prefix}/children/alice/state");
+    let mut wait_for = async |topic: &str, check: &dyn Fn(&str) -> bool| -> String {
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            let (t, payload) = tokio::time::timeout_at(deadline, rx.recv())
+                .await
+                .unwrap_or_else(|_| panic!("timed out waiting for {topic}"))
+                .expect("mqtt client running");
+            if t == topic && check(&payload) {
+                return payload;
+            }
+        }
+    };
+
+    let button = wait_for(
+        &format!("{prefix}-ha/button/gamiscreen_test-tenant/alice_task_homework/config"),
+        &|_| true,
+    )
+    .await;
+    let button: Value = serde_json::from_str(&button).unwrap();
+    assert_eq!(
+        button["command_topic"],
+        format!("{prefix}/children/alice/reward/set")
+    );
+    wait_for(&format!("{prefix}/status"), &|p| p == "online").await;
+    wait_for(&state_topic, &|p| {
+        serde_json::from_str::<Value>(p).unwrap()["remaining_minutes"] == 0
+    })
+    .await;
+    wait_for(&format!("{prefix}/pending_count"), &|p| p == "0").await;
+
+    // Pressing the button grants the task through the reward path
+    client
+        .publish(
+            button["command_topic"].as_str().unwrap(),
+            QoS::AtLeastOnce,
+            false,
+            button["payload_press"].as_str().unwrap(),
+        )
+        .await
+        .unwrap();
+    wait_for(&state_topic, &|p| {
+        serde_json::from_str::<Value>(p).unwrap()["remaining_minutes"] == 2
+    })
+    .await;
+    assert_eq!(
+        get_remaining(&server, &parent, "alice")
+            .await
+            .remaining_minutes,
+        2
+    );
+
+    client
+        .publish(
+            format!("{prefix}/children/alice/clear/set"),
+            QoS::AtLeastOnce,
+            false,
+            "CLEAR",
+        )
+        .await
+        .unwrap();
+    wait_for(&state_topic, &|p| {
+        serde_json::from_str::<Value>(p).unwrap()["remaining_minutes"] == 0
+    })
+    .await;
+
+    let entries: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?child_id=alice"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let actions: Vec<_> = entries.iter().map(|e| e.action.as_str()).collect();
+    assert_eq!(actions, vec!["reward.penalty", "reward.grant"]);
+    assert!(entries.iter().all(|e| e.actor == "parent"));
+    assert!(entries.iter().all(|e| {
+        e.request_id
+            .as_deref()
+            .is_some_and(|r| r.starts_with("mqtt-"))
+    }));
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_requires_token_and_reports_activity() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    // Disabled unless configured
+    server
+        .request_expect_status("GET", "/metrics", None, None, StatusCode::NOT_FOUND)
+        .await;
+    drop(server);
+
+    let mut config = test_config(default_tasks());
+    config.metrics = Some(server::MetricsConfig {
+        enabled: true,
+        token: Some("scrape-token".into()),
+    });
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(5), None, None),
+    )
+    .await;
+    let dev = register_device(&server, &parent, "alice", "metrics-laptop").await;
+    send_heartbeat(
+        &server,
+        &dev.token,
+        "alice",
+        "metrics-laptop",
+        &[now_minute() - 1],
+    )
+    .await;
+
+    for token in [None, Some("wrong"), Some(parent.as_str())] {
+        server
+            .request_expect_status("GET", "/metrics", token, None, StatusCode::UNAUTHORIZED)
+            .await;
+    }
+    let body = server
+        .request_expect_text(
+            "GET",
+            "/metrics",
+            Some("scrape-token"),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let has_line = |line: &str| body.lines().any(|l| l == line);
+    assert!(
+        has_line("gamiscreen_heartbeats_total{child_id=\"alice\",device_id=\"metrics-laptop\"} 1"),
+        "{body}"
+    );
+    assert!(
+        has_line("gamiscreen_child_remaining_minutes{child_id=\"alice\"} 4"),
+        "{body}"
+    );
+    assert!(body.contains("gamiscreen_rewards_total{kind=\"grant\"}"));
+    assert!(body.contains("gamiscreen_db_query_duration_seconds_count"));
+    assert!(body.contains("gamiscreen_sse_connections"));
+}
+
+#[tokio::test]
+async fn test_openapi_document_covers_every_route() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let spec: Value = server
+        .request_expect_json("GET", "/api/v1/openapi.json", None, None, StatusCode::OK)
+        .await;
+    assert_eq!(spec["openapi"], "3.1.0");
+    let schemas = spec["components"]["schemas"].as_object().expect("schemas");
+    assert!(schemas.contains_key("RewardReq"));
+    assert!(schemas.contains_key("Error"));
+    assert!(spec["components"]["securitySchemes"]["bearerAuth"].is_object());
+
+    // Build the route table from a throwaway state so the test fails when a
+    // route is added to `router()` without a matching spec entry.
+    let dir = tempfile::tempdir().unwrap();
+    let store = storage::Store::connect_sqlite(dir.path().join("routes.db").to_str().unwrap())
+        .await
+        .expect("db");
+    let state = server::AppState::new(test_config(default_tasks()), store);
+    let served = server::served_routes(&state);
+    assert!(!served.is_empty());
+
+    let paths = spec["paths"].as_object().expect("paths");
+    for (method, path) in &served {
+        let method = method.as_str().to_ascii_lowercase();
+        assert!(
+            paths.get(path).and_then(|p| p.get(&method)).is_some(),
+            "{method} {path} is served but missing from the OpenAPI document"
+        );
+    }
+    let documented: usize = paths
+        .values()
+        .map(|item| item.as_object().map_or(0, |ops| ops.len()))
+        .sum();
+    assert_eq!(
+        documented,
+        served.len(),
+        "OpenAPI document lists operations that are not served"
+    );
+    for (path, item) in paths {
+        for (method, op) in item.as_object().unwrap() {
+            assert!(
+                op["responses"].as_object().is_some_and(|r| !r.is_empty()),
+                "{method} {path} has no responses"
+            );
+        }
+    }
+}
+
+type AgentSocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn connect_agent(
+    server: &TestServer,
+    token: Option<&str>,
+    child_id: &str,
+    device_id: &str,
+) -> Result<AgentSocket, tokio_tungstenite::tungstenite::Error> {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    let url = format!(
+        "{}{}",
+        server.base.replacen("http", "ws", 1),
+        tenant_path(&format!("children/{child_id}/device/{device_id}/ws"))
+    );
+    let mut req = url.into_client_request().unwrap();
+    if let Some(token) = token {
+        req.headers_mut()
+            .insert("authorization", format!("Bearer {token}").parse().unwrap());
+    }
+    tokio_tungstenite::connect_async(req)
+        .await
+        .map(|(ws, _)| ws)
+}
+
+/// Next agent message, skipping inbox notifications (rewards create those too).
+async fn next_agent_msg(ws: &mut AgentSocket) -> api::AgentServerMessage {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    loop {
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next())
+            .await
+            .expect("timed out waiting for agent message")
+            .expect("socket closed")
+            .expect("socket error");
+        let Message::Text(text) = msg else {
+            continue;
+        };
+        match serde_json::from_str(text.as_str()).expect("agent message") {
+            api::AgentServerMessage::Event {
+                event: api::ServerEvent::NotificationCreated { .. },
+            } => continue,
+            msg => return msg,
+        }
+    }
+}
+
+fn expect_remaining_event(msg: api::AgentServerMessage, expected: i32) {
+    match msg {
+        api::AgentServerMessage::Event {
+            event:
+                api::ServerEvent::RemainingUpdated {
+                    child_id,
+                    remaining_minutes,
+                    ..
+                },
+        } => {
+            assert_eq!(child_id, "alice");
+            assert_eq!(remaining_minutes, expected);
+        }
+        other => panic!("expected remaining_updated, got {other:?}"),
+    }
+}
+
+fn expect_command(msg: api::AgentServerMessage, expected: api::AgentCommand) {
+    match msg {
+        api::AgentServerMessage::Command { command } => assert_eq!(command, expected),
+        other => panic!("expected command, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_agent_websocket_streams_heartbeats_events_and_commands() {
+    use futures::SinkExt;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::tungstenite::error::Error as WsError;
+
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let dev = register_device(&server, &parent, "alice", "ws-laptop").await;
+
+    // The upgrade goes through bearer auth and the device ACL.
+    for (token, device, status) in [
+        (None, "ws-laptop", StatusCode::UNAUTHORIZED),
+        (
+            Some(dev.token.as_str()),
+            "other-laptop",
+            StatusCode::FORBIDDEN,
+        ),
+        (Some(parent.as_str()), "ws-laptop", StatusCode::FORBIDDEN),
+    ] {
+        match connect_agent(&server, token, "alice", device).await {
+            Err(WsError::Http(resp)) => assert_eq!(resp.status().as_u16(), status.as_u16()),
+            other => panic!(
+                "expected {status} for {device}, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+    }
+
+    let mut ws = connect_agent(&server, Some(&dev.token), "alice", "ws-laptop")
+        .await
+        .expect("agent connects");
+    expect_remaining_event(next_agent_msg(&mut ws).await, 0);
+    expect_command(next_agent_msg(&mut ws).await, api::AgentCommand::Lock);
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(5), None, None),
+    )
+    .await;
+    expect_remaining_event(next_agent_msg(&mut ws).await, 5);
+    expect_command(next_agent_msg(&mut ws).await, api::AgentCommand::Unlock);
+
+    let minute = now_minute();
+    ws.send(Message::Text(
+        serde_json::to_string(&api::AgentMessage::Heartbeat {
+            minutes: vec![minute],
+        })
+        .unwrap()
+        .into(),
+    ))
+    .await
+    .unwrap();
+    match next_agent_msg(&mut ws).await {
+        api::AgentServerMessage::HeartbeatAck {
+            minutes,
+            remaining_minutes,
+            ..
+        } => {
+            assert_eq!(minutes, vec![minute]);
+            assert_eq!(remaining_minutes, 4);
+        }
+        other => panic!("expected heartbeat ack, got {other:?}"),
+    }
+    expect_remaining_event(next_agent_msg(&mut ws).await, 4);
+    assert_eq!(
+        get_remaining(&server, &parent, "alice")
+            .await
+            .remaining_minutes,
+        4
+    );
+
+    ws.send(Message::Text("{\"type\":\"bogus\"}".into()))
+        .await
+        .unwrap();
+    assert!(matches!(
+        next_agent_msg(&mut ws).await,
+        api::AgentServerMessage::Error { .. }
+    ));
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(-4), None, None),
+    )
+    .await;
+    expect_remaining_event(next_agent_msg(&mut ws).await, 0);
+    expect_command(next_agent_msg(&mut ws).await, api::AgentCommand::Lock);
+
+    ws.close(None).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_agent_websocket_closes_when_device_is_blocked() {
+    use futures::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let dev = register_device(&server, &parent, "alice", "ws-blocked").await;
+    let mut ws = connect_agent(&server, Some(&dev.token), "alice", "ws-blocked")
+        .await
+        .expect("agent connects");
+    expect_remaining_event(next_agent_msg(&mut ws).await, 0);
+    expect_command(next_agent_msg(&mut ws).await, api::AgentCommand::Lock);
+
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/devices/ws-blocked/block"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+
+    // The server closes the socket instead of serving the revoked session.
+    loop {
+        let msg = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next())
+            .await
+            .expect("timed out waiting for the socket to close");
+        match msg {
+            None | Some(Ok(Message::Close(_))) | Some(Err(_)) => break,
+            Some(Ok(_)) => continue,
+        }
+    }
+}
+
+/// One server-sent event frame; keep-alive comments are skipped.
+#[derive(Debug, Default)]
+struct SseFrame {
+    id: Option<String>,
+    retry: Option<u64>,
+    data: Option<Value>,
+}
+
+struct SseReader {
+    resp: reqwest::Response,
+    buf: String,
+}
+
+async fn sse_ticket(server: &TestServer, token: &str) -> String {
+    let resp: api::SseTicketResp = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("sse/ticket"),
+            Some(token),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert!(resp.expires_in_secs > 0);
+    resp.ticket
+}
+
+async fn sse_get(server: &TestServer, query: &str) -> reqwest::Response {
+    let url = format!("{}{}?{query}", server.base, tenant_path("sse"));
+    server.client.get(url).send().await.expect("sse connect")
+}
+
+impl SseReader {
+    async fn connect(server: &TestServer, token: &str, last_event_id: Option<&str>) -> Self {
+        let ticket = sse_ticket(server, token).await;
+        let url = format!("{}{}?ticket={ticket}", server.base, tenant_path("sse"));
+        let mut req = server.client.get(url);
+        if let Some(id) = last_event_id {
+            req = req.header("last-event-id", id);
+        }
+        let resp = req.send().await.expect("sse connect");
+        assert_eq!(resp.status(), StatusCode::OK);
+        Self::new(resp)
+    }
+
+    fn new(resp: reqwest::Response) -> Self {
+        Self {
+            resp,
+            buf: String::new(),
+        }
+    }
+
+    /// Read until the server closes the stream.
+    async fn expect_end(&mut self) {
+        loop {
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), self.resp.chunk())
+                .await
+                .expect("timed out waiting for SSE stream to end")
+                .expect("sse read");
+            if chunk.is_none() {
+                return;
+            }
+        }
+    }
+
+    async fn next_frame(&mut self) -> SseFrame {
+        loop {
+            if let Some(end) = self.buf.find("\n\n") {
+                let raw: String = self.buf.drain(..end + 2).collect();
+                let mut frame = SseFrame::default();
+                for line in raw.lines() {
+                    if let Some(v) = line.strip_prefix("id:") {
+                        frame.id = Some(v.trim().to_string());
+                    } else if let Some(v) = line.strip_prefix("retry:") {
+                        frame.retry = v.trim().parse().ok();
+                    } else if let Some(v) = line.strip_prefix("data:") {
+                        frame.data = Some(serde_json::from_str(v.trim()).expect("event json"));
+                    }
+                }
+                if frame.id.is_none() && frame.retry.is_none() && frame.data.is_none() {
+                    continue;
+                }
+                return frame;
+            }
+            let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), self.resp.chunk())
+                .await
+                .expect("timed out waiting for SSE frame")
+                .expect("sse read")
+                .expect("sse stream ended");
+            self.buf
+                .push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n"));
+        }
+    }
+
+    async fn next_of_type(&mut self, ty: &str) -> SseFrame {
+        loop {
+            let frame = self.next_frame().await;
+            if frame.data.as_ref().is_some_and(|d| d["type"] == ty) {
+                return frame;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_sse_event_ids_replay_and_resync() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+
+    let mut sse = SseReader::connect(&server, &parent, None).await;
+    let hint = sse.next_frame().await;
+    assert_eq!(hint.retry, Some(3000));
+    let snapshot = sse.next_frame().await;
+    assert_eq!(snapshot.data.unwrap()["type"], "pending_count");
+    assert!(snapshot.id.is_none(), "snapshots are not replayable");
+
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(5), None, None),
+    )
+    .await;
+    let first = sse.next_of_type("remaining_updated").await;
+    let first_id = first.id.expect("live events carry an id");
+    drop(sse);
+
+    // Broadcast while the parent is disconnected.
+    parent_reward(
+        &server,
+        &parent,
+        "alice",
+        &reward_req("alice", None, Some(2), None, None),
+    )
+    .await;
+
+    let mut sse = SseReader::connect(&server, &parent, Some(&first_id)).await;
+    assert_eq!(sse.next_frame().await.retry, Some(3000));
+    let replayed = sse.next_frame().await;
+    let data = replayed.data.expect("replayed event");
+    assert_eq!(data["type"], "remaining_updated");
+    assert_eq!(data["remaining_minutes"], 7);
+    let replayed_id = replayed.id.expect("replayed id");
+    let seq = |id: &str| id.rsplit('-').next().unwrap().parse::<u64>().unwrap();
+    assert!(seq(&replayed_id) > seq(&first_id));
+    assert_eq!(
+        sse.next_frame().await.data.unwrap()["type"],
+        "pending_count"
+    );
+    drop(sse);
+
+    // Ids from another server run cannot be replayed.
+    let mut sse = SseReader::connect(&server, &parent, Some("1-1")).await;
+    assert_eq!(sse.next_frame().await.retry, Some(3000));
+    assert_eq!(sse.next_frame().await.data.unwrap()["type"], "resync");
+}
+
+#[tokio::test]
+async fn test_sse_tickets_are_single_use_and_session_bound() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("sse/ticket"),
+            None,
+            None,
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+
+    // Bearer tokens are no longer accepted in the query string.
+    let resp = sse_get(&server, &format!("token={parent}")).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // A ticket opens one stream only.
+    let ticket = sse_ticket(&server, &child).await;
+    let resp = sse_get(&server, &format!("ticket={ticket}")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let mut sse = SseReader::new(resp);
+    assert_eq!(sse.next_frame().await.retry, Some(3000));
+    assert_eq!(
+        sse.next_frame().await.data.unwrap()["type"],
+        "remaining_updated"
+    );
+    let resp = sse_get(&server, &format!("ticket={ticket}")).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let resp = sse_get(&server, "ticket=made-up").await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Revoking the session (here by renewing it) closes its open streams and
+    // invalidates tickets it issued; other sessions are unaffected.
+    let mut parent_sse = SseReader::connect(&server, &parent, None).await;
+    assert_eq!(parent_sse.next_frame().await.retry, Some(3000));
+    let unused = sse_ticket(&server, &parent).await;
+    let renewed: api::AuthResp = server
+        .request_expect_json("POST", RENEW_PATH, Some(&parent), None, StatusCode::OK)
+        .await;
+    parent_sse.expect_end().await;
+    let resp = sse_get(&server, &format!("ticket={unused}")).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    parent_reward(
+        &server,
+        &renewed.token,
+        "alice",
+        &reward_req("alice", None, Some(3), None, None),
+    )
+    .await;
+    let update = sse.next_of_type("remaining_updated").await;
+    assert_eq!(update.data.unwrap()["remaining_minutes"], 3);
+}
+
+fn jwt_header(token: &str) -> Value {
+    use base64::Engine;
+    let header = token.split('.').next().expect("jwt header");
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(header)
+        .expect("jwt header base64");
+    serde_json::from_slice(&bytes).expect("jwt header json")
+}
+
+#[tokio::test]
+async fn test_signing_keys_jwks_rotation_and_legacy_tokens() {
+    use gamiscreen_shared::jwt::{self, JwkSet, JwtClaims, KeySet, SigningAlgorithm};
+
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let token = server.login("parent", "secret123").await;
+    let header = jwt_header(&token);
+    assert_eq!(header["alg"], "EdDSA");
+    let jwks: JwkSet = server
+        .request_expect_json("GET", "/.well-known/jwks.json", None, None, StatusCode::OK)
+        .await;
+    assert_eq!(jwks.keys.len(), 1);
+    assert_eq!(
+        jwks.keys[0].common.key_id.as_deref(),
+        header["kid"].as_str()
+    );
+    // Anyone holding the JWKS can verify tokens without a shared secret.
+    let verified = KeySet::from_jwks(&jwks).unwrap().verify(&token).unwrap();
+    assert_eq!(verified.sub, "parent");
+
+    // HS256 tokens signed with `jwt_secret` keep working during migration.
+    let store = storage::Store::connect_sqlite(server.db_path.to_str().unwrap())
+        .await
+        .expect("db");
+    let legacy_claims = JwtClaims {
+        sub: "parent".into(),
+        jti: uuid::Uuid::new_v4().to_string(),
+        exp: (Utc::now() + Duration::hours(1)).timestamp(),
+        role: server::Role::Parent,
+        child_id: None,
+        device_id: None,
+        tenant_id: TENANT_ID.into(),
+    };
+    store
+        .create_session(&legacy_claims.jti, "parent", None)
+        .await
+        .unwrap();
+    let children_path = tenant_path("children");
+    let legacy = jwt::encode(&legacy_claims, b"testsecret").unwrap();
+    server
+        .request_expect_status("GET", &children_path, Some(&legacy), None, StatusCode::OK)
+        .await;
+    let forged = jwt::encode(&legacy_claims, b"othersecret").unwrap();
+    server
+        .request_expect_status(
+            "GET",
+            &children_path,
+            Some(&forged),
+            None,
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+
+    // After a rotation new tokens use the new key, while tokens signed by
+    // the retired one stay valid.
+    let kid = server::keys::rotate(&store, SigningAlgorithm::ES256)
+        .await
+        .unwrap();
+    let (addr, handle) = start_server(&server.db_path).await.unwrap();
+    let restarted = TestServer {
+        base: format!("http://{}", addr),
+        client: Client::new(),
+        handle,
+        _tempdir: tempfile::tempdir().unwrap(),
+        db_path: server.db_path.clone(),
+    };
+    let rotated = restarted.login("parent", "secret123").await;
+    let header = jwt_header(&rotated);
+    assert_eq!(header["alg"], "ES256");
+    assert_eq!(header["kid"], kid.as_str());
+    for token in [&token, &rotated] {
+        restarted
+            .request_expect_status("GET", &children_path, Some(token), None, StatusCode::OK)
+            .await;
+    }
+    let jwks: JwkSet = restarted
+        .request_expect_json("GET", "/.well-known/jwks.json", None, None, StatusCode::OK)
+        .await;
+    assert_eq!(jwks.keys.len(), 2);
+    let keys = KeySet::from_jwks(&jwks).unwrap();
+    assert!(keys.verify(&token).is_ok() && keys.verify(&rotated).is_ok());
+}
+
+#[tokio::test]
+async fn test_parent_totp_enrollment_login_and_recovery() {
+    use gamiscreen_server::server::totp;
+
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let token = server.login("parent", "secret123").await;
+    let other_session = server.login("parent", "secret123").await;
+    let children_path = tenant_path("children");
+    let login_body = |code: Option<&str>| {
+        Some(to_value(&api::AuthReq {
+            username: "parent".into(),
+            password: "secret123".into(),
+            totp_code: code.map(str::to_string),
+        }))
+    };
+
+    // Children cannot enroll.
+    let kid = server.login("alice", "kidpass").await;
+    server
+        .request_expect_status(
+            "POST",
+            TOTP_ENROLL_PATH,
+            Some(&kid),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+
+    let enroll: api::TotpEnrollResp = server
+        .request_expect_json("POST", TOTP_ENROLL_PATH, Some(&token), None, StatusCode::OK)
+        .await;
+    assert!(
+        enroll
+            .otpauth_uri
+            .starts_with("otpauth://totp/GamiScreen%3Aparent?")
+    );
+    let secret = data_encoding::BASE32_NOPAD
+        .decode(enroll.secret.as_bytes())
+        .unwrap();
+    // Until the enrollment is confirmed the password alone still works.
+    server.login("parent", "secret123").await;
+    server
+        .request_expect_status(
+            "POST",
+            TOTP_VERIFY_PATH,
+            Some(&token),
+            Some(serde_json::json!({ "code": "000000x" })),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    let now = Utc::now().timestamp();
+    let codes: api::TotpRecoveryCodesResp = server
+        .request_expect_json(
+            "POST",
+            TOTP_VERIFY_PATH,
+            Some(&token),
+            Some(serde_json::json!({ "code": totp::code_at(&secret, now) })),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(codes.recovery_codes.len(), totp::RECOVERY_CODE_COUNT);
+    let status: api::TotpStatusDto = server
+        .request_expect_json("GET", TOTP_PATH, Some(&token), None, StatusCode::OK)
+        .await;
+    assert!(status.enabled);
+    assert_eq!(status.recovery_codes_left, 10);
+
+    // Other sessions opened with only the password are signed out.
+    server
+        .request_expect_status(
+            "GET",
+            &children_path,
+            Some(&other_session),
+            None,
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    server
+        .request_expect_status("GET", &children_path, Some(&token), None, StatusCode::OK)
+        .await;
+    let entries: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?action=totp.enable"),
+            Some(&token),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].after,
+        Some(serde_json::json!({ "revoked_sessions": 2 }))
+    );
+
+    // The password alone now asks for a code.
+    let (status, body) = server
+        .request_raw("POST", LOGIN_PATH, None, login_body(None))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "totp_required");
+
+    // The code used to enroll cannot be replayed; the next one logs in once.
+    let enrolled_code = totp::code_at(&secret, now);
+    server
+        .request_expect_status(
+            "POST",
+            LOGIN_PATH,
+            None,
+            login_body(Some(&enrolled_code)),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    let next_code = totp::code_at(&secret, now + 30);
+    server
+        .request_expect_status(
+            "POST",
+            LOGIN_PATH,
+            None,
+            login_body(Some(&next_code)),
+            StatusCode::OK,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            LOGIN_PATH,
+            None,
+            login_body(Some(&next_code)),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+
+    // Recovery codes work however they are typed, but only once. (Logins
+    // are rate limited, so reuse is checked through the disable endpoint.)
+    let typed = codes.recovery_codes[0].to_uppercase().replace('-', " ");
+    server
+        .request_expect_status(
+            "POST",
+            LOGIN_PATH,
+            None,
+            login_body(Some(&typed)),
+            StatusCode::OK,
+        )
+        .await;
+
+    // Disabling needs a valid code; afterwards the password is enough again.
+    server
+        .request_expect_status(
+            "POST",
+            TOTP_DISABLE_PATH,
+            Some(&token),
+            Some(serde_json::json!({ "code": codes.recovery_codes[0] })),
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            TOTP_DISABLE_PATH,
+            Some(&token),
+            Some(serde_json::json!({ "code": codes.recovery_codes[1] })),
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    server.login("parent", "secret123").await;
+
+    // A locked-out parent is reset through the store, as `totp reset` does.
+    server
+        .request_expect_status("POST", TOTP_ENROLL_PATH, Some(&token), None, StatusCode::OK)
+        .await;
+    let store = storage::Store::connect_sqlite(server.db_path.to_str().unwrap())
+        .await
+        .expect("db");
+    assert!(store.delete_totp("parent").await.unwrap());
+    assert!(!store.delete_totp("parent").await.unwrap());
+    let status: api::TotpStatusDto = server
+        .request_expect_json("GET", TOTP_PATH, Some(&token), None, StatusCode::OK)
+        .await;
+    assert!(!status.enabled);
+}
+
+/// The login the mock OIDC provider expects next, and the identity it
+/// vouches for.
+#[derive(Default)]
+struct MockOidcLogin {
+    code_challenge: String,
+    nonce: String,
+    subject: String,
+    groups: Vec<String>,
+}
+
+type MockOidcProvider = std::sync::Arc<std::sync::Mutex<MockOidcLogin>>;
+
+/// Start an OpenID provider with discovery, an Ed25519 JWKS and a token
+/// endpoint that checks the PKCE verifier and the client credentials.
+/// Returns its issuer URL.
+async fn spawn_mock_oidc_provider() -> (String, MockOidcProvider) {
+    use axum::extract::State;
+    use axum::routing::{get, post};
+    use base64::Engine;
+    use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+    use ed25519_dalek::pkcs8::EncodePrivateKey;
+    use sha2::{Digest, Sha256};
+
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))
+        .await
+        .expect("bind mock provider");
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let key = ed25519_dalek::SigningKey::generate(&mut rand_core::OsRng);
+    let jwks = serde_json::json!({ "keys": [{
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "kid": "mock-key",
+        "alg": "EdDSA",
+        "use": "sig",
+        "x": URL_SAFE_NO_PAD.encode(key.verifying_key().as_bytes()),
+    }]});
+    let encoding_key =
+        jsonwebtoken::EncodingKey::from_ed_der(key.to_pkcs8_der().unwrap().as_bytes());
+    let discovery = serde_json::json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{issuer}/authorize"),
+        "token_endpoint": format!("{issuer}/token"),
+        "jwks_uri": format!("{issuer}/jwks"),
+    });
+    let provider: MockOidcProvider = Default::default();
+
+    let token_issuer = issuer.clone();
+    let token =
+        move |State(provider): State<MockOidcProvider>,
+              headers: axum::http::HeaderMap,
+              axum::Form(form): axum::Form<std::collections::HashMap<String, String>>| {
+            let response = (|| {
+                let login = provider.lock().unwrap();
+                let challenge = form
+                    .get("code_verifier")
+                    .map(|v| URL_SAFE_NO_PAD.encode(Sha256::digest(v.as_bytes())));
+                let basic = format!("Basic {}", STANDARD.encode("gamiscreen:s3cret"));
+                let valid = form.get("grant_type").map(String::as_str)
+                    == Some("authorization_code")
+                    && form.get("code").map(String::as_str) == Some("good-code")
+                    && challenge.as_deref() == Some(login.code_challenge.as_str())
+                    && headers.get("authorization").and_then(|v| v.to_str().ok())
+                        == Some(basic.as_str());
+                if !valid {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        axum::Json(serde_json::json!({ "error": "invalid_grant" })),
+                    );
+                }
+                let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::EdDSA);
+                header.kid = Some("mock-key".into());
+                let claims = serde_json::json!({
+                    "iss": token_issuer,
+                    "aud": "gamiscreen",
+                    "sub": login.subject,
+                    "exp": (Utc::now() + Duration::minutes(5)).timestamp(),
+                    "iat": Utc::now().timestamp(),
+                    "nonce": login.nonce,
+                    "groups": login.groups,
+                });
+                let id_token = jsonwebtoken::encode(&header, &claims, &encoding_key).unwrap();
+                (
+                    StatusCode::OK,
+                    axum::Json(serde_json::json!({ "id_token": id_token, "token_type": "Bearer" })),
+                )
+            })();
+            std::future::ready(response)
+        };
+    let app = axum::Router::new()
+        .route(
+            "/.well-known/openid-configuration",
+            get(move || async move { axum::Json(discovery) }),
+        )
+        .route("/jwks", get(move || async move { axum::Json(jwks) }))
+        .route("/token", post(token))
+        .with_state(provider.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (issuer, provider)
+}
+
+/// Where the OIDC callback sends the browser after it returns from the
+/// provider with `code` and `state`.
+/// Send the browser back from the provider, with the state cookie if given.
+async fn oidc_callback(
+    server: &TestServer,
+    client: &Client,
+    cookie: Option<&str>,
+    params: &[(&str, &str)],
+) -> String {
+    let url = reqwest::Url::parse_with_params(
+        &format!("{}/api/v1/auth/oidc/callback", server.base),
+        params,
+    )
+    .unwrap();
+    let mut req = client.get(url);
+    if let Some(cookie) = cookie {
+        req = req.header(reqwest::header::COOKIE, cookie);
+    }
+    let resp = req.send().await.unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    let cleared = resp.headers()[reqwest::header::SET_COOKIE]
+        .to_str()
+        .unwrap();
+    assert!(cleared.starts_with("gamiscreen_oidc_state=;"), "{cleared}");
+    assert!(cleared.contains("Max-Age=0"), "{cleared}");
+    resp.headers()["location"].to_str().unwrap().to_string()
+}
+
+/// Start a login; returns the provider's authorize URL parameters and the
+/// state cookie to send back with the callback.
+async fn oidc_start(
+    server: &TestServer,
+    client: &Client,
+) -> (std::collections::HashMap<String, String>, String) {
+    let resp = client
+        .get(format!("{}/api/v1/auth/oidc/login", server.base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::SEE_OTHER);
+    let set_cookie = resp.headers()[reqwest::header::SET_COOKIE]
+        .to_str()
+        .unwrap();
+    for attr in [
+        "Path=/api/v1/auth/oidc/callback",
+        "HttpOnly",
+        "Secure",
+        "SameSite=Lax",
+    ] {
+        assert!(set_cookie.contains(attr), "{set_cookie}");
+    }
+    let cookie = set_cookie.split(';').next().unwrap().to_string();
+    let authorize = reqwest::Url::parse(resp.headers()["location"].to_str().unwrap()).unwrap();
+    assert_eq!(authorize.path(), "/authorize");
+    (authorize.query_pairs().into_owned().collect(), cookie)
+}
+
+/// Log in through the mock provider as `subject`; returns the login state,
+/// its cookie and where the callback redirected to.
+async fn oidc_login(
+    server: &TestServer,
+    client: &Client,
+    provider: &MockOidcProvider,
+    subject: &str,
+    groups: &[&str],
+) -> (String, String, String) {
+    let (params, cookie) = oidc_start(server, client).await;
+    assert_eq!(params["response_type"], "code");
+    assert_eq!(params["client_id"], "gamiscreen");
+    assert_eq!(params["scope"], "openid groups");
+    assert_eq!(params["code_challenge_method"], "S256");
+    *provider.lock().unwrap() = MockOidcLogin {
+        code_challenge: params["code_challenge"].clone(),
+        nonce: params["nonce"].clone(),
+        subject: subject.into(),
+        groups: groups.iter().map(|g| g.to_string()).collect(),
+    };
+    let state = params["state"].clone();
+    let target = oidc_callback(
+        server,
+        client,
+        Some(&cookie),
+        &[("code", "good-code"), ("state", &state)],
+    )
+    .await;
+    (state, cookie, target)
+}
+
+#[tokio::test]
+async fn test_oidc_login_maps_identities_to_parents() {
+    let (issuer, provider) = spawn_mock_oidc_provider().await;
+    let mut config = test_config(default_tasks());
+    config.users.push(server::UserConfig {
+        username: "mom".into(),
+        password_hash: None,
+        role: server::Role::Parent,
+        child_id: None,
+    });
+    config.oidc = Some(server::OidcConfig {
+        enabled: true,
+        issuer,
+        client_id: "gamiscreen".into(),
+        client_secret: Some("s3cret".into()),
+        redirect_url: "http://screen.test/api/v1/auth/oidc/callback".into(),
+        scopes: vec!["openid".into(), "groups".into()],
+        groups_claim: "groups".into(),
+        display_name: "Keycloak".into(),
+        users: vec![
+            server::OidcUserMapping {
+                username: "mom".into(),
+                subject: Some("mom-sub".into()),
+                group: None,
+            },
+            server::OidcUserMapping {
+                username: "parent".into(),
+                subject: None,
+                group: Some("parents".into()),
+            },
+        ],
+    });
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let client = Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let options: api::AuthOptionsDto = server
+        .request_expect_json("GET", "/api/v1/auth/options", None, None, StatusCode::OK)
+        .await;
+    assert_eq!(options.oidc_name.as_deref(), Some("Keycloak"));
+    let exchange = |code: &str| {
+        Some(to_value(&api::OidcExchangeReq {
+            code: code.to_string(),
+        }))
+    };
+    let code_from = |target: &str| {
+        target
+            .strip_prefix("/#login?oidc_code=")
+            .unwrap_or_else(|| panic!("no code in {target}"))
+            .to_string()
+    };
+
+    // A mapped subject logs in as its parent, without a password.
+    let (state, cookie, target) = oidc_login(&server, &client, &provider, "mom-sub", &[]).await;
+    let code = code_from(&target);
+    let resp: api::AuthResp = server
+        .request_expect_json(
+            "POST",
+            "/api/v1/auth/oidc/exchange",
+            None,
+            exchange(&code),
+            StatusCode::OK,
+        )
+        .await;
+    let claims = gamiscreen_shared::jwt::decode_unverified(&resp.token).unwrap();
+    assert_eq!(claims.sub, "mom");
+    assert_eq!(claims.role, server::Role::Parent);
+    server
+        .request_expect_status(
+            "GET",
+            &tenant_path("children"),
+            Some(&resp.token),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    // Codes and login states work once.
+    server
+        .request_expect_status(
+            "POST",
+            "/api/v1/auth/oidc/exchange",
+            None,
+            exchange(&code),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    let replay = oidc_callback(
+        &server,
+        &client,
+        Some(&cookie),
+        &[("code", "good-code"), ("state", &state)],
+    )
+    .await;
+    assert!(replay.starts_with("/#login?oidc_error="), "{replay}");
+    // Parents without a password_hash cannot log in with a password.
+    server
+        .request_expect_status(
+            "POST",
+            LOGIN_PATH,
+            None,
+            Some(to_value(&api::AuthReq {
+                username: "mom".into(),
+                password: "".into(),
+                totp_code: None,
+            })),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+
+    // Group members log in as the parent mapped to the group.
+    let (_, _, target) = oidc_login(
+        &server,
+        &client,
+        &provider,
+        "dad-sub",
+        &["users", "parents"],
+    )
+    .await;
+    let resp: api::AuthResp = server
+        .request_expect_json(
+            "POST",
+            "/api/v1/auth/oidc/exchange",
+            None,
+            exchange(&code_from(&target)),
+            StatusCode::OK,
+        )
+        .await;
+    let claims = gamiscreen_shared::jwt::decode_unverified(&resp.token).unwrap();
+    assert_eq!(claims.sub, "parent");
+
+    // Unmapped identities, rejected codes and provider errors end on the
+    // login page with a reason.
+    let (_, _, target) = oidc_login(&server, &client, &provider, "kid-sub", &["kids"]).await;
+    assert!(target.starts_with("/#login?oidc_error="), "{target}");
+    let (params, cookie) = oidc_start(&server, &client).await;
+    let state = params["state"].as_str();
+    let target = oidc_callback(
+        &server,
+        &client,
+        Some(&cookie),
+        &[("code", "bad-code"), ("state", state)],
+    )
+    .await;
+    assert!(target.contains("invalid%5Fgrant"), "{target}");
+
+    // The callback only finishes a login in the browser that started it:
+    // without the state cookie, or with another login's, it is refused.
+    let (params, cookie) = oidc_start(&server, &client).await;
+    *provider.lock().unwrap() = MockOidcLogin {
+        code_challenge: params["code_challenge"].clone(),
+        nonce: params["nonce"].clone(),
+        subject: "mom-sub".into(),
+        groups: Vec::new(),
+    };
+    let callback = [("code", "good-code"), ("state", params["state"].as_str())];
+    let (_, other_cookie) = oidc_start(&server, &client).await;
+    for cookie in [None, Some(other_cookie.as_str())] {
+        let target = oidc_callback(&server, &client, cookie, &callback).await;
+        assert!(target.starts_with("/#login?oidc_error="), "{target}");
+    }
+    let target = oidc_callback(&server, &client, Some(&cookie), &callback).await;
+    assert!(target.starts_with("/#login?oidc_code="), "{target}");
+
+    let target = oidc_callback(
+        &server,
+        &client,
+        None,
+        &[
+            ("error", "access_denied"),
+            ("error_description", "User cancelled"),
+        ],
+    )
+    .await;
+    assert_eq!(target, "/#login?oidc_error=User%20cancelled");
+
+    // Without an `oidc` section the endpoints are not available.
+    let Some(plain) = TestServer::spawn().await else {
+        return;
+    };
+    let options: api::AuthOptionsDto = plain
+        .request_expect_json("GET", "/api/v1/auth/options", None, None, StatusCode::OK)
+        .await;
+    assert!(options.oidc_name.is_none());
+    plain
+        .request_expect_status(
+            "GET",
+            "/api/v1/auth/oidc/login",
+            None,
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+}
+
+#[tokio::test]
+async fn test_pairing_code_registers_device_without_password() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    let pair = |code: &str, device_id: &str| {
+        Some(to_value(&api::PairReq {
+            code: code.to_string(),
+            device_id: device_id.to_string(),
+        }))
+    };
+
+    // Only parents create codes, and only for known children
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/pairing-code"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/nobody/pairing-code"),
+            Some(&parent),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+    let created: api::PairingCodeResp = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("children/alice/pairing-code"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(created.code.len(), 9);
+    assert!(created.expires_in_secs > 0);
+
+    // Codes are entered without a token, in any case and with any separator
+    let typed = created.code.replace('-', " ").to_lowercase();
+    let reg: api::ClientRegisterResp = server
+        .request_expect_json(
+            "POST",
+            PAIR_PATH,
+            None,
+            pair(&typed, "laptop1"),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(reg.child_id, "alice");
+    assert_eq!(reg.device_id, "laptop1");
+    let claims = gamiscreen_shared::jwt::decode_unverified(&reg.token).unwrap();
+    assert_eq!(claims.sub, "alice");
+    assert_eq!(claims.role, gamiscreen_shared::auth::Role::Child);
+    assert_eq!(claims.device_id.as_deref(), Some("laptop1"));
+    send_heartbeat(&server, &reg.token, "alice", "laptop1", &[now_minute()]).await;
+
+    // A code works once; unknown codes are rejected
+    server
+        .request_expect_status(
+            "POST",
+            PAIR_PATH,
+            None,
+            pair(&created.code, "laptop2"),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            PAIR_PATH,
+            None,
+            pair("AAAA-AAAA", "laptop2"),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+
+    // The registration is attributed to the parent who created the code
+    let entries: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?action=device.pair"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].actor, "parent");
+    assert_eq!(entries[0].child_id.as_deref(), Some("alice"));
+    assert_eq!(entries[0].target_id.as_deref(), Some("laptop1"));
+}
+
+#[tokio::test]
+async fn test_child_registered_devices_need_parent_approval() {
+    let Some(server) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+    let register = |device_id: &str| {
+        Some(to_value(&api::ClientRegisterReq {
+            child_id: None,
+            device_id: device_id.to_string(),
+        }))
+    };
+    let register_path = tenant_path("children/alice/register");
+    let device_path = |device_id: &str| tenant_path(&format!("children/alice/devices/{device_id}"));
+
+    // A child registering a new device gets a pending request, not a token
+    let pending: api::DeviceDto = server
+        .request_expect_json(
+            "POST",
+            &register_path,
+            Some(&child),
+            register("laptop2"),
+            StatusCode::ACCEPTED,
+        )
+        .await;
+    assert_eq!(pending.status, "pending");
+    assert_eq!(pending.device_id, "laptop2");
+    // Asking again does not open a second request
+    let again: api::DeviceDto = server
+        .request_expect_json(
+            "POST",
+            &register_path,
+            Some(&child),
+            register("laptop2"),
+            StatusCode::ACCEPTED,
+        )
+        .await;
+    assert_eq!(again.id, pending.id);
+
+    let notifications: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].kind, "device_registration");
+    let api::NotificationPayload::DeviceRegistration {
+        request_id,
+        ref device_id,
+    } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
+    assert_eq!(request_id, pending.id);
+    assert_eq!(device_id, "laptop2");
+    let count: api::NotificationsCountDto = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications/count"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(count.count, 1);
+
+    // The device polls its status; children cannot approve it themselves
+    let polled: api::DeviceDto = server
+        .request_expect_json(
+            "GET",
+            &device_path("laptop2"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(polled.status, "pending");
+    let approve_path = tenant_path(&format!("notifications/devices/{}/approve", pending.id));
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &approve_path,
+            Some(&parent),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+
+    // Once approved, registering returns the device token
+    let reg: api::ClientRegisterResp = server
+        .request_expect_json(
+            "POST",
+            &register_path,
+            Some(&child),
+            register("laptop2"),
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(reg.device_id, "laptop2");
+    send_heartbeat(&server, &reg.token, "alice", "laptop2", &[now_minute()]).await;
+
+    // A denied request can be asked for again
+    let denied: api::DeviceDto = server
+        .request_expect_json(
+            "POST",
+            &register_path,
+            Some(&child),
+            register("phone"),
+            StatusCode::ACCEPTED,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path(&format!("notifications/devices/{}/discard", denied.id)),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    let polled: api::DeviceDto = server
+        .request_expect_json(
+            "GET",
+            &device_path("phone"),
+            Some(&child),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(polled.status, "denied");
+    let reopened: api::DeviceDto = server
+        .request_expect_json(
+            "POST",
+            &register_path,
+            Some(&child),
+            register("phone"),
+            StatusCode::ACCEPTED,
+        )
+        .await;
+    assert_eq!(reopened.status, "pending");
+
+    // Blocking ends the device's sessions and keeps it from registering
+    let blocked: api::DeviceDto = server
+        .request_expect_json(
+            "POST",
+            &format!("{}/block", device_path("laptop2")),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(blocked.status, "blocked");
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("children/alice/device/laptop2/heartbeat"),
+            Some(&reg.token),
+            Some(to_value(&api::HeartbeatReq {
+                minutes: vec![now_minute()],
+            })),
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    for token in [&child, &parent] {
+        server
+            .request_expect_status(
+                "POST",
+                &register_path,
+                Some(token),
+                register("laptop2"),
+                StatusCode::FORBIDDEN,
+            )
+            .await;
+    }
+    let devices: Vec<api::DeviceDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children/alice/devices"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    let statuses: Vec<(&str, &str)> = devices
+        .iter()
+        .map(|d| (d.device_id.as_str(), d.status.as_str()))
+        .collect();
+    assert!(statuses.contains(&("laptop2", "blocked")));
+    assert!(statuses.contains(&("phone", "pending")));
+
+    // Unblocking lets the device be registered again
+    server
+        .request_expect_status(
+            "POST",
+            &format!("{}/unblock", device_path("laptop2")),
+            Some(&parent),
+            None,
+            StatusCode::NO_CONTENT,
+        )
+        .await;
+    server
+        .request_expect_status(
+            "POST",
+            &format!("{}/unblock", device_path("laptop2")),
+            Some(&parent),
+            None,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+    register_device(&server, &parent, "alice", "laptop2").await;
+
+    let entries: Vec<api::AuditEntryDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("audit?action=device.block"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].target_id.as_deref(), Some("laptop2"));
+}
+
+#[tokio::test]
+async fn test_repeated_login_failures_lock_the_account() {
+    let mut config = test_config(default_tasks());
+    config.security = Some(server::SecurityConfig {
+        trusted_proxies: vec!["127.0.0.1".into(), "::1".into()],
+        lockout_after: 3,
+        lockout_secs: 60,
+        max_lockout_secs: 3600,
+    });
+    let Some(server) = TestServer::spawn_with_config(config).await else {
+        return;
+    };
+    let login_from = |ip: &str, username: &str, password: &str| {
+        server
+            .client
+            .post(format!("{}{}", server.base, LOGIN_PATH))
+            .header("x-forwarded-for", ip)
+            .json(&api::AuthReq {
+                username: username.to_string(),
+                password: password.to_string(),
+                totp_code: None,
+            })
+            .send()
+    };
+
+    // Behind a trusted proxy each forwarded client gets its own bucket
+    for _ in 0..10 {
+        let resp = login_from("203.0.113.1", "nobody", "guess").await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = login_from("203.0.113.1", "nobody", "guess").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let resp = login_from("203.0.113.2", "nobody", "guess").await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Guesses spread over addresses still lock the account
+    for i in 0..3 {
+        let resp = login_from(&format!("198.51.100.{i}"), "alice", "wrong")
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = login_from("198.51.100.9", "alice", "kidpass")
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = resp
+        .headers()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .expect("retry-after header");
+    assert!((1..=61).contains(&retry_after));
+
+    // Other accounts are unaffected, and parents hear about the child's lock
+    let parent = server.login("parent", "secret123").await;
+    let notifications: Vec<api::NotificationItemDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("notifications"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].child_id, "alice");
+    let api::NotificationPayload::AccountLocked { ref username, .. } = notifications[0].payload
+    else {
+        panic!("unexpected payload: {:?}", notifications[0].payload);
+    };
+    assert_eq!(username, "alice");
+
+    // Every failure is kept with the client address
+    let db_path = server.db_path.to_str().unwrap().to_string();
+    let failures: Vec<(String, String)> = tokio::task::spawn_blocking(move || {
+        use diesel::prelude::*;
+        #[derive(QueryableByName)]
+        struct Failure {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            ip: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            reason: String,
+        }
+        let mut conn = SqliteConnection::establish(&db_path).unwrap();
+        diesel::sql_query("SELECT ip, reason FROM login_failures WHERE username = 'alice'")
+            .load::<Failure>(&mut conn)
+            .unwrap()
+            .into_iter()
+            .map(|f| (f.ip, f.reason))
+            .collect()
+    })
+    .await
+    .unwrap();
+    assert_eq!(failures.len(), 3);
+    assert_eq!(
+        failures[0],
+        ("198.51.100.0".to_string(), "password".to_string())
+    );
+}
+
+/// Config file for the reload test, with `homework` worth `minutes` and the
+/// given extra YAML appended.
+fn reload_test_yaml(
+    (parent_hash, child_hash): &(String, String),
+    minutes: i32,
+    extra: &str,
+) -> String {
+    format!(
+        r#"config_version: "{version}"
+tenant_id: "{TENANT_ID}"
+jwt_secret: "testsecret"
+listen_port: 5151
+users:
+  - username: parent
+    password_hash: "{parent_hash}"
+    role: parent
+  - username: alice
+    password_hash: "{child_hash}"
+    role: child
+    child_id: alice
+tasks:
+  - id: homework
+    name: Homework
+    minutes: {minutes}
+{extra}"#,
+        version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+#[tokio::test]
+async fn test_config_reload_applies_changes_from_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.yaml");
+    let hashes = (
+        bcrypt::hash("secret123", 4).unwrap(),
+        bcrypt::hash("kidpass", 4).unwrap(),
+    );
+    let children = "children:\n  - id: alice\n    display_name: Alice\n";
+    std::fs::write(&path, reload_test_yaml(&hashes, 2, children)).unwrap();
+    let Some(server) = TestServer::spawn_from_file(&path).await else {
+        return;
+    };
+    let parent = server.login("parent", "secret123").await;
+    let child = server.login("alice", "kidpass").await;
+
+    // Nothing changed yet
+    let report: api::ConfigReloadResp = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("config/reload"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert!(report.changed.is_empty());
+    assert!(report.restart_required.is_empty());
+
+    // New minutes, a new child and photo retention apply at once; a new port
+    // and photo size wait for a restart
+    let children = format!(
+        "{children}  - id: carol\n    display_name: Carol\nattachments:\n  max_bytes: 1024\n  retention_days: 7\n"
+    );
+    let yaml =
+        reload_test_yaml(&hashes, 5, &children).replace("listen_port: 5151", "listen_port: 6000");
+    std::fs::write(&path, yaml).unwrap();
+    let report: api::ConfigReloadResp = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("config/reload"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(report.changed, vec!["children", "tasks", "attachments"]);
+    assert_eq!(
+        report.restart_required,
+        vec!["listen_port", "attachments.max_bytes"]
+    );
+    let kids: Vec<api::ChildDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("children"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert!(kids.iter().any(|c| c.id == "carol"));
+    let tasks: Vec<api::TaskDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("tasks"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(
+        tasks.iter().find(|t| t.id == "homework").unwrap().minutes,
+        5
+    );
+    // The port was not applied, so the next reload still reports it
+    let report: api::ConfigReloadResp = server
+        .request_expect_json(
+            "POST",
+            &tenant_path("config/reload"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert!(report.changed.is_empty());
+    assert_eq!(
+        report.restart_required,
+        vec!["listen_port", "attachments.max_bytes"]
+    );
+
+    // An invalid file is rejected and the running config kept
+    std::fs::write(&path, reload_test_yaml(&hashes, 9, "children: [")).unwrap();
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("config/reload"),
+            Some(&parent),
+            None,
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    let tasks: Vec<api::TaskDto> = server
+        .request_expect_json(
+            "GET",
+            &tenant_path("tasks"),
+            Some(&parent),
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    assert_eq!(
+        tasks.iter().find(|t| t.id == "homework").unwrap().minutes,
+        5
+    );
+
+    // Only parents may reload, and only servers started from a file can
+    server
+        .request_expect_status(
+            "POST",
+            &tenant_path("config/reload"),
+            Some(&child),
+            None,
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    let Some(other) = TestServer::spawn().await else {
+        return;
+    };
+    let parent = other.login("parent", "secret123").await;
+    other
+        .request_expect_status(
+            "POST",
+            &tenant_path("config/reload"),
+            Some(&parent),
+            None,
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+}
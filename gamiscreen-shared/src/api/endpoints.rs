@@ -23,6 +23,11 @@ pub fn auth_login(base: &str) -> String {
     base_join(base, &format!("{}/auth/login", API_V1_PREFIX))
 }
 
+/// `POST` -- redeem a pairing code for a device token.
+pub fn auth_pair(base: &str) -> String {
+    base_join(base, &format!("{}/auth/pair", API_V1_PREFIX))
+}
+
 /// `POST` -- renew an existing session token before it expires.
 pub fn auth_renew(base: &str) -> String {
     base_join(base, &format!("{}/auth/renew", API_V1_PREFIX))
@@ -86,6 +91,78 @@ pub fn child_usage(base: &str, tenant_id: &str, child_id: &str) -> String {
     )
 }
 
+/// `GET` -- fetch a child's savings pot and interest settings.
+pub fn child_savings(base: &str, tenant_id: &str, child_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/savings",
+            tenant_scope(tenant_id),
+            enc(child_id)
+        ),
+    )
+}
+
+/// `POST` -- move minutes from remaining time into the savings pot.
+pub fn child_savings_deposit(base: &str, tenant_id: &str, child_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/savings/deposit",
+            tenant_scope(tenant_id),
+            enc(child_id)
+        ),
+    )
+}
+
+/// `POST` -- move minutes from the savings pot back into remaining time.
+pub fn child_savings_withdraw(base: &str, tenant_id: &str, child_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/savings/withdraw",
+            tenant_scope(tenant_id),
+            enc(child_id)
+        ),
+    )
+}
+
+/// `GET` -- list a child's savings ledger (deposits, withdrawals, interest).
+pub fn child_savings_history(base: &str, tenant_id: &str, child_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/savings/history",
+            tenant_scope(tenant_id),
+            enc(child_id)
+        ),
+    )
+}
+
+/// `POST` -- give minutes from this child to a sibling.
+pub fn child_transfers(base: &str, tenant_id: &str, child_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/transfers",
+            tenant_scope(tenant_id),
+            enc(child_id)
+        ),
+    )
+}
+
+/// `GET` -- list a child's time requests; `POST` -- ask for extra minutes.
+pub fn child_time_requests(base: &str, tenant_id: &str, child_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/time-requests",
+            tenant_scope(tenant_id),
+            enc(child_id)
+        ),
+    )
+}
+
 /// `POST` -- register a device client for a child and obtain a device token.
 pub fn child_register(base: &str, tenant_id: &str, child_id: &str) -> String {
     base_join(
@@ -98,6 +175,19 @@ pub fn child_register(base: &str, tenant_id: &str, child_id: &str) -> String {
     )
 }
 
+/// `GET` -- approval status of a device registered for a child.
+pub fn child_device(base: &str, tenant_id: &str, child_id: &str, device_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/devices/{}",
+            tenant_scope(tenant_id),
+            enc(child_id),
+            enc(device_id)
+        ),
+    )
+}
+
 /// `POST` -- submit a heartbeat batch from a device, reporting active-use minutes.
 pub fn child_device_heartbeat(
     base: &str,
@@ -116,6 +206,20 @@ pub fn child_device_heartbeat(
     )
 }
 
+/// `GET` -- WebSocket upgrade for a device agent: heartbeats in, events and
+/// commands out. Returns an `http(s)` URL; callers switch the scheme.
+pub fn child_device_ws(base: &str, tenant_id: &str, child_id: &str, device_id: &str) -> String {
+    base_join(
+        base,
+        &format!(
+            "{}/children/{}/device/{}/ws",
+            tenant_scope(tenant_id),
+            enc(child_id),
+            enc(device_id)
+        ),
+    )
+}
+
 /// `GET` -- retrieve the server's version information.
 pub fn version(base: &str) -> String {
     base_join(base, &format!("{}/version", API_V1_PREFIX))
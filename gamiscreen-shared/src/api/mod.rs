@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 /// URL prefix for all versioned API endpoints.
@@ -19,22 +21,98 @@ pub mod ts_export;
 /// Credentials submitted by a parent to obtain a session token.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct AuthReq {
     pub username: String,
     pub password: String,
+    /// Code from the authenticator app, or an unused recovery code. Required
+    /// for parents with two-factor authentication enabled; without it the
+    /// login fails with `totp_required`.
+    #[serde(default)]
+    pub totp_code: Option<String>,
 }
 
 /// Session token returned after successful parent authentication.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct AuthResp {
     /// JWT bearer token for subsequent authenticated requests.
     pub token: String,
 }
 
+/// Single-use ticket for opening the SSE stream, which cannot carry a bearer
+/// header.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SseTicketResp {
+    /// Opaque value for the `ticket` query parameter of the SSE endpoint.
+    pub ticket: String,
+    /// Seconds until the ticket expires if unused.
+    pub expires_in_secs: u32,
+}
+
+/// Login methods offered besides username and password.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct AuthOptionsDto {
+    /// Label of the OpenID Connect login, if one is configured.
+    pub oidc_name: Option<String>,
+}
+
+/// Code the OIDC callback redirects the web app with, traded once for a
+/// session token.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct OidcExchangeReq {
+    pub code: String,
+}
+
+/// Whether the calling parent has two-factor authentication enabled.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TotpStatusDto {
+    pub enabled: bool,
+    /// Recovery codes not used yet.
+    pub recovery_codes_left: u32,
+}
+
+/// A started TOTP enrollment, to be added to an authenticator app and then
+/// confirmed with a code.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TotpEnrollResp {
+    /// Base32 secret for entering the account by hand.
+    pub secret: String,
+    /// `otpauth://` URI for enrolling by QR code.
+    pub otpauth_uri: String,
+}
+
+/// A code from the authenticator app (or, to disable, a recovery code).
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TotpCodeReq {
+    pub code: String,
+}
+
+/// Single-use recovery codes, shown once when TOTP is enabled.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TotpRecoveryCodesResp {
+    pub recovery_codes: Vec<String>,
+}
+
 /// Summary of a child profile, used in list responses.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct ChildDto {
     pub id: String,
     /// Human-readable name shown in the UI.
@@ -44,6 +122,7 @@ pub struct ChildDto {
 /// A task definition that can earn screen time when completed.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct TaskDto {
     pub id: String,
     /// Short label shown to the child (e.g. "Brush teeth").
@@ -59,6 +138,7 @@ pub struct TaskDto {
 /// Returned by the per-child tasks endpoint so the UI can show completion state.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct TaskWithStatusDto {
     pub id: String,
     pub name: String,
@@ -68,6 +148,30 @@ pub struct TaskWithStatusDto {
     pub required: bool,
     /// RFC 3339 UTC timestamp of the most recent completion, or `None` if never done.
     pub last_done: Option<String>,
+    /// Scheduled instances of this task for the child: open ones and those
+    /// missed during the last week.
+    #[serde(default)]
+    pub chores: Vec<ChoreInstanceDto>,
+}
+
+/// One dated instance of a scheduled chore.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ChoreInstanceDto {
+    pub id: i32,
+    /// Chore ID from the server configuration.
+    pub chore_id: String,
+    /// RFC 3339 UTC timestamp by which the task must be completed.
+    pub due_at: String,
+    /// `"open"` or `"missed"`.
+    pub status: String,
+    /// Whether completing the task now counts for this instance.
+    pub available: bool,
+    /// Open but past its due time (the penalty is applied shortly).
+    pub overdue: bool,
+    /// Minutes deducted if the instance is missed.
+    pub penalty_minutes: i32,
 }
 
 /// Current screen-time state for a child.
@@ -76,6 +180,7 @@ pub struct TaskWithStatusDto {
 /// how many minutes the child can use and whether access is blocked.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct RemainingDto {
     pub child_id: String,
     /// Actual usable screen-time minutes (stored in DB, updated transactionally).
@@ -97,6 +202,7 @@ pub struct RemainingDto {
 /// but create a negative balance (debt) that must be repaid through future earnings.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct RewardReq {
     pub child_id: String,
     /// If set, reward is for completing this task (minutes taken from task definition).
@@ -114,6 +220,7 @@ pub struct RewardReq {
 /// Updated screen-time totals returned after a reward is granted.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct RewardResp {
     /// New remaining minutes after the reward.
     pub remaining_minutes: i32,
@@ -127,6 +234,7 @@ pub struct RewardResp {
 /// The server deduplicates and decrements `remaining_minutes` for each new minute.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct HeartbeatReq {
     /// UTC epoch-minute timestamps (seconds since epoch / 60).
     #[cfg_attr(feature = "ts", ts(type = "Array<number>"))]
@@ -136,6 +244,7 @@ pub struct HeartbeatReq {
 /// Updated screen-time state returned after processing a heartbeat.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct HeartbeatResp {
     /// Remaining minutes after deducting newly reported usage.
     pub remaining_minutes: i32,
@@ -148,6 +257,7 @@ pub struct HeartbeatResp {
 /// Web Push subscription request. Called by a child's browser to receive notifications.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct PushSubscribeReq {
     /// Push service endpoint URL provided by the browser.
     pub endpoint: String,
@@ -160,6 +270,7 @@ pub struct PushSubscribeReq {
 /// Confirmation of a new push subscription.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct PushSubscribeResp {
     /// Server-assigned ID for managing this subscription.
     pub subscription_id: i32,
@@ -168,6 +279,7 @@ pub struct PushSubscribeResp {
 /// Request to remove a push subscription by its endpoint URL.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct PushUnsubscribeReq {
     /// The push service endpoint URL to unsubscribe.
     pub endpoint: String,
@@ -176,14 +288,28 @@ pub struct PushUnsubscribeReq {
 /// Tenant-level configuration exposed to clients.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct ConfigResp {
     /// VAPID public key for Web Push. `None` if push notifications are not configured.
     pub push_public_key: Option<String>,
 }
 
+/// Result of reloading the server configuration.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct ConfigReloadResp {
+    /// Top-level settings that changed and are now in effect.
+    pub changed: Vec<String>,
+    /// Settings that changed in the file but keep their running value until
+    /// the server is restarted.
+    pub restart_required: Vec<String>,
+}
+
 /// Device client registration request. Sent by the Linux/Windows agent on first launch.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct ClientRegisterReq {
     /// Child to associate with this device. If `None`, the server may auto-assign.
     pub child_id: Option<String>,
@@ -194,6 +320,7 @@ pub struct ClientRegisterReq {
 /// Credentials returned after successful device registration.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct ClientRegisterResp {
     /// JWT bearer token the device uses for heartbeat and remaining calls.
     pub token: String,
@@ -202,9 +329,54 @@ pub struct ClientRegisterResp {
     pub device_id: String,
 }
 
+/// A device registered or waiting to be registered for a child.
+///
+/// Registrations started with a child's token wait for a parent to approve
+/// them; `POST .../register` answers `202 Accepted` with this until then.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct DeviceDto {
+    /// Server-assigned ID (used by the approve/discard routes).
+    pub id: i32,
+    pub child_id: String,
+    pub device_id: String,
+    /// `"pending"`, `"approved"`, `"denied"` or `"blocked"`.
+    pub status: String,
+    /// RFC 3339 UTC timestamp when the device was first seen or last asked
+    /// for approval.
+    pub created_at: String,
+    /// RFC 3339 UTC timestamp when a parent decided.
+    pub resolved_at: Option<String>,
+}
+
+/// Code a parent hands to a new device so it can register for a child
+/// without the child's password.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PairingCodeResp {
+    /// Single-use code formatted `XXXX-XXXX`; case and dashes are ignored
+    /// when it is entered.
+    pub code: String,
+    /// Seconds until the code expires if unused.
+    pub expires_in_secs: u32,
+}
+
+/// Pairing code redeemed by the device being registered.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct PairReq {
+    pub code: String,
+    /// Unique device identifier (e.g. machine-id).
+    pub device_id: String,
+}
+
 /// A single reward event in the child's history.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct RewardHistoryItemDto {
     /// RFC 3339 UTC timestamp when the reward was granted.
     pub time: String,
@@ -219,6 +391,7 @@ pub struct RewardHistoryItemDto {
 /// A single bucket in a usage time series (e.g. one hour of a daily chart).
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct UsageBucketDto {
     /// RFC 3339 UTC timestamp for the start of this bucket.
     pub start: String,
@@ -229,6 +402,7 @@ pub struct UsageBucketDto {
 /// Aggregated usage over a time range, split into fixed-size buckets.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct UsageSeriesDto {
     /// RFC 3339 UTC start of the requested range (inclusive).
     pub start: String,
@@ -242,35 +416,418 @@ pub struct UsageSeriesDto {
     pub total_minutes: u32,
 }
 
+/// State of a child's savings pot.
+///
+/// Saved minutes are not spendable until withdrawn back into `remaining_minutes`.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SavingsDto {
+    pub child_id: String,
+    /// Minutes currently held in the savings pot.
+    pub savings_minutes: i32,
+    /// Effective remaining minutes (same semantics as `RemainingDto`).
+    pub remaining_minutes: i32,
+    /// RFC 3339 UTC timestamp until which the child cannot withdraw, if locked.
+    pub locked_until: Option<String>,
+    /// Interest credited weekly, as a percentage of the pot.
+    pub weekly_interest_percent: f64,
+    /// RFC 3339 UTC timestamp of the next interest payment, or `None` when the pot is empty.
+    pub next_interest_at: Option<String>,
+}
+
+/// Request to move minutes into or out of the savings pot.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SavingsReq {
+    /// Minutes to move (must be positive).
+    pub minutes: i32,
+}
+
+/// A single savings ledger entry.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SavingsTransactionDto {
+    /// RFC 3339 UTC timestamp of the movement.
+    pub time: String,
+    /// One of `"deposit"`, `"withdraw"` or `"interest"`.
+    pub kind: String,
+    /// Signed change to the pot (negative for withdrawals).
+    pub amount: i32,
+    /// Pot balance after this entry.
+    pub savings_after: i32,
+}
+
 /// Request from a child to submit a completed task for parent approval.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct SubmitTaskReq {
     pub child_id: String,
     pub task_id: String,
 }
 
-/// Count of pending notifications (e.g. task submissions awaiting approval).
+/// Unread notifications in the caller's inbox.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct NotificationsCountDto {
+    /// Total unread, unresolved notifications.
     pub count: u32,
+    /// Unread notifications keyed by `kind`; kinds with none are omitted.
+    pub by_kind: BTreeMap<String, u32>,
+}
+
+/// Typed content of a notification. The `kind` tag is also exposed as
+/// [`NotificationItemDto::kind`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NotificationPayload {
+    /// Parents: a child submitted a task for approval.
+    TaskSubmission {
+        submission_id: i32,
+        task_id: String,
+        task_name: String,
+        minutes: i32,
+    },
+    /// Parents: a child wants to give minutes to a sibling.
+    Transfer {
+        transfer_id: i32,
+        to_child_id: String,
+        to_child_display_name: String,
+        minutes: i32,
+        note: Option<String>,
+    },
+    /// Parents: a child asked for extra screen time.
+    TimeRequest {
+        request_id: i32,
+        minutes: i32,
+        reason: String,
+    },
+    /// Parents: a child wants to register a new device.
+    DeviceRegistration {
+        /// ID of the device request (see [`DeviceDto::id`]).
+        request_id: i32,
+        device_id: String,
+    },
+    /// Parents: a child's account was locked after repeated failed logins.
+    AccountLocked {
+        username: String,
+        /// RFC 3339 UTC time the lock ends.
+        locked_until: String,
+    },
+    /// Parents: a device agent disconnected and did not come back.
+    DeviceOffline { child_id: String, device_id: String },
+    /// Parents: borrowing took a child's account balance to the configured
+    /// debt limit.
+    DebtLimitReached {
+        /// Account balance after the loan (negative: minutes owed).
+        balance: i32,
+        /// Configured `debt_limit_minutes`.
+        limit: i32,
+    },
+    /// Child: a parent approved or rejected a submitted task.
+    TaskReviewed {
+        task_id: String,
+        task_name: String,
+        /// Minutes credited (may be less than the task's minutes).
+        minutes: i32,
+        approved: bool,
+        /// Username of the parent who decided.
+        by: String,
+        /// Parent's comment, e.g. why the task was rejected.
+        #[serde(default)]
+        comment: Option<String>,
+    },
+    /// Child: a scheduled chore passed its due time without being completed.
+    ChoreMissed {
+        chore_id: String,
+        task_id: String,
+        task_name: String,
+        /// RFC 3339 UTC due time of the missed instance.
+        due_at: String,
+        /// Minutes deducted (0 when the chore has no penalty).
+        penalty_minutes: i32,
+    },
+    /// Child: a parent granted minutes (or applied a penalty when negative).
+    RewardGranted {
+        minutes: i32,
+        description: Option<String>,
+        is_borrowed: bool,
+        by: String,
+    },
+    /// Child: minutes were moved between this child and a sibling, or a
+    /// transfer the child asked for was denied.
+    TransferResolved {
+        transfer_id: i32,
+        from_child_id: String,
+        from_child_display_name: String,
+        to_child_id: String,
+        to_child_display_name: String,
+        minutes: i32,
+        approved: bool,
+    },
+    /// Child: a parent approved or denied an extra-time request.
+    TimeRequestResolved {
+        request_id: i32,
+        minutes: i32,
+        approved: bool,
+        is_borrowed: bool,
+    },
+}
+
+impl NotificationPayload {
+    /// The `kind` tag this payload serializes with.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::TaskSubmission { .. } => "task_submission",
+            Self::Transfer { .. } => "transfer",
+            Self::TimeRequest { .. } => "time_request",
+            Self::DeviceRegistration { .. } => "device_registration",
+            Self::AccountLocked { .. } => "account_locked",
+            Self::DeviceOffline { .. } => "device_offline",
+            Self::DebtLimitReached { .. } => "debt_limit_reached",
+            Self::TaskReviewed { .. } => "task_reviewed",
+            Self::ChoreMissed { .. } => "chore_missed",
+            Self::RewardGranted { .. } => "reward_granted",
+            Self::TransferResolved { .. } => "transfer_resolved",
+            Self::TimeRequestResolved { .. } => "time_request_resolved",
+        }
+    }
 }
 
-/// A pending task-submission notification shown to the parent.
+/// Who a notification is addressed to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationAudience {
+    /// Every parent of the family.
+    Parents,
+    /// The child the notification is about.
+    Child,
+}
+
+/// An entry in a parent's or child's notification inbox.
+///
+/// Parent notifications for submissions, transfers and time requests are
+/// resolved through the kind-specific approve/discard routes, using the
+/// source ID carried in `payload`.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct NotificationItemDto {
-    /// Server-assigned notification ID.
+    /// Server-assigned notification ID (used by the read/dismiss routes).
     pub id: i32,
-    /// Notification type discriminator (currently always `"task_submission"`).
+    /// Notification type discriminator, same as the `kind` tag of `payload`.
     pub kind: String,
+    /// Child the notification is about.
     pub child_id: String,
     pub child_display_name: String,
+    pub payload: NotificationPayload,
+    /// RFC 3339 UTC timestamp when the notification was created.
+    pub created_at: String,
+    /// Whether the caller has read the notification.
+    pub read: bool,
+    /// Whether the underlying request was already approved or discarded.
+    pub resolved: bool,
+    /// Photo proof attached to a task submission, while it is retained.
+    #[serde(default)]
+    pub attachments: Vec<AttachmentDto>,
+}
+
+/// Metadata of a photo attached to a task submission.
+///
+/// Parents fetch the image itself from `attachments/{id}` in the family scope.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct AttachmentDto {
+    pub id: i32,
+    /// MIME type detected from the uploaded bytes (e.g. `image/jpeg`).
+    pub content_type: String,
+    pub size_bytes: i32,
+}
+
+/// Request to give minutes from one child to another.
+///
+/// Sent to the giving child's transfer endpoint. Transfers initiated by the
+/// child need parent approval; transfers initiated by a parent apply at once.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TransferReq {
+    /// Child receiving the minutes.
+    pub to_child_id: String,
+    /// Minutes to give (must be positive and covered by the giver's remaining time).
+    pub minutes: i32,
+    /// Optional reason shown to the approving parent.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Outcome of a transfer request.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TransferResp {
+    /// Server-assigned transfer ID (used by the approve/discard routes).
+    pub transfer_id: i32,
+    /// `"pending"` when awaiting parent approval, `"approved"` when applied.
+    pub status: String,
+    /// Giving child's effective remaining minutes after the request.
+    pub remaining_minutes: i32,
+}
+
+/// Child's request for extra screen time.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TimeRequestReq {
+    /// Extra minutes asked for (must be positive).
+    pub minutes: i32,
+    /// Why the child needs the time, shown to the approving parent.
+    pub reason: String,
+}
+
+/// Parent decision when approving a time request.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TimeRequestApproveReq {
+    /// Grant the minutes as a loan that is repaid from future earnings.
+    #[serde(default)]
+    pub is_borrowed: bool,
+}
+
+/// Parent decision when approving a task submission.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SubmissionApproveReq {
+    /// Partial credit: minutes to grant instead of the task's full minutes.
+    #[serde(default)]
+    pub minutes: Option<i32>,
+    /// Optional note delivered to the child.
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Parent decision when rejecting a task submission.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct SubmissionRejectReq {
+    /// Why the submission was rejected, delivered to the child.
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// A task submitted by a child and its outcome.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TaskSubmissionDto {
+    /// Server-assigned submission ID (used by the approve/discard routes).
+    pub id: i32,
+    pub child_id: String,
     pub task_id: String,
     pub task_name: String,
-    /// RFC 3339 UTC timestamp when the child submitted the task.
+    /// Minutes the task is worth.
+    pub task_minutes: i32,
+    /// `"pending"`, `"approved"` or `"rejected"`.
+    pub status: String,
+    /// Minutes credited on approval; less than `task_minutes` for partial credit.
+    pub minutes_awarded: Option<i32>,
+    /// Parent's comment on the decision.
+    pub comment: Option<String>,
+    /// RFC 3339 UTC timestamp when the child submitted.
     pub submitted_at: String,
+    /// Username of the parent who decided.
+    pub resolved_by: Option<String>,
+    /// RFC 3339 UTC timestamp when a parent decided.
+    pub resolved_at: Option<String>,
+}
+
+/// One audit log entry: an action, who performed it and what it changed.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct AuditEntryDto {
+    pub id: i32,
+    /// RFC 3339 UTC timestamp of the action.
+    pub created_at: String,
+    /// Username that performed the action.
+    pub actor: String,
+    pub role: crate::auth::Role,
+    /// `x-request-id` of the request that performed the action.
+    pub request_id: Option<String>,
+    /// Action name such as `"reward.grant"` or `"submission.approve"`.
+    pub action: String,
+    pub child_id: Option<String>,
+    /// ID of the affected submission, transfer, time request or device.
+    pub target_id: Option<String>,
+    /// State before the action, when there was one.
+    pub before: Option<serde_json::Value>,
+    /// State after the action.
+    pub after: Option<serde_json::Value>,
+}
+
+/// A configured webhook (the signing secret is never returned).
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct WebhookDto {
+    pub id: String,
+    pub url: String,
+    /// Event names delivered to this webhook; empty means all.
+    pub events: Vec<String>,
+}
+
+/// One event delivery to a webhook and the state of its attempts.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct WebhookDeliveryDto {
+    pub id: i32,
+    pub webhook_id: String,
+    /// Event name, e.g. `"remaining_updated"` or `"ping"`.
+    pub event: String,
+    /// `"pending"`, `"delivered"` or `"failed"`.
+    pub status: String,
+    pub attempts: i32,
+    /// HTTP status of the last attempt, if the endpoint answered.
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    /// RFC 3339 UTC timestamp when the event was queued.
+    pub created_at: String,
+    /// RFC 3339 UTC timestamp of the next retry while pending.
+    pub next_attempt_at: Option<String>,
+    pub delivered_at: Option<String>,
+}
+
+/// A child's request for extra screen time and its outcome.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+pub struct TimeRequestDto {
+    /// Server-assigned request ID (used by the approve/discard routes).
+    pub id: i32,
+    pub child_id: String,
+    pub minutes: i32,
+    pub reason: String,
+    /// `"pending"`, `"approved"` or `"denied"`.
+    pub status: String,
+    /// Whether an approved request was granted as a loan.
+    pub is_borrowed: bool,
+    /// RFC 3339 UTC timestamp when the child asked.
+    pub created_at: String,
+    /// RFC 3339 UTC timestamp when a parent decided.
+    pub resolved_at: Option<String>,
 }
 
 /// OTA update manifest listing available client packages and versions.
@@ -279,6 +836,7 @@ pub struct NotificationItemDto {
 /// and semantic version. Clients select the newest compatible version.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct UpdateManifestDto {
     /// Manifest schema version (currently 2).
     pub schema_version: u32,
@@ -291,6 +849,7 @@ pub struct UpdateManifestDto {
 /// A single updatable package with its version and platform artifacts.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct UpdateItemDto {
     /// Package name (e.g. `"gamiscreen-client"`).
     pub package: String,
@@ -303,6 +862,7 @@ pub struct UpdateItemDto {
 /// A downloadable binary for a specific OS/arch combination.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct UpdateArtifactDto {
     /// Target operating system (e.g. `"linux"`, `"windows"`).
     pub os: String,
@@ -317,13 +877,16 @@ pub struct UpdateArtifactDto {
 /// Server version information.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct VersionInfoDto {
     /// Semantic version string (e.g. `"1.2.3"`).
     pub version: String,
 }
 
-/// Real-time events pushed to connected clients over WebSocket.
+/// Real-time events pushed to connected clients over SSE and the device agent
+/// WebSocket.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 #[serde(tag = "type")]
 pub enum ServerEvent {
     /// Notifies the parent that the count of pending task submissions changed.
@@ -337,4 +900,79 @@ pub enum ServerEvent {
         balance: i32,
         blocked_by_tasks: bool,
     },
+    /// Notifies inbox owners that a notification was added; parents receive
+    /// `parents` notifications, a child only its own `child` notifications.
+    #[serde(rename = "notification_created")]
+    NotificationCreated {
+        notification_id: i32,
+        audience: NotificationAudience,
+        child_id: String,
+        payload: NotificationPayload,
+    },
+    /// Notifies a child that a parent approved or denied their time request.
+    #[serde(rename = "time_request_resolved")]
+    TimeRequestResolved {
+        child_id: String,
+        request_id: i32,
+        minutes: i32,
+        approved: bool,
+        is_borrowed: bool,
+    },
+    /// Notifies parents that a device agent opened or lost its WebSocket.
+    #[serde(rename = "device_connection")]
+    DeviceConnection {
+        child_id: String,
+        device_id: String,
+        connected: bool,
+    },
+    /// Sent on a stream that missed events which cannot be replayed; the
+    /// client should refetch the state it displays.
+    #[serde(rename = "resync")]
+    Resync,
+}
+
+/// Messages a device agent sends over its WebSocket.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum AgentMessage {
+    /// Active-use minutes, processed like [`HeartbeatReq`].
+    #[serde(rename = "heartbeat")]
+    Heartbeat { minutes: Vec<i64> },
+}
+
+/// Messages the server sends to a device agent over its WebSocket.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(tag = "type")]
+pub enum AgentServerMessage {
+    /// Result of a heartbeat; `minutes` echoes the acknowledged timestamps.
+    #[serde(rename = "heartbeat_ack")]
+    HeartbeatAck {
+        minutes: Vec<i64>,
+        remaining_minutes: i32,
+        balance: i32,
+        blocked_by_tasks: bool,
+    },
+    /// A server event addressed to the device's child.
+    #[serde(rename = "event")]
+    Event { event: ServerEvent },
+    /// Sent on connect and whenever the child's screen time becomes locked
+    /// or unlocked.
+    #[serde(rename = "command")]
+    Command { command: AgentCommand },
+    /// A message from the agent could not be processed.
+    #[serde(rename = "error")]
+    Error { message: String },
+}
+
+/// Action the server asks a device agent to take.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum AgentCommand {
+    /// No minutes left, or required tasks are still pending.
+    Lock,
+    /// Screen time is available again.
+    Unlock,
 }
@@ -74,13 +74,21 @@ pub async fn renew_token(base: &str, bearer: &str) -> Result<AuthResp, RestError
     handle_json(res).await
 }
 
+/// Result of registering a device.
+#[derive(Debug)]
+pub enum RegisterOutcome {
+    Registered(ClientRegisterResp),
+    /// A child registered a device a parent has not approved yet.
+    Pending(DeviceDto),
+}
+
 pub async fn child_register(
     base: &str,
     tenant_id: &str,
     child_id: &str,
     device_id: &str,
     bearer: &str,
-) -> Result<ClientRegisterResp, RestError> {
+) -> Result<RegisterOutcome, RestError> {
     let client = mk_client()?;
     let url = ep::child_register(base, tenant_id, child_id);
     let body = ClientRegisterReq {
@@ -94,6 +102,39 @@ pub async fn child_register(
         .send()
         .await
         .map_err(|e| RestError::Http(e.to_string()))?;
+    if res.status() == StatusCode::ACCEPTED {
+        return handle_json(res).await.map(RegisterOutcome::Pending);
+    }
+    handle_json(res).await.map(RegisterOutcome::Registered)
+}
+
+pub async fn child_device(
+    base: &str,
+    tenant_id: &str,
+    child_id: &str,
+    device_id: &str,
+    bearer: &str,
+) -> Result<DeviceDto, RestError> {
+    let client = mk_client()?;
+    let url = ep::child_device(base, tenant_id, child_id, device_id);
+    let res = client
+        .get(url)
+        .bearer_auth(bearer)
+        .send()
+        .await
+        .map_err(|e| RestError::Http(e.to_string()))?;
+    handle_json(res).await
+}
+
+pub async fn pair_device(base: &str, req: &PairReq) -> Result<ClientRegisterResp, RestError> {
+    let client = mk_client()?;
+    let url = ep::auth_pair(base);
+    let res = client
+        .post(url)
+        .json(req)
+        .send()
+        .await
+        .map_err(|e| RestError::Http(e.to_string()))?;
     handle_json(res).await
 }
 
@@ -139,6 +180,25 @@ pub async fn child_reward(
     handle_json(res).await
 }
 
+pub async fn child_request_time(
+    base: &str,
+    tenant_id: &str,
+    child_id: &str,
+    bearer: &str,
+    body: &TimeRequestReq,
+) -> Result<TimeRequestDto, RestError> {
+    let client = mk_client()?;
+    let url = ep::child_time_requests(base, tenant_id, child_id);
+    let res = client
+        .post(url)
+        .bearer_auth(bearer)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| RestError::Http(e.to_string()))?;
+    handle_json(res).await
+}
+
 pub async fn list_children(
     base: &str,
     tenant_id: &str,
@@ -6,11 +6,17 @@ use ts_rs::{Config, TS};
 
 #[cfg(feature = "ts")]
 use super::{
-    AuthReq, AuthResp, ChildDto, ClientRegisterReq, ClientRegisterResp, ConfigResp, HeartbeatReq,
-    HeartbeatResp, NotificationItemDto, NotificationsCountDto, PushSubscribeReq, PushSubscribeResp,
-    PushUnsubscribeReq, RemainingDto, RewardHistoryItemDto, RewardReq, RewardResp, SubmitTaskReq,
-    TaskDto, TaskWithStatusDto, UpdateArtifactDto, UpdateItemDto, UpdateManifestDto,
-    UsageBucketDto, UsageSeriesDto, VersionInfoDto,
+    AttachmentDto, AuditEntryDto, AuthOptionsDto, AuthReq, AuthResp, ChildDto, ChoreInstanceDto,
+    ClientRegisterReq, ClientRegisterResp, ConfigReloadResp, ConfigResp, DeviceDto, HeartbeatReq,
+    HeartbeatResp, NotificationAudience, NotificationItemDto, NotificationPayload,
+    NotificationsCountDto, OidcExchangeReq, PairReq, PairingCodeResp, PushSubscribeReq,
+    PushSubscribeResp, PushUnsubscribeReq, RemainingDto, RewardHistoryItemDto, RewardReq,
+    RewardResp, SavingsDto, SavingsReq, SavingsTransactionDto, SseTicketResp, SubmissionApproveReq,
+    SubmissionRejectReq, SubmitTaskReq, TaskDto, TaskSubmissionDto, TaskWithStatusDto,
+    TimeRequestApproveReq, TimeRequestDto, TimeRequestReq, TotpCodeReq, TotpEnrollResp,
+    TotpRecoveryCodesResp, TotpStatusDto, TransferReq, TransferResp, UpdateArtifactDto,
+    UpdateItemDto, UpdateManifestDto, UsageBucketDto, UsageSeriesDto, VersionInfoDto,
+    WebhookDeliveryDto, WebhookDto,
 };
 #[cfg(feature = "ts")]
 use crate::{auth::Role, jwt::JwtClaims};
@@ -47,8 +53,16 @@ pub fn export_types(path: impl AsRef<Path>) -> std::io::Result<()> {
 
     write_decl(AuthReq::decl(&config))?;
     write_decl(AuthResp::decl(&config))?;
+    write_decl(SseTicketResp::decl(&config))?;
+    write_decl(AuthOptionsDto::decl(&config))?;
+    write_decl(OidcExchangeReq::decl(&config))?;
+    write_decl(TotpStatusDto::decl(&config))?;
+    write_decl(TotpEnrollResp::decl(&config))?;
+    write_decl(TotpCodeReq::decl(&config))?;
+    write_decl(TotpRecoveryCodesResp::decl(&config))?;
     write_decl(ChildDto::decl(&config))?;
     write_decl(TaskDto::decl(&config))?;
+    write_decl(ChoreInstanceDto::decl(&config))?;
     write_decl(TaskWithStatusDto::decl(&config))?;
     write_decl(RemainingDto::decl(&config))?;
     write_decl(RewardReq::decl(&config))?;
@@ -56,22 +70,44 @@ pub fn export_types(path: impl AsRef<Path>) -> std::io::Result<()> {
     write_decl(HeartbeatReq::decl(&config))?;
     write_decl(HeartbeatResp::decl(&config))?;
     write_decl(ConfigResp::decl(&config))?;
+    write_decl(ConfigReloadResp::decl(&config))?;
     write_decl(PushSubscribeReq::decl(&config))?;
     write_decl(PushSubscribeResp::decl(&config))?;
     write_decl(PushUnsubscribeReq::decl(&config))?;
     write_decl(ClientRegisterReq::decl(&config))?;
     write_decl(ClientRegisterResp::decl(&config))?;
+    write_decl(DeviceDto::decl(&config))?;
+    write_decl(PairingCodeResp::decl(&config))?;
+    write_decl(PairReq::decl(&config))?;
     write_decl(RewardHistoryItemDto::decl(&config))?;
     write_decl(UsageBucketDto::decl(&config))?;
     write_decl(UsageSeriesDto::decl(&config))?;
+    write_decl(SavingsDto::decl(&config))?;
+    write_decl(SavingsReq::decl(&config))?;
+    write_decl(SavingsTransactionDto::decl(&config))?;
     write_decl(SubmitTaskReq::decl(&config))?;
+    write_decl(SubmissionApproveReq::decl(&config))?;
+    write_decl(SubmissionRejectReq::decl(&config))?;
+    write_decl(TaskSubmissionDto::decl(&config))?;
+    write_decl(AuditEntryDto::decl(&config))?;
+    write_decl(WebhookDto::decl(&config))?;
+    write_decl(WebhookDeliveryDto::decl(&config))?;
     write_decl(NotificationsCountDto::decl(&config))?;
+    write_decl(NotificationAudience::decl(&config))?;
+    write_decl(NotificationPayload::decl(&config))?;
+    write_decl(AttachmentDto::decl(&config))?;
     write_decl(NotificationItemDto::decl(&config))?;
+    write_decl(TransferReq::decl(&config))?;
+    write_decl(TransferResp::decl(&config))?;
+    write_decl(TimeRequestReq::decl(&config))?;
+    write_decl(TimeRequestApproveReq::decl(&config))?;
+    write_decl(TimeRequestDto::decl(&config))?;
     write_decl(UpdateManifestDto::decl(&config))?;
     write_decl(UpdateItemDto::decl(&config))?;
     write_decl(UpdateArtifactDto::decl(&config))?;
     write_decl(VersionInfoDto::decl(&config))?;
     write_decl(Role::decl(&config))?;
+    write_decl(serde_json::Value::decl(&config))?;
     write_decl(JwtClaims::decl(&config))?;
 
     Ok(())
@@ -58,7 +58,8 @@ impl Minutes {
 }
 
 /// A child profile as stored in the database.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct Child {
     pub id: String,
     /// Human-readable name shown in the UI.
@@ -66,7 +67,8 @@ pub struct Child {
 }
 
 /// A task that a child can complete to earn screen time.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(schemars::JsonSchema))]
 pub struct Task {
     pub id: String,
     /// Short label shown to the child (e.g. "Brush teeth").
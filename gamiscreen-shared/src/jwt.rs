@@ -1,5 +1,11 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
 use base64::Engine;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use jsonwebtoken::jwk::KeyAlgorithm;
+pub use jsonwebtoken::jwk::{self, Jwk, JwkSet};
 use jsonwebtoken::{self, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -27,6 +33,164 @@ pub enum JwtError {
     MissingTenant,
     #[error("encoding failed: {0}")]
     Encode(String),
+    #[error("unknown signing key: {0}")]
+    UnknownKey(String),
+    #[error("invalid key: {0}")]
+    InvalidKey(String),
+}
+
+/// Asymmetric algorithms supported for signing keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SigningAlgorithm {
+    EdDSA,
+    ES256,
+}
+
+impl SigningAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SigningAlgorithm::EdDSA => "EdDSA",
+            SigningAlgorithm::ES256 => "ES256",
+        }
+    }
+
+    fn algorithm(self) -> Algorithm {
+        match self {
+            SigningAlgorithm::EdDSA => Algorithm::EdDSA,
+            SigningAlgorithm::ES256 => Algorithm::ES256,
+        }
+    }
+}
+
+impl fmt::Display for SigningAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SigningAlgorithm {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "eddsa" => Ok(SigningAlgorithm::EdDSA),
+            "es256" => Ok(SigningAlgorithm::ES256),
+            other => Err(JwtError::InvalidKey(format!(
+                "unsupported algorithm: {other}"
+            ))),
+        }
+    }
+}
+
+/// Private key that signs new tokens; its `kid` goes into the token header.
+pub struct SigningKey {
+    kid: String,
+    alg: SigningAlgorithm,
+    key: EncodingKey,
+}
+
+impl SigningKey {
+    /// Wrap a PKCS#8 DER private key for `alg`.
+    pub fn from_pkcs8_der(kid: impl Into<String>, alg: SigningAlgorithm, der: &[u8]) -> Self {
+        let key = match alg {
+            SigningAlgorithm::EdDSA => EncodingKey::from_ed_der(der),
+            SigningAlgorithm::ES256 => EncodingKey::from_ec_der(der),
+        };
+        Self {
+            kid: kid.into(),
+            alg,
+            key,
+        }
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn alg(&self) -> SigningAlgorithm {
+        self.alg
+    }
+
+    pub fn sign(&self, claims: &JwtClaims) -> Result<String, JwtError> {
+        let mut header = Header::new(self.alg.algorithm());
+        header.kid = Some(self.kid.clone());
+        jsonwebtoken::encode(&header, claims, &self.key)
+            .map_err(|e| JwtError::Encode(e.to_string()))
+    }
+}
+
+/// Keys accepted when verifying tokens: public keys looked up by the token's
+/// `kid`, and optionally an HS256 secret for legacy tokens without one.
+#[derive(Default)]
+pub struct KeySet {
+    keys: HashMap<String, (Algorithm, DecodingKey)>,
+    hs256: Option<DecodingKey>,
+}
+
+impl KeySet {
+    /// Build a key set from a JWKS document, such as the server's
+    /// `/.well-known/jwks.json`.
+    pub fn from_jwks(jwks: &JwkSet) -> Result<Self, JwtError> {
+        let mut set = Self::default();
+        for jwk in &jwks.keys {
+            set.insert(jwk)?;
+        }
+        Ok(set)
+    }
+
+    /// Also accept HS256 tokens without a `kid`, signed with `secret`.
+    pub fn with_hs256_secret(mut self, secret: &[u8]) -> Self {
+        self.hs256 = Some(DecodingKey::from_secret(secret));
+        self
+    }
+
+    /// Add a public key; it must carry a `kid` and an `alg`.
+    pub fn insert(&mut self, jwk: &Jwk) -> Result<(), JwtError> {
+        let kid = jwk
+            .common
+            .key_id
+            .clone()
+            .ok_or_else(|| JwtError::InvalidKey("key without kid".into()))?;
+        let alg = match jwk.common.key_algorithm {
+            Some(KeyAlgorithm::EdDSA) => Algorithm::EdDSA,
+            Some(KeyAlgorithm::ES256) => Algorithm::ES256,
+            Some(other) => {
+                return Err(JwtError::InvalidKey(format!(
+                    "key {kid} uses unsupported algorithm {other}"
+                )));
+            }
+            None => return Err(JwtError::InvalidKey(format!("key {kid} without alg"))),
+        };
+        let key = DecodingKey::from_jwk(jwk).map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+        self.keys.insert(kid, (alg, key));
+        Ok(())
+    }
+
+    pub fn verify(&self, token: &str) -> Result<JwtClaims, JwtError> {
+        let header =
+            jsonwebtoken::decode_header(token).map_err(|e| JwtError::Decode(e.to_string()))?;
+        let (alg, key) = match &header.kid {
+            Some(kid) => {
+                let (alg, key) = self
+                    .keys
+                    .get(kid)
+                    .ok_or_else(|| JwtError::UnknownKey(kid.clone()))?;
+                (*alg, key)
+            }
+            None => {
+                let key = self
+                    .hs256
+                    .as_ref()
+                    .ok_or_else(|| JwtError::Decode("token has no key id".into()))?;
+                (Algorithm::HS256, key)
+            }
+        };
+        // Rejects tokens whose header names a different algorithm than the key.
+        let validation = Validation::new(alg);
+        jsonwebtoken::decode::<JwtClaims>(token, key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| JwtError::Decode(e.to_string()))
+    }
 }
 
 pub fn decode_unverified(token: &str) -> Result<JwtClaims, JwtError> {
@@ -42,14 +206,12 @@ pub fn decode_unverified(token: &str) -> Result<JwtClaims, JwtError> {
         .map_err(|e| JwtError::Decode(format!("invalid json payload: {e}")))
 }
 
+/// Verify a legacy HS256 token signed with a shared secret.
 pub fn decode_and_verify(token: &str, secret: &[u8]) -> Result<JwtClaims, JwtError> {
-    let key = DecodingKey::from_secret(secret);
-    let validation = Validation::new(Algorithm::HS256);
-    jsonwebtoken::decode::<JwtClaims>(token, &key, &validation)
-        .map(|data| data.claims)
-        .map_err(|e| JwtError::Decode(e.to_string()))
+    KeySet::default().with_hs256_secret(secret).verify(token)
 }
 
+/// Sign a legacy HS256 token with a shared secret.
 pub fn encode(token: &JwtClaims, secret: &[u8]) -> Result<String, JwtError> {
     jsonwebtoken::encode(
         &Header::new(Algorithm::HS256),
@@ -37,6 +37,58 @@
     };
   }
 
+  function minutesDesc(minutes) {
+    const m = typeof minutes === 'number' ? minutes : 0;
+    return m === 1 ? '1 minute' : `${m} minutes`;
+  }
+
+  function formatInboxItem(event) {
+    const payload = event.payload || {};
+    const desc = minutesDesc(payload.minutes);
+    let body = null;
+    switch (payload.kind) {
+      case 'task_reviewed':
+        body = payload.approved
+          ? `"${payload.task_name}" was approved: +${desc}.`
+          : `"${payload.task_name}" was rejected.`;
+        if (payload.comment) body += ` "${payload.comment}"`;
+        break;
+      case 'chore_missed':
+        body = payload.penalty_minutes > 0
+          ? `You missed "${payload.task_name}": -${minutesDesc(payload.penalty_minutes)}.`
+          : `You missed "${payload.task_name}".`;
+        break;
+      case 'reward_granted':
+        body = payload.is_borrowed ? `You borrowed ${desc}.` : `You received ${desc}.`;
+        break;
+      case 'transfer_resolved':
+        if (!payload.approved) {
+          body = `Your gift of ${desc} to ${payload.to_child_display_name} was declined.`;
+        } else if (event.child_id === payload.to_child_id) {
+          body = `${payload.from_child_display_name} gave you ${desc}.`;
+        } else {
+          body = `You gave ${desc} to ${payload.to_child_display_name}.`;
+        }
+        break;
+      case 'time_request_resolved':
+        if (!payload.approved) {
+          body = `Your request for ${desc} was denied.`;
+        } else {
+          body = payload.is_borrowed
+            ? `Your request for ${desc} was approved as a loan.`
+            : `Your request for ${desc} was approved.`;
+        }
+        break;
+      default:
+        return null;
+    }
+    return {
+      title: 'Gamiscreen',
+      body,
+      url: '#notifications',
+    };
+  }
+
   function formatNotification(event) {
     if (!event || typeof event !== 'object') return null;
     const type = event.type;
@@ -46,6 +98,9 @@
     if (type === 'pending_count') {
       return formatPending(event);
     }
+    if (type === 'notification_created') {
+      return formatInboxItem(event);
+    }
     if (event.title || event.body) {
       return {
         title: event.title || 'Gamiscreen',
@@ -1,6 +1,6 @@
 import { useCallback, useEffect, useMemo, useRef, useState } from 'react'
 import packageInfo from '../package.json'
-import { getAuthClaims, getServerVersion, getToken, notificationsCount, pushUnsubscribe, renewToken, setToken } from './api'
+import { childNotificationsCount, getAuthClaims, getServerVersion, getSseTicket, getToken, notificationsCount, pushUnsubscribe, renewToken, setToken } from './api'
 const API_V1_PREFIX = '/api/v1'
 const EMBEDDED_MODE_CHECK_MAX_RETRIES = 60 // 1 minute (60 seconds * 1 second interval)
 import { ChildDetailsPage } from './pages/ChildDetailsPage'
@@ -179,7 +179,15 @@ export function App() {
     }
   }, [])
 
-  // Notifications polling (parent)
+  // Unread count of the signed-in user's inbox
+  const fetchNotifCount = async () => {
+    const cl = getAuthClaims()
+    if (cl?.role === 'parent') return (await notificationsCount()).count
+    if (cl?.role === 'child' && cl.child_id) return (await childNotificationsCount(cl.child_id)).count
+    return 0
+  }
+
+  // Notifications polling
   const [notifCount, setNotifCount] = useState<number>(0)
   useEffect(() => {
     if (!authReady) {
@@ -194,12 +202,8 @@ export function App() {
     let timer: ReturnType<typeof setTimeout> | undefined
     const tick = async () => {
       try {
-        if (getAuthClaims()?.role === 'parent') {
-          const { count } = await notificationsCount()
-          if (!cancelled) setNotifCount(count)
-        } else if (!cancelled) {
-          setNotifCount(0)
-        }
+        const count = await fetchNotifCount()
+        if (!cancelled) setNotifCount(count)
       } catch { }
       if (!cancelled) {
         timer = setTimeout(tick, 30000)
@@ -222,53 +226,89 @@ export function App() {
       if (ls) return ls
       return serverBase
     })()
-    const sseUrl = (() => {
+    const sseUrl = (ticket: string) => {
       const scope = `${API_V1_PREFIX}/family/${encodeURIComponent(tenantId)}`
+      const params = new URLSearchParams({ ticket })
+      if (lastEventId) params.set('last_event_id', lastEventId)
       try {
         const u = new URL(base)
         // keep http/https, use tenant-scoped SSE
         u.pathname = (u.pathname.replace(/\/+$/, '')) + `${scope}/sse`
-        u.search = '?token=' + encodeURIComponent(token)
+        u.search = '?' + params.toString()
         return u.toString()
       } catch {
         const loc = window.location
-        return `${loc.protocol}//${loc.host}${scope}/sse?token=${encodeURIComponent(token)}`
+        return `${loc.protocol}//${loc.host}${scope}/sse?${params.toString()}`
       }
-    })()
+    }
     let es: EventSource | null = null
-    const connect = () => {
+    let cancelled = false
+    let lastEventId = ''
+    let retryMs = 3000
+    let timer: ReturnType<typeof setTimeout> | undefined
+    const reconnect = () => {
+      if (cancelled) return
+      timer = setTimeout(connect, retryMs)
+      retryMs = Math.min(retryMs * 2, 30000)
+    }
+    const connect = async () => {
+      let ticket: string
       try {
-        es = new EventSource(sseUrl, { withCredentials: false })
+        ticket = (await getSseTicket()).ticket
+      } catch {
+        reconnect()
+        return
+      }
+      if (cancelled) return
+      try {
+        es = new EventSource(sseUrl(ticket), { withCredentials: false })
       } catch {
         es = null
+        reconnect()
         return
       }
+      es.onopen = () => { retryMs = 3000 }
       es.onmessage = (ev) => {
+        if (ev.lastEventId) lastEventId = ev.lastEventId
         try {
           const msg = JSON.parse(ev.data)
-          if (msg && msg.type === 'pending_count' && typeof msg.count === 'number') {
-            setNotifCount(msg.count)
+          if (msg && (msg.type === 'pending_count' || msg.type === 'notification_created')) {
+            window.dispatchEvent(new Event('gamiscreen:notif-refresh'))
+            if (msg.type === 'notification_created') {
+              window.dispatchEvent(new CustomEvent('gamiscreen:notification-created', { detail: msg }))
+            }
           } else if (msg && msg.type === 'remaining_updated' && msg.child_id && typeof msg.remaining_minutes === 'number') {
             window.dispatchEvent(new CustomEvent('gamiscreen:remaining-updated', { detail: { child_id: msg.child_id, remaining_minutes: msg.remaining_minutes, balance: msg.balance, blocked_by_tasks: msg.blocked_by_tasks } }))
+          } else if (msg && msg.type === 'time_request_resolved' && msg.child_id) {
+            window.dispatchEvent(new CustomEvent('gamiscreen:time-request-resolved', { detail: msg }))
+          } else if (msg && msg.type === 'resync') {
+            // Missed events the server could not replay; reload what is shown
+            window.dispatchEvent(new Event('gamiscreen:notif-refresh'))
+            window.dispatchEvent(new Event('gamiscreen:resync'))
           }
         } catch { }
       }
       es.onerror = () => {
-        // Browser will auto-reconnect SSE; nothing to do.
+        // Tickets are single-use, so the browser's own retry would be refused;
+        // reconnect with a fresh one, passing the last event id for replay.
+        if (es) { try { es.close() } catch { } }
+        es = null
+        reconnect()
       }
     }
     connect()
-    return () => { if (es) { try { es.close() } catch { } } }
+    return () => {
+      cancelled = true
+      if (timer) clearTimeout(timer)
+      if (es) { try { es.close() } catch { } }
+    }
   }, [authReady, claims?.tenant_id, token])
   // Immediate refresh when notifications change (approve/discard)
   useEffect(() => {
     if (!authReady) return
     const refresh = async () => {
       try {
-        if (getAuthClaims()?.role === 'parent') {
-          const { count } = await notificationsCount()
-          setNotifCount(count)
-        }
+        setNotifCount(await fetchNotifCount())
       } catch { }
     }
     const handler = () => { refresh() }
@@ -280,7 +320,7 @@ export function App() {
     if (!hasToken && route !== 'login') nav('login')
     if (authReady) {
       const cl = getAuthClaims()
-    if (cl?.role === 'child' && cl.child_id && route !== 'child' && route !== 'settings') {
+    if (cl?.role === 'child' && cl.child_id && route !== 'child' && route !== 'settings' && route !== 'notifications') {
         nav('child', { childId: cl.child_id })
       }
     }
@@ -368,6 +408,9 @@ export function App() {
       )
     }
     if (isChild && claims?.child_id) {
+      if (route === 'notifications') {
+        return <NotificationsPage childId={claims.child_id} />
+      }
       return <ChildDetailsPage childId={claims.child_id} />
     }
     if (route === 'child' && params.childId) {
@@ -391,7 +434,7 @@ export function App() {
           </div>
           {authReady && (
             <div className="row" style={{ alignItems: 'center', gap: 8, position: 'relative' }}>
-              {claims?.role === 'parent' && (
+              {(claims?.role === 'parent' || claims?.child_id) && (
                 <button
                   className="secondary outline iconButton"
                   onClick={() => nav('notifications')}
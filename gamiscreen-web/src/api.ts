@@ -1,23 +1,39 @@
 import type {
+  AuthOptionsDto,
   AuthReq,
   AuthResp,
   ChildDto,
   ClientRegisterReq,
   ClientRegisterResp,
   ConfigResp,
+  DeviceDto,
   HeartbeatReq,
   HeartbeatResp,
   JwtClaims,
   NotificationItemDto,
   NotificationsCountDto,
+  OidcExchangeReq,
+  PairingCodeResp,
   RemainingDto,
   RewardHistoryItemDto,
   RewardReq,
   RewardResp,
   Role,
+  SseTicketResp,
+  SubmissionApproveReq,
+  SubmissionRejectReq,
   SubmitTaskReq,
   TaskDto,
+  TaskSubmissionDto,
   TaskWithStatusDto,
+  TimeRequestDto,
+  TimeRequestReq,
+  TotpCodeReq,
+  TotpEnrollResp,
+  TotpRecoveryCodesResp,
+  TotpStatusDto,
+  TransferReq,
+  TransferResp,
   UsageBucketDto,
   UsageSeriesDto,
   VersionInfoDto,
@@ -25,25 +41,41 @@ import type {
 } from './generated/api-types'
 
 export type {
+  AttachmentDto,
   AuthReq,
   AuthResp,
   ChildDto,
   ClientRegisterReq,
   ClientRegisterResp,
   ConfigResp,
+  DeviceDto,
   HeartbeatReq,
   HeartbeatResp,
   JwtClaims,
+  NotificationAudience,
   NotificationItemDto,
+  NotificationPayload,
   NotificationsCountDto,
   RemainingDto,
   RewardHistoryItemDto,
   RewardReq,
   RewardResp,
   Role,
+  SseTicketResp,
+  SubmissionApproveReq,
+  SubmissionRejectReq,
   SubmitTaskReq,
   TaskDto,
+  TaskSubmissionDto,
   TaskWithStatusDto,
+  TimeRequestDto,
+  TimeRequestReq,
+  TotpCodeReq,
+  TotpEnrollResp,
+  TotpRecoveryCodesResp,
+  TotpStatusDto,
+  TransferReq,
+  TransferResp,
   UsageBucketDto,
   UsageSeriesDto,
   VersionInfoDto,
@@ -193,7 +225,8 @@ async function request<T>(path: string, init?: RequestInit): Promise<T> {
   const base = apiBase()
   const url = base + path
   const headers: Record<string, string> = {
-    'Content-Type': 'application/json',
+    // Let the browser set the multipart boundary for form uploads
+    ...(init?.body instanceof FormData ? {} : { 'Content-Type': 'application/json' }),
     ...(init?.headers as Record<string, string> || {}),
   }
   const token = getToken()
@@ -231,8 +264,10 @@ function tenantPath(path: string): string {
   return `${scope}/${path.replace(/^\/+/, '')}`
 }
 
-export async function login(username: string, password: string) {
-  const body: AuthReq = { username, password }
+// Fails with the message `totp_required` when the user has two-factor
+// authentication enabled and no code was given.
+export async function login(username: string, password: string, totpCode?: string) {
+  const body: AuthReq = { username, password, totp_code: totpCode || null }
   const data = await request<AuthResp>(`${API_V1_PREFIX}/auth/login`, {
     method: 'POST',
     body: JSON.stringify(body),
@@ -241,6 +276,27 @@ export async function login(username: string, password: string) {
   return data
 }
 
+// Which login methods the server offers besides passwords
+export async function getAuthOptions() {
+  return request<AuthOptionsDto>(`${API_V1_PREFIX}/auth/options`)
+}
+
+// Where to send the browser to log in with the identity provider; it comes
+// back to `#login?oidc_code=...` or `#login?oidc_error=...`.
+export function oidcLoginUrl() {
+  return `${apiBase()}${API_V1_PREFIX}/auth/oidc/login`
+}
+
+export async function exchangeOidcCode(code: string) {
+  const body: OidcExchangeReq = { code }
+  const data = await request<AuthResp>(`${API_V1_PREFIX}/auth/oidc/exchange`, {
+    method: 'POST',
+    body: JSON.stringify(body),
+  })
+  setToken(data.token)
+  return data
+}
+
 export async function renewToken() {
   const data = await request<AuthResp>(`${API_V1_PREFIX}/auth/renew`, {
     method: 'POST',
@@ -249,6 +305,37 @@ export async function renewToken() {
   return data
 }
 
+// Two-factor authentication of the logged-in parent
+export async function getTotpStatus() {
+  return request<TotpStatusDto>(`${API_V1_PREFIX}/auth/totp`)
+}
+
+export async function enrollTotp() {
+  return request<TotpEnrollResp>(`${API_V1_PREFIX}/auth/totp/enroll`, { method: 'POST' })
+}
+
+export async function verifyTotp(code: string) {
+  const body: TotpCodeReq = { code }
+  return request<TotpRecoveryCodesResp>(`${API_V1_PREFIX}/auth/totp/verify`, {
+    method: 'POST',
+    body: JSON.stringify(body),
+  })
+}
+
+export async function disableTotp(code: string) {
+  const body: TotpCodeReq = { code }
+  return request<void>(`${API_V1_PREFIX}/auth/totp/disable`, {
+    method: 'POST',
+    body: JSON.stringify(body),
+  })
+}
+
+// Single-use ticket for opening the event stream; EventSource cannot send
+// the bearer token as a header.
+export async function getSseTicket() {
+  return request<SseTicketResp>(tenantPath('sse/ticket'), { method: 'POST' })
+}
+
 export async function listChildren() {
   return request<ChildDto[]>(tenantPath('children'))
 }
@@ -282,6 +369,28 @@ export async function listChildUsage(
   return request<UsageSeriesDto>(url)
 }
 
+// Code that registers one device for the child with
+// `gamiscreen-client login --code`
+export async function createPairingCode(childId: string) {
+  return request<PairingCodeResp>(tenantPath(`children/${encodeURIComponent(childId)}/pairing-code`), {
+    method: 'POST',
+  })
+}
+
+export async function listChildDevices(childId: string) {
+  return request<DeviceDto[]>(tenantPath(`children/${encodeURIComponent(childId)}/devices`))
+}
+
+export async function blockDevice(childId: string, deviceId: string) {
+  const path = tenantPath(`children/${encodeURIComponent(childId)}/devices/${encodeURIComponent(deviceId)}/block`)
+  return request<DeviceDto>(path, { method: 'POST' })
+}
+
+export async function unblockDevice(childId: string, deviceId: string) {
+  const path = tenantPath(`children/${encodeURIComponent(childId)}/devices/${encodeURIComponent(deviceId)}/unblock`)
+  return request<void>(path, { method: 'POST' })
+}
+
 export async function rewardMinutes(body: RewardReq) {
   const path = tenantPath(`children/${encodeURIComponent(body.child_id)}/reward`)
   return request<RewardResp>(path, {
@@ -290,10 +399,47 @@ export async function rewardMinutes(body: RewardReq) {
   })
 }
 
-// Child task submission
-export async function submitTask(childId: string, taskId: string) {
+// Child task submission, optionally with photos as proof
+export async function submitTask(childId: string, taskId: string, photos: File[] = []) {
   const path = tenantPath(`children/${encodeURIComponent(childId)}/tasks/${encodeURIComponent(taskId)}/submit`)
-  return request<void>(path, { method: 'POST' })
+  if (photos.length === 0) {
+    return request<void>(path, { method: 'POST' })
+  }
+  const form = new FormData()
+  for (const photo of photos) form.append('photo', photo)
+  return request<void>(path, { method: 'POST', body: form })
+}
+
+// Submission photos need the bearer token, so they are fetched as blobs
+export async function fetchAttachment(id: number): Promise<Blob> {
+  const headers: Record<string, string> = {}
+  const token = getToken()
+  if (token) headers['Authorization'] = `Bearer ${token}`
+  const resp = await fetch(apiBase() + tenantPath(`attachments/${id}`), { headers })
+  if (!resp.ok) throw new Error(`${resp.status} ${resp.statusText}`)
+  return await resp.blob()
+}
+
+// Sibling transfers: pending approval when sent by a child, applied directly by a parent
+export async function transferMinutes(fromChildId: string, body: TransferReq) {
+  const path = tenantPath(`children/${encodeURIComponent(fromChildId)}/transfers`)
+  return request<TransferResp>(path, {
+    method: 'POST',
+    body: JSON.stringify(body)
+  })
+}
+
+// Extra-time requests: asked for by a child, approved as a gift or loan by a parent
+export async function requestMoreTime(childId: string, body: TimeRequestReq) {
+  const path = tenantPath(`children/${encodeURIComponent(childId)}/time-requests`)
+  return request<TimeRequestDto>(path, {
+    method: 'POST',
+    body: JSON.stringify(body)
+  })
+}
+
+export async function listTimeRequests(childId: string) {
+  return request<TimeRequestDto[]>(tenantPath(`children/${encodeURIComponent(childId)}/time-requests`))
 }
 
 // Notifications
@@ -301,16 +447,110 @@ export async function notificationsCount() {
   return request<NotificationsCountDto>(tenantPath('notifications/count'))
 }
 
-export async function listNotifications() {
-  return request<NotificationItemDto[]>(tenantPath('notifications'))
+export type NotificationListOptions = {
+  page?: number
+  perPage?: number
+  unreadOnly?: boolean
+  includeResolved?: boolean
+}
+
+function notificationsQuery(opts: NotificationListOptions) {
+  const qs = new URLSearchParams()
+  if (opts.page) qs.set('page', String(opts.page))
+  if (opts.perPage) qs.set('per_page', String(opts.perPage))
+  if (opts.unreadOnly) qs.set('unread_only', 'true')
+  if (opts.includeResolved) qs.set('include_resolved', 'true')
+  const q = qs.toString()
+  return q ? `?${q}` : ''
+}
+
+export async function listNotifications(opts: NotificationListOptions = {}) {
+  return request<NotificationItemDto[]>(tenantPath('notifications') + notificationsQuery(opts))
+}
+
+export async function markNotificationRead(id: number) {
+  return request<void>(tenantPath(`notifications/${id}/read`), { method: 'POST' })
+}
+
+export async function dismissNotification(id: number) {
+  return request<void>(tenantPath(`notifications/${id}/dismiss`), { method: 'POST' })
+}
+
+export async function markAllNotificationsRead() {
+  return request<void>(tenantPath('notifications/read-all'), { method: 'POST' })
+}
+
+// Child inbox: outcomes of the child's own submissions, transfers and requests
+function childInboxPath(childId: string, suffix = '') {
+  return tenantPath(`children/${encodeURIComponent(childId)}/notifications${suffix}`)
+}
+
+export async function childNotificationsCount(childId: string) {
+  return request<NotificationsCountDto>(childInboxPath(childId, '/count'))
+}
+
+export async function listChildNotifications(childId: string, opts: NotificationListOptions = {}) {
+  return request<NotificationItemDto[]>(childInboxPath(childId) + notificationsQuery(opts))
+}
+
+export async function markChildNotificationRead(childId: string, id: number) {
+  return request<void>(childInboxPath(childId, `/${id}/read`), { method: 'POST' })
+}
+
+export async function dismissChildNotification(childId: string, id: number) {
+  return request<void>(childInboxPath(childId, `/${id}/dismiss`), { method: 'POST' })
+}
+
+export async function markAllChildNotificationsRead(childId: string) {
+  return request<void>(childInboxPath(childId, '/read-all'), { method: 'POST' })
+}
+
+// `minutes` grants partial credit instead of the task's full minutes
+export async function approveSubmission(id: number, minutes?: number, comment?: string) {
+  const body: SubmissionApproveReq = { minutes: minutes ?? null, comment: comment || null }
+  return request<void>(tenantPath(`notifications/task-submissions/${id}/approve`), {
+    method: 'POST',
+    body: JSON.stringify(body)
+  })
+}
+
+export async function discardSubmission(id: number, comment?: string) {
+  const body: SubmissionRejectReq = { comment: comment || null }
+  return request<void>(tenantPath(`notifications/task-submissions/${id}/discard`), {
+    method: 'POST',
+    body: JSON.stringify(body)
+  })
+}
+
+export async function listChildSubmissions(childId: string) {
+  return request<TaskSubmissionDto[]>(tenantPath(`children/${encodeURIComponent(childId)}/submissions`))
+}
+
+export async function approveTransfer(id: number) {
+  return request<void>(tenantPath(`notifications/transfers/${id}/approve`), { method: 'POST' })
+}
+
+export async function discardTransfer(id: number) {
+  return request<void>(tenantPath(`notifications/transfers/${id}/discard`), { method: 'POST' })
+}
+
+export async function approveTimeRequest(id: number, isBorrowed: boolean) {
+  return request<void>(tenantPath(`notifications/time-requests/${id}/approve`), {
+    method: 'POST',
+    body: JSON.stringify({ is_borrowed: isBorrowed })
+  })
+}
+
+export async function discardTimeRequest(id: number) {
+  return request<void>(tenantPath(`notifications/time-requests/${id}/discard`), { method: 'POST' })
 }
 
-export async function approveSubmission(id: number) {
-  return request<void>(tenantPath(`notifications/task-submissions/${id}/approve`), { method: 'POST' })
+export async function approveDevice(id: number) {
+  return request<void>(tenantPath(`notifications/devices/${id}/approve`), { method: 'POST' })
 }
 
-export async function discardSubmission(id: number) {
-  return request<void>(tenantPath(`notifications/task-submissions/${id}/discard`), { method: 'POST' })
+export async function discardDevice(id: number) {
+  return request<void>(tenantPath(`notifications/devices/${id}/discard`), { method: 'POST' })
 }
 
 function arrayBufferToBase64Url(buffer: ArrayBuffer | null): string | null {
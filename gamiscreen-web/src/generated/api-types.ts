@@ -1,7 +1,13 @@
 // This file is @generated by gamiscreen-server/build.rs
 // Do not edit manually.
 
-export type AuthReq = { username: string, password: string, };
+export type AuthReq = { username: string, password: string, 
+/**
+ * Code from the authenticator app, or an unused recovery code. Required
+ * for parents with two-factor authentication enabled; without it the
+ * login fails with `totp_required`.
+ */
+totp_code: string | null, };
 
 export type AuthResp = { 
 /**
@@ -9,6 +15,44 @@ export type AuthResp = {
  */
 token: string, };
 
+export type SseTicketResp = { 
+/**
+ * Opaque value for the `ticket` query parameter of the SSE endpoint.
+ */
+ticket: string, 
+/**
+ * Seconds until the ticket expires if unused.
+ */
+expires_in_secs: number, };
+
+export type AuthOptionsDto = { 
+/**
+ * Label of the OpenID Connect login, if one is configured.
+ */
+oidc_name: string | null, };
+
+export type OidcExchangeReq = { code: string, };
+
+export type TotpStatusDto = { enabled: boolean, 
+/**
+ * Recovery codes not used yet.
+ */
+recovery_codes_left: number, };
+
+export type TotpEnrollResp = { 
+/**
+ * Base32 secret for entering the account by hand.
+ */
+secret: string, 
+/**
+ * `otpauth://` URI for enrolling by QR code.
+ */
+otpauth_uri: string, };
+
+export type TotpCodeReq = { code: string, };
+
+export type TotpRecoveryCodesResp = { recovery_codes: Array<string>, };
+
 export type ChildDto = { id: string, 
 /**
  * Human-readable name shown in the UI.
@@ -29,6 +73,32 @@ minutes: number,
  */
 required: boolean, };
 
+export type ChoreInstanceDto = { id: number, 
+/**
+ * Chore ID from the server configuration.
+ */
+chore_id: string, 
+/**
+ * RFC 3339 UTC timestamp by which the task must be completed.
+ */
+due_at: string, 
+/**
+ * `"open"` or `"missed"`.
+ */
+status: string, 
+/**
+ * Whether completing the task now counts for this instance.
+ */
+available: boolean, 
+/**
+ * Open but past its due time (the penalty is applied shortly).
+ */
+overdue: boolean, 
+/**
+ * Minutes deducted if the instance is missed.
+ */
+penalty_minutes: number, };
+
 export type TaskWithStatusDto = { id: string, name: string, 
 /**
  * Screen-time minutes awarded on completion.
@@ -41,7 +111,12 @@ required: boolean,
 /**
  * RFC 3339 UTC timestamp of the most recent completion, or `None` if never done.
  */
-last_done: string | null, };
+last_done: string | null, 
+/**
+ * Scheduled instances of this task for the child: open ones and those
+ * missed during the last week.
+ */
+chores: Array<ChoreInstanceDto>, };
 
 export type RemainingDto = { child_id: string, 
 /**
@@ -116,6 +191,17 @@ export type ConfigResp = {
  */
 push_public_key: string | null, };
 
+export type ConfigReloadResp = { 
+/**
+ * Top-level settings that changed and are now in effect.
+ */
+changed: Array<string>, 
+/**
+ * Settings that changed in the file but keep their running value until
+ * the server is restarted.
+ */
+restart_required: Array<string>, };
+
 export type PushSubscribeReq = { 
 /**
  * Push service endpoint URL provided by the browser.
@@ -162,6 +248,42 @@ token: string,
  */
 child_id: string, device_id: string, };
 
+export type DeviceDto = { 
+/**
+ * Server-assigned ID (used by the approve/discard routes).
+ */
+id: number, child_id: string, device_id: string, 
+/**
+ * `"pending"`, `"approved"`, `"denied"` or `"blocked"`.
+ */
+status: string, 
+/**
+ * RFC 3339 UTC timestamp when the device was first seen or last asked
+ * for approval.
+ */
+created_at: string, 
+/**
+ * RFC 3339 UTC timestamp when a parent decided.
+ */
+resolved_at: string | null, };
+
+export type PairingCodeResp = { 
+/**
+ * Single-use code formatted `XXXX-XXXX`; case and dashes are ignored
+ * when it is entered.
+ */
+code: string, 
+/**
+ * Seconds until the code expires if unused.
+ */
+expires_in_secs: number, };
+
+export type PairReq = { code: string, 
+/**
+ * Unique device identifier (e.g. machine-id).
+ */
+device_id: string, };
+
 export type RewardHistoryItemDto = { 
 /**
  * RFC 3339 UTC timestamp when the reward was granted.
@@ -212,23 +334,313 @@ buckets: Array<UsageBucketDto>,
  */
 total_minutes: number, };
 
+export type SavingsDto = { child_id: string, 
+/**
+ * Minutes currently held in the savings pot.
+ */
+savings_minutes: number, 
+/**
+ * Effective remaining minutes (same semantics as `RemainingDto`).
+ */
+remaining_minutes: number, 
+/**
+ * RFC 3339 UTC timestamp until which the child cannot withdraw, if locked.
+ */
+locked_until: string | null, 
+/**
+ * Interest credited weekly, as a percentage of the pot.
+ */
+weekly_interest_percent: number, 
+/**
+ * RFC 3339 UTC timestamp of the next interest payment, or `None` when the pot is empty.
+ */
+next_interest_at: string | null, };
+
+export type SavingsReq = { 
+/**
+ * Minutes to move (must be positive).
+ */
+minutes: number, };
+
+export type SavingsTransactionDto = { 
+/**
+ * RFC 3339 UTC timestamp of the movement.
+ */
+time: string, 
+/**
+ * One of `"deposit"`, `"withdraw"` or `"interest"`.
+ */
+kind: string, 
+/**
+ * Signed change to the pot (negative for withdrawals).
+ */
+amount: number, 
+/**
+ * Pot balance after this entry.
+ */
+savings_after: number, };
+
 export type SubmitTaskReq = { child_id: string, task_id: string, };
 
-export type NotificationsCountDto = { count: number, };
+export type SubmissionApproveReq = { 
+/**
+ * Partial credit: minutes to grant instead of the task's full minutes.
+ */
+minutes: number | null, 
+/**
+ * Optional note delivered to the child.
+ */
+comment: string | null, };
+
+export type SubmissionRejectReq = { 
+/**
+ * Why the submission was rejected, delivered to the child.
+ */
+comment: string | null, };
+
+export type TaskSubmissionDto = { 
+/**
+ * Server-assigned submission ID (used by the approve/discard routes).
+ */
+id: number, child_id: string, task_id: string, task_name: string, 
+/**
+ * Minutes the task is worth.
+ */
+task_minutes: number, 
+/**
+ * `"pending"`, `"approved"` or `"rejected"`.
+ */
+status: string, 
+/**
+ * Minutes credited on approval; less than `task_minutes` for partial credit.
+ */
+minutes_awarded: number | null, 
+/**
+ * Parent's comment on the decision.
+ */
+comment: string | null, 
+/**
+ * RFC 3339 UTC timestamp when the child submitted.
+ */
+submitted_at: string, 
+/**
+ * Username of the parent who decided.
+ */
+resolved_by: string | null, 
+/**
+ * RFC 3339 UTC timestamp when a parent decided.
+ */
+resolved_at: string | null, };
+
+export type AuditEntryDto = { id: number, 
+/**
+ * RFC 3339 UTC timestamp of the action.
+ */
+created_at: string, 
+/**
+ * Username that performed the action.
+ */
+actor: string, role: Role, 
+/**
+ * `x-request-id` of the request that performed the action.
+ */
+request_id: string | null, 
+/**
+ * Action name such as `"reward.grant"` or `"submission.approve"`.
+ */
+action: string, child_id: string | null, 
+/**
+ * ID of the affected submission, transfer, time request or device.
+ */
+target_id: string | null, 
+/**
+ * State before the action, when there was one.
+ */
+before: JsonValue | null, 
+/**
+ * State after the action.
+ */
+after: JsonValue | null, };
+
+export type WebhookDto = { id: string, url: string, 
+/**
+ * Event names delivered to this webhook; empty means all.
+ */
+events: Array<string>, };
+
+export type WebhookDeliveryDto = { id: number, webhook_id: string, 
+/**
+ * Event name, e.g. `"remaining_updated"` or `"ping"`.
+ */
+event: string, 
+/**
+ * `"pending"`, `"delivered"` or `"failed"`.
+ */
+status: string, attempts: number, 
+/**
+ * HTTP status of the last attempt, if the endpoint answered.
+ */
+last_status_code: number | null, last_error: string | null, 
+/**
+ * RFC 3339 UTC timestamp when the event was queued.
+ */
+created_at: string, 
+/**
+ * RFC 3339 UTC timestamp of the next retry while pending.
+ */
+next_attempt_at: string | null, delivered_at: string | null, };
+
+export type NotificationsCountDto = { 
+/**
+ * Total unread, unresolved notifications.
+ */
+count: number, 
+/**
+ * Unread notifications keyed by `kind`; kinds with none are omitted.
+ */
+by_kind: { [key in string]: number }, };
+
+export type NotificationAudience = "parents" | "child";
+
+export type NotificationPayload = { "kind": "task_submission", submission_id: number, task_id: string, task_name: string, minutes: number, } | { "kind": "transfer", transfer_id: number, to_child_id: string, to_child_display_name: string, minutes: number, note: string | null, } | { "kind": "time_request", request_id: number, minutes: number, reason: string, } | { "kind": "device_registration", 
+/**
+ * ID of the device request (see [`DeviceDto::id`]).
+ */
+request_id: number, device_id: string, } | { "kind": "account_locked", username: string, 
+/**
+ * RFC 3339 UTC time the lock ends.
+ */
+locked_until: string, } | { "kind": "device_offline", child_id: string, device_id: string, } | { "kind": "debt_limit_reached", 
+/**
+ * Account balance after the loan (negative: minutes owed).
+ */
+balance: number, 
+/**
+ * Configured `debt_limit_minutes`.
+ */
+limit: number, } | { "kind": "task_reviewed", task_id: string, task_name: string, 
+/**
+ * Minutes credited (may be less than the task's minutes).
+ */
+minutes: number, approved: boolean, 
+/**
+ * Username of the parent who decided.
+ */
+by: string, 
+/**
+ * Parent's comment, e.g. why the task was rejected.
+ */
+comment: string | null, } | { "kind": "chore_missed", chore_id: string, task_id: string, task_name: string, 
+/**
+ * RFC 3339 UTC due time of the missed instance.
+ */
+due_at: string, 
+/**
+ * Minutes deducted (0 when the chore has no penalty).
+ */
+penalty_minutes: number, } | { "kind": "reward_granted", minutes: number, description: string | null, is_borrowed: boolean, by: string, } | { "kind": "transfer_resolved", transfer_id: number, from_child_id: string, from_child_display_name: string, to_child_id: string, to_child_display_name: string, minutes: number, approved: boolean, } | { "kind": "time_request_resolved", request_id: number, minutes: number, approved: boolean, is_borrowed: boolean, };
+
+export type AttachmentDto = { id: number, 
+/**
+ * MIME type detected from the uploaded bytes (e.g. `image/jpeg`).
+ */
+content_type: string, size_bytes: number, };
 
 export type NotificationItemDto = { 
 /**
- * Server-assigned notification ID.
+ * Server-assigned notification ID (used by the read/dismiss routes).
  */
 id: number, 
 /**
- * Notification type discriminator (currently always `"task_submission"`).
+ * Notification type discriminator, same as the `kind` tag of `payload`.
+ */
+kind: string, 
+/**
+ * Child the notification is about.
+ */
+child_id: string, child_display_name: string, payload: NotificationPayload, 
+/**
+ * RFC 3339 UTC timestamp when the notification was created.
+ */
+created_at: string, 
+/**
+ * Whether the caller has read the notification.
  */
-kind: string, child_id: string, child_display_name: string, task_id: string, task_name: string, 
+read: boolean, 
 /**
- * RFC 3339 UTC timestamp when the child submitted the task.
+ * Whether the underlying request was already approved or discarded.
  */
-submitted_at: string, };
+resolved: boolean, 
+/**
+ * Photo proof attached to a task submission, while it is retained.
+ */
+attachments: Array<AttachmentDto>, };
+
+export type TransferReq = { 
+/**
+ * Child receiving the minutes.
+ */
+to_child_id: string, 
+/**
+ * Minutes to give (must be positive and covered by the giver's remaining time).
+ */
+minutes: number, 
+/**
+ * Optional reason shown to the approving parent.
+ */
+note: string | null, };
+
+export type TransferResp = { 
+/**
+ * Server-assigned transfer ID (used by the approve/discard routes).
+ */
+transfer_id: number, 
+/**
+ * `"pending"` when awaiting parent approval, `"approved"` when applied.
+ */
+status: string, 
+/**
+ * Giving child's effective remaining minutes after the request.
+ */
+remaining_minutes: number, };
+
+export type TimeRequestReq = { 
+/**
+ * Extra minutes asked for (must be positive).
+ */
+minutes: number, 
+/**
+ * Why the child needs the time, shown to the approving parent.
+ */
+reason: string, };
+
+export type TimeRequestApproveReq = { 
+/**
+ * Grant the minutes as a loan that is repaid from future earnings.
+ */
+is_borrowed: boolean, };
+
+export type TimeRequestDto = { 
+/**
+ * Server-assigned request ID (used by the approve/discard routes).
+ */
+id: number, child_id: string, minutes: number, reason: string, 
+/**
+ * `"pending"`, `"approved"` or `"denied"`.
+ */
+status: string, 
+/**
+ * Whether an approved request was granted as a loan.
+ */
+is_borrowed: boolean, 
+/**
+ * RFC 3339 UTC timestamp when the child asked.
+ */
+created_at: string, 
+/**
+ * RFC 3339 UTC timestamp when a parent decided.
+ */
+resolved_at: string | null, };
 
 export type UpdateManifestDto = { 
 /**
@@ -284,5 +696,7 @@ version: string, };
 
 export type Role = "parent" | "child";
 
+export type JsonValue = number | string | boolean | Array<JsonValue> | { [key in string]: JsonValue } | null;
+
 export type JwtClaims = { sub: string, jti: string, exp: number, role: Role, child_id: string | null, device_id: string | null, tenant_id: string, };
 
@@ -1,17 +1,23 @@
 import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import {
+  blockDevice,
+  createPairingCode,
+  DeviceDto,
   getAuthClaims,
   getConfig,
   getRemaining,
+  listChildDevices,
   listChildren,
   listChildRewards,
   listChildTasks,
   listChildUsage,
   pushSubscribe,
+  requestMoreTime,
   RewardHistoryItemDto,
   rewardMinutes,
   submitTask,
   TaskWithStatusDto,
+  unblockDevice,
   UsageSeriesDto,
 } from "../api";
 import {
@@ -104,7 +110,7 @@ function TaskRow(props: {
   wasSubmitted: boolean;
   childId: string;
   onConfirm: (task: TaskWithStatusDto) => void;
-  onSubmit: (taskId: string) => Promise<void>;
+  onSubmit: (taskId: string, photos?: File[]) => Promise<void>;
 }) {
   const {
     task: t,
@@ -122,6 +128,9 @@ function TaskRow(props: {
       last.getDate() === now.getDate()
     : false;
   const isNegative = t.minutes < 0;
+  // Scheduled chore instances: the next open one and recent misses
+  const nextChore = t.chores.find((c) => c.status === "open");
+  const missedChores = t.chores.filter((c) => c.status === "missed").length;
   const taskRowStyle: React.CSSProperties = t.required
     ? {
         borderLeft: "4px solid #2563eb",
@@ -144,6 +153,28 @@ function TaskRow(props: {
       <div className="row taskRowHeader">
         <span>{t.required ? <strong>* {t.name}</strong> : t.name}</span>
         {isDoneToday && <mark title={last?.toLocaleString() || ""}>Done</mark>}
+        {nextChore && (
+          <mark
+            className={nextChore.overdue ? "choreOverdue" : undefined}
+            title={
+              nextChore.penalty_minutes > 0
+                ? `-${nextChore.penalty_minutes} min if missed`
+                : undefined
+            }
+          >
+            {nextChore.overdue ? "Overdue" : "Due"}{" "}
+            {new Date(nextChore.due_at).toLocaleString([], {
+              weekday: "short",
+              hour: "2-digit",
+              minute: "2-digit",
+            })}
+          </mark>
+        )}
+        {missedChores > 0 && (
+          <mark className="choreOverdue" title="Missed in the last week">
+            Missed ×{missedChores}
+          </mark>
+        )}
       </div>
       <div className="row taskRowActions">
         <span className={`subtitle${isNegative ? " negativeMinutes" : ""}`}>
@@ -170,13 +201,146 @@ function TaskRow(props: {
               {isDoneToday ? "Done" : "Submitted"}
             </button>
           ) : (
-            <button onClick={() => onSubmit(t.id)}>Submit</button>
+            <>
+              <button onClick={() => onSubmit(t.id)}>Submit</button>
+              <label
+                role="button"
+                className="secondary outline iconButton"
+                title="Submit with a photo"
+                aria-label="Submit with a photo"
+              >
+                📷
+                <input
+                  type="file"
+                  accept="image/jpeg,image/png,image/webp,image/heic"
+                  capture="environment"
+                  multiple
+                  hidden
+                  onChange={(e) => {
+                    const files = Array.from(e.target.files || []);
+                    e.target.value = "";
+                    if (files.length > 0) onSubmit(t.id, files);
+                  }}
+                />
+              </label>
+            </>
           ))}
       </div>
     </div>
   );
 }
 
+/** Lets a parent create a pairing code for a new device of the child. */
+function PairDeviceCard(props: { childId: string }) {
+  const [code, setCode] = useState<string | null>(null);
+  const [expiresAt, setExpiresAt] = useState<Date | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  async function create() {
+    setLoading(true);
+    setError(null);
+    try {
+      const resp = await createPairingCode(props.childId);
+      setCode(resp.code);
+      setExpiresAt(new Date(Date.now() + resp.expires_in_secs * 1000));
+    } catch (e: any) {
+      setError(e.message || "Failed to create pairing code");
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  return (
+    <div className="card" style={{ padding: "12px" }}>
+      <h3 className="title" style={{ fontSize: 16, marginBottom: 8 }}>
+        Pair a device
+      </h3>
+      {code ? (
+        <div className="col" style={{ gap: 8 }}>
+          <code style={{ fontSize: 24, letterSpacing: 2 }}>{code}</code>
+          <p className="subtitle">
+            On the device, run <code>gamiscreen-client login --code {code}</code>
+            {expiresAt && <> before {expiresAt.toLocaleTimeString()}</>}. The
+            code works once.
+          </p>
+        </div>
+      ) : (
+        <p className="subtitle">
+          Create a one-time code to register a computer for this child without
+          their password.
+        </p>
+      )}
+      {error && <div className="error">{error}</div>}
+      <button type="button" onClick={create} disabled={loading}>
+        {code ? "New code" : "Create pairing code"}
+      </button>
+    </div>
+  );
+}
+
+function DevicesCard(props: { childId: string }) {
+  const [devices, setDevices] = useState<DeviceDto[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  const load = useCallback(async () => {
+    try {
+      setDevices(await listChildDevices(props.childId));
+    } catch (e: any) {
+      setError(e.message || "Failed to load devices");
+    }
+  }, [props.childId]);
+
+  useEffect(() => {
+    load();
+  }, [load]);
+
+  async function toggle(device: DeviceDto) {
+    setError(null);
+    try {
+      if (device.status === "blocked") {
+        await unblockDevice(props.childId, device.device_id);
+      } else {
+        await blockDevice(props.childId, device.device_id);
+      }
+      await load();
+    } catch (e: any) {
+      setError(e.message || "Failed to update device");
+    }
+  }
+
+  if (devices.length === 0 && !error) return null;
+  return (
+    <div className="card" style={{ padding: "12px" }}>
+      <h3 className="title" style={{ fontSize: 16, marginBottom: 8 }}>
+        Devices
+      </h3>
+      {error && <div className="error">{error}</div>}
+      <ul className="col" style={{ gap: 8, listStyle: "none", padding: 0 }}>
+        {devices.map((d) => (
+          <li
+            key={d.id}
+            className="row"
+            style={{ justifyContent: "space-between", alignItems: "center" }}
+          >
+            <span>
+              <code>{d.device_id}</code>{" "}
+              <span className="subtitle">{d.status}</span>
+            </span>
+            <button
+              type="button"
+              className="secondary outline"
+              onClick={() => toggle(d)}
+            >
+              {d.status === "blocked" ? "Unblock" : "Block"}
+            </button>
+          </li>
+        ))}
+      </ul>
+    </div>
+  );
+}
+
 export function ChildDetailsPage(props: { childId: string }) {
   const { childId } = props;
   const [displayName, setDisplayName] = useState<string>(childId);
@@ -205,6 +369,9 @@ export function ChildDetailsPage(props: { childId: string }) {
   const [customLabel, setCustomLabel] = useState("");
   const [isBorrowed, setIsBorrowed] = useState(false);
   const [rewardFeedback, setRewardFeedback] = useState<string | null>(null);
+  const [extraMinutes, setExtraMinutes] = useState("");
+  const [extraReason, setExtraReason] = useState("");
+  const [extraFeedback, setExtraFeedback] = useState<string | null>(null);
   const [rewards, setRewards] = useState<RewardHistoryItemDto[]>([]);
   const [usage, setUsage] = useState<UsageSeriesDto | null>(null);
   const [usageLoading, setUsageLoading] = useState(false);
@@ -246,9 +413,9 @@ export function ChildDetailsPage(props: { childId: string }) {
   }, []);
 
   const handleTaskSubmit = useCallback(
-    async (taskId: string) => {
+    async (taskId: string, photos?: File[]) => {
       try {
-        await submitTask(childId, taskId);
+        await submitTask(childId, taskId, photos);
         setError(null);
         setSubmitted((prev) => {
           const next = new Set(prev);
@@ -262,6 +429,21 @@ export function ChildDetailsPage(props: { childId: string }) {
     [childId],
   );
 
+  const handleRequestTime = useCallback(async () => {
+    const minutes = parseInt(extraMinutes, 10);
+    const reason = extraReason.trim();
+    if (!Number.isFinite(minutes) || minutes <= 0 || !reason) return;
+    try {
+      await requestMoreTime(childId, { minutes, reason });
+      setError(null);
+      setExtraMinutes("");
+      setExtraReason("");
+      setExtraFeedback(`Asked for ${minutes} more minutes`);
+    } catch (e: any) {
+      setError(e.message || "Failed to request more time");
+    }
+  }, [childId, extraMinutes, extraReason]);
+
   const handleEnableNotifications = useCallback(async () => {
     try {
       const permission = await requestNotificationPermission();
@@ -391,6 +573,14 @@ export function ChildDetailsPage(props: { childId: string }) {
   useEffect(() => {
     load();
   }, [childId]);
+  // Reload after the event stream reports missed events
+  useEffect(() => {
+    const handler = () => {
+      load();
+    };
+    window.addEventListener("gamiscreen:resync", handler);
+    return () => window.removeEventListener("gamiscreen:resync", handler);
+  }, [childId]);
   // Live update remaining via SSE events (relayed by App via window event)
   useEffect(() => {
     const handler = (e: any) => {
@@ -415,6 +605,30 @@ export function ChildDetailsPage(props: { childId: string }) {
       );
   }, [childId]);
 
+  // Tell the child how a parent decided on their extra-time request
+  useEffect(() => {
+    const handler = (e: any) => {
+      const d = e?.detail;
+      if (d?.child_id !== childId || typeof d.minutes !== "number") return;
+      if (!d.approved) {
+        setExtraFeedback(`Request for ${d.minutes} minutes was denied`);
+      } else if (d.is_borrowed) {
+        setExtraFeedback(`Got ${d.minutes} minutes as a loan`);
+      } else {
+        setExtraFeedback(`Got ${d.minutes} extra minutes`);
+      }
+    };
+    window.addEventListener(
+      "gamiscreen:time-request-resolved",
+      handler as EventListener,
+    );
+    return () =>
+      window.removeEventListener(
+        "gamiscreen:time-request-resolved",
+        handler as EventListener,
+      );
+  }, [childId]);
+
   useEffect(() => {
     if (!isChild) return;
     if (notificationPermission !== "granted") return;
@@ -767,6 +981,59 @@ export function ChildDetailsPage(props: { childId: string }) {
           {tasks.length === 0 && <p className="subtitle">No tasks</p>}
         </div>
       </div>
+      {isChild && (
+        <div className="card" style={{ padding: "12px" }}>
+          <h3 className="title" style={{ fontSize: 16, marginBottom: 8 }}>
+            Need more time?
+          </h3>
+          <form
+            onSubmit={(e) => {
+              e.preventDefault();
+              handleRequestTime();
+            }}
+            className="col"
+            style={{ gap: 8 }}
+          >
+            <div
+              className="row"
+              style={{ gap: 8, alignItems: "center", flexWrap: "wrap" }}
+            >
+              <input
+                type="number"
+                min={1}
+                step={1}
+                placeholder="10"
+                aria-label="Extra minutes"
+                value={extraMinutes}
+                onChange={(e) => setExtraMinutes(e.target.value)}
+                inputMode="numeric"
+                pattern="[0-9]*"
+                style={{ width: "14ch", textAlign: "right" }}
+              />
+              <span
+                className="subtitle"
+                style={{ whiteSpace: "nowrap", alignSelf: "center" }}
+              >
+                minutes
+              </span>
+            </div>
+            <label className="col">
+              <span>Reason</span>
+              <input
+                type="text"
+                placeholder="Finishing the level"
+                maxLength={200}
+                value={extraReason}
+                onChange={(e) => setExtraReason(e.target.value)}
+              />
+            </label>
+            <button type="submit" className="acceptButton">
+              Ask a parent
+            </button>
+            {extraFeedback && <p className="subtitle">{extraFeedback}</p>}
+          </form>
+        </div>
+      )}
       {isParent && (
         <div className="card" style={{ padding: "12px" }}>
           <h3 className="title" style={{ fontSize: 16, marginBottom: 8 }}>
@@ -1063,6 +1330,8 @@ export function ChildDetailsPage(props: { childId: string }) {
           </article>
         </dialog>
       )}
+      {isParent && <PairDeviceCard childId={props.childId} />}
+      {isParent && <DevicesCard childId={props.childId} />}
       {isParent && (
         <p>
           <a href="#status" className="subtitle">
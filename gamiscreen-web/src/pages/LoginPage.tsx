@@ -1,12 +1,15 @@
-import React, { useMemo, useState } from 'react'
-import { getServerBase, login, setServerBase } from '../api'
+import React, { useEffect, useMemo, useState } from 'react'
+import { exchangeOidcCode, getAuthOptions, getServerBase, login, oidcLoginUrl, setServerBase } from '../api'
 
 export function LoginPage(props: { onLogin: (token: string) => void }) {
   const [username, setUsername] = useState('')
   const [password, setPassword] = useState('')
+  const [totpCode, setTotpCode] = useState('')
+  const [needsCode, setNeedsCode] = useState(false)
   const [loading, setLoading] = useState(false)
   const [error, setError] = useState<string | null>(null)
   const [serverUrl, setServerUrl] = useState<string>(() => getServerBase() || '')
+  const [oidcName, setOidcName] = useState<string | null>(null)
 
   const showServerInput = useMemo(() => {
     const env = (import.meta as any).env || {}
@@ -18,6 +21,33 @@ export function LoginPage(props: { onLogin: (token: string) => void }) {
     }
   }, [])
 
+  useEffect(() => {
+    let cancelled = false
+    getAuthOptions()
+      .then(opts => { if (!cancelled) setOidcName(opts.oidc_name ?? null) })
+      .catch(() => {})
+    return () => { cancelled = true }
+  }, [])
+
+  // The identity provider login comes back to `#login?oidc_code=...`
+  // (or `oidc_error=...`); trade the code for a token once.
+  useEffect(() => {
+    const qs = new URLSearchParams(window.location.hash.split('?')[1] || '')
+    const code = qs.get('oidc_code')
+    const oidcError = qs.get('oidc_error')
+    if (!code && !oidcError) return
+    history.replaceState(null, '', '#login')
+    if (oidcError) {
+      setError(oidcError)
+      return
+    }
+    setLoading(true)
+    exchangeOidcCode(code!)
+      .then(resp => props.onLogin(resp.token))
+      .catch((e: any) => setError(e.message || 'Login failed'))
+      .finally(() => setLoading(false))
+  }, [])
+
   async function onSubmit(e: React.FormEvent) {
     e.preventDefault()
     setError(null)
@@ -28,10 +58,14 @@ export function LoginPage(props: { onLogin: (token: string) => void }) {
         const v = serverUrl.trim().replace(/\/+$/, '')
         setServerBase(v || null)
       }
-      const resp = await login(username, password)
+      const resp = await login(username, password, needsCode ? totpCode.trim() : undefined)
       props.onLogin(resp.token)
     } catch (e: any) {
-      setError(e.message || 'Login failed')
+      if (e.message === 'totp_required') {
+        setNeedsCode(true)
+      } else {
+        setError(e.message || 'Login failed')
+      }
     } finally {
       setLoading(false)
     }
@@ -88,10 +122,34 @@ export function LoginPage(props: { onLogin: (token: string) => void }) {
           placeholder="••••••"
         />
       </label>
+      {needsCode && (
+        <label className="col" htmlFor="totp-code">
+          <span>Authentication code</span>
+          <input
+            type="text"
+            id="totp-code"
+            name="totp-code"
+            inputMode="numeric"
+            autoComplete="one-time-code"
+            autoFocus
+            value={totpCode}
+            onChange={e=>setTotpCode(e.target.value)}
+            placeholder="123456"
+          />
+          <small>From your authenticator app, or one of your recovery codes.</small>
+        </label>
+      )}
       {error && <div className="error">{error}</div>}
       <div className="row">
         <button type="submit" disabled={loading}>{loading? 'Logging in…':'Login'}</button>
       </div>
+      {oidcName && (
+        <div className="row">
+          <button type="button" disabled={loading} onClick={() => window.location.assign(oidcLoginUrl())}>
+            Log in with {oidcName}
+          </button>
+        </div>
+      )}
     </form>
   )
 }
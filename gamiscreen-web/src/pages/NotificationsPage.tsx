@@ -1,17 +1,118 @@
 import { useEffect, useState } from 'react'
-import { approveSubmission, discardSubmission, listNotifications, NotificationItemDto } from '../api'
+import {
+  approveDevice,
+  approveSubmission,
+  approveTimeRequest,
+  approveTransfer,
+  AttachmentDto,
+  discardDevice,
+  discardSubmission,
+  discardTimeRequest,
+  discardTransfer,
+  dismissChildNotification,
+  dismissNotification,
+  fetchAttachment,
+  listChildNotifications,
+  listNotifications,
+  markAllChildNotificationsRead,
+  markAllNotificationsRead,
+  markChildNotificationRead,
+  markNotificationRead,
+  NotificationItemDto,
+} from '../api'
 
-export function NotificationsPage() {
+const PER_PAGE = 20
+
+function PhotoThumb({ attachment }: { attachment: AttachmentDto }) {
+  const [url, setUrl] = useState<string | null>(null)
+  useEffect(() => {
+    let objectUrl: string | null = null
+    let cancelled = false
+    fetchAttachment(attachment.id)
+      .then(blob => {
+        if (cancelled) return
+        objectUrl = URL.createObjectURL(blob)
+        setUrl(objectUrl)
+      })
+      .catch(() => { })
+    return () => {
+      cancelled = true
+      if (objectUrl) URL.revokeObjectURL(objectUrl)
+    }
+  }, [attachment.id])
+  if (!url) return null
+  return (
+    <a href={url} target="_blank" rel="noreferrer" title="Open photo">
+      <img src={url} alt="Photo proof" style={{ width: 64, height: 64, objectFit: 'cover', borderRadius: 4 }} />
+    </a>
+  )
+}
+
+function describe(item: NotificationItemDto) {
+  const p = item.payload
+  const who = <strong>{item.child_display_name}</strong>
+  switch (p.kind) {
+    case 'task_submission':
+      return <div>{who} submitted: {p.task_name}</div>
+    case 'transfer':
+      return <div>{who} wants to give {p.minutes} min to <strong>{p.to_child_display_name}</strong></div>
+    case 'time_request':
+      return <div>{who} asks for {p.minutes} more min</div>
+    case 'device_registration':
+      return <div>{who} wants to register device <code>{p.device_id}</code></div>
+    case 'account_locked':
+      return <div>{who}'s account <code>{p.username}</code> is locked until {new Date(p.locked_until).toLocaleTimeString()} after repeated failed logins</div>
+    case 'device_offline':
+      return <div>{who}'s device <code>{p.device_id}</code> went offline</div>
+    case 'debt_limit_reached':
+      return <div>{who} now owes {-p.balance} min, reaching the limit of {p.limit} min</div>
+    case 'task_reviewed':
+      return p.approved
+        ? <div>“{p.task_name}” was approved: +{p.minutes} min</div>
+        : <div>“{p.task_name}” was rejected</div>
+    case 'chore_missed':
+      return <div>Missed chore “{p.task_name}”{p.penalty_minutes > 0 ? `: -${p.penalty_minutes} min` : ''}</div>
+    case 'reward_granted':
+      return <div>{p.is_borrowed ? 'Borrowed' : 'Received'} {p.minutes} min{p.description ? `: ${p.description}` : ''}</div>
+    case 'transfer_resolved':
+      if (!p.approved) return <div>Your gift of {p.minutes} min to {p.to_child_display_name} was declined</div>
+      return item.child_id === p.to_child_id
+        ? <div>{p.from_child_display_name} gave you {p.minutes} min</div>
+        : <div>You gave {p.minutes} min to {p.to_child_display_name}</div>
+    case 'time_request_resolved':
+      return p.approved
+        ? <div>Your request for {p.minutes} more min was approved{p.is_borrowed ? ' as a loan' : ''}</div>
+        : <div>Your request for {p.minutes} more min was declined</div>
+  }
+}
+
+function detail(item: NotificationItemDto) {
+  const p = item.payload
+  if (p.kind === 'transfer') return p.note
+  if (p.kind === 'time_request') return p.reason
+  if (p.kind === 'task_reviewed') return p.comment
+  return null
+}
+
+// The parents' inbox, or a child's own inbox when `childId` is given
+export function NotificationsPage({ childId }: { childId?: string }) {
   const [items, setItems] = useState<NotificationItemDto[]>([])
+  const [page, setPage] = useState(1)
+  const [hasMore, setHasMore] = useState(false)
   const [loading, setLoading] = useState(false)
   const [error, setError] = useState<string | null>(null)
+  // Task submission being reviewed with adjusted minutes or a comment
+  const [review, setReview] = useState<{ id: number, minutes: string, comment: string } | null>(null)
 
-  async function load() {
+  async function load(nextPage = 1) {
     setLoading(true)
     setError(null)
     try {
-      const data = await listNotifications()
-      setItems(data)
+      const opts = { page: nextPage, perPage: PER_PAGE }
+      const data = childId ? await listChildNotifications(childId, opts) : await listNotifications(opts)
+      setItems(prev => nextPage === 1 ? data : [...prev, ...data])
+      setPage(nextPage)
+      setHasMore(data.length === PER_PAGE)
     } catch (e: any) {
       setError(e.message || 'Failed to load notifications')
     } finally {
@@ -19,58 +120,176 @@ export function NotificationsPage() {
     }
   }
 
-  useEffect(() => { load() }, [])
+  useEffect(() => { load() }, [childId])
+
+  useEffect(() => {
+    const handler = () => { load() }
+    window.addEventListener('gamiscreen:notification-created', handler as EventListener)
+    window.addEventListener('gamiscreen:resync', handler)
+    return () => {
+      window.removeEventListener('gamiscreen:notification-created', handler as EventListener)
+      window.removeEventListener('gamiscreen:resync', handler)
+    }
+  }, [childId])
+
+  function removeItem(item: NotificationItemDto) {
+    setItems(prev => prev.filter(x => x.id !== item.id))
+    // Refresh header badge count
+    window.dispatchEvent(new Event('gamiscreen:notif-refresh'))
+  }
 
-  async function onApprove(item: NotificationItemDto) {
+  async function onApprove(item: NotificationItemDto, isBorrowed = false) {
+    const p = item.payload
     try {
-      await approveSubmission(item.id)
-      // Remove from local list immediately
-      setItems(prev => prev.filter(x => x.id !== item.id))
-      // Refresh header badge count
-      window.dispatchEvent(new Event('gamiscreen:notif-refresh'))
+      if (p.kind === 'transfer') {
+        await approveTransfer(p.transfer_id)
+      } else if (p.kind === 'time_request') {
+        await approveTimeRequest(p.request_id, isBorrowed)
+      } else if (p.kind === 'device_registration') {
+        await approveDevice(p.request_id)
+      } else if (p.kind === 'task_submission') {
+        const r = review?.id === item.id ? review : null
+        const minutes = r ? parseInt(r.minutes, 10) : NaN
+        await approveSubmission(p.submission_id, Number.isFinite(minutes) ? minutes : undefined, r?.comment)
+        setReview(null)
+      } else {
+        return
+      }
+      removeItem(item)
       // Navigate to the child's page so the parent can see reward history updated
       window.location.hash = `child/${encodeURIComponent(item.child_id)}`
+    } catch (e: any) {
+      setError(e.message || 'Failed to approve')
+    }
+  }
+  async function onDiscard(item: NotificationItemDto) {
+    const p = item.payload
+    try {
+      if (p.kind === 'transfer') {
+        await discardTransfer(p.transfer_id)
+      } else if (p.kind === 'time_request') {
+        await discardTimeRequest(p.request_id)
+      } else if (p.kind === 'device_registration') {
+        await discardDevice(p.request_id)
+      } else if (p.kind === 'task_submission') {
+        await discardSubmission(p.submission_id, review?.id === item.id ? review.comment : undefined)
+        setReview(null)
+      } else {
+        return
+      }
+      removeItem(item)
+    } catch { }
+  }
+  async function onRead(item: NotificationItemDto) {
+    try {
+      if (childId) await markChildNotificationRead(childId, item.id)
+      else await markNotificationRead(item.id)
+      setItems(prev => prev.map(x => x.id === item.id ? { ...x, read: true } : x))
+      window.dispatchEvent(new Event('gamiscreen:notif-refresh'))
     } catch { }
   }
-  async function onDiscard(id: number) {
+  async function onDismiss(item: NotificationItemDto) {
     try {
-      await discardSubmission(id)
-      setItems(prev => prev.filter(x => x.id !== id))
-      // Refresh header badge count
+      if (childId) await dismissChildNotification(childId, item.id)
+      else await dismissNotification(item.id)
+      removeItem(item)
+    } catch { }
+  }
+  async function onReadAll() {
+    try {
+      if (childId) await markAllChildNotificationsRead(childId)
+      else await markAllNotificationsRead()
+      setItems(prev => prev.map(x => ({ ...x, read: true })))
       window.dispatchEvent(new Event('gamiscreen:notif-refresh'))
     } catch { }
   }
 
+  const actionable = (item: NotificationItemDto) =>
+    !childId && !item.resolved && ['task_submission', 'transfer', 'time_request', 'device_registration'].includes(item.kind)
+
   return (
     <section className="col" style={{ gap: 12 }}>
       <header className="row" style={{ justifyContent: 'space-between', alignItems: 'center' }}>
         <h2 className="title" style={{ margin: 0 }}>Notifications</h2>
         <div className="row" style={{ gap: 8 }}>
-          <button className="secondary outline iconButton" onClick={load} disabled={loading} title={loading ? 'Refreshing…' : 'Refresh'} aria-label="Refresh">↻</button>
+          <button className="secondary outline" onClick={onReadAll} disabled={loading || items.every(x => x.read)}>Mark all read</button>
+          <button className="secondary outline iconButton" onClick={() => load()} disabled={loading} title={loading ? 'Refreshing…' : 'Refresh'} aria-label="Refresh">↻</button>
         </div>
       </header>
       {error && <p className="error">{error}</p>}
       <div className="card" style={{ padding: '12px' }}>
-        {items.length === 0 && <p className="subtitle">No pending notifications</p>}
+        {items.length === 0 && <p className="subtitle">No notifications</p>}
         {items.length > 0 && (
           <div className="col" style={{ gap: 8 }}>
             {items.map(item => (
-              <div key={item.id} className="row" style={{ justifyContent: 'space-between', alignItems: 'center', borderBottom: '1px solid #eee', paddingBottom: 8 }}>
-                <div className="col" style={{ gap: 2 }}>
-                  <div><strong>{item.child_display_name}</strong> submitted: {item.task_name}</div>
-                  <div className="subtitle">{new Date(item.submitted_at).toLocaleString()}</div>
+              <div key={item.id} className="row" style={{ justifyContent: 'space-between', alignItems: 'center', borderBottom: '1px solid #eee', paddingBottom: 8, opacity: item.read ? 0.7 : 1 }}>
+                <div className="col" style={{ gap: 2, fontWeight: item.read ? undefined : 600 }}>
+                  {describe(item)}
+                  {detail(item) && <div className="subtitle">“{detail(item)}”</div>}
+                  {review?.id === item.id && item.payload.kind === 'task_submission' && (
+                    <div className="row" style={{ gap: 8, alignItems: 'center', flexWrap: 'wrap' }}>
+                      <input
+                        type="number"
+                        min={1}
+                        max={item.payload.minutes}
+                        aria-label="Minutes to grant"
+                        value={review.minutes}
+                        onChange={e => setReview({ ...review, minutes: e.target.value })}
+                        style={{ width: '8ch', textAlign: 'right' }}
+                      />
+                      <span className="subtitle">of {item.payload.minutes} min</span>
+                      <input
+                        type="text"
+                        maxLength={200}
+                        placeholder="Comment for the child"
+                        aria-label="Comment"
+                        value={review.comment}
+                        onChange={e => setReview({ ...review, comment: e.target.value })}
+                      />
+                    </div>
+                  )}
+                  {item.attachments.length > 0 && (
+                    <div className="row" style={{ gap: 4 }}>
+                      {item.attachments.map(a => <PhotoThumb key={a.id} attachment={a} />)}
+                    </div>
+                  )}
+                  <div className="subtitle">{new Date(item.created_at).toLocaleString()}</div>
                 </div>
                 <div className="row" style={{ gap: 8 }}>
-                  <button className="secondary outline iconButton" onClick={() => onApprove(item)} title="Approve" aria-label="Approve">✔️</button>
-                  <button className="secondary outline iconButton" onClick={() => onDiscard(item.id)} title="Discard" aria-label="Discard">✖️</button>
+                  {actionable(item) && (
+                    <>
+                      <button className="secondary outline iconButton" onClick={() => onApprove(item)} title="Approve" aria-label="Approve">✔️</button>
+                      {item.payload.kind === 'task_submission' && review?.id !== item.id && (
+                        <button
+                          className="secondary outline iconButton"
+                          onClick={() => setReview({ id: item.id, minutes: String((item.payload as { minutes: number }).minutes), comment: '' })}
+                          title="Adjust minutes or add a comment"
+                          aria-label="Review"
+                        >✎</button>
+                      )}
+                      {item.kind === 'time_request' && (
+                        <button className="secondary outline" onClick={() => onApprove(item, true)} title="Approve as a loan, repaid from future earnings" aria-label="Approve as loan">Loan</button>
+                      )}
+                      <button className="secondary outline iconButton" onClick={() => onDiscard(item)} title={item.kind === 'task_submission' ? 'Reject' : 'Discard'} aria-label={item.kind === 'task_submission' ? 'Reject' : 'Discard'}>✖️</button>
+                    </>
+                  )}
+                  {!item.read && (
+                    <button className="secondary outline iconButton" onClick={() => onRead(item)} title="Mark as read" aria-label="Mark as read">👁</button>
+                  )}
+                  {!actionable(item) && (
+                    <button className="secondary outline iconButton" onClick={() => onDismiss(item)} title="Dismiss" aria-label="Dismiss">🗑</button>
+                  )}
                 </div>
               </div>
             ))}
+            {hasMore && (
+              <button className="secondary outline" onClick={() => load(page + 1)} disabled={loading}>Load more</button>
+            )}
           </div>
         )}
       </div>
       <p>
-        <a href="#status" className="subtitle">← Back to list</a>
+        <a href={childId ? `#child/${encodeURIComponent(childId)}` : '#status'} className="subtitle">← Back</a>
       </p>
     </section>
   )
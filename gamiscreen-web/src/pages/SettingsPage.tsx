@@ -1,5 +1,16 @@
 import { useCallback, useEffect, useMemo, useState } from 'react'
-import { Role, getConfig, pushSubscribe, pushUnsubscribe } from '../api'
+import {
+  Role,
+  TotpEnrollResp,
+  TotpStatusDto,
+  disableTotp,
+  enrollTotp,
+  getConfig,
+  getTotpStatus,
+  pushSubscribe,
+  pushUnsubscribe,
+  verifyTotp,
+} from '../api'
 import {
   NotificationSettings,
   base64UrlToUint8Array,
@@ -215,6 +226,113 @@ export function SettingsPage(props: Props) {
     <div className="col" style={{ gap: 16 }}>
       {installSection}
       {notificationsSection}
+      {role === 'parent' && <TwoFactorSection />}
     </div>
   )
 }
+
+function TwoFactorSection() {
+  const [status, setStatus] = useState<TotpStatusDto | null>(null)
+  const [enrollment, setEnrollment] = useState<TotpEnrollResp | null>(null)
+  const [recoveryCodes, setRecoveryCodes] = useState<string[] | null>(null)
+  const [code, setCode] = useState('')
+  const [busy, setBusy] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    getTotpStatus().then(setStatus).catch((err) => setError(err?.message || 'Failed to load status.'))
+  }, [])
+
+  const run = useCallback(async (action: () => Promise<void>) => {
+    setError(null)
+    setBusy(true)
+    try {
+      await action()
+    } catch (err: any) {
+      setError(err?.message || 'Request failed.')
+    } finally {
+      setBusy(false)
+    }
+  }, [])
+
+  const onStart = () => run(async () => {
+    setRecoveryCodes(null)
+    setCode('')
+    setEnrollment(await enrollTotp())
+  })
+
+  const onConfirm = () => run(async () => {
+    const resp = await verifyTotp(code.trim())
+    setEnrollment(null)
+    setCode('')
+    setRecoveryCodes(resp.recovery_codes)
+    setStatus(await getTotpStatus())
+  })
+
+  const onDisable = () => run(async () => {
+    await disableTotp(code.trim())
+    setCode('')
+    setRecoveryCodes(null)
+    setStatus(await getTotpStatus())
+  })
+
+  const codeInput = (
+    <input
+      type="text"
+      inputMode="numeric"
+      autoComplete="one-time-code"
+      value={code}
+      onChange={e => setCode(e.target.value)}
+      placeholder="123456"
+      aria-label="Authentication code"
+    />
+  )
+
+  return (
+    <section className="card" style={{ padding: 16 }}>
+      <h2 className="title" style={{ marginTop: 0 }}>Two-factor authentication</h2>
+      <p className="subtitle">
+        Ask for a code from an authenticator app in addition to the password when logging in.
+      </p>
+      {status?.enabled && !recoveryCodes && (
+        <div className="col" style={{ gap: 8 }}>
+          <span className="subtitle">
+            Enabled. {status.recovery_codes_left} recovery codes left.
+          </span>
+          {codeInput}
+          <button className="secondary" onClick={onDisable} disabled={busy || !code.trim()} style={{ alignSelf: 'flex-start' }}>
+            Disable
+          </button>
+        </div>
+      )}
+      {status && !status.enabled && !enrollment && (
+        <button onClick={onStart} disabled={busy} style={{ alignSelf: 'flex-start' }}>
+          Set up
+        </button>
+      )}
+      {enrollment && (
+        <div className="col" style={{ gap: 8 }}>
+          <span className="subtitle">
+            Add this account to your authenticator app, then enter the code it shows.
+          </span>
+          <a href={enrollment.otpauth_uri}>Open in authenticator app</a>
+          <span>Key: <code>{enrollment.secret}</code></span>
+          {codeInput}
+          <button onClick={onConfirm} disabled={busy || !code.trim()} style={{ alignSelf: 'flex-start' }}>
+            Confirm
+          </button>
+        </div>
+      )}
+      {recoveryCodes && (
+        <div className="col" style={{ gap: 8 }}>
+          <strong>Save these recovery codes</strong>
+          <span className="subtitle">
+            Each works once in place of a code if you lose your authenticator. They are not shown again.
+          </span>
+          <pre>{recoveryCodes.join('\n')}</pre>
+        </div>
+      )}
+      {error && <p className="error">{error}</p>}
+    </section>
+  )
+}